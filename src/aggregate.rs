@@ -0,0 +1,289 @@
+//! Aggregation of fields over time, e.g. daily means from hourly messages. The time each field is valid for is taken from its time range
+//! indicator, so accumulations and averages count for their whole period, and the result is given the period covered in its PDS.
+//! Accumulations from the start of a forecast can also be turned into the accumulations over each interval between them.
+
+use crate::error::Grib1Error;
+use crate::time::DateTime;
+use crate::{Grib, PDS};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// The average, given time range indicator 3
+    Mean,
+    /// The total, given time range indicator 4 as an accumulation
+    Sum,
+}
+
+/// Aggregate fields of the same parameter and level on the same grid, valid for distinct times or periods that don't overlap.
+/// Points missing from some of the fields are aggregated over the others, and are missing in the result only if missing from all.
+pub fn aggregate(fields: &[Grib], aggregation: Aggregation) -> Result<Grib, Grib1Error> {
+    aggregate_expecting(&fields.iter().collect::<Vec<_>>(), aggregation, fields.len())
+}
+
+/// Aggregate the fields of each day as by `aggregate`, days being those of the valid times, or of the end of the periods less a minute
+/// so that the accumulation up to midnight belongs to the day before. `expected` is the number of fields a complete day has, and the
+/// number short of it is recorded in the PDS of the result.
+pub fn daily(fields: &[Grib], aggregation: Aggregation, expected: usize) -> Result<Vec<Grib>, Grib1Error> {
+    let mut days: BTreeMap<(i32, u8, u8), Vec<&Grib>> = BTreeMap::new();
+    for field in fields {
+        let (start, end) = period(&field.pds)?;
+        let time = if end > start { end.add_minutes(-1) } else { end };
+        days.entry((time.year, time.month, time.day)).or_default().push(field);
+    }
+    days.values().map(|day| aggregate_expecting(day, aggregation, expected)).collect()
+}
+
+fn aggregate_expecting(fields: &[&Grib], aggregation: Aggregation, expected: usize) -> Result<Grib, Grib1Error> {
+    let Some(first) = fields.first().copied() else {
+        return Err(Grib1Error::IncompatibleFields("there are no fields".to_string()));
+    };
+
+    let mut periods = vec![];
+    for field in fields {
+        let pds = &field.pds;
+        if (pds.indicator_of_parameter_and_units, pds.indicator_of_type_of_level_or_layer, pds.level_or_layer_value)
+            != (first.pds.indicator_of_parameter_and_units, first.pds.indicator_of_type_of_level_or_layer, first.pds.level_or_layer_value)
+        {
+            return Err(Grib1Error::IncompatibleFields("the parameters or levels differ".to_string()));
+        }
+        periods.push(period(pds)?);
+    }
+    for field in &fields[1..] {
+        first.check_same_grid(field)?;
+    }
+    periods.sort();
+    if let Some(pair) = periods.windows(2).find(|pair| pair[1].0 < pair[0].1 || pair[1].0 == pair[0].0) {
+        return Err(Grib1Error::IncompatibleFields(format!("the fields valid from {} and {} overlap", pair[0].0, pair[1].0)));
+    }
+
+    let length = first.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?.data.len();
+    let (mut totals, mut counts) = (vec![0f64; length], vec![0u32; length]);
+    for field in fields {
+        let bds = field.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        for (i, value) in bds.data.iter().enumerate().filter(|(_, value)| !bds.is_missing(**value)) {
+            totals[i] += *value as f64;
+            counts[i] += 1;
+        }
+    }
+    let data = totals
+        .iter()
+        .zip(&counts)
+        .map(|(total, count)| match (count, aggregation) {
+            (0, _) => f32::NAN,
+            (_, Aggregation::Mean) => (total / *count as f64) as f32,
+            (_, Aggregation::Sum) => *total as f32,
+        })
+        .collect();
+
+    let bds = first.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let mut result = first.with_data(bds, data);
+    let (start, end) = (periods[0].0, periods.iter().map(|period| period.1).max().unwrap_or(periods[0].1));
+    set_period(&mut result.pds, start, end, aggregation)?;
+    result.pds.number_included_in_average = fields.len() as u16;
+    result.pds.number_missing_from_averages_or_accumulations = expected.saturating_sub(fields.len()).min(255) as u8;
+    Ok(result)
+}
+
+/// Turn accumulations (time range indicator 4) into the accumulations over the intervals between them, e.g. 0-6h, 0-12h and 0-18h into
+/// 0-6h, 6-12h and 12-18h. An accumulation is differenced with the one before it of the same field and forecast, i.e. with the same parameter,
+/// level, reference time, start and time unit, and kept as it is if there is none, as at the start of each forecast cycle. The results are
+/// ordered by parameter and level, then by reference time and end of the interval. Quantisation may leave small negative values in fields that can't be negative, which aren't changed.
+pub fn deaccumulate(fields: &[Grib]) -> Result<Vec<Grib>, Grib1Error> {
+    if let Some(field) = fields.iter().find(|field| field.pds.time_range_indicator != 4) {
+        return Err(Grib1Error::IncompatibleFields(format!("time range indicator {} isn't an accumulation", field.pds.time_range_indicator)));
+    }
+
+    // The field and forecast an accumulation belongs to, so that those of each follow one another when sorted
+    let forecast = |pds: &PDS| {
+        (
+            pds.indicator_of_parameter_and_units,
+            pds.indicator_of_type_of_level_or_layer,
+            pds.level_or_layer_value,
+            pds.reference_time(),
+            pds.forecast_time_unit,
+            pds.p1_period_of_time,
+        )
+    };
+    let mut sorted: Vec<&Grib> = fields.iter().collect();
+    sorted.sort_by_key(|field| (forecast(&field.pds), field.pds.p2_period_of_time));
+
+    let mut result = vec![];
+    for (i, field) in sorted.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| sorted[i]).filter(|previous| forecast(&previous.pds) == forecast(&field.pds));
+        let interval = match previous {
+            Some(previous) if previous.pds.p2_period_of_time == field.pds.p2_period_of_time => {
+                return Err(Grib1Error::IncompatibleFields(format!(
+                    "there are several accumulations to {} from {}",
+                    field.pds.p2_period_of_time,
+                    field.pds.reference_time()
+                )));
+            }
+            Some(previous) => {
+                let mut interval = field.subtract(previous)?;
+                interval.pds.p1_period_of_time = previous.pds.p2_period_of_time;
+                interval
+            }
+            None => {
+                let bds = field.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+                field.with_data(bds, bds.nan_values().into_owned())
+            }
+        };
+        result.push(interval);
+    }
+    Ok(result)
+}
+
+// The start and end of the time the field is valid for, the same for fields valid at a single time
+pub(crate) fn period(pds: &PDS) -> Result<(DateTime, DateTime), Grib1Error> {
+    let unknown = || Grib1Error::IncompatibleFields(format!("the forecast time unit {} isn't known", pds.forecast_time_unit));
+    let after = |amount: u32| pds.reference_time().add_time_unit(pds.forecast_time_unit, amount as i64).ok_or_else(unknown);
+    match pds.time_range_indicator {
+        0 | 1 | 10 => Ok((after(pds.step())?, after(pds.step())?)),
+        2..=5 => Ok((after(pds.p1_period_of_time as u32)?, after(pds.p2_period_of_time as u32)?)),
+        indicator => Err(Grib1Error::IncompatibleFields(format!("time range indicator {} isn't supported", indicator))),
+    }
+}
+
+// Make the PDS describe the period, starting at the reference time, in the smallest unit in which its length fits P2
+fn set_period(pds: &mut PDS, start: DateTime, end: DateTime, aggregation: Aggregation) -> Result<(), Grib1Error> {
+    let minutes = end.to_minutes() - start.to_minutes();
+    let (unit, amount) = [(0, 1), (1, 60), (2, 1440)]
+        .into_iter()
+        .find(|(_, length)| minutes % length == 0 && minutes / length <= 255)
+        .map(|(unit, length)| (unit, minutes / length))
+        .ok_or_else(|| Grib1Error::IncompatibleFields(format!("the period from {} to {} can't be given in the PDS", start, end)))?;
+
+    pds.century_of_initial_reference_time = ((start.year - 1) / 100 + 1) as u8;
+    pds.year = ((start.year - 1) % 100 + 1) as u8;
+    (pds.month, pds.day, pds.hour, pds.minute) = (start.month, start.day, start.hour, start.minute);
+    pds.forecast_time_unit = unit;
+    pds.p1_period_of_time = 0;
+    pds.p2_period_of_time = amount as u8;
+    pds.time_range_indicator = match aggregation {
+        Aggregation::Mean => 3,
+        Aggregation::Sum => 4,
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::missing::MissingValues;
+    use crate::synthetic::MessageBuilder;
+
+    #[test]
+    fn aggregate_test() -> Result<(), Grib1Error> {
+        // Hourly analyses over two days with the hour as value, the first point missing at noon and the 05 UTC field missing on the first day
+        let hourly: Vec<Grib> = (0..48)
+            .filter(|hour| *hour != 5)
+            .map(|hour| {
+                let time = DateTime::new(2024, 3, 1 + hour / 24, hour % 24, 0);
+                MessageBuilder::new(2, 1).reference_time(time).values_with(|i, _| if i == 0 && time.hour == 12 { f32::NAN } else { time.hour as f32 }).grib()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let days = daily(&hourly, Aggregation::Mean, 24)?;
+        assert_eq!(days.len(), 2);
+        let (first, second) = (&days[0], &days[1]);
+        assert_eq!(first.pds.reference_time(), DateTime::new(2024, 3, 1, 0, 0));
+        assert_eq!((first.pds.forecast_time_unit, first.pds.p2_period_of_time, first.pds.time_range_indicator), (1, 23, 3));
+        assert_eq!((first.pds.number_included_in_average, first.pds.number_missing_from_averages_or_accumulations), (23, 1));
+        assert_eq!(second.pds.number_missing_from_averages_or_accumulations, 0);
+        assert_eq!(first.bds.as_ref().unwrap().data, vec![(276 - 5 - 12) as f32 / 22.0, (276 - 5) as f32 / 23.0]);
+        assert_eq!(second.bds.as_ref().unwrap().data[1], 11.5);
+
+        // Six-hour accumulations add up to a daily one
+        let accumulations: Vec<Grib> = (0..4)
+            .map(|i| {
+                MessageBuilder::new(2, 1)
+                    .reference_time(DateTime::new(2024, 3, 1, 0, 0))
+                    .pds(|pds| (pds.p1_period_of_time, pds.p2_period_of_time, pds.time_range_indicator) = (6 * i, 6 * i + 6, 4))
+                    .values(vec![1.0, 2.0])
+                    .grib()
+            })
+            .collect::<Result<_, _>>()?;
+        let total = aggregate(&accumulations, Aggregation::Sum)?;
+        assert_eq!((total.pds.p2_period_of_time, total.pds.time_range_indicator), (24, 4));
+        assert_eq!(total.bds.as_ref().unwrap().data, vec![4.0, 8.0]);
+        assert_eq!(daily(&accumulations, Aggregation::Sum, 4)?.len(), 1);
+
+        // Overlapping periods, differing grids and differing parameters aren't aggregated
+        let at = |day, hour| MessageBuilder::new(2, 1).reference_time(DateTime::new(2024, 3, day, hour, 0));
+        assert!(aggregate(&[at(1, 0).grib()?, at(1, 0).grib()?], Aggregation::Sum).is_err());
+        assert!(aggregate(&[at(1, 0).grib()?, MessageBuilder::new(1, 2).reference_time(DateTime::new(2024, 3, 1, 1, 0)).grib()?], Aggregation::Mean).is_err());
+        assert!(aggregate(&[at(1, 0).grib()?, at(1, 1).parameter(33).grib()?], Aggregation::Mean).is_err());
+        assert!(aggregate(&[at(1, 0).grib()?, at(1, 1).grib()?], Aggregation::Mean).is_ok());
+
+        // A point missing from every field read with a sentinel is given as the sentinel
+        let sentinel = |hour| -> Result<Grib, Grib1Error> {
+            let mut field = at(1, hour).values(vec![f32::NAN, hour as f32]).grib()?;
+            if let Some(bds) = field.bds.as_mut() {
+                bds.set_missing_values(MissingValues::Sentinel(9999.0));
+            }
+            Ok(field)
+        };
+        let mean = aggregate(&[sentinel(0)?, sentinel(2)?], Aggregation::Mean)?.bds.unwrap();
+        assert_eq!(mean.data, vec![9999.0, 1.0]);
+        assert_eq!(mean.optional_values(), vec![None, Some(1.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn deaccumulate_test() -> Result<(), Grib1Error> {
+        let accumulation = |hour, p2, values: Vec<f32>| {
+            MessageBuilder::new(2, 1)
+                .reference_time(DateTime::new(2024, 3, 1, hour, 0))
+                .pds(|pds| (pds.p1_period_of_time, pds.p2_period_of_time, pds.time_range_indicator) = (0, p2, 4))
+                .values(values)
+                .grib()
+        };
+        // Two forecasts, given out of order
+        let fields = vec![
+            accumulation(0, 12, vec![3.0, 0.0])?,
+            accumulation(12, 6, vec![2.0, 1.0])?,
+            accumulation(0, 6, vec![1.0, 0.0])?,
+            accumulation(0, 18, vec![6.0, f32::NAN])?,
+        ];
+
+        let intervals = deaccumulate(&fields)?;
+        let periods: Vec<(u8, u8, u8)> = intervals.iter().map(|field| (field.pds.hour, field.pds.p1_period_of_time, field.pds.p2_period_of_time)).collect();
+        assert_eq!(periods, vec![(0, 0, 6), (0, 6, 12), (0, 12, 18), (12, 0, 6)]);
+        let values: Vec<&[f32]> = intervals.iter().map(|field| field.bds.as_ref().unwrap().data.as_slice()).collect();
+        assert_eq!(values[..2], [&[1.0, 0.0][..], &[2.0, 0.0][..]]);
+        assert_eq!(values[2][0], 3.0);
+        assert!(values[2][1].is_nan());
+        assert_eq!(values[3], &[2.0, 1.0][..]);
+
+        assert!(deaccumulate(&[accumulation(0, 6, vec![1.0, 0.0])?, accumulation(0, 6, vec![1.0, 0.0])?]).is_err());
+        assert!(deaccumulate(&[MessageBuilder::new(2, 1).grib()?]).is_err());
+
+        // Interleaved parameters, and a parameter at two levels, are deaccumulated separately
+        let field = |parameter, level, p2, value| {
+            MessageBuilder::new(2, 1)
+                .parameter(parameter)
+                .level(level, 0)
+                .pds(|pds| (pds.p1_period_of_time, pds.p2_period_of_time, pds.time_range_indicator) = (0, p2, 4))
+                .values(vec![value, value])
+                .grib()
+        };
+        let fields = vec![field(61, 1, 6, 1.0)?, field(62, 1, 6, 2.0)?, field(61, 1, 12, 4.0)?, field(62, 1, 12, 7.0)?, field(61, 8, 6, 10.0)?, field(61, 8, 12, 30.0)?];
+        let intervals = deaccumulate(&fields)?;
+        let summary: Vec<(u8, u8, u8, u8, f32)> = intervals
+            .iter()
+            .map(|field| {
+                let pds = &field.pds;
+                (
+                    pds.indicator_of_parameter_and_units,
+                    pds.indicator_of_type_of_level_or_layer,
+                    pds.p1_period_of_time,
+                    pds.p2_period_of_time,
+                    field.bds.as_ref().unwrap().data[0],
+                )
+            })
+            .collect();
+        assert_eq!(summary, vec![(61, 1, 0, 6, 1.0), (61, 1, 6, 12, 3.0), (61, 8, 0, 6, 10.0), (61, 8, 6, 12, 20.0), (62, 1, 0, 6, 2.0), (62, 1, 6, 12, 5.0)]);
+        Ok(())
+    }
+}
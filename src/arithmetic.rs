@@ -0,0 +1,132 @@
+//! Element-wise arithmetic between decoded messages on the same grid, e.g. differencing two accumulations.
+//! The results are new in-memory messages that can be encoded again using `writer::repack`.
+
+use crate::diff::diff_gds;
+use crate::error::Grib1Error;
+use crate::missing::MissingValues;
+use crate::{Grib, BDS};
+
+impl Grib {
+    /// Add the values of another message on the same grid
+    pub fn add(&self, other: &Grib) -> Result<Grib, Grib1Error> {
+        self.combine(other, |a, b| a + b)
+    }
+
+    /// Subtract the values of another message on the same grid, e.g. to get the precipitation between two accumulations.
+    /// The PDS is copied from `self`, so the time range may need adjusting before the result is written.
+    pub fn subtract(&self, other: &Grib) -> Result<Grib, Grib1Error> {
+        self.combine(other, |a, b| a - b)
+    }
+
+    /// The smaller of the values at each point
+    pub fn minimum(&self, other: &Grib) -> Result<Grib, Grib1Error> {
+        self.combine(other, |a, b| if a.is_nan() || b.is_nan() { f32::NAN } else { a.min(b) })
+    }
+
+    /// The larger of the values at each point
+    pub fn maximum(&self, other: &Grib) -> Result<Grib, Grib1Error> {
+        self.combine(other, |a, b| if a.is_nan() || b.is_nan() { f32::NAN } else { a.max(b) })
+    }
+
+    /// Multiply every value by `factor` and add `offset`, e.g. to convert units
+    pub fn scale(&self, factor: f32, offset: f32) -> Result<Grib, Grib1Error> {
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        Ok(self.with_data(bds, bds.nan_values().iter().map(|v| v * factor + offset).collect()))
+    }
+
+    // Apply the operation point by point. Missing values stay missing, given as they are in `self`.
+    fn combine<F: Fn(f32, f32) -> f32>(&self, other: &Grib, operation: F) -> Result<Grib, Grib1Error> {
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        let other_bds = other.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        self.check_same_grid(other)?;
+
+        let data = bds.nan_values().iter().zip(other_bds.nan_values().iter()).map(|(a, b)| operation(*a, *b)).collect();
+        Ok(self.with_data(bds, data))
+    }
+
+    // Fail unless both messages are on the same grid and have the same number of values
+    pub(crate) fn check_same_grid(&self, other: &Grib) -> Result<(), Grib1Error> {
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        let other_bds = other.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+
+        match (&self.gds, &other.gds) {
+            (Some(left), Some(right)) => {
+                let mut differences = vec![];
+                diff_gds(&mut differences, left, right);
+                if let Some(difference) = differences.first() {
+                    return Err(Grib1Error::IncompatibleFields(format!("{} is {} and {}", difference.field, difference.left, difference.right)));
+                }
+            }
+            (None, None) if self.pds.grid_identification == other.pds.grid_identification => {}
+            _ => return Err(Grib1Error::IncompatibleFields("the grids differ".to_string())),
+        }
+        if bds.data.len() != other_bds.data.len() {
+            return Err(Grib1Error::IncompatibleFields(format!("{} and {} values", bds.data.len(), other_bds.data.len())));
+        }
+        Ok(())
+    }
+
+    // A copy of the message with other data, whose missing points are NaN. They are given as in `bds` in the result.
+    pub(crate) fn with_data(&self, bds: &BDS, data: Vec<f32>) -> Grib {
+        let mut result_bds = BDS {
+            data,
+            missing_values: MissingValues::Nan,
+            ..bds.clone()
+        };
+        result_bds.set_missing_values(bds.missing_values);
+        Grib {
+            offset: self.offset,
+            length: 0,
+            pds: self.pds.clone(),
+            gds: self.gds.clone(),
+            bitmap: None,
+            bds: Some(result_bds),
+            digest: None,
+            bulletin: None,
+            sections: None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::{writer, BoundingBox, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn arithmetic_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }, SearchParams { param: 34, level: 700 }]).await?;
+        let (u, v) = (&result[0], &result[1]);
+        let u_values = &u.bds.as_ref().unwrap().data;
+        let v_values = &v.bds.as_ref().unwrap().data;
+
+        let sum = u.add(v)?;
+        let difference = sum.subtract(v)?;
+        assert!(difference.bds.as_ref().unwrap().data.iter().zip(u_values).all(|(a, b)| (a - b).abs() < 1e-3));
+
+        let maximum = u.maximum(v)?.bds.unwrap().data;
+        let minimum = u.minimum(v)?.bds.unwrap().data;
+        assert!((0..u_values.len()).all(|i| maximum[i] == u_values[i].max(v_values[i]) && minimum[i] == u_values[i].min(v_values[i])));
+
+        let scaled = u.scale(2.0, 1.0)?;
+        assert_eq!(scaled.bds.as_ref().unwrap().data[0], u_values[0] * 2.0 + 1.0);
+
+        // The result can be written out
+        let bytes = writer::repack(&difference, 16)?;
+        assert_eq!(Grib::from_bytes(&bytes)?.pds.indicator_of_parameter_and_units, 33);
+
+        let cropped = u.crop(&BoundingBox {
+            north: 58.0,
+            south: 54.0,
+            west: 8.0,
+            east: 13.0,
+        })?;
+        assert!(matches!(u.subtract(&cropped), Err(Grib1Error::IncompatibleFields(_))));
+
+        Ok(())
+    }
+}
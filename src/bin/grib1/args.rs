@@ -0,0 +1,77 @@
+//! A small command line parser: positional arguments, `--name value` options that may be repeated, and switches without a value.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Vec<String>>,
+}
+
+impl Args {
+    /// Parse the arguments following the subcommand. The names in `switches` take no value.
+    pub fn parse(args: impl IntoIterator<Item = String>, switches: &[&str]) -> Result<Args, String> {
+        let mut result = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                result.positional.push(arg);
+                continue;
+            };
+
+            // Both --name value and --name=value are accepted
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None if switches.contains(&name) => (name.to_string(), String::new()),
+                None => (name.to_string(), args.next().ok_or_else(|| format!("--{} needs a value", name))?),
+            };
+            result.options.entry(name).or_default().push(value);
+        }
+        Ok(result)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Whether the switch or option was given
+    pub fn has(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    /// The last value given for the option
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|values| values.last()).map(String::as_str)
+    }
+
+    /// Every value given for the option, in order
+    pub fn values(&self, name: &str) -> &[String] {
+        self.options.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Fail on options the subcommand doesn't know, which are most likely typing errors
+    pub fn expect_only(&self, known: &[&str]) -> Result<(), String> {
+        match self.options.keys().find(|name| !known.contains(&name.as_str())) {
+            Some(name) => Err(format!("Unknown option --{}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn args_test() {
+        let args = ["a.grb", "--param", "33", "--level=700", "--param", "34", "--verbose", "b.grb"].map(String::from);
+        let args = Args::parse(args, &["verbose"]).unwrap();
+        assert_eq!(args.positional(), ["a.grb", "b.grb"]);
+        assert_eq!(args.values("param"), ["33", "34"]);
+        assert_eq!(args.value("level"), Some("700"));
+        assert!(args.has("verbose"));
+        assert!(args.expect_only(&["param", "level", "verbose"]).is_ok());
+        assert!(args.expect_only(&["param", "level"]).is_err());
+        assert!(Args::parse(["--param".to_string()], &[]).is_err());
+    }
+}
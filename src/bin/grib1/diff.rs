@@ -0,0 +1,77 @@
+//! `grib1 diff LEFT RIGHT` compares two files, listing the messages found in only one of them and how the values of the others differ.
+
+use crate::args::Args;
+use crate::CliResult;
+use grib1_reader::diff::{diff_files, MessageKey};
+use grib1_reader::tables;
+use grib1_reader::Grib1Reader;
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 diff LEFT RIGHT [--tolerance T]\n    Compare two files message by message, failing if they differ by more than the tolerance (default 0)";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&["tolerance"])?;
+    let [left, right] = args.positional() else {
+        return Err("Give the two files to compare".into());
+    };
+    let tolerance = match args.value("tolerance") {
+        Some(value) => value.parse::<f32>().map_err(|_| format!("Invalid tolerance {}", value))?,
+        None => 0.0,
+    };
+
+    let diff = diff_files(&mut Grib1Reader::open(left).await?, &mut Grib1Reader::open(right).await?, tolerance).await?;
+    for key in &diff.only_in_left {
+        writeln!(out, "only in {}: {}", left, describe(key))?;
+    }
+    for key in &diff.only_in_right {
+        writeln!(out, "only in {}: {}", right, describe(key))?;
+    }
+    for (key, message) in &diff.common {
+        let mut notes = vec![];
+        for field in message.metadata.iter().chain(&message.grid) {
+            notes.push(format!("{} {} != {}", field.field, field.left, field.right));
+        }
+        match &message.values {
+            Some(values) if values.differing > 0 => notes.push(format!("{} of {} values differ, max {} rmse {:.6}", values.differing, values.compared, values.max_abs_diff, values.rmse)),
+            Some(_) => {}
+            None => notes.push("values can't be compared".to_string()),
+        }
+
+        match notes.is_empty() {
+            true => writeln!(out, "same: {}", describe(key))?,
+            false => writeln!(out, "differs: {}: {}", describe(key), notes.join(", "))?,
+        }
+    }
+
+    if !diff.is_identical() {
+        return Err("The files differ".into());
+    }
+    Ok(())
+}
+
+fn describe(key: &MessageKey) -> String {
+    let name = tables::parameter(0, key.param).map_or(format!("param={}", key.param), |parameter| format!("{}={}", parameter.abbreviation, key.param));
+    format!("{} level {} {} {} step {}", name, key.type_of_level, key.level, key.reference_time, key.step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn diff_test() {
+        let args = Args::parse(["data/sample.grib", "data/sample.grib"].map(String::from), &[]).unwrap();
+        let mut out = vec![];
+        run(&args, &mut out).await.unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().filter(|line| line.starts_with("same: ")).count(), 2);
+
+        // Leave out the second message
+        let path = std::env::temp_dir().join("grib1_diff_test.grb");
+        std::fs::write(&path, &std::fs::read("data/sample.grib").unwrap()[..2542704]).unwrap();
+        let args = Args::parse(["data/sample.grib".to_string(), path.to_str().unwrap().to_string()], &[]).unwrap();
+        let mut out = vec![];
+        assert!(run(&args, &mut out).await.is_err());
+        assert!(String::from_utf8(out).unwrap().starts_with("only in data/sample.grib: VGRD=34 level 100 700 2023-10-02T15:00Z step 1\n"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
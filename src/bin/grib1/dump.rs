@@ -0,0 +1,134 @@
+//! `grib1 dump FILE` decodes a single message to lat,lon,value rows (CSV) or a JSON document including its metadata.
+
+use crate::args::Args;
+use crate::index;
+use crate::select::{self, Selection};
+use crate::CliResult;
+use grib1_reader::data_array::DataArray;
+use grib1_reader::{DataRepresentation, Grib, RotatedLatLon};
+use std::cell::Cell;
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 dump FILE [--message N] [selection] [--format csv|json] [--output OUT] [--use-index]\n    Decode a message, given by its index in ls or as the only one selected, to geographical lat,lon,value rows or JSON";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&[&select::OPTIONS[..], &["message", "format", "output", index::OPTION]].concat())?;
+    let [path] = args.positional() else {
+        return Err("Give a single file to dump from".into());
+    };
+    let message = args.value("message").map(|value| value.parse::<usize>().map_err(|_| format!("Invalid message index {}", value))).transpose()?;
+    let selection = Selection::from_args(args)?;
+    let json = match args.value("format").unwrap_or("csv") {
+        "csv" => false,
+        "json" => true,
+        other => return Err(format!("Unknown format {}, expected csv or json", other).into()),
+    };
+
+    // Messages are numbered from 1, as listed by ls
+    let count = Cell::new(0);
+    let mut result = index::read_where(path, args, |pds| {
+        count.set(count.get() + 1);
+        message.is_none_or(|message| message == count.get()) && selection.matches(pds)
+    })
+    .await?;
+    let grib = match result.len() {
+        1 => result.remove(0),
+        0 => return Err("No message is selected".into()),
+        count => return Err(format!("{} messages are selected, give --message or narrow the selection", count).into()),
+    };
+
+    let mut text = vec![];
+    if json {
+        write_json(&grib, &mut text)?;
+    } else {
+        write_csv(&grib, &mut text)?;
+    }
+    match args.value("output") {
+        Some(output) => tokio::fs::write(output, text).await?,
+        None => out.write_all(&text)?,
+    }
+    Ok(())
+}
+
+// The geographical position and value of every point, in the order the values are stored
+fn points(grib: &Grib) -> CliResult<Vec<(f32, f32, f32)>> {
+    let array = DataArray::from_grib(grib)?;
+    let grid: RotatedLatLon = match grib.gds.as_ref().map(|gds| gds.data) {
+        Some(DataRepresentation::RotatedLatLon(grid)) => grid,
+        Some(DataRepresentation::RegularLatLon(grid)) => grid.into(),
+        _ => return Err("The grid of the message isn't supported".into()),
+    };
+
+    let mut result = Vec::with_capacity(array.values.len());
+    for (j, lat) in array.latitude.iter().enumerate() {
+        for (i, lon) in array.longitude.iter().enumerate() {
+            let (lat, lon) = grid.to_geographic(*lat, *lon);
+            result.push((lat, lon, array.values[j * array.shape[1] + i]));
+        }
+    }
+    Ok(result)
+}
+
+fn write_csv(grib: &Grib, out: &mut impl Write) -> CliResult {
+    writeln!(out, "lat,lon,value")?;
+    for (lat, lon, value) in points(grib)? {
+        // Missing values are left empty
+        match value.is_nan() {
+            true => writeln!(out, "{},{},", lat, lon)?,
+            false => writeln!(out, "{},{},{}", lat, lon, value)?,
+        }
+    }
+    Ok(())
+}
+
+fn write_json(grib: &Grib, out: &mut impl Write) -> CliResult {
+    let pds = &grib.pds;
+    let parameter = pds.parameter();
+    let string = |value: Option<String>| value.map_or("null".to_string(), |value| format!("\"{}\"", value));
+    let points = points(grib)?;
+    let list = |values: &mut dyn Iterator<Item = f32>| values.map(|value| if value.is_nan() { "null".to_string() } else { value.to_string() }).collect::<Vec<_>>().join(",");
+
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"parameter\": {},", pds.indicator_of_parameter_and_units)?;
+    writeln!(out, "  \"abbreviation\": {},", string(parameter.map(|p| p.abbreviation.to_string())))?;
+    writeln!(out, "  \"name\": {},", string(parameter.map(|p| p.name.to_string())))?;
+    writeln!(out, "  \"units\": {},", string(parameter.map(|p| p.units.to_string())))?;
+    writeln!(out, "  \"type_of_level\": {},", pds.indicator_of_type_of_level_or_layer)?;
+    writeln!(out, "  \"level\": {},", pds.level_or_layer_value)?;
+    writeln!(out, "  \"reference_time\": {},", string(Some(pds.reference_time().to_string())))?;
+    writeln!(out, "  \"step\": {},", pds.step())?;
+    writeln!(out, "  \"valid_time\": {},", string(pds.valid_time().map(|time| time.to_string())))?;
+    writeln!(out, "  \"latitude\": [{}],", list(&mut points.iter().map(|point| point.0)))?;
+    writeln!(out, "  \"longitude\": [{}],", list(&mut points.iter().map(|point| point.1)))?;
+    writeln!(out, "  \"values\": [{}]", list(&mut points.iter().map(|point| point.2)))?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dump_test() {
+        let args = ["data/sample.grib", "--message", "2"].map(String::from);
+        let mut out = vec![];
+        run(&Args::parse(args, &[]).unwrap(), &mut out).await.unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert_eq!(csv.lines().count(), 1 + 1271041);
+        let first: Vec<f32> = csv.lines().nth(1).unwrap().split(',').map(|field| field.parse().unwrap()).collect();
+        assert!(first[0] > 30.0 && first[0] < 50.0 && first[1] > -60.0 && first[1] < 0.0, "{:?}", first);
+
+        let args = ["data/sample.grib", "--param", "UGRD", "--format", "json"].map(String::from);
+        let mut out = vec![];
+        run(&Args::parse(args, &[]).unwrap(), &mut out).await.unwrap();
+        let json = String::from_utf8(out).unwrap();
+        // The parameters of the local table of the sample aren't known
+        assert!(json.contains("\"abbreviation\": null,\n"));
+        assert!(json.contains("\"valid_time\": \"2023-10-02T16:00Z\",\n"));
+
+        // Both messages are selected without a parameter
+        let args = ["data/sample.grib", "--level", "700"].map(String::from);
+        assert!(run(&Args::parse(args, &[]).unwrap(), &mut vec![]).await.is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! `grib1 extract FILE --output OUT` copies the selected messages of a file, as they are, to a new file.
+
+use crate::args::Args;
+use crate::index;
+use crate::select::{self, Selection};
+use crate::CliResult;
+use grib1_reader::Grib1Reader;
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 extract FILE --output OUT [selection] [--use-index]\n    Write the selected messages, unchanged, to OUT";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&[&select::OPTIONS[..], &["output", index::OPTION]].concat())?;
+    let [path] = args.positional() else {
+        return Err("Give a single file to extract from".into());
+    };
+    let output = args.value("output").ok_or("No --output file given")?;
+    let selection = Selection::from_args(args)?;
+
+    let mut reader = Grib1Reader::open(path).await?;
+    let (count, bytes) = if args.has(index::OPTION) {
+        // Only the selected messages are read
+        let mut bytes = vec![];
+        let selected: Vec<_> = index::inventory(path, args).await?.into_iter().filter(|grib| selection.matches(&grib.pds)).collect();
+        for grib in &selected {
+            bytes.extend(reader.read_raw(grib).await?);
+        }
+        (selected.len(), bytes)
+    } else {
+        let bytes = reader.read_binary_where(|pds| selection.matches(pds)).await?;
+        (reader.scan_report().matched, bytes)
+    };
+    tokio::fs::write(output, &bytes).await?;
+
+    writeln!(out, "Wrote {} messages, {} octets, to {}", count, bytes.len(), output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn extract_test() {
+        let output = std::env::temp_dir().join("grib1_extract_test.grb");
+        let args = ["data/sample.grib", "--param", "VGRD", "--level", "700", "--output", output.to_str().unwrap()].map(String::from);
+        let mut out = vec![];
+        run(&Args::parse(args, &[]).unwrap(), &mut out).await.unwrap();
+
+        let sample = std::fs::read("data/sample.grib").unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), sample[2542704..]);
+        assert!(String::from_utf8(out).unwrap().starts_with("Wrote 1 messages"));
+        std::fs::remove_file(&output).unwrap();
+    }
+}
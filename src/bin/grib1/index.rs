@@ -0,0 +1,70 @@
+//! `grib1 index FILE...` writes an index next to each file, which the other subcommands read instead of scanning the file when given `--use-index`.
+
+use crate::args::Args;
+use crate::CliResult;
+use grib1_reader::index::{self, Index};
+use grib1_reader::Grib1Reader;
+use grib1_reader::{Grib, PDS};
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 index FILE...\n    Write an index of each file to FILE.g1idx, used by ls, extract and dump when given --use-index";
+
+// The switch making the subcommands read the index
+pub const OPTION: &str = "use-index";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&[])?;
+    if args.positional().is_empty() {
+        return Err("No file given".into());
+    }
+
+    for path in args.positional() {
+        let index = Index::build(path).await?;
+        index.write(index_path(path)).await?;
+        writeln!(out, "Indexed {} messages of {} in {}", index.entries.len(), path, index_path(path))?;
+    }
+    Ok(())
+}
+
+pub fn index_path(path: &str) -> String {
+    index::index_path(path).display().to_string()
+}
+
+// The messages of a file without their data
+pub async fn inventory(path: &str, args: &Args) -> CliResult<Vec<Grib>> {
+    if args.has(OPTION) {
+        return Ok(Index::read(index_path(path)).await?.messages()?);
+    }
+    Ok(Grib1Reader::open(path).await?.inventory().await?)
+}
+
+// The decoded messages of a file accepted by the filter. A stale index is rebuilt first, as the offsets of the messages are needed.
+pub async fn read_where(path: &str, args: &Args, filter: impl Fn(&PDS) -> bool) -> CliResult<Vec<Grib>> {
+    if args.has(OPTION) {
+        let (mut reader, index) = Grib1Reader::with_index(path).await?;
+        return Ok(reader.read_indexed(&index, filter).await?);
+    }
+    Ok(Grib1Reader::open(path).await?.read_where(filter).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn index_test() {
+        let path = std::env::temp_dir().join("grib1_index_test.grb");
+        std::fs::copy("data/sample.grib", &path).unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let mut out = vec![];
+        run(&Args::parse([path.clone()], &[]).unwrap(), &mut out).await.unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("Indexed 2 messages of {} in {}.g1idx\n", path, path));
+
+        // The index is used even once the file is gone
+        let args = Args::parse([path.clone(), "--use-index".to_string()], &[OPTION]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(inventory(&path, &args).await.unwrap().len(), 2);
+        std::fs::remove_file(index_path(&path)).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+//! `grib1 ls FILE...` lists the messages of files one per line, in the spirit of wgrib.
+
+use crate::args::Args;
+use crate::index;
+use crate::CliResult;
+use grib1_reader::{DataRepresentation, Grib};
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 ls FILE... [--format idx] [--use-index]\n    List the messages of the files: index, offset, parameter, level, reference time, step, valid time and grid, or as an NCEP .idx inventory";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&["format", index::OPTION])?;
+    if args.positional().is_empty() {
+        return Err("No file given".into());
+    }
+    let idx = match args.value("format") {
+        None => false,
+        Some("idx") => true,
+        Some(other) => return Err(format!("Unknown format {}, expected idx", other).into()),
+    };
+
+    for path in args.positional() {
+        let messages = index::inventory(path, args).await?;
+        if idx {
+            write!(out, "{}", grib1_reader::idx::to_idx(&messages))?;
+            continue;
+        }
+        for (index, grib) in messages.iter().enumerate() {
+            writeln!(out, "{}", line(index, grib))?;
+        }
+    }
+    Ok(())
+}
+
+// index:offset:parameter:level:reference time:step:valid time:grid
+fn line(index: usize, grib: &Grib) -> String {
+    let pds = &grib.pds;
+    let parameter = match pds.parameter() {
+        Some(parameter) => format!("{}={}", parameter.abbreviation, pds.indicator_of_parameter_and_units),
+        None => format!("param={}", pds.indicator_of_parameter_and_units),
+    };
+    let valid_time = pds.valid_time().map_or("unknown".to_string(), |time| time.to_string());
+
+    format!(
+        "{}:{}:{}:level {} {}:{}:step {}:{}:{}",
+        index + 1,
+        grib.offset,
+        parameter,
+        pds.indicator_of_type_of_level_or_layer,
+        pds.level_or_layer_value,
+        pds.reference_time(),
+        pds.step(),
+        valid_time,
+        grid(grib)
+    )
+}
+
+fn grid(grib: &Grib) -> String {
+    match grib.gds.as_ref().map(|gds| (gds.data_representation_type, &gds.data)) {
+        Some((_, DataRepresentation::RegularLatLon(grid))) => format!("latlon {}x{}", grid.number_of_lat_values, grid.number_of_lon_values),
+        Some((_, DataRepresentation::RotatedLatLon(grid))) => format!("rotated latlon {}x{}", grid.number_of_lat_values, grid.number_of_lon_values),
+        Some((_, DataRepresentation::AlbersEqualArea(grid))) => format!("albers {}x{}", grid.number_of_x_values, grid.number_of_y_values),
+        Some((_, DataRepresentation::ObliqueLambert(grid))) => format!("oblique lambert {}x{}", grid.number_of_x_values, grid.number_of_y_values),
+        Some((data_representation_type, DataRepresentation::Unhandled)) => format!("grid type {}", data_representation_type),
+        None => format!("grid {}", grib.pds.grid_identification),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ls_test() {
+        let args = Args::parse(["data/sample.grib".to_string()], &[]).unwrap();
+        let mut out = vec![];
+        run(&args, &mut out).await.unwrap();
+
+        let lines: Vec<_> = String::from_utf8(out).unwrap().lines().map(str::to_string).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], "2:2542704:param=34:level 100 700:2023-10-02T15:00Z:step 1:2023-10-02T16:00Z:rotated latlon 1189x1069");
+
+        let args = Args::parse(["data/sample.grib", "--format", "idx"].map(String::from), &[]).unwrap();
+        let mut out = vec![];
+        run(&args, &mut out).await.unwrap();
+        assert!(String::from_utf8(out).unwrap().ends_with("\n2:2542704:d=2023100215:var34:700 mb:1 hour fcst:\n"));
+    }
+}
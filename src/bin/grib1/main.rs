@@ -0,0 +1,59 @@
+//! `grib1`, a command line tool for GRIB1 files built on the library. Run `grib1 help` for the subcommands.
+
+mod args;
+mod diff;
+mod dump;
+mod extract;
+mod index;
+mod ls;
+mod point;
+mod select;
+mod split;
+mod stats;
+
+use args::Args;
+use std::error::Error;
+use std::io::Write;
+use std::process::ExitCode;
+
+// Errors are reported to the user as they are, whatever their type
+type CliResult<T = ()> = Result<T, Box<dyn Error>>;
+
+const SUBCOMMANDS: [&str; 8] = [ls::USAGE, extract::USAGE, dump::USAGE, index::USAGE, diff::USAGE, stats::USAGE, point::USAGE, split::USAGE];
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(subcommand) = args.next() else {
+        eprintln!("{}", usage());
+        return ExitCode::FAILURE;
+    };
+
+    match run(&subcommand, args, &mut std::io::stdout().lock()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("grib1: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(subcommand: &str, args: impl Iterator<Item = String>, out: &mut impl Write) -> CliResult {
+    let args = Args::parse(args, &[index::OPTION])?;
+    match subcommand {
+        "ls" => ls::run(&args, out).await,
+        "extract" => extract::run(&args, out).await,
+        "dump" => dump::run(&args, out).await,
+        "index" => index::run(&args, out).await,
+        "diff" => diff::run(&args, out).await,
+        "stats" => stats::run(&args, out).await,
+        "point" => point::run(&args, out).await,
+        "split" => split::run(&args, out).await,
+        "help" | "--help" | "-h" => Ok(writeln!(out, "{}", usage())?),
+        other => Err(format!("Unknown subcommand {}\n\n{}", other, usage()).into()),
+    }
+}
+
+fn usage() -> String {
+    format!("Usage:\n{}\n\nSelection options:\n{}", SUBCOMMANDS.join("\n"), select::USAGE)
+}
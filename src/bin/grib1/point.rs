@@ -0,0 +1,83 @@
+//! `grib1 point FILE... --lat LAT --lon LON` prints the values at a location in every selected message, ordered by valid time, e.g. to compare a forecast with a station.
+
+use crate::args::Args;
+use crate::index;
+use crate::select::{self, Selection};
+use crate::CliResult;
+use grib1_reader::sample::Interpolation;
+use grib1_reader::{Grib, Grib1Reader};
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 point FILE... --lat LAT --lon LON [--method nearest|bilinear] [selection] [--use-index]\n    Print the values at a geographical position in the selected messages of all files, ordered by valid time";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&[&select::OPTIONS[..], &["lat", "lon", "method", index::OPTION]].concat())?;
+    if args.positional().is_empty() {
+        return Err("No file given".into());
+    }
+    let coordinate = |name: &str| -> CliResult<f32> {
+        let value = args.value(name).ok_or(format!("--{} is required", name))?;
+        Ok(value.parse().map_err(|_| format!("Invalid --{} {}", name, value))?)
+    };
+    let (lat, lon) = (coordinate("lat")?, coordinate("lon")?);
+    let interpolation = match args.value("method").unwrap_or("nearest") {
+        "nearest" => Interpolation::Nearest,
+        "bilinear" => Interpolation::Bilinear,
+        other => return Err(format!("Unknown method {}, expected nearest or bilinear", other).into()),
+    };
+    let selection = Selection::from_args(args)?;
+
+    let mut rows = vec![];
+    for path in args.positional() {
+        let mut reader = Grib1Reader::open(path).await?;
+        for grib in index::inventory(path, args).await? {
+            if !selection.matches(&grib.pds) {
+                continue;
+            }
+            let value = Grib::from_bytes(&reader.read_raw(&grib).await?)?.value_at(lat, lon, interpolation)?;
+
+            let pds = &grib.pds;
+            let name = pds.parameter().map_or(pds.indicator_of_parameter_and_units.to_string(), |parameter| parameter.abbreviation.to_string());
+            let level = format!("{} {}", pds.indicator_of_type_of_level_or_layer, pds.level_or_layer_value);
+            rows.push((pds.valid_time(), path, name, level, value));
+        }
+    }
+    // Sorting is stable, so messages valid at the same time stay in file order
+    rows.sort_by_key(|row| row.0);
+
+    writeln!(out, "valid time\tfile\tparameter\tlevel\tvalue")?;
+    for (valid_time, path, name, level, value) in rows {
+        // Positions outside the grid and missing values are left empty
+        let valid_time = valid_time.map_or("unknown".to_string(), |time| time.to_string());
+        let value = value.map_or(String::new(), |value| value.to_string());
+        writeln!(out, "{}\t{}\t{}\t{}\t{}", valid_time, path, name, level, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn point_test() {
+        let args = Args::parse(["data/sample.grib", "data/sample.grib", "--lat", "55.7", "--lon", "12.6", "--method", "bilinear"].map(String::from), &[]).unwrap();
+        let mut out = vec![];
+        run(&args, &mut out).await.unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<Vec<&str>> = out.lines().skip(1).map(|line| line.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0][..4], ["2023-10-02T16:00Z", "data/sample.grib", "33", "100 700"]);
+        assert_eq!(lines[1][2], "34");
+        assert!(lines.iter().all(|line| line[4].parse::<f32>().is_ok()));
+
+        // Far outside the grid there's no value
+        let args = Args::parse(["data/sample.grib", "--lat", "-40", "--lon", "170", "--param", "UGRD"].map(String::from), &[]).unwrap();
+        let mut out = vec![];
+        run(&args, &mut out).await.unwrap();
+        assert!(String::from_utf8(out).unwrap().ends_with("\t33\t100 700\t\n"));
+
+        let args = Args::parse(["data/sample.grib", "--lat", "55.7"].map(String::from), &[]).unwrap();
+        assert!(run(&args, &mut vec![]).await.is_err());
+    }
+}
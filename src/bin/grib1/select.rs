@@ -0,0 +1,78 @@
+//! Selecting messages by their metadata with the `--param`, `--level`, `--step` and `--date` options shared by the subcommands.
+//! Each option may be repeated to accept any of the values, and a message must match all the options given.
+
+use crate::args::Args;
+use grib1_reader::tables;
+use grib1_reader::PDS;
+
+pub const OPTIONS: [&str; 4] = ["param", "level", "step", "date"];
+
+pub const USAGE: &str = "    Select messages with --param (number or abbreviation, e.g. UGRD), --level, --step and --date (reference time as YYYYMMDDHH[MM])";
+
+#[derive(Debug, Default)]
+pub struct Selection {
+    params: Vec<u8>,
+    levels: Vec<u16>,
+    steps: Vec<u32>,
+    // Reference times in minutes, and whether the minutes were given
+    dates: Vec<(i64, bool)>,
+}
+
+impl Selection {
+    pub fn from_args(args: &Args) -> Result<Selection, String> {
+        let params = args.values("param").iter().map(|value| parameter(value)).collect::<Result<_, _>>()?;
+        let levels = parse_all(args, "level")?;
+        let steps = parse_all(args, "step")?;
+        let dates = args.values("date").iter().map(|value| date(value)).collect::<Result<_, _>>()?;
+
+        Ok(Selection { params, levels, steps, dates })
+    }
+
+    pub fn matches(&self, pds: &PDS) -> bool {
+        let time = pds.reference_time();
+        let hour = time.to_minutes() - time.minute as i64;
+
+        (self.params.is_empty() || self.params.contains(&pds.indicator_of_parameter_and_units))
+            && (self.levels.is_empty() || self.levels.contains(&pds.level_or_layer_value))
+            && (self.steps.is_empty() || self.steps.contains(&pds.step()))
+            && (self.dates.is_empty() || self.dates.iter().any(|(date, minutes)| *date == if *minutes { time.to_minutes() } else { hour }))
+    }
+}
+
+fn parse_all<T: std::str::FromStr>(args: &Args, name: &str) -> Result<Vec<T>, String> {
+    args.values(name).iter().map(|value| value.parse().map_err(|_| format!("Invalid value {} for --{}", value, name))).collect()
+}
+
+fn parameter(value: &str) -> Result<u8, String> {
+    match value.parse() {
+        Ok(indicator) => Ok(indicator),
+        Err(_) => tables::parameter_by_abbreviation(value).map(|parameter| parameter.indicator).ok_or_else(|| format!("Unknown parameter {}", value)),
+    }
+}
+
+fn date(value: &str) -> Result<(i64, bool), String> {
+    let invalid = || format!("Invalid date {}, expected YYYYMMDDHH or YYYYMMDDHHMM", value);
+    if !(value.len() == 10 || value.len() == 12) || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let field = |range: std::ops::Range<usize>| value.get(range).map_or(Ok(0), str::parse).map_err(|_| invalid());
+    let time = grib1_reader::time::DateTime::new(field(0..4)?, field(4..6)? as u8, field(6..8)? as u8, field(8..10)? as u8, field(10..12)? as u8);
+    Ok((time.to_minutes(), value.len() == 12))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_test() {
+        let args = ["--param", "UGRD", "--param", "34", "--date", "2023100215"].map(String::from);
+        let selection = Selection::from_args(&Args::parse(args, &[]).unwrap()).unwrap();
+        assert_eq!(selection.params, [33, 34]);
+        assert_eq!(selection.dates.len(), 1);
+
+        assert!(Selection::from_args(&Args::parse(["--param", "NOPE"].map(String::from), &[]).unwrap()).is_err());
+        assert!(Selection::from_args(&Args::parse(["--date", "20231002"].map(String::from), &[]).unwrap()).is_err());
+    }
+}
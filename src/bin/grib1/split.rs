@@ -0,0 +1,51 @@
+//! `grib1 split FILE --template TEMPLATE` writes every selected message to a file of its own, named from its metadata.
+
+use crate::args::Args;
+use crate::select::{self, Selection};
+use crate::CliResult;
+use grib1_reader::Grib1Reader;
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 split FILE [--dir DIR] [--template TEMPLATE] [selection]\n    Write each selected message to its own file in DIR, named by TEMPLATE with placeholders such as {name}, {level}, {validtime} and {index}";
+
+// Unique for every message of a file, as the index is part of it
+const DEFAULT_TEMPLATE: &str = "{index}_{name}_{level_type}_{level}_{validtime}.grb";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&[&select::OPTIONS[..], &["dir", "template"]].concat())?;
+    let [path] = args.positional() else {
+        return Err("Give a single file to split".into());
+    };
+    let dir = args.value("dir").unwrap_or(".");
+    let template = args.value("template").unwrap_or(DEFAULT_TEMPLATE);
+    let selection = Selection::from_args(args)?;
+
+    let paths = Grib1Reader::open(path).await?.split_to_dir_where(dir, template, |pds| selection.matches(pds)).await?;
+    for path in &paths {
+        writeln!(out, "{}", path.display())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn split_test() {
+        let dir = std::env::temp_dir().join("grib1_split_test");
+        let args = ["data/sample.grib", "--param", "VGRD", "--dir", dir.to_str().unwrap()].map(String::from);
+        let mut out = vec![];
+        run(&Args::parse(args, &[]).unwrap(), &mut out).await.unwrap();
+
+        let written = dir.join("1_34_100_700_202310021600.grb");
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", written.display()));
+        let sample = std::fs::read("data/sample.grib").unwrap();
+        assert_eq!(std::fs::read(&written).unwrap(), sample[2542704..]);
+
+        // Without the index both messages get the same name
+        let args = ["data/sample.grib", "--template", "{level}.grb", "--dir", dir.to_str().unwrap()].map(String::from);
+        assert!(run(&Args::parse(args, &[]).unwrap(), &mut vec![]).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+//! `grib1 stats FILE...` prints statistics of the values of every selected message as tab separated columns, decoding one message at a time.
+
+use crate::args::Args;
+use crate::index;
+use crate::select::{self, Selection};
+use crate::CliResult;
+use grib1_reader::{Grib, Grib1Reader};
+use std::io::Write;
+
+pub const USAGE: &str = "grib1 stats FILE... [selection] [--use-index]\n    Print the minimum, maximum, mean, standard deviation and number of missing values of the selected messages";
+
+pub async fn run(args: &Args, out: &mut impl Write) -> CliResult {
+    args.expect_only(&[&select::OPTIONS[..], &[index::OPTION]].concat())?;
+    if args.positional().is_empty() {
+        return Err("No file given".into());
+    }
+    let selection = Selection::from_args(args)?;
+
+    writeln!(out, "file\tindex\tparameter\tlevel\tvalid time\tpoints\tmissing\tmin\tmax\tmean\tstddev")?;
+    for path in args.positional() {
+        let mut reader = Grib1Reader::open(path).await?;
+        for (i, grib) in index::inventory(path, args).await?.iter().enumerate() {
+            if !selection.matches(&grib.pds) {
+                continue;
+            }
+            let decoded = Grib::from_bytes(&reader.read_raw(grib).await?)?;
+            let points = decoded.bds.as_ref().map_or(0, |bds| bds.data.len());
+            let stats = decoded.stats()?;
+
+            let pds = &grib.pds;
+            let name = pds.parameter().map_or(pds.indicator_of_parameter_and_units.to_string(), |parameter| parameter.abbreviation.to_string());
+            let valid_time = pds.valid_time().map_or("unknown".to_string(), |time| time.to_string());
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{} {}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                path,
+                i + 1,
+                name,
+                pds.indicator_of_type_of_level_or_layer,
+                pds.level_or_layer_value,
+                valid_time,
+                points,
+                stats.missing,
+                stats.min,
+                stats.max,
+                stats.mean,
+                stats.standard_deviation()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stats_test() {
+        let args = Args::parse(["data/sample.grib", "--param", "VGRD"].map(String::from), &[]).unwrap();
+        let mut out = vec![];
+        run(&args, &mut out).await.unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let line: Vec<_> = out.lines().nth(1).unwrap().split('\t').collect();
+        assert_eq!(line[..3], ["data/sample.grib", "2", "34"]);
+        assert_eq!(line[4..7], ["2023-10-02T16:00Z", "1271041", "0"]);
+        assert_eq!(out.lines().count(), 2);
+    }
+}
@@ -0,0 +1,93 @@
+//! A reader for synchronous code, such as the workers of a rayon pool, running the asynchronous reader on a runtime of its own.
+//! It must not be used from within an asynchronous task, where blocking on the runtime would panic.
+
+use crate::error::Grib1Error;
+use crate::options::Grib1ReaderOptions;
+use crate::{Grib, Grib1Reader, SearchParams, PDS};
+use std::path::Path;
+use tokio::runtime::{Builder, Runtime};
+
+/// Reads a file with blocking calls. Each reader has a single threaded runtime, so readers can be created freely on worker threads.
+pub struct Grib1ReaderBlocking {
+    reader: Grib1Reader,
+    runtime: Runtime,
+}
+
+impl Grib1ReaderBlocking {
+    /// Open a file for reading
+    pub fn open(path: impl AsRef<Path>) -> Result<Grib1ReaderBlocking, Grib1Error> {
+        Grib1ReaderBlocking::open_with(path, Grib1ReaderOptions::default())
+    }
+
+    /// Open a file for reading, configured by the options
+    pub fn open_with(path: impl AsRef<Path>, options: Grib1ReaderOptions) -> Result<Grib1ReaderBlocking, Grib1Error> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let reader = runtime.block_on(Grib1Reader::open_with(path, options))?;
+        Ok(Grib1ReaderBlocking { reader, runtime })
+    }
+
+    /// See `Grib1Reader::read`
+    pub fn read(&mut self, search: Vec<SearchParams>) -> Result<Vec<Grib>, Grib1Error> {
+        self.runtime.block_on(self.reader.read(search))
+    }
+
+    /// See `Grib1Reader::read_where`
+    pub fn read_where<F: Fn(&PDS) -> bool>(&mut self, filter: F) -> Result<Vec<Grib>, Grib1Error> {
+        self.runtime.block_on(self.reader.read_where(filter))
+    }
+
+    /// See `Grib1Reader::read_binary_where`
+    pub fn read_binary_where<F: Fn(&PDS) -> bool>(&mut self, filter: F) -> Result<Vec<u8>, Grib1Error> {
+        self.runtime.block_on(self.reader.read_binary_where(filter))
+    }
+
+    /// See `Grib1Reader::inventory`
+    pub fn inventory(&mut self) -> Result<Vec<Grib>, Grib1Error> {
+        self.runtime.block_on(self.reader.inventory())
+    }
+
+    /// See `Grib1Reader::read_raw`
+    pub fn read_raw(&mut self, grib: &Grib) -> Result<Vec<u8>, Grib1Error> {
+        self.runtime.block_on(self.reader.read_raw(grib))
+    }
+
+    /// See `Grib1Reader::read_message_at`
+    pub fn read_message_at(&mut self, offset: u64) -> Result<Grib, Grib1Error> {
+        self.runtime.block_on(self.reader.read_message_at(offset))
+    }
+
+    /// The asynchronous reader, for the warnings, report and metrics of the reads made
+    pub fn reader(&self) -> &Grib1Reader {
+        &self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocking_test() -> Result<(), Grib1Error> {
+        // Several readers working at once on threads of their own
+        let threads: Vec<_> = [33, 34]
+            .into_iter()
+            .map(|param| {
+                std::thread::spawn(move || -> Result<Grib, Grib1Error> {
+                    let mut reader = Grib1ReaderBlocking::open("data/sample.grib")?;
+                    Ok(reader.read(vec![SearchParams { param, level: 700 }])?.remove(0))
+                })
+            })
+            .collect();
+        for (thread, param) in threads.into_iter().zip([33, 34]) {
+            let grib = thread.join().unwrap()?;
+            assert_eq!((grib.pds.indicator_of_parameter_and_units, grib.values().len()), (param, 1271041));
+        }
+
+        let mut reader = Grib1ReaderBlocking::open("data/sample.grib")?;
+        let inventory = reader.inventory()?;
+        assert_eq!(reader.read_raw(&inventory[1])?.len(), 2542704);
+        assert_eq!(reader.read_message_at(inventory[1].offset)?.pds.indicator_of_parameter_and_units, 34);
+        assert_eq!(reader.reader().metrics().messages_decoded, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+//! Caching of decoded messages, e.g. by a service decoding the same popular fields for many requests. A field is identified by the size,
+//! modification time and digest of its file, its offset and the options it was decoded with, so a cache can be shared between readers and
+//! survives files being replaced.
+
+use crate::error::Grib1Error;
+use crate::index::SourceFile;
+use crate::Grib;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// What a decoded message is cached by
+pub struct CacheKey {
+    /// The file as it was when the message was read. The digest only covers the start and end of the file, so the size and
+    /// modification time tell apart files that differ in the middle.
+    pub source: SourceFile,
+    pub offset: u64,
+    /// A description of the options the message was decoded with, as messages decoded differently have different values
+    pub options: String,
+}
+
+impl CacheKey {
+    /// The key of the message at the offset of the file, decoded with the options, e.g. `Grib1ReaderOptions`
+    pub fn new(source: &SourceFile, offset: u64, options: &impl Debug) -> CacheKey {
+        CacheKey {
+            source: *source,
+            offset,
+            options: format!("{:?}", options),
+        }
+    }
+}
+
+/// A store of decoded messages that can be shared between tasks. Entries may be dropped at any time, so a miss only means decoding again.
+pub trait FieldCache: Send + Sync {
+    fn get(&self, key: &CacheKey) -> Option<Arc<Grib>>;
+
+    fn insert(&self, key: CacheKey, grib: Arc<Grib>);
+
+    fn remove(&self, key: &CacheKey);
+
+    fn clear(&self);
+}
+
+/// The cached message, or the one decoded and cached if there is none
+pub async fn cached<C, F, Fut>(cache: &C, key: CacheKey, decode: F) -> Result<Arc<Grib>, Grib1Error>
+where
+    C: FieldCache + ?Sized,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Grib, Grib1Error>>,
+{
+    if let Some(grib) = cache.get(&key) {
+        return Ok(grib);
+    }
+    let grib = Arc::new(decode().await?);
+    cache.insert(key, grib.clone());
+    Ok(grib)
+}
+
+/// A cache in memory, dropping the entries used longest ago when it is full and entries older than the time to live
+#[derive(Debug)]
+pub struct MemoryCache {
+    ttl: Option<Duration>,
+    max_bytes: u64,
+    max_entries: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<CacheKey, (Arc<Grib>, Instant)>,
+    // Keys from the one used longest ago to the one used last
+    recent: VecDeque<CacheKey>,
+    bytes: u64,
+}
+
+impl Default for MemoryCache {
+    fn default() -> MemoryCache {
+        MemoryCache {
+            ttl: None,
+            max_bytes: u64::MAX,
+            max_entries: usize::MAX,
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl MemoryCache {
+    /// A cache without limits, to be narrowed down with `ttl`, `max_bytes` and `max_entries`
+    pub fn new() -> MemoryCache {
+        MemoryCache::default()
+    }
+
+    /// Drop entries this long after they were inserted
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keep the decoded values of the entries within this many bytes. A message larger than that isn't cached.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Number of entries and the bytes of their decoded values
+    pub fn usage(&self) -> (usize, u64) {
+        self.state.lock().map_or((0, 0), |state| (state.entries.len(), state.bytes))
+    }
+}
+
+impl FieldCache for MemoryCache {
+    fn get(&self, key: &CacheKey) -> Option<Arc<Grib>> {
+        let mut state = self.state.lock().ok()?;
+        let (grib, inserted) = state.entries.get(key)?.clone();
+        if self.ttl.is_some_and(|ttl| inserted.elapsed() >= ttl) {
+            state.remove(key);
+            return None;
+        }
+        state.recent.retain(|recent| recent != key);
+        state.recent.push_back(key.clone());
+        Some(grib)
+    }
+
+    fn insert(&self, key: CacheKey, grib: Arc<Grib>) {
+        let size = size(&grib);
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.remove(&key);
+        if size > self.max_bytes || self.max_entries == 0 {
+            return;
+        }
+
+        while state.bytes + size > self.max_bytes || state.entries.len() >= self.max_entries {
+            let Some(oldest) = state.recent.front().cloned() else {
+                break;
+            };
+            state.remove(&oldest);
+        }
+        state.bytes += size;
+        state.recent.push_back(key.clone());
+        state.entries.insert(key, (grib, Instant::now()));
+    }
+
+    fn remove(&self, key: &CacheKey) {
+        if let Ok(mut state) = self.state.lock() {
+            state.remove(key);
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = State::default();
+        }
+    }
+}
+
+impl State {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some((grib, _)) = self.entries.remove(key) {
+            self.bytes -= size(&grib);
+            self.recent.retain(|recent| recent != key);
+        }
+    }
+}
+
+// The bytes taken by the decoded values of a message
+fn size(grib: &Grib) -> u64 {
+    grib.bds.as_ref().map_or(0, |bds| bds.data.len() as u64 * 4)
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::synthetic::MessageBuilder;
+
+    #[tokio::test]
+    async fn cache_test() -> Result<(), Grib1Error> {
+        let source = SourceFile::of("data/sample.grib")?;
+        let key = |offset| CacheKey::new(&source, offset, &"default");
+        let field = || async { MessageBuilder::new(10, 10).grib() };
+
+        // Two fields of 400 bytes fit, so the third drops the one used longest ago
+        let cache = MemoryCache::new().max_bytes(800);
+        let first = cached(&cache, key(0), field).await?;
+        assert!(Arc::ptr_eq(&first, &cached(&cache, key(0), || async { unreachable!() }).await?));
+        cached(&cache, key(1), field).await?;
+        cache.get(&key(0));
+        cached(&cache, key(2), field).await?;
+        assert_eq!(cache.usage(), (2, 800));
+        assert!(cache.get(&key(0)).is_some() && cache.get(&key(1)).is_none());
+        assert_ne!(key(0), CacheKey::new(&source, 0, &"other options"));
+        let rewritten = SourceFile { size: source.size + 1, ..source };
+        assert_ne!(key(0), CacheKey::new(&rewritten, 0, &"default"));
+
+        let cache = MemoryCache::new().ttl(Duration::from_millis(20)).max_entries(1);
+        cache.insert(key(0), first.clone());
+        cache.insert(key(1), first.clone());
+        assert!(cache.get(&key(0)).is_none() && cache.get(&key(1)).is_some());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(&key(1)).is_none());
+        assert_eq!(cache.usage(), (0, 0));
+        Ok(())
+    }
+}
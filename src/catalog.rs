@@ -0,0 +1,178 @@
+//! Messages of many files, e.g. a month of runs, queried together. Each file is indexed as by `Grib1Reader::with_index`, and a field found
+//! in several files is taken from the one modified last, so a rerun replaces the run it repeats.
+
+use crate::error::Grib1Error;
+use crate::index::{GribIndex, IndexedMessage};
+use crate::time::DateTime;
+use crate::{Grib, Grib1Reader};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A message of a catalogued file, knowing which file to read it from
+pub struct MessageHandle {
+    pub path: PathBuf,
+    pub message: IndexedMessage,
+}
+
+impl MessageHandle {
+    /// Open the file and decode the message
+    pub async fn read(&self) -> Result<Grib, Grib1Error> {
+        Grib1Reader::open(&self.path).await?.read_indexed_message(&self.message).await
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CatalogFile {
+    path: PathBuf,
+    modified: Option<Duration>,
+    index: GribIndex,
+}
+
+#[derive(Debug, Clone, Default)]
+/// The messages of the files added, answering queries across all of them
+pub struct Catalog {
+    files: Vec<CatalogFile>,
+}
+
+impl Catalog {
+    pub fn new() -> Catalog {
+        Catalog::default()
+    }
+
+    /// Add a file, indexing it unless it has an index that is up to date
+    pub async fn add_file(&mut self, path: impl AsRef<Path>) -> Result<(), Grib1Error> {
+        let (index, modified) = index_file(&path).await?;
+        self.add_index(path, index, modified);
+        Ok(())
+    }
+
+    /// Add an index of a file built elsewhere. Files without a modification time are older than those with one.
+    pub fn add_index(&mut self, path: impl AsRef<Path>, index: GribIndex, modified: Option<Duration>) {
+        self.files.push(CatalogFile {
+            path: path.as_ref().to_path_buf(),
+            modified,
+            index,
+        });
+    }
+
+    /// The files added, in the order they were added
+    pub fn files(&self) -> Vec<&Path> {
+        self.files.iter().map(|file| file.path.as_path()).collect()
+    }
+
+    /// The messages accepted by the filter, ordered by reference time, step, parameter and level. Of the messages with the same
+    /// parameter, level, reference time and step only the one from the file modified last is kept, or added last if they were modified at the same time.
+    pub fn query<F: Fn(&IndexedMessage) -> bool>(&self, filter: F) -> Vec<MessageHandle> {
+        let mut order: Vec<usize> = (0..self.files.len()).collect();
+        order.sort_by_key(|i| self.files[*i].modified);
+
+        let mut newest = HashMap::new();
+        for file in order.into_iter().map(|i| &self.files[i]) {
+            for message in file.index.messages.iter().filter(|message| filter(message)) {
+                let handle = MessageHandle { path: file.path.clone(), message: *message };
+                newest.insert((message.param, message.type_of_level, message.level, message.reference_time, message.step), handle);
+            }
+        }
+
+        let mut result: Vec<MessageHandle> = newest.into_values().collect();
+        result.sort_by_key(|handle| (handle.message.reference_time, handle.message.step, handle.message.param, handle.message.type_of_level, handle.message.level));
+        result
+    }
+
+    /// The parameter at the level and step from every run, e.g. the 2 m temperature at +12h as `find(11, 105, 2, 12)`
+    pub fn find(&self, param: u8, type_of_level: u8, level: u16, step: u32) -> Vec<MessageHandle> {
+        self.query(|m| (m.param, m.type_of_level, m.level, m.step) == (param, type_of_level, level, step))
+    }
+
+    /// The distinct reference times of all files, in ascending order
+    pub fn reference_times(&self) -> Vec<DateTime> {
+        let mut result: Vec<DateTime> = self.files.iter().flat_map(|file| file.index.reference_times()).collect();
+        result.sort();
+        result.dedup();
+        result
+    }
+}
+
+/// A catalogue of the files, indexing up to `concurrency` of them at the same time as tasks on the runtime, e.g. a directory of hourly files.
+/// The files are added in the order given, whichever is indexed first. Indexing stops at the first file that fails.
+pub async fn scan_files<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>, concurrency: usize) -> Result<Catalog, Grib1Error> {
+    let mut pending = paths.into_iter().map(|path| path.as_ref().to_path_buf()).enumerate();
+    let mut running = JoinSet::new();
+    let mut scanned = vec![];
+    loop {
+        while running.len() < concurrency.max(1) {
+            let Some((i, path)) = pending.next() else {
+                break;
+            };
+            running.spawn(async move { index_file(&path).await.map(|(index, modified)| (i, path, index, modified)) });
+        }
+        match running.join_next().await {
+            Some(joined) => scanned.push(joined.map_err(std::io::Error::other)??),
+            None => break,
+        }
+    }
+
+    scanned.sort_by_key(|(i, ..)| *i);
+    let mut catalog = Catalog::new();
+    for (_, path, index, modified) in scanned {
+        catalog.add_index(path, index, modified);
+    }
+    Ok(catalog)
+}
+
+// Index a file as `Grib1Reader::with_index` does, giving when it was modified
+async fn index_file(path: impl AsRef<Path>) -> Result<(GribIndex, Option<Duration>), Grib1Error> {
+    let (_, index) = Grib1Reader::with_index(&path).await?;
+    let modified = index.source.as_ref().and_then(|source| source.modified);
+    Ok((GribIndex::from_index(&index)?, modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::index_path;
+    use std::time::SystemTime;
+
+    #[tokio::test]
+    async fn catalog_test() -> Result<(), Grib1Error> {
+        let bytes = std::fs::read("data/sample.grib")?;
+        let paths: Vec<PathBuf> = ["old", "new"].iter().map(|name| std::env::temp_dir().join(format!("grib1_reader_catalog_test_{}.grb", name))).collect();
+        std::fs::write(&paths[0], &bytes)?;
+        std::fs::write(&paths[1], &bytes[2542704..])?;
+        // The file with only the V component is the newer one, though added first
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        std::fs::File::options().write(true).open(&paths[0])?.set_modified(hour_ago)?;
+
+        let mut catalog = Catalog::new();
+        catalog.add_file(&paths[1]).await?;
+        catalog.add_file(&paths[0]).await?;
+        assert_eq!(catalog.files().len(), 2);
+        assert_eq!(catalog.reference_times(), vec![DateTime::new(2023, 10, 2, 15, 0)]);
+
+        let all = catalog.query(|_| true);
+        assert_eq!(all.len(), 2);
+        assert_eq!((all[0].message.param, &all[0].path), (33, &paths[0]));
+        assert_eq!((all[1].message.param, &all[1].path), (34, &paths[1]));
+
+        let found = catalog.find(34, 100, 700, 1);
+        assert_eq!(found, vec![all[1].clone()]);
+        assert!(catalog.find(34, 100, 700, 12).is_empty());
+        let decoded = found[0].read().await?;
+        assert_eq!((decoded.pds.indicator_of_parameter_and_units, decoded.offset), (34, 0));
+
+        // Scanning concurrently adds the files in the order given
+        let scanned = scan_files(&paths, 4).await?;
+        assert_eq!(scanned.files(), vec![paths[0].as_path(), paths[1].as_path()]);
+        assert_eq!(scanned.query(|_| true), all);
+        assert!(scan_files([&paths[0], &PathBuf::from("data/missing.grib")], 1).await.is_err());
+
+        for path in &paths {
+            std::fs::remove_file(index_path(path))?;
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
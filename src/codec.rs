@@ -0,0 +1,130 @@
+//! The integer and floating point encodings used by GRIB1, for parsing sections by hand, e.g. local use sections of the PDS.
+//!
+//! Integers are big-endian. Signed integers use sign and magnitude, with the sign in the top bit, so they can't hold the most negative
+//! two's complement value and have both a positive and a negative zero. Floats are IBM System/370 single precision, see `ibm`.
+//! The readers expect the slice to hold at least the octets of the value and panic otherwise.
+
+#[cfg(feature = "std")]
+pub use crate::ibm::to_ibm as write_f32_ibm;
+
+/// Read an IBM float from the first four octets
+pub fn read_f32_ibm(data: &[u8]) -> f32 {
+    let sign = if (data[0] & 0x80) > 0 { -1.0 } else { 1.0 };
+    let a = (data[0] & 0x7f) as i32;
+    let b = (((data[1] as i32) << 16) + ((data[2] as i32) << 8) + data[3] as i32) as f64;
+
+    // Calculated in double precision, as the power of sixteen alone may be outside the range of f32
+    (sign * b * pow2(-24) * pow2(4 * (a - 64))) as f32
+}
+
+// 2 to the power of `exp`, exact wherever it is representable, without needing std for `powi`
+pub(crate) fn pow2(exp: i32) -> f64 {
+    match exp {
+        1024.. => f64::INFINITY,
+        -1022..=1023 => f64::from_bits(((exp + 1023) as u64) << 52),
+        -1074..=-1023 => f64::from_bits(1 << (exp + 1074)),
+        _ => 0.0,
+    }
+}
+
+/// Read a signed 16 bit integer from the first two octets
+pub fn read_i16_be(array: &[u8]) -> i16 {
+    let mut val = (array[1] as i16) + (((array[0] & 127) as i16) << 8);
+    if array[0] & 0x80 > 0 {
+        val = -val;
+    }
+    val
+}
+
+/// Read a signed 24 bit integer from the first three octets
+pub fn read_i24_be(array: &[u8]) -> i32 {
+    let mut val = (array[2] as i32) + ((array[1] as i32) << 8) + (((array[0] & 127) as i32) << 16);
+    if array[0] & 0x80 > 0 {
+        val = -val;
+    }
+    val
+}
+
+/// Read an unsigned 16 bit integer from the first two octets
+pub fn read_u16_be(array: &[u8]) -> u16 {
+    (array[1] as u16) + ((array[0] as u16) << 8)
+}
+
+/// Read an unsigned 24 bit integer from the first three octets
+pub fn read_u24_be(array: &[u8]) -> u32 {
+    (array[2] as u32) + ((array[1] as u32) << 8) + ((array[0] as u32) << 16)
+}
+
+/// Encode a signed 16 bit integer. Magnitudes above 0x7fff don't fit and are truncated.
+pub fn write_i16_be(value: i16) -> [u8; 2] {
+    let magnitude = value.unsigned_abs() & 0x7fff;
+    let sign = if value < 0 { 0x80 } else { 0 };
+    [sign | (magnitude >> 8) as u8, magnitude as u8]
+}
+
+/// Encode a signed 24 bit integer. Magnitudes above 0x7fffff don't fit and are truncated.
+pub fn write_i24_be(value: i32) -> [u8; 3] {
+    let magnitude = value.unsigned_abs() & 0x7f_ffff;
+    let sign = if value < 0 { 0x80 } else { 0 };
+    [sign | (magnitude >> 16) as u8, (magnitude >> 8) as u8, magnitude as u8]
+}
+
+/// Encode an unsigned 16 bit integer
+pub fn write_u16_be(value: u16) -> [u8; 2] {
+    value.to_be_bytes()
+}
+
+/// Encode an unsigned 24 bit integer. Values above 0xffffff don't fit and are truncated.
+pub fn write_u24_be(value: u32) -> [u8; 3] {
+    [(value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_test() {
+        // Every value that fits survives a round trip, and the sign only depends on the top bit
+        for value in -0x7fff..=0x7fff {
+            let bytes = write_i16_be(value);
+            assert_eq!(read_i16_be(&bytes), value);
+            assert_eq!(bytes[0] & 0x80 > 0, value < 0);
+        }
+        for value in (-0x7f_ffff..=0x7f_ffff).step_by(97).chain([-0x7f_ffff, -1, 0, 1, 0x7f_ffff]) {
+            assert_eq!(read_i24_be(&write_i24_be(value)), value);
+        }
+        for value in 0..=u16::MAX {
+            assert_eq!(read_u16_be(&write_u16_be(value)), value);
+        }
+        for value in (0..=0xff_ffff).step_by(89).chain([0xff_ffff]) {
+            assert_eq!(read_u24_be(&write_u24_be(value)), value);
+        }
+
+        // Negative zero reads as zero, and every octet pattern decodes to a value that encodes back to the same pattern or its positive zero
+        assert_eq!(read_i16_be(&[0x80, 0]), 0);
+        assert_eq!(read_i24_be(&[0x80, 0, 0]), 0);
+        for bytes in (0..=u16::MAX).map(u16::to_be_bytes) {
+            let expected = if bytes == [0x80, 0] { [0, 0] } else { bytes };
+            assert_eq!(write_i16_be(read_i16_be(&bytes)), expected);
+        }
+
+        // Octets of the first message of the sample file: the longitude of the first grid point, -22.75 degrees, and the binary scale factor of -10
+        assert_eq!(read_i24_be(&[0x80, 0x58, 0xde]), -22750);
+        assert_eq!(read_i16_be(&[0x80, 0x0a]), -10);
+
+        // IBM floats read back what was written wherever the value is representable
+        for value in [0.0, 1.0, -118.625, 0.15625, 273.15, -1.0e-5] {
+            let expected = read_f32_ibm(&write_f32_ibm(value));
+            assert_eq!(write_f32_ibm(expected), write_f32_ibm(value));
+        }
+        assert_eq!(read_f32_ibm(&[0xc2, 0x76, 0xa0, 0x00]), -118.625);
+
+        for exp in -1022..=1023 {
+            assert_eq!(pow2(exp), 2f64.powi(exp), "{}", exp);
+        }
+        for exp in (-1100..=1100).chain([i16::MIN as i32, i16::MAX as i32]) {
+            assert_eq!(pow2(exp) as f32, 2f32.powf(exp as f32), "{}", exp);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Comparison of decoded fields point by point within a tolerance, for testing round-trips, repacking and regridding. Missing
+//! points must be missing in both fields, and the tolerance of messages is widened by the quantisation of their packing.
+
+use crate::Grib;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Why two fields aren't close
+pub enum FieldMismatch {
+    /// The fields have different numbers of points
+    Length { left: usize, right: usize },
+    /// A point is missing in one field only
+    Missing { index: usize, left: f32, right: f32 },
+    /// A point differs by more than allowed
+    Value { index: usize, left: f32, right: f32, allowed: f32 },
+    /// A message has no decoded values
+    NoData,
+}
+
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMismatch::Length { left, right } => write!(f, "The fields have {} and {} points", left, right),
+            FieldMismatch::Missing { index, left, right } => write!(f, "Point {} is missing in one field only: {} and {}", index, left, right),
+            FieldMismatch::Value { index, left, right, allowed } => write!(f, "Point {} differs by {}, more than {}: {} and {}", index, (left - right).abs(), allowed, left, right),
+            FieldMismatch::NoData => write!(f, "A message has no decoded values"),
+        }
+    }
+}
+
+/// Check that the values differ by at most `abs_tol` plus `rel_tol` times the larger magnitude, with NaN as missing.
+/// The first point that doesn't is reported.
+pub fn values_close(left: &[f32], right: &[f32], abs_tol: f32, rel_tol: f32) -> Result<(), FieldMismatch> {
+    if left.len() != right.len() {
+        return Err(FieldMismatch::Length { left: left.len(), right: right.len() });
+    }
+
+    for (index, (a, b)) in left.iter().zip(right).enumerate() {
+        let (a, b) = (*a, *b);
+        match (a.is_nan(), b.is_nan()) {
+            (true, true) => continue,
+            (false, false) => {}
+            _ => return Err(FieldMismatch::Missing { index, left: a, right: b }),
+        }
+
+        let allowed = abs_tol + rel_tol * a.abs().max(b.abs());
+        if (a - b).abs() > allowed {
+            return Err(FieldMismatch::Value { index, left: a, right: b, allowed });
+        }
+    }
+    Ok(())
+}
+
+/// Check that the decoded values of two messages are close as `values_close` does, allowing in addition for the largest error
+/// the packing of each message introduces. Points missing from either message, whatever they are given as, must be missing in both.
+pub fn fields_close(left: &Grib, right: &Grib, abs_tol: f32, rel_tol: f32) -> Result<(), FieldMismatch> {
+    let (Some(left), Some(right)) = (missing_as_nan(left), missing_as_nan(right)) else {
+        return Err(FieldMismatch::NoData);
+    };
+    values_close(&left.0, &right.0, abs_tol + left.1 + right.1, rel_tol)
+}
+
+/// The largest error the packing of the message introduces, half a packing step, or 0 if it has no decoded values
+pub fn quantisation(grib: &Grib) -> f32 {
+    grib.bds.as_ref().map_or(0.0, |bds| {
+        let step = 2f64.powi(bds.binary_scale_factor as i32) / 10f64.powi(grib.pds.decimal_scale_factor as i32);
+        (step / 2.0) as f32
+    })
+}
+
+/// Panic with the first mismatch unless the values are close. See `values_close`.
+#[track_caller]
+pub fn assert_values_close(left: &[f32], right: &[f32], abs_tol: f32, rel_tol: f32) {
+    if let Err(mismatch) = values_close(left, right, abs_tol, rel_tol) {
+        panic!("Values aren't close: {}", mismatch);
+    }
+}
+
+/// Panic with the first mismatch unless the fields are close. See `fields_close`.
+#[track_caller]
+pub fn assert_fields_close(left: &Grib, right: &Grib, abs_tol: f32, rel_tol: f32) {
+    if let Err(mismatch) = fields_close(left, right, abs_tol, rel_tol) {
+        panic!("Fields aren't close: {}", mismatch);
+    }
+}
+
+// The values with missing points as NaN, and the quantisation of the message
+pub(crate) fn missing_as_nan(grib: &Grib) -> Option<(Vec<f32>, f32)> {
+    let bds = grib.bds.as_ref()?;
+    let values = bds.data.iter().map(|value| if bds.is_missing(*value) { f32::NAN } else { *value }).collect();
+    Some((values, quantisation(grib)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::read_file;
+    use crate::writer::repack;
+
+    #[test]
+    fn fields_close_test() -> Result<(), crate::error::Grib1Error> {
+        assert_values_close(&[1.0, f32::NAN, 100.0], &[1.05, f32::NAN, 101.0], 0.1, 0.01);
+        assert_eq!(values_close(&[1.0], &[1.0, 2.0], 0.0, 0.0), Err(FieldMismatch::Length { left: 1, right: 2 }));
+        assert!(matches!(values_close(&[1.0, 2.0], &[1.0, f32::NAN], 1.0, 0.0), Err(FieldMismatch::Missing { index: 1, .. })));
+        assert!(matches!(values_close(&[0.0, 2.0], &[0.0, 2.5], 0.1, 0.1), Err(FieldMismatch::Value { index: 1, .. })));
+
+        // Repacking with fewer bits is only within the coarser quantisation
+        let source = read_file("data/sample.grib")?.remove(0);
+        let repacked = crate::Grib::from_bytes(&repack(&source, 8)?)?;
+        assert_fields_close(&source, &repacked, 1e-4, 0.0);
+        assert!(values_close(&source.bds.as_ref().unwrap().data, &repacked.bds.as_ref().unwrap().data, quantisation(&source), 0.0).is_err());
+        Ok(())
+    }
+}
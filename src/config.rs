@@ -0,0 +1,215 @@
+//! Query specifications that can be loaded from TOML or YAML, so batch extraction jobs can be driven by configuration files.
+//!
+//! ```toml
+//! parameters = [33, 34]
+//! levels = [700, 850]
+//! steps = [0, 6, 12]
+//!
+//! [region]
+//! north = 60.0
+//! south = 50.0
+//! west = 0.0
+//! east = 15.0
+//!
+//! [output]
+//! format = "binary"
+//! path = "subset.grib"
+//! ```
+
+use crate::error::Grib1Error;
+use crate::writer;
+use crate::{BoundingBox, Grib, Grib1Reader, SearchParams, PDS};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Whether the matching messages should be decoded or returned as raw bytes
+pub enum OutputFormat {
+    #[default]
+    Decoded,
+    Binary,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+/// What to do with the matching messages
+pub struct OutputOptions {
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Write the raw matching messages to this file
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+/// A query specification. Every combination of `parameters` and `levels` is searched for, in addition to the explicit `search` entries.
+pub struct QueryConfig {
+    #[serde(default)]
+    pub parameters: Vec<u32>,
+    #[serde(default)]
+    pub levels: Vec<u32>,
+    #[serde(default)]
+    pub search: Vec<SearchParams>,
+    /// Only accept messages with these forecast steps. All steps are accepted if empty.
+    #[serde(default)]
+    pub steps: Vec<u32>,
+    /// Crop the matching messages to this region
+    pub region: Option<BoundingBox>,
+    #[serde(default)]
+    pub output: OutputOptions,
+}
+
+#[derive(Debug)]
+/// The result of running a query
+pub enum QueryOutput {
+    Decoded(Vec<Grib>),
+    Binary(Vec<u8>),
+}
+
+impl QueryConfig {
+    /// Parse a query specification from TOML
+    pub fn from_toml_str(text: &str) -> Result<QueryConfig, Grib1Error> {
+        toml::from_str(text).map_err(|e| Grib1Error::InvalidConfig(e.to_string()))
+    }
+
+    /// Parse a query specification from YAML
+    pub fn from_yaml_str(text: &str) -> Result<QueryConfig, Grib1Error> {
+        serde_yaml::from_str(text).map_err(|e| Grib1Error::InvalidConfig(e.to_string()))
+    }
+
+    /// Load a query specification from a file, choosing the format by the `.toml`, `.yaml` or `.yml` extension
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<QueryConfig, Grib1Error> {
+        let path = path.as_ref();
+        let text = tokio::fs::read_to_string(path).await?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => QueryConfig::from_toml_str(&text),
+            Some("yaml") | Some("yml") => QueryConfig::from_yaml_str(&text),
+            _ => Err(Grib1Error::InvalidConfig(format!("Unknown configuration format for {}", path.display()))),
+        }
+    }
+
+    /// The search parameters described by the query
+    pub fn search_params(&self) -> Vec<SearchParams> {
+        let mut result: Vec<SearchParams> = self.parameters.iter().flat_map(|&param| self.levels.iter().map(move |&level| SearchParams { param, level })).collect();
+        result.extend(self.search.iter().map(|item| SearchParams { param: item.param, level: item.level }));
+        result
+    }
+
+    /// Check whether a message with the given PDS is selected by the query
+    pub fn matches(&self, pds: &PDS) -> bool {
+        let param = pds.indicator_of_parameter_and_units as u32;
+        let level = pds.level_or_layer_value as u32;
+        let searched = (self.parameters.contains(&param) && self.levels.contains(&level)) || self.search.iter().any(|item| item.matches(pds));
+
+        searched && (self.steps.is_empty() || self.steps.contains(&pds.step()))
+    }
+
+    /// Run the query against a reader, writing the raw messages to the output path if one is given.
+    /// If a region is given the messages are cropped to it, and binary output is encoded again using the original number of bits per value.
+    pub async fn run(&self, reader: &mut Grib1Reader) -> Result<QueryOutput, Grib1Error> {
+        let filter = |pds: &PDS| self.matches(pds);
+        let binary = self.output.format == OutputFormat::Binary || self.output.path.is_some();
+
+        // The file is scanned once, the raw bytes of decoded messages being read from where they were found
+        let (decoded, bytes) = match (&self.region, self.output.format) {
+            (None, OutputFormat::Binary) => (None, Some(reader.read_binary_where(filter).await?)),
+            (None, OutputFormat::Decoded) => {
+                let decoded = reader.read_where(filter).await?;
+                let mut bytes = None;
+                if self.output.path.is_some() {
+                    let mut raw = vec![];
+                    for grib in &decoded {
+                        raw.extend(reader.read_raw(grib).await?);
+                    }
+                    bytes = Some(raw);
+                }
+                (Some(decoded), bytes)
+            }
+            (Some(region), _) => {
+                let decoded = reader.read_where(filter).await?.iter().map(|grib| grib.crop(region)).collect::<Result<Vec<_>, _>>()?;
+                let bytes = if binary { Some(encode_all(&decoded)?) } else { None };
+                (Some(decoded), bytes)
+            }
+        };
+
+        if let (Some(path), Some(bytes)) = (&self.output.path, &bytes) {
+            tokio::fs::write(path, bytes).await?;
+        }
+
+        match (self.output.format, decoded, bytes) {
+            (OutputFormat::Binary, _, Some(bytes)) => Ok(QueryOutput::Binary(bytes)),
+            (_, decoded, _) => Ok(QueryOutput::Decoded(decoded.unwrap_or_default())),
+        }
+    }
+}
+
+fn encode_all(messages: &[Grib]) -> Result<Vec<u8>, Grib1Error> {
+    let mut result = vec![];
+    for grib in messages {
+        let bits_per_value = grib.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?.bits_per_value;
+        result.extend(writer::repack(grib, bits_per_value)?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_test() -> Result<(), Grib1Error> {
+        let toml = QueryConfig::from_toml_str("parameters = [33, 34]\nlevels = [700]\nsteps = [1]\n[output]\nformat = \"binary\"\n")?;
+        let yaml = QueryConfig::from_yaml_str("parameters: [33, 34]\nlevels: [700]\nsteps: [1]\noutput:\n  format: binary\n")?;
+
+        for config in [toml, yaml] {
+            assert_eq!(config.search_params().len(), 2);
+            assert_eq!(config.output.format, OutputFormat::Binary);
+            assert_eq!(config.steps, vec![1]);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_test() -> Result<(), Grib1Error> {
+        let sample = std::fs::read("data/sample.grib")?;
+        let path = std::env::temp_dir().join("grib1_reader_config_run_test.grb");
+        let mut config = QueryConfig {
+            parameters: vec![34],
+            levels: vec![700],
+            output: OutputOptions {
+                format: OutputFormat::Decoded,
+                path: Some(path.clone()),
+            },
+            ..Default::default()
+        };
+
+        // Decoded messages are written to the path as they are in the file, from a single scan
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+        let QueryOutput::Decoded(decoded) = config.run(&mut reader).await? else { panic!("the output should be decoded") };
+        assert_eq!((decoded.len(), decoded[0].offset), (1, 2542704));
+        assert_eq!(std::fs::read(&path)?, sample[2542704..]);
+        assert_eq!(reader.metrics().messages_scanned, 2);
+
+        // Cropped messages are encoded again
+        config.region = Some(BoundingBox {
+            north: 58.0,
+            south: 54.0,
+            west: 8.0,
+            east: 13.0,
+        });
+        config.output.format = OutputFormat::Binary;
+        let QueryOutput::Binary(bytes) = config.run(&mut reader).await? else { panic!("the output should be binary") };
+        let cropped = Grib::from_bytes(&bytes)?;
+        assert!(cropped.bds.unwrap().data.len() < 1271041 / 10);
+        assert_eq!(std::fs::read(&path)?, bytes);
+
+        // Steps narrow down the messages
+        config.steps = vec![0];
+        config.output.path = None;
+        assert!(matches!(config.run(&mut reader).await?, QueryOutput::Binary(bytes) if bytes.is_empty()));
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
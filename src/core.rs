@@ -0,0 +1,1003 @@
+//! The sections of a message and their parsers, working on slices and needing nothing beyond `alloc`. Built without the `std` feature
+//! this is all the crate holds, so messages received by other means, e.g. over a radio link, can be decoded on targets without an operating system.
+//!
+//! The types are re-exported at the root of the crate, where the rest of the library adds to them.
+
+use crate::codec::{pow2, read_f32_ibm, read_i16_be, read_i24_be, read_u16_be, read_u24_be};
+use ::core::fmt;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// The errors of each section have the same shape, but are types of their own so they can be told apart when matching
+macro_rules! section_error {
+    ($name:ident, $section:literal) => {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[doc = concat!("A problem parsing the ", $section, ", with the octet of the section it was found at")]
+        pub enum $name {
+            /// The section is too short to hold the fields that are read from it
+            TooShort { needed: usize, got: usize },
+
+            /// A field has a value that can't be handled. The octet is counted from 1 at the start of the section, as in the WMO documentation.
+            InvalidField { octet: usize, field: &'static str, reason: String },
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $name::TooShort { needed, got } => write!(f, "The {} is {} octets, but at least {} are needed", $section, got, needed),
+                    $name::InvalidField { octet, field, reason } => write!(f, "Invalid {} at octet {} of the {}: {}", field, octet, $section, reason),
+                }
+            }
+        }
+
+        #[cfg(feature = "std")]
+        impl std::error::Error for $name {}
+    };
+}
+
+section_error!(PdsError, "PDS");
+section_error!(GdsError, "GDS");
+section_error!(BmsError, "BMS");
+section_error!(BdsError, "BDS");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The errors of the parsers, each of which has a `Grib1Error` variant of the same name it is converted into
+pub enum ParseError {
+    Pds(PdsError),
+    Gds(GdsError),
+    Bms(BmsError),
+    Bds(BdsError),
+    MalformedMessage(String),
+    ValueCountMismatch { expected: usize, actual: usize },
+    DataDecodeFailed { expected_points: usize, decoded: usize },
+    ResourceLimitExceeded { what: &'static str, requested: u64, limit: u64 },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Pds(error) => error.fmt(f),
+            ParseError::Gds(error) => error.fmt(f),
+            ParseError::Bms(error) => error.fmt(f),
+            ParseError::Bds(error) => error.fmt(f),
+            ParseError::MalformedMessage(reason) => write!(f, "Malformed message: {}", reason),
+            ParseError::ValueCountMismatch { expected, actual } => write!(f, "Expected {} values but got {}", expected, actual),
+            ParseError::DataDecodeFailed { expected_points, decoded } => write!(f, "Tried to decode more data than we have, got {} of {} values", decoded, expected_points),
+            ParseError::ResourceLimitExceeded { what, requested, limit } => write!(f, "The {} needs {}, more than the limit of {}", what, requested, limit),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl From<PdsError> for ParseError {
+    fn from(error: PdsError) -> ParseError {
+        ParseError::Pds(error)
+    }
+}
+
+impl From<GdsError> for ParseError {
+    fn from(error: GdsError) -> ParseError {
+        ParseError::Gds(error)
+    }
+}
+
+impl From<BmsError> for ParseError {
+    fn from(error: BmsError) -> ParseError {
+        ParseError::Bms(error)
+    }
+}
+
+impl From<BdsError> for ParseError {
+    fn from(error: BdsError) -> ParseError {
+        ParseError::Bds(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Rotated latitude/longitude
+pub struct RotatedLatLon {
+    /// Number of points along a parallel (Ni)
+    pub number_of_lat_values: u16,
+    /// Number of points along a meridian (Nj)
+    pub number_of_lon_values: u16,
+    pub latitude_of_first_grid_point: f32,
+    pub longitude_of_first_grid_point: f32,
+    pub latitude_of_last_grid_point: f32,
+    pub longitude_of_last_grid_point: f32,
+    pub resolution_and_component_flags: u8,
+    /// Increment in millidegrees, all ones if not given
+    pub i_direction_increment: u16,
+    /// Increment in millidegrees, all ones if not given
+    pub j_direction_increment: u16,
+    pub scanning_mode: u8,
+    pub latitude_of_southern_pole: f32,
+    pub longitude_of_southern_pole: f32,
+    pub angle_of_rotation: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Regular latitude/longitude
+pub struct RegularLatLon {
+    /// Number of points along a parallel (Ni)
+    pub number_of_lat_values: u16,
+    /// Number of points along a meridian (Nj)
+    pub number_of_lon_values: u16,
+    pub latitude_of_first_grid_point: f32,
+    pub longitude_of_first_grid_point: f32,
+    pub latitude_of_last_grid_point: f32,
+    pub longitude_of_last_grid_point: f32,
+    pub resolution_and_component_flags: u8,
+    /// Increment in millidegrees, all ones if not given
+    pub i_direction_increment: u16,
+    /// Increment in millidegrees, all ones if not given
+    pub j_direction_increment: u16,
+    pub scanning_mode: u8,
+}
+
+impl From<RegularLatLon> for RotatedLatLon {
+    /// A regular grid is a rotated grid with the southern pole in its usual place
+    fn from(grid: RegularLatLon) -> RotatedLatLon {
+        RotatedLatLon {
+            number_of_lat_values: grid.number_of_lat_values,
+            number_of_lon_values: grid.number_of_lon_values,
+            latitude_of_first_grid_point: grid.latitude_of_first_grid_point,
+            longitude_of_first_grid_point: grid.longitude_of_first_grid_point,
+            latitude_of_last_grid_point: grid.latitude_of_last_grid_point,
+            longitude_of_last_grid_point: grid.longitude_of_last_grid_point,
+            resolution_and_component_flags: grid.resolution_and_component_flags,
+            i_direction_increment: grid.i_direction_increment,
+            j_direction_increment: grid.j_direction_increment,
+            scanning_mode: grid.scanning_mode,
+            latitude_of_southern_pole: -90.0,
+            longitude_of_southern_pole: 0.0,
+            angle_of_rotation: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A grid on a conic projection, laid out the same way for Albers equal-area and oblique Lambert conformal grids
+pub struct ConicProjection {
+    /// Number of points along the x-axis (Nx)
+    pub number_of_x_values: u16,
+    /// Number of points along the y-axis (Ny)
+    pub number_of_y_values: u16,
+    pub latitude_of_first_grid_point: f32,
+    pub longitude_of_first_grid_point: f32,
+    pub resolution_and_component_flags: u8,
+    /// The meridian parallel to the y-axis (LoV)
+    pub orientation_of_the_grid: f32,
+    /// Grid length along the x-axis in metres
+    pub x_direction_grid_length: u32,
+    /// Grid length along the y-axis in metres
+    pub y_direction_grid_length: u32,
+    /// Bit 1 set if the south pole is on the projection plane, bit 2 set if the projection is bipolar
+    pub projection_centre_flag: u8,
+    pub scanning_mode: u8,
+    /// The first latitude at which the cone cuts the sphere, closest to the pole
+    pub latin_1: f32,
+    /// The second latitude at which the cone cuts the sphere, equal to `latin_1` for a tangent cone
+    pub latin_2: f32,
+    pub latitude_of_southern_pole: f32,
+    pub longitude_of_southern_pole: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// List of data types the library supports (is able to decode)
+pub enum DataRepresentation {
+    Unhandled,
+    RegularLatLon(RegularLatLon),
+    RotatedLatLon(RotatedLatLon),
+    /// Albers equal-area, GDS type 8
+    AlbersEqualArea(ConicProjection),
+    /// Oblique Lambert conformal, GDS type 13
+    ObliqueLambert(ConicProjection),
+}
+
+#[derive(Debug, Clone)]
+/// Grid description section
+pub struct GDS {
+    pub number_of_vertical_coordinate_values: u8,
+    pub pvl_location: u8,
+    pub data_representation_type: u8,
+    pub data: DataRepresentation,
+    /// The vertical coordinate parameters, e.g. the coefficients of hybrid levels. Empty if there are none.
+    pub vertical_coordinates: Vec<f32>,
+}
+
+impl GDS {
+    /// Number of points in the grid, or 0 if the grid type isn't handled
+    pub fn number_of_points(&self) -> usize {
+        self.dimensions().map_or(0, |(ni, nj, _)| ni * nj)
+    }
+
+    /// Number of consecutive values forming a row of the grid, or a column when scanning along columns
+    pub fn row_length(&self) -> Option<usize> {
+        let (ni, nj, scanning_mode) = self.dimensions()?;
+        Some(if scanning_mode & 0x20 > 0 { nj } else { ni })
+    }
+
+    // Number of points along the i and j axes, and the scanning mode
+    fn dimensions(&self) -> Option<(usize, usize, u8)> {
+        match self.data {
+            DataRepresentation::RegularLatLon(grid) => Some((grid.number_of_lat_values as usize, grid.number_of_lon_values as usize, grid.scanning_mode)),
+            DataRepresentation::RotatedLatLon(grid) => Some((grid.number_of_lat_values as usize, grid.number_of_lon_values as usize, grid.scanning_mode)),
+            DataRepresentation::AlbersEqualArea(grid) | DataRepresentation::ObliqueLambert(grid) => Some((grid.number_of_x_values as usize, grid.number_of_y_values as usize, grid.scanning_mode)),
+            DataRepresentation::Unhandled => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Product definition section
+pub struct PDS {
+    pub parameter_table_version_number: u8,
+    pub identification_of_center: u8,
+    pub generating_process_id_number: u8,
+    pub grid_identification: u8,
+    pub flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms: u8,
+    pub indicator_of_parameter_and_units: u8,
+    pub indicator_of_type_of_level_or_layer: u8,
+    pub level_or_layer_value: u16,
+    pub year: u8,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub forecast_time_unit: u8,
+    pub p1_period_of_time: u8,
+    pub p2_period_of_time: u8,
+    pub time_range_indicator: u8,
+    pub number_included_in_average: u16,
+    pub number_missing_from_averages_or_accumulations: u8,
+    pub century_of_initial_reference_time: u8,
+    pub identification_of_sub_center: u8,
+    pub decimal_scale_factor: i16,
+    /// The octets after the first 28, from octet 29 on, holding the local definitions of some centres. Empty if there are none.
+    pub local_extension: Vec<u8>,
+}
+
+impl PDS {
+    pub fn has_gds(&self) -> bool {
+        self.flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms & 128 > 0
+    }
+
+    pub fn has_bmp(&self) -> bool {
+        self.flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms & 64 > 0
+    }
+
+    /// The forecast step in units of `forecast_time_unit`. For accumulations and averages this is the end of the period.
+    pub fn step(&self) -> u32 {
+        match self.time_range_indicator {
+            2..=5 => self.p2_period_of_time as u32,
+            // P1 occupies both octets
+            10 => ((self.p1_period_of_time as u32) << 8) + self.p2_period_of_time as u32,
+            _ => self.p1_period_of_time as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+///Bit-map section
+pub struct Bitmap {
+    pub number_of_unused_bits_at_end_of_section3: u8,
+    /// 0 when the bits are included, otherwise the number of a bit-map predefined by the center
+    pub table_reference: u16,
+    /// The bits following the header, one per grid point with the most significant bit first. Empty for predefined bit-maps.
+    pub bits: Vec<u8>,
+    /// Number of bits used, excluding the unused ones at the end
+    pub number_of_bits: usize,
+}
+
+impl Bitmap {
+    /// Whether the point has a value. Points beyond the end of the bit-map are reported as missing.
+    pub fn is_present(&self, index: usize) -> bool {
+        index < self.number_of_bits && self.bits[index / 8] & (0x80 >> (index % 8)) > 0
+    }
+
+    /// Number of points with a value
+    pub fn count_present(&self) -> usize {
+        (0..self.number_of_bits).filter(|i| self.is_present(*i)).count()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Binary data section
+pub struct BDS {
+    pub data_flag: u8,
+    pub binary_scale_factor: i16,
+    /// The decimal scale factor the values were divided by, which is 0 if the reader has `decimal_scaling` turned off
+    pub decimal_scale_factor: i16,
+    pub reference_value: f32,
+    pub bits_per_value: u8,
+    pub data: Vec<f32>,
+    /// What the missing points in `data` are given as
+    pub missing_values: MissingValues,
+    /// How the values are packed, from the flags of octets 4 and 14
+    pub packing: PackingInfo,
+    /// The dimensions of the matrix of values at each point, if there is one rather than a single value
+    pub matrix: Option<MatrixValues>,
+}
+
+impl BDS {
+    /// Number of values in `data` for each point of the grid. With a matrix at each point the values of a point follow each other,
+    /// the second dimension varying fastest, so value `(i, j)` of point `p` is `data[(p * first_dimension + i) * second_dimension + j]`.
+    pub fn values_per_point(&self) -> usize {
+        self.matrix.as_ref().map_or(1, |matrix| matrix.first_dimension as usize * matrix.second_dimension as usize)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The matrix of values at each point of a BDS, e.g. the directions and frequencies of a wave spectrum
+pub struct MatrixValues {
+    /// Number of values along the first dimension (N1)
+    pub first_dimension: u16,
+    /// Number of values along the second dimension (N2)
+    pub second_dimension: u16,
+    /// Code of how the coefficients give the coordinates of the first dimension
+    pub first_distribution: u8,
+    pub second_distribution: u8,
+    /// Code of the physical meaning of the first dimension, e.g. direction or frequency
+    pub first_significance: u8,
+    pub second_significance: u8,
+    pub first_coefficients: Vec<f32>,
+    pub second_coefficients: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The kind of packing given by the flags of octet 4 of the BDS
+pub enum PackingKind {
+    #[default]
+    Simple,
+    /// Second-order packing of grid point values
+    SecondOrder,
+    SphericalHarmonicsSimple,
+    SphericalHarmonicsComplex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The packing of a BDS, with the extended flags of octet 14 when octet 4 says they are there. Only simple packing, of single values or
+/// matrices, and second-order packing with one group per row and without a bit-map or any of the extended features are decoded, so the others can be
+/// told apart before decoding.
+pub struct PackingInfo {
+    pub kind: PackingKind,
+    /// The packed values are integers rather than floating point
+    pub integer_values: bool,
+    /// Each point holds a matrix of values rather than a single one
+    pub matrix_values: bool,
+    /// Secondary bit-maps are present
+    pub secondary_bitmaps: bool,
+    /// The second-order values of each group have a width of their own rather than one for all
+    pub different_widths: bool,
+    /// General extended second-order packing is used
+    pub general_extended: bool,
+    /// Rows of second-order packing alternate direction
+    pub boustrophedonic: bool,
+}
+
+impl PackingInfo {
+    /// The packing of a raw BDS, with no extended flags if the section is too short to hold them
+    pub fn of(bds: &[u8]) -> PackingInfo {
+        let flag = bds.get(3).copied().unwrap_or(0);
+        let extended = if flag & 0x10 > 0 { bds.get(13).copied().unwrap_or(0) } else { 0 };
+        PackingInfo {
+            kind: match flag & 0xc0 {
+                0x00 => PackingKind::Simple,
+                0x40 => PackingKind::SecondOrder,
+                0x80 => PackingKind::SphericalHarmonicsSimple,
+                _ => PackingKind::SphericalHarmonicsComplex,
+            },
+            integer_values: flag & 0x20 > 0,
+            matrix_values: extended & 0x40 > 0,
+            secondary_bitmaps: extended & 0x20 > 0,
+            different_widths: extended & 0x10 > 0,
+            general_extended: extended & 0x08 > 0,
+            boustrophedonic: extended & 0x04 > 0,
+        }
+    }
+
+    /// Whether values packed this way can be decoded by the crate, leaving aside that second-order packing can't be combined with a bit-map
+    pub fn is_supported(&self) -> bool {
+        match self.kind {
+            PackingKind::Simple => self.matrix_values || !self.secondary_bitmaps,
+            PackingKind::SecondOrder => !self.matrix_values && !self.secondary_bitmaps && !self.general_extended && !self.boustrophedonic,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// How points missing from the bit-map are given in `BDS::data`, set with `Grib1Reader::missing_values`
+pub enum MissingValues {
+    /// `f32::NAN`, which the statistics, sampling and arithmetic of the library leave out
+    #[default]
+    Nan,
+    /// The value given, which the rest of the library treats as any other value
+    Sentinel(f32),
+}
+
+/// Get the section starting at `offset` of a message and move the offset past it
+pub fn next_section<'a>(bytes: &'a [u8], offset: &mut usize) -> Result<&'a [u8], ParseError> {
+    let start = *offset;
+    if start + 3 > bytes.len() {
+        return Err(ParseError::MalformedMessage(format!("Message ends before the section at offset {}", start)));
+    }
+
+    let end = start + read_u24_be(&bytes[start..]) as usize;
+    if end > bytes.len() {
+        return Err(ParseError::MalformedMessage(format!("Section at offset {} extends past the end of the message", start)));
+    }
+
+    *offset = end;
+    Ok(&bytes[start..end])
+}
+
+fn check_limit(what: &'static str, requested: u64, limit: u64) -> Result<(), ParseError> {
+    if requested > limit {
+        return Err(ParseError::ResourceLimitExceeded { what, requested, limit });
+    }
+    Ok(())
+}
+
+// Make sure a section holds the octets its parser reads
+fn check_length(section: &'static str, buffer: &[u8], needed: usize) -> Result<(), ParseError> {
+    if buffer.len() >= needed {
+        return Ok(());
+    }
+
+    let got = buffer.len();
+    Err(match section {
+        "PDS" => PdsError::TooShort { needed, got }.into(),
+        "GDS" => GdsError::TooShort { needed, got }.into(),
+        "BMS" => BmsError::TooShort { needed, got }.into(),
+        _ => BdsError::TooShort { needed, got }.into(),
+    })
+}
+
+/// Parse a PDS as laid out in edition 1
+pub fn parse_pds(buffer: &[u8]) -> Result<PDS, ParseError> {
+    check_length("PDS", buffer, 28)?;
+
+    Ok(PDS {
+        parameter_table_version_number: buffer[3],
+        identification_of_center: buffer[4],
+        generating_process_id_number: buffer[5],
+        grid_identification: buffer[6],
+        flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms: buffer[7],
+        indicator_of_parameter_and_units: buffer[8],
+        indicator_of_type_of_level_or_layer: buffer[9],
+        level_or_layer_value: read_u16_be(&buffer[10..]),
+        year: buffer[12],
+        month: buffer[13],
+        day: buffer[14],
+        hour: buffer[15],
+        minute: buffer[16],
+        forecast_time_unit: buffer[17],
+        p1_period_of_time: buffer[18],
+        p2_period_of_time: buffer[19],
+        time_range_indicator: buffer[20],
+        number_included_in_average: read_u16_be(&buffer[21..]),
+        number_missing_from_averages_or_accumulations: buffer[23],
+        century_of_initial_reference_time: buffer[24],
+        identification_of_sub_center: buffer[25],
+        decimal_scale_factor: read_i16_be(&buffer[26..]),
+        local_extension: buffer[28..].to_vec(),
+    })
+}
+
+/// Parse a GDS. Grids of other types than those of `DataRepresentation` are given as `Unhandled`.
+pub fn parse_gds(buffer: &[u8]) -> Result<GDS, ParseError> {
+    check_length("GDS", buffer, 6)?;
+    let data_representation_type = buffer[5];
+
+    let mut data = DataRepresentation::Unhandled;
+    if data_representation_type == 0 || data_representation_type == 10 {
+        check_length("GDS", buffer, if data_representation_type == 10 { 42 } else { 28 })?;
+        let grid = RegularLatLon {
+            number_of_lat_values: read_u16_be(&buffer[6..]),
+            number_of_lon_values: read_u16_be(&buffer[8..]),
+            latitude_of_first_grid_point: read_i24_be(&buffer[10..]) as f32 * 0.001,
+            longitude_of_first_grid_point: read_i24_be(&buffer[13..]) as f32 * 0.001,
+            latitude_of_last_grid_point: read_i24_be(&buffer[17..]) as f32 * 0.001,
+            longitude_of_last_grid_point: read_i24_be(&buffer[20..]) as f32 * 0.001,
+            resolution_and_component_flags: buffer[16],
+            i_direction_increment: read_u16_be(&buffer[23..]),
+            j_direction_increment: read_u16_be(&buffer[25..]),
+            scanning_mode: buffer[27],
+        };
+
+        data = if data_representation_type == 10 {
+            DataRepresentation::RotatedLatLon(RotatedLatLon {
+                latitude_of_southern_pole: read_i24_be(&buffer[32..]) as f32 * 0.001,
+                longitude_of_southern_pole: read_i24_be(&buffer[35..]) as f32 * 0.001,
+                angle_of_rotation: read_f32_ibm(&buffer[38..]),
+                ..grid.into()
+            })
+        } else {
+            DataRepresentation::RegularLatLon(grid)
+        };
+    } else if data_representation_type == 8 || data_representation_type == 13 {
+        check_length("GDS", buffer, 40)?;
+        let grid = ConicProjection {
+            number_of_x_values: read_u16_be(&buffer[6..]),
+            number_of_y_values: read_u16_be(&buffer[8..]),
+            latitude_of_first_grid_point: read_i24_be(&buffer[10..]) as f32 * 0.001,
+            longitude_of_first_grid_point: read_i24_be(&buffer[13..]) as f32 * 0.001,
+            resolution_and_component_flags: buffer[16],
+            orientation_of_the_grid: read_i24_be(&buffer[17..]) as f32 * 0.001,
+            x_direction_grid_length: read_u24_be(&buffer[20..]),
+            y_direction_grid_length: read_u24_be(&buffer[23..]),
+            projection_centre_flag: buffer[26],
+            scanning_mode: buffer[27],
+            latin_1: read_i24_be(&buffer[28..]) as f32 * 0.001,
+            latin_2: read_i24_be(&buffer[31..]) as f32 * 0.001,
+            latitude_of_southern_pole: read_i24_be(&buffer[34..]) as f32 * 0.001,
+            longitude_of_southern_pole: read_i24_be(&buffer[37..]) as f32 * 0.001,
+        };
+
+        data = if data_representation_type == 8 {
+            DataRepresentation::AlbersEqualArea(grid)
+        } else {
+            DataRepresentation::ObliqueLambert(grid)
+        };
+    }
+
+    // The parameters are IBM floats from the octet given by the location, which is 255 if there are none
+    let (count, location) = (buffer[3] as usize, buffer[4] as usize);
+    let vertical_coordinates = match buffer.get(location.saturating_sub(1)..(location.saturating_sub(1) + count * 4)) {
+        Some(octets) if count > 0 && (1..255).contains(&location) => octets.chunks_exact(4).map(read_f32_ibm).collect(),
+        _ => vec![],
+    };
+
+    Ok(GDS {
+        number_of_vertical_coordinate_values: buffer[3],
+        pvl_location: buffer[4],
+        data_representation_type: buffer[5],
+        data,
+        vertical_coordinates,
+    })
+}
+
+/// Parse a bit-map section, keeping its bits as they are
+pub fn parse_bitmap(buffer: &[u8]) -> Result<Bitmap, ParseError> {
+    check_length("BMS", buffer, 6)?;
+
+    let bits = buffer[6..].to_vec();
+    Ok(Bitmap {
+        number_of_unused_bits_at_end_of_section3: buffer[3],
+        table_reference: read_u16_be(&buffer[4..]),
+        number_of_bits: (bits.len() * 8).saturating_sub(buffer[3] as usize),
+        bits,
+    })
+}
+
+/// Decode the BDS, placing the values at the points marked as present in the bit-map if there is one. Missing points become NaN.
+/// Grids of more than `max_points` points aren't decoded.
+pub fn decode_data(bds: &[u8], bms: Option<&[u8]>, gds: Option<&GDS>, decimal_scale_factor: i16, max_points: usize) -> Result<BDS, ParseError> {
+    let number_of_data_points = number_of_data_points(bds, bms, gds);
+    check_limit("grid", number_of_data_points as u64, max_points as u64)?;
+    // Decoding a packing that isn't supported as simple packing would give values that look right but aren't
+    let packing = PackingInfo::of(bds);
+    if !packing.is_supported() || (packing.kind == PackingKind::SecondOrder && bms.is_some()) {
+        return Err(BdsError::InvalidField {
+            octet: 4,
+            field: "data_flag",
+            reason: format!("{:?} isn't supported", packing),
+        }
+        .into());
+    }
+    if packing.matrix_values {
+        let present = match bms {
+            Some(bms) => present_points(bms, number_of_data_points)?,
+            None => vec![true; number_of_data_points],
+        };
+        return parse_matrix_bds(bds, &present, decimal_scale_factor);
+    }
+    let bms = match bms {
+        Some(bms) => bms,
+        None if bds.len() > 3 && bds[3] & 0x40 > 0 => {
+            let row_length = gds.and_then(|gds| gds.row_length()).ok_or(ParseError::DataDecodeFailed {
+                expected_points: number_of_data_points,
+                decoded: 0,
+            })?;
+            return parse_second_order_bds(bds, number_of_data_points, row_length, decimal_scale_factor);
+        }
+        None => return parse_bds(bds, number_of_data_points, decimal_scale_factor),
+    };
+
+    let present = present_points(bms, number_of_data_points)?;
+    let mut result = parse_bds(bds, present.iter().filter(|p| **p).count(), decimal_scale_factor)?;
+    let mut values = result.data.into_iter();
+    result.data = present.iter().map(|p| if *p { values.next().unwrap_or(f32::NAN) } else { f32::NAN }).collect();
+
+    Ok(result)
+}
+
+// The number of points of the grid, including those missing from the bit-map
+pub(crate) fn number_of_data_points(bds: &[u8], bms: Option<&[u8]>, gds: Option<&GDS>) -> usize {
+    match gds {
+        Some(gds) => gds.number_of_points(),
+        // Without a GDS the number of points follows from the length of the packed data, or of the bit-map if there is one
+        None if bds.len() > 10 && bds[10] > 0 && bms.is_none() => ((bds.len() - 11) * 8).saturating_sub((bds[3] & 0x0f) as usize) / bds[10] as usize,
+        None => bms.map(|bms| (bms.len().saturating_sub(6) * 8).saturating_sub(bms[3] as usize)).unwrap_or(0),
+    }
+}
+
+// Whether each point of the grid is marked as present in the bit-map
+pub(crate) fn present_points(bms: &[u8], number_of_data_points: usize) -> Result<Vec<bool>, ParseError> {
+    check_length("BMS", bms, 6)?;
+    if read_u16_be(&bms[4..]) != 0 {
+        return Err(BmsError::InvalidField {
+            octet: 5,
+            field: "table_reference",
+            reason: "only bit-maps included in the message are supported".to_string(),
+        }
+        .into());
+    }
+    if (bms.len() - 6) * 8 < number_of_data_points {
+        return Err(BmsError::TooShort {
+            needed: 6 + number_of_data_points.div_ceil(8),
+            got: bms.len(),
+        }
+        .into());
+    }
+    Ok((0..number_of_data_points).map(|i| bms[6 + i / 8] & (0x80 >> (i % 8)) > 0).collect())
+}
+
+// Decode simple packing of a matrix of values at each point. The secondary bit-maps, if any, follow the coefficients of the dimensions,
+// one of N1 x N2 bits for each point present in the primary bit-map, and mark which values of its matrix are packed.
+fn parse_matrix_bds(buffer: &[u8], present: &[bool], decimal_scale_factor: i16) -> Result<BDS, ParseError> {
+    check_length("BDS", buffer, 26)?;
+    let packing = PackingInfo::of(buffer);
+    if packing.kind != PackingKind::Simple {
+        return Err(BdsError::InvalidField {
+            octet: 4,
+            field: "data_flag",
+            reason: "only simple packing of matrix values is supported".to_string(),
+        }
+        .into());
+    }
+
+    let (nc1, nc2) = (buffer[21] as usize, buffer[23] as usize);
+    let coefficients = |start: usize, count: usize| (0..count).map(|i| read_f32_ibm(&buffer[start + i * 4..])).collect::<Vec<f32>>();
+    let bitmaps_start = 26 + (nc1 + nc2) * 4;
+    check_length("BDS", buffer, bitmaps_start)?;
+    let matrix = MatrixValues {
+        first_dimension: read_u16_be(&buffer[16..]),
+        second_dimension: read_u16_be(&buffer[18..]),
+        first_distribution: buffer[20],
+        second_distribution: buffer[22],
+        first_significance: buffer[24],
+        second_significance: buffer[25],
+        first_coefficients: coefficients(26, nc1),
+        second_coefficients: coefficients(26 + nc1 * 4, nc2),
+    };
+
+    let values_per_point = matrix.first_dimension as usize * matrix.second_dimension as usize;
+    let points_present = present.iter().filter(|p| **p).count();
+    let data_start = read_u16_be(&buffer[11..]) as usize;
+    if data_start <= bitmaps_start || data_start > buffer.len() + 1 {
+        return Err(BdsError::InvalidField {
+            octet: 12,
+            field: "data_offset",
+            reason: format!("the packed data can't start at octet {}", data_start),
+        }
+        .into());
+    }
+
+    // Which of the values of the present points are packed
+    let packed: Vec<bool> = if packing.secondary_bitmaps {
+        let bitmaps = &buffer[bitmaps_start..data_start - 1];
+        if bitmaps.len() * 8 < points_present * values_per_point {
+            return Err(BdsError::TooShort {
+                needed: bitmaps_start + (points_present * values_per_point).div_ceil(8),
+                got: data_start - 1,
+            }
+            .into());
+        }
+        (0..points_present * values_per_point).map(|i| bitmaps[i / 8] & (0x80 >> (i % 8)) > 0).collect()
+    } else {
+        vec![true; points_present * values_per_point]
+    };
+
+    let binary_scale = read_i16_be(&buffer[4..]);
+    let ref_value = read_f32_ibm(&buffer[6..]);
+    let bit_count = buffer[10];
+    let count = packed.iter().filter(|p| **p).count();
+    let values = unpack_simple(&buffer[data_start - 1..], (buffer[3] & 0x0f) as usize, bit_count, count, ref_value, binary_scale, decimal_scale_factor)?;
+
+    let mut values = values.into_iter();
+    let mut packed = packed.into_iter();
+    let mut data = Vec::with_capacity(present.len() * values_per_point);
+    for p in present {
+        for _ in 0..values_per_point {
+            let value = if *p && packed.next().unwrap_or(false) { values.next() } else { None };
+            data.push(value.unwrap_or(f32::NAN));
+        }
+    }
+
+    Ok(BDS {
+        data_flag: buffer[3],
+        binary_scale_factor: binary_scale,
+        decimal_scale_factor,
+        reference_value: ref_value,
+        bits_per_value: bit_count,
+        data,
+        missing_values: MissingValues::Nan,
+        packing,
+        matrix: Some(matrix),
+    })
+}
+
+// Decode second-order packing with one group per row, as written by the writer: the first-order value of each row plus the second-order values of the row
+fn parse_second_order_bds(buffer: &[u8], number_of_data_points: usize, row_length: usize, decimal_scale_factor: i16) -> Result<BDS, ParseError> {
+    // Other kinds of complex packing (spherical harmonics, matrices, secondary bit-maps, general extended packing) aren't supported
+    let failed = |decoded| ParseError::DataDecodeFailed {
+        expected_points: number_of_data_points,
+        decoded,
+    };
+    if buffer.len() < 22 || buffer[3] & 0x90 != 0x10 || buffer[13] & 0x6c != 0 || row_length == 0 {
+        return Err(failed(0));
+    }
+
+    let binary_scale = read_i16_be(&buffer[4..]);
+    let ref_value = read_f32_ibm(&buffer[6..]);
+    let bit_count = buffer[10];
+    let n1 = read_u16_be(&buffer[11..]) as usize;
+    let different_widths = buffer[13] & 0x10 > 0;
+    let n2 = read_u16_be(&buffer[14..]) as usize;
+    let number_of_rows = read_u16_be(&buffer[16..]) as usize;
+    if number_of_rows != number_of_data_points.div_ceil(row_length) || n1 < 22 || n2 < n1 || n2 > buffer.len() + 1 {
+        return Err(failed(0));
+    }
+
+    let widths = if different_widths { buffer.get(21..21 + number_of_rows) } else { buffer.get(21..22) }.ok_or(failed(0))?;
+    check_width(11, "bits_per_value", bit_count)?;
+    for (i, width) in widths.iter().enumerate() {
+        check_width(22 + i, "second_order_width", *width)?;
+    }
+    let factor = pow2(binary_scale as i32) as f32;
+    let decimal_factor = pow10(-(decimal_scale_factor as i32));
+
+    let mut first_order = BitReader::new(&buffer[n1 - 1..]);
+    let mut second_order = BitReader::new(&buffer[n2 - 1..]);
+    let mut result = Vec::with_capacity(number_of_data_points);
+    for row in 0..number_of_rows {
+        let min = first_order.read(bit_count as u32).ok_or(failed(result.len()))?;
+        let width = if different_widths { widths[row] } else { widths[0] };
+        let count = row_length.min(number_of_data_points - row * row_length);
+
+        for _ in 0..count {
+            let x = second_order.read(width as u32).ok_or(failed(result.len()))?;
+            result.push((ref_value + ((min as u64 + x as u64) as f32) * factor) * decimal_factor);
+        }
+    }
+
+    Ok(BDS {
+        data_flag: buffer[3],
+        binary_scale_factor: binary_scale,
+        decimal_scale_factor,
+        reference_value: ref_value,
+        bits_per_value: bit_count,
+        data: result,
+        missing_values: MissingValues::Nan,
+        packing: PackingInfo::of(buffer),
+        matrix: None,
+    })
+}
+
+/// Unpack the values of a BDS using simple packing, without placing them on the grid
+pub fn parse_bds(buffer: &[u8], number_of_data_points: usize, decimal_scale_factor: i16) -> Result<BDS, ParseError> {
+    check_length("BDS", buffer, 11)?;
+    let binary_scale = read_i16_be(&buffer[4..]);
+    let ref_value = read_f32_ibm(&buffer[6..]);
+    let bit_count = buffer[10];
+    let result = unpack_simple(&buffer[11..], (buffer[3] & 0x0f) as usize, bit_count, number_of_data_points, ref_value, binary_scale, decimal_scale_factor)?;
+
+    Ok(BDS {
+        data_flag: buffer[3],
+        binary_scale_factor: binary_scale,
+        decimal_scale_factor,
+        reference_value: ref_value,
+        bits_per_value: bit_count,
+        data: result,
+        missing_values: MissingValues::Nan,
+        packing: PackingInfo::of(buffer),
+        matrix: None,
+    })
+}
+
+// Unpack `count` values of `bit_count` bits each from packed data ending in `unused_bits` bits of padding
+fn unpack_simple(packed: &[u8], unused_bits: usize, bit_count: u8, count: usize, ref_value: f32, binary_scale: i16, decimal_scale_factor: i16) -> Result<Vec<f32>, ParseError> {
+    check_width(11, "bits_per_value", bit_count)?;
+    // More points than there are packed values means the GDS or bit-map is wrong, rather than silently decoding part of the data
+    let available = if bit_count == 0 { count } else { (packed.len() * 8).saturating_sub(unused_bits) / bit_count as usize };
+    if available < count {
+        return Err(ParseError::ValueCountMismatch { expected: count, actual: available });
+    }
+
+    let mut r = BitReader::new(packed);
+    let mut result = Vec::with_capacity(count);
+    let factor = pow2(binary_scale as i32) as f32;
+    let decimal_factor = pow10(-(decimal_scale_factor as i32));
+
+    // Convert all the packed data into f32 values
+    for _ in 0..count {
+        let Some(x) = r.read(bit_count as u32) else {
+            return Err(ParseError::DataDecodeFailed {
+                expected_points: count,
+                decoded: result.len(),
+            });
+        };
+        result.push((ref_value + (x as f32) * factor) * decimal_factor);
+    }
+    Ok(result)
+}
+
+// Values wider than 32 bits can't be unpacked, which is an error in the section rather than data that fails to decode
+fn check_width(octet: usize, field: &'static str, bits: u8) -> Result<(), ParseError> {
+    if bits > 32 {
+        return Err(BdsError::InvalidField {
+            octet,
+            field,
+            reason: format!("{} bits is more than the 32 supported", bits),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+// 10 to the power of `exp`, computed the same way as `f32::powi`, which isn't available without std
+fn pow10(exp: i32) -> f32 {
+    let (mut base, mut remaining, mut result) = (10.0f32, exp.unsigned_abs(), 1.0f32);
+    loop {
+        if remaining & 1 > 0 {
+            result *= base;
+        }
+        remaining /= 2;
+        if remaining == 0 {
+            break;
+        }
+        base *= base;
+    }
+    if exp < 0 {
+        1.0 / result
+    } else {
+        result
+    }
+}
+
+// Reads big endian values of up to 32 bits from packed data
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader { bytes, position: 0 }
+    }
+
+    // The next value of `bits` bits, or None if the data ends before it does or the value doesn't fit
+    fn read(&mut self, bits: u32) -> Option<u32> {
+        if bits == 0 {
+            return Some(0);
+        }
+        if bits > 32 {
+            return None;
+        }
+
+        let end = self.position + bits as usize;
+        if end > self.bytes.len() * 8 {
+            return None;
+        }
+
+        // A value of up to 32 bits starting anywhere in a byte spans at most 5 bytes
+        let (first, last) = (self.position / 8, (end - 1) / 8);
+        let value = self.bytes[first..=last].iter().fold(0u64, |value, byte| (value << 8) | *byte as u64);
+        let value = value >> ((last + 1) * 8 - end);
+        self.position = end;
+
+        Some((value & ((1 << bits) - 1)) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_test() {
+        // The sections of a message on a 2x2 grid with one missing point
+        let gds = [0, 0, 32, 0, 255, 0, 0, 2, 0, 2, 0, 0, 0, 0, 0, 0, 128, 0, 3, 232, 0, 3, 232, 3, 232, 3, 232, 0, 0, 0, 0, 0];
+        let bms = [0, 0, 7, 4, 0, 0, 0xd0];
+        let bds = [0, 0, 14, 0, 0, 1, 0x41, 0x10, 0, 0, 8, 0, 3, 6];
+
+        let gds = parse_gds(&gds).unwrap();
+        assert_eq!(gds.number_of_points(), 4);
+        assert_eq!(gds.row_length(), Some(2));
+
+        let result = decode_data(&bds, Some(&bms), Some(&gds), 0, 4).unwrap();
+        assert_eq!(result.data[..2], [1.0, 7.0]);
+        assert!(result.data[2].is_nan());
+        assert_eq!(result.data[3], 13.0);
+        assert_eq!(result.packing, PackingInfo::default());
+        assert!(result.packing.is_supported());
+
+        // Second-order packing of matrices with secondary bit-maps isn't decoded
+        let packing = PackingInfo::of(&[0, 0, 22, 0x50, 0, 0, 0, 0, 0, 0, 8, 0, 22, 0x70]);
+        assert_eq!((packing.kind, packing.matrix_values, packing.secondary_bitmaps, packing.different_widths), (PackingKind::SecondOrder, true, true, true));
+        assert!(!packing.is_supported());
+        assert!(!PackingInfo::of(&[0, 0, 11, 0x80]).is_supported());
+        let mut spherical = bds;
+        spherical[3] = 0x80;
+        assert!(matches!(decode_data(&spherical, Some(&bms), Some(&gds), 0, 4), Err(ParseError::Bds(BdsError::InvalidField { octet: 4, .. }))));
+        let mut second_order = bds;
+        second_order[3] = 0x40;
+        assert!(matches!(decode_data(&second_order, Some(&bms), Some(&gds), 0, 4), Err(ParseError::Bds(BdsError::InvalidField { octet: 4, .. }))));
+
+        // A matrix of 1x2 values at each point, with secondary bit-maps marking which are packed
+        let mut matrix = vec![0, 0, 39, 0x10, 0, 0, 0, 0, 0, 0, 8, 0, 36, 0x60, 0, 0, 0, 1, 0, 2, 0, 1, 0, 1, 30, 31];
+        matrix.extend_from_slice(&[0x41, 0x10, 0, 0, 0x42, 0x10, 0, 0, 0b1101_1000, 1, 2, 3, 4]);
+        let result = decode_data(&matrix, Some(&bms), Some(&gds), 0, 4).unwrap();
+        assert_eq!(result.values_per_point(), 2);
+        let matrix_values = result.matrix.unwrap();
+        assert_eq!((matrix_values.first_coefficients, matrix_values.second_coefficients), (vec![1.0], vec![16.0]));
+        let expected = [1.0, 2.0, f32::NAN, 3.0, f32::NAN, f32::NAN, 4.0, f32::NAN];
+        assert_eq!(result.data.iter().map(|v| v.to_bits()).collect::<Vec<_>>(), expected.map(f32::to_bits));
+        assert!(decode_data(&matrix[..38], Some(&bms), Some(&gds), 0, 4).is_err());
+        // Without the flag of octet 4 octet 14 is packed data
+        assert_eq!(PackingInfo::of(&[0, 0, 14, 0, 0, 0, 0, 0, 0, 0, 8, 0, 0, 0xff]), PackingInfo::default());
+
+        assert!(matches!(decode_data(&bds, Some(&bms), Some(&gds), 0, 3), Err(ParseError::ResourceLimitExceeded { what: "grid", .. })));
+        assert_eq!(parse_pds(&[0; 20]).unwrap_err().to_string(), "The PDS is 20 octets, but at least 28 are needed");
+
+        // Odd widths cross byte boundaries
+        let mut reader = BitReader::new(&[0b1011_0110, 0b0101_1111, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(reader.read(3), Some(0b101));
+        assert_eq!(reader.read(7), Some(0b101_1001));
+        assert_eq!(reader.read(0), Some(0));
+        assert_eq!(reader.read(32), Some(0b0111_1111_1111_1111_1111_1111_1111_1111));
+        assert_eq!(reader.read(8), None);
+        assert_eq!(BitReader::new(&[0xff; 8]).read(33), None);
+
+        // Widths that aren't a whole number of octets, with the padding at the end given by octet 4
+        for bits in [1, 10, 11, 13, 24, 31, 32] {
+            let values: Vec<u64> = (0..5).map(|i| ((1u64 << bits) - 1) >> i).collect();
+            let mut packed = vec![0u8; (5 * bits as usize).div_ceil(8)];
+            for (i, value) in values.iter().enumerate() {
+                for bit in 0..bits as usize {
+                    if value >> (bits as usize - 1 - bit) & 1 > 0 {
+                        let position = i * bits as usize + bit;
+                        packed[position / 8] |= 0x80 >> (position % 8);
+                    }
+                }
+            }
+            if packed.len().is_multiple_of(2) {
+                packed.push(0);
+            }
+            let length = 11 + packed.len();
+            let unused = (packed.len() * 8 - 5 * bits as usize) as u8;
+            let mut section = vec![0, 0, length as u8, unused, 0, 0, 0, 0, 0, 0, bits];
+            section.extend(packed);
+
+            // Without a GDS the padding mustn't be taken for another value
+            let result = decode_data(&section, None, None, 0, 10).unwrap();
+            assert_eq!(result.data, values.iter().map(|v| *v as f32).collect::<Vec<_>>(), "{} bits", bits);
+            assert!(matches!(parse_bds(&section, 6, 0), Err(ParseError::ValueCountMismatch { expected: 6, actual: 5 })));
+        }
+        let mut wide = [0u8; 13];
+        wide[10] = 33;
+        assert!(matches!(parse_bds(&wide, 1, 0), Err(ParseError::Bds(BdsError::InvalidField { octet: 11, .. }))));
+        matrix[10] = 40;
+        assert!(matches!(decode_data(&matrix, Some(&bms), Some(&gds), 0, 4), Err(ParseError::Bds(BdsError::InvalidField { octet: 11, .. }))));
+
+        for exp in -40..=40 {
+            assert_eq!(pow10(std::hint::black_box(exp)).to_bits(), 10f32.powi(exp).to_bits(), "{}", exp);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! A (time, level, y, x) view of the messages of a catalogue, decoding a field only when a value of it is asked for and keeping
+//! the fields decoded last in a cache, so a whole model run can be sliced without holding all of it in memory.
+
+use crate::catalog::MessageHandle;
+use crate::error::Grib1Error;
+use crate::index::distinct;
+use crate::time::DateTime;
+use crate::{Grib, Grib1Reader};
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Fields kept decoded unless set otherwise
+const DEFAULT_CACHE_CAPACITY: usize = 8;
+
+/// Values of a (time, level, y, x) cube. Times are reference times with steps, levels are types with values, both in ascending order.
+/// Fields missing from the messages are NaN.
+pub struct Hypercube {
+    times: Vec<(DateTime, u32)>,
+    levels: Vec<(u8, u16)>,
+    ny: usize,
+    nx: usize,
+    handles: HashMap<(usize, usize), MessageHandle>,
+    readers: HashMap<PathBuf, Grib1Reader>,
+    cache: HashMap<(usize, usize), Arc<Vec<f32>>>,
+    recent: VecDeque<(usize, usize)>,
+    cache_capacity: usize,
+    decoded: usize,
+}
+
+impl Hypercube {
+    /// A cube of the messages, e.g. from `Query::handles`, which must share their grid and have a single message per time and level.
+    /// The first message is decoded to find the shape of the grid.
+    pub async fn open(handles: Vec<MessageHandle>) -> Result<Hypercube, Grib1Error> {
+        let times = distinct(handles.iter().map(|h| (h.message.reference_time, h.message.step)));
+        let levels = distinct(handles.iter().map(|h| (h.message.type_of_level, h.message.level)));
+
+        let mut cube = Hypercube {
+            times,
+            levels,
+            ny: 0,
+            nx: 0,
+            handles: HashMap::new(),
+            readers: HashMap::new(),
+            cache: HashMap::new(),
+            recent: VecDeque::new(),
+            cache_capacity: DEFAULT_CACHE_CAPACITY,
+            decoded: 0,
+        };
+        for handle in handles {
+            let key = cube.key(&handle);
+            if cube.handles.insert(key, handle).is_some() {
+                return Err(Grib1Error::IncompatibleFields("There are several messages for the same time and level".to_string()));
+            }
+        }
+
+        let Some(first) = cube.handles.keys().min().copied() else {
+            return Ok(cube);
+        };
+        let grib = cube.decode(first).await?;
+        let gds = grib.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+        cube.nx = gds.row_length().ok_or(Grib1Error::UnsupportedGrid(gds.data_representation_type))?;
+        cube.ny = gds.number_of_points() / cube.nx.max(1);
+        cube.store(first, grib)?;
+        Ok(cube)
+    }
+
+    /// Keep at most this many fields decoded, at least one
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity.max(1);
+        self
+    }
+
+    /// Number of times, levels, rows and columns
+    pub fn shape(&self) -> (usize, usize, usize, usize) {
+        (self.times.len(), self.levels.len(), self.ny, self.nx)
+    }
+
+    pub fn times(&self) -> &[(DateTime, u32)] {
+        &self.times
+    }
+
+    pub fn levels(&self) -> &[(u8, u16)] {
+        &self.levels
+    }
+
+    /// Number of fields decoded so far, counting those decoded again after leaving the cache
+    pub fn decoded(&self) -> usize {
+        self.decoded
+    }
+
+    /// The values of the field at the time and level, or None if there is no message for them
+    pub async fn field(&mut self, time: usize, level: usize) -> Result<Option<Arc<Vec<f32>>>, Grib1Error> {
+        let key = (time, level);
+        if let Some(values) = self.cache.get(&key).cloned() {
+            self.recent.retain(|recent| *recent != key);
+            self.recent.push_back(key);
+            return Ok(Some(values));
+        }
+        if !self.handles.contains_key(&key) {
+            return Ok(None);
+        }
+
+        let grib = self.decode(key).await?;
+        self.store(key, grib).map(Some)
+    }
+
+    /// The value at the time, level, row and column
+    pub async fn get(&mut self, time: usize, level: usize, y: usize, x: usize) -> Result<f32, Grib1Error> {
+        Ok(self.slice(time..time + 1, level..level + 1, y..y + 1, x..x + 1).await?[0])
+    }
+
+    /// The values of a block of the cube in (time, level, y, x) order. Only the fields of the times and levels given are decoded.
+    pub async fn slice(&mut self, times: Range<usize>, levels: Range<usize>, ys: Range<usize>, xs: Range<usize>) -> Result<Vec<f32>, Grib1Error> {
+        let (nt, nl, ny, nx) = self.shape();
+        if times.end > nt || levels.end > nl || ys.end > ny || xs.end > nx || xs.start > xs.end {
+            return Err(Grib1Error::RegionOutsideGrid);
+        }
+
+        let mut result = Vec::with_capacity(times.len() * levels.len() * ys.len() * xs.len());
+        for time in times {
+            for level in levels.clone() {
+                let field = self.field(time, level).await?;
+                for y in ys.clone() {
+                    match &field {
+                        Some(values) => result.extend_from_slice(&values[y * nx + xs.start..y * nx + xs.end]),
+                        None => result.extend(xs.clone().map(|_| f32::NAN)),
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn key(&self, handle: &MessageHandle) -> (usize, usize) {
+        let time = self.times.binary_search(&(handle.message.reference_time, handle.message.step)).unwrap_or_default();
+        let level = self.levels.binary_search(&(handle.message.type_of_level, handle.message.level)).unwrap_or_default();
+        (time, level)
+    }
+
+    async fn decode(&mut self, key: (usize, usize)) -> Result<Grib, Grib1Error> {
+        let handle = &self.handles[&key];
+        let reader = match self.readers.get_mut(&handle.path) {
+            Some(reader) => reader,
+            None => self.readers.entry(handle.path.clone()).or_insert(Grib1Reader::open(&handle.path).await?),
+        };
+        self.decoded += 1;
+        reader.read_indexed_message(&handle.message).await
+    }
+
+    // Cache the values of a decoded field, dropping the one used longest ago if the cache is full
+    fn store(&mut self, key: (usize, usize), grib: Grib) -> Result<Arc<Vec<f32>>, Grib1Error> {
+        let values = Arc::new(grib.bds.ok_or(Grib1Error::MissingSection("BDS"))?.data);
+        if values.len() != self.ny * self.nx {
+            return Err(Grib1Error::IncompatibleFields("The messages don't share their grid".to_string()));
+        }
+
+        while self.recent.len() >= self.cache_capacity {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.cache.remove(&oldest);
+            }
+        }
+        self.cache.insert(key, values.clone());
+        self.recent.push_back(key);
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+
+    #[tokio::test]
+    async fn hypercube_test() -> Result<(), Grib1Error> {
+        let mut catalog = Catalog::new();
+        catalog.add_index("data/sample.grib", Grib1Reader::open("data/sample.grib").await?.grib_index().await?, None);
+        let reference = Grib1Reader::open("data/sample.grib").await?.read_where(|pds| pds.indicator_of_parameter_and_units == 34).await?.remove(0);
+        let values = &reference.bds.as_ref().unwrap().data;
+
+        let mut cube = Hypercube::open(catalog.select().param_number(34).handles()?).await?.cache_capacity(1);
+        assert_eq!(cube.shape(), (1, 1, 1069, 1189));
+        assert_eq!(cube.levels(), &[(100, 700)]);
+        assert_eq!(cube.decoded(), 1);
+
+        // The field decoded to find the shape is cached
+        assert_eq!(cube.get(0, 0, 2, 3).await?, values[2 * 1189 + 3]);
+        let block = cube.slice(0..1, 0..1, 10..12, 100..103).await?;
+        assert_eq!(block, [&values[10 * 1189 + 100..10 * 1189 + 103], &values[11 * 1189 + 100..11 * 1189 + 103]].concat());
+        assert_eq!(cube.decoded(), 1);
+        assert!(cube.slice(0..1, 0..1, 0..1, 0..1190).await.is_err());
+
+        // Both components on the same level and time are two messages for one cell of the cube
+        assert!(Hypercube::open(catalog.select().handles()?).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Labelled representation of a decoded message, pairing the values with named dimensions, coordinates and attributes.
+
+use crate::error::Grib1Error;
+use crate::time::DateTime;
+use crate::{DataRepresentation, Grib};
+
+#[derive(Debug, Clone)]
+/// Metadata describing the values of a `DataArray`
+pub struct Attributes {
+    pub parameter: u8,
+    pub abbreviation: Option<&'static str>,
+    pub long_name: Option<&'static str>,
+    pub units: Option<&'static str>,
+    pub type_of_level: u8,
+    pub level: u16,
+    pub reference_time: DateTime,
+    pub valid_time: Option<DateTime>,
+    /// Latitude and longitude of the southern pole of rotation, if the coordinates are rotated
+    pub southern_pole: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Clone)]
+/// Decoded values together with the dimensions and coordinates describing them, similar to an xarray DataArray.
+/// The values are stored row major, i.e. the last dimension varies fastest, with the rows from north to south and each row from west to east
+/// whatever the scanning mode of the message.
+pub struct DataArray {
+    pub dims: [&'static str; 2],
+    pub shape: [usize; 2],
+    pub latitude: Vec<f32>,
+    pub longitude: Vec<f32>,
+    pub values: Vec<f32>,
+    pub attrs: Attributes,
+}
+
+impl DataArray {
+    /// Build a labelled array from a message that has been read including its data
+    pub fn from_grib(grib: &Grib) -> Result<DataArray, Grib1Error> {
+        let gds = grib.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+        let bds = grib.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+
+        let (grid, southern_pole) = match gds.data {
+            DataRepresentation::RotatedLatLon(grid) => (grid, Some((grid.latitude_of_southern_pole, grid.longitude_of_southern_pole))),
+            DataRepresentation::RegularLatLon(grid) => (grid.into(), None),
+            _ => return Err(Grib1Error::UnsupportedGrid(gds.data_representation_type)),
+        };
+
+        let (ni, nj) = (grid.number_of_lat_values as usize, grid.number_of_lon_values as usize);
+        if bds.data.len() != ni * nj {
+            return Err(Grib1Error::ValueCountMismatch { expected: ni * nj, actual: bds.data.len() });
+        }
+        // The coordinates are given from the first grid point, so they are turned around where the values are
+        let (mut latitude, mut longitude) = (grid.latitudes(), grid.longitudes());
+        if grid.scanning_mode & 0x40 > 0 {
+            latitude.reverse();
+        }
+        if grid.scanning_mode & 0x80 > 0 {
+            longitude.reverse();
+        }
+        let parameter = grib.pds.parameter();
+
+        Ok(DataArray {
+            dims: ["latitude", "longitude"],
+            shape: [latitude.len(), longitude.len()],
+            latitude,
+            longitude,
+            values: grib.rows().flat_map(|row| row.iter()).collect(),
+            attrs: Attributes {
+                parameter: grib.pds.indicator_of_parameter_and_units,
+                abbreviation: parameter.map(|p| p.abbreviation),
+                long_name: parameter.map(|p| p.name),
+                units: parameter.map(|p| p.units),
+                type_of_level: grib.pds.indicator_of_type_of_level_or_layer,
+                level: grib.pds.level_or_layer_value,
+                reference_time: grib.pds.reference_time(),
+                valid_time: grib.pds.valid_time(),
+                southern_pole,
+            },
+        })
+    }
+
+    /// Get the coordinate values of a dimension by name
+    pub fn coord(&self, dim: &str) -> Option<&[f32]> {
+        match dim {
+            "latitude" => Some(&self.latitude),
+            "longitude" => Some(&self.longitude),
+            _ => None,
+        }
+    }
+
+    /// Get the value at the given latitude and longitude index
+    pub fn get(&self, lat_index: usize, lon_index: usize) -> Option<f32> {
+        if lat_index >= self.shape[0] || lon_index >= self.shape[1] {
+            return None;
+        }
+        self.values.get(lat_index * self.shape[1] + lon_index).copied()
+    }
+}
+
+impl Grib {
+    /// Convert the message into a labelled `DataArray`. The message must have been read including its data.
+    pub fn to_data_array(&self) -> Result<DataArray, Grib1Error> {
+        DataArray::from_grib(self)
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use crate::synthetic::MessageBuilder;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn data_array_test() -> Result<(), crate::error::Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let mut result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        // The parameters of the local table of the sample aren't known
+        assert_eq!(result[0].to_data_array()?.attrs.units, None);
+        result[0].pds.parameter_table_version_number = 2;
+
+        let array = result[0].to_data_array()?;
+        assert_eq!(array.shape, [1069, 1189]);
+        assert_eq!(array.values.len(), array.shape[0] * array.shape[1]);
+        assert_eq!(array.coord("latitude").unwrap().len(), 1069);
+        assert_eq!(array.attrs.units, Some("m/s"));
+        assert_eq!(array.attrs.reference_time.year, 2023);
+        assert!(array.get(1069, 0).is_none());
+        assert!(array.attrs.southern_pole.is_some());
+
+        // A regular grid has no pole of rotation
+        let regular = MessageBuilder::new(3, 2).grib()?.to_data_array()?;
+        assert_eq!((regular.shape, regular.attrs.southern_pole), ([2, 3], None));
+
+        // Values stored column by column from the south are given row by row from the north, with the coordinates alike
+        let gds = crate::grid::RegularLatLonBuilder::new(3, 2).first_grid_point(50.0, 0.0).increments(1.0, 1.0).scanning_mode(0x60).build()?;
+        let columns = MessageBuilder::new(3, 2).grid(gds).values(vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]).grib()?.to_data_array()?;
+        assert_eq!(columns.values, vec![4.0, 5.0, 6.0, 1.0, 2.0, 3.0]);
+        let round = |coordinates: &[f32]| coordinates.iter().map(|c| c.round()).collect::<Vec<_>>();
+        assert_eq!((round(&columns.latitude), round(&columns.longitude)), (vec![51.0, 50.0], vec![0.0, 1.0, 2.0]));
+        assert_eq!(columns.get(1, 2), Some(3.0));
+
+        Ok(())
+    }
+}
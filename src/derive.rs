@@ -0,0 +1,153 @@
+//! Quantities derived point by point from fields on the same grid: relative humidity and dew point, wind speed, wind chill and
+//! the heat index. The parameters of the inputs are looked up in code table 2 and must be in the units the formulas expect.
+
+use crate::error::Grib1Error;
+use crate::tables::parameter;
+use crate::Grib;
+
+/// Parameters of the results from code table 2
+const DEW_POINT: u8 = 17;
+const WIND_SPEED: u8 = 32;
+const RELATIVE_HUMIDITY: u8 = 52;
+
+/// Relative humidity in % from the temperature and the dew point in K, over water as by the Magnus formula
+pub fn relative_humidity(temperature: &Grib, dew_point: &Grib) -> Result<Grib, Grib1Error> {
+    expect_units(temperature, "temperature", "K")?;
+    expect_units(dew_point, "dew point", "K")?;
+    pointwise(temperature, dew_point, RELATIVE_HUMIDITY, |t, td| 100.0 * saturation_pressure(td - 273.15) / saturation_pressure(t - 273.15))
+}
+
+/// Dew point in K from the temperature in K and the relative humidity in %, the inverse of `relative_humidity`
+pub fn dew_point(temperature: &Grib, relative_humidity: &Grib) -> Result<Grib, Grib1Error> {
+    expect_units(temperature, "temperature", "K")?;
+    expect_units(relative_humidity, "relative humidity", "%")?;
+    pointwise(temperature, relative_humidity, DEW_POINT, |t, rh| {
+        let t = t - 273.15;
+        let gamma = (rh / 100.0).ln() + MAGNUS_B * t / (t + MAGNUS_C);
+        MAGNUS_C * gamma / (MAGNUS_B - gamma) + 273.15
+    })
+}
+
+/// Wind speed in m/s from the u and v components
+pub fn wind_speed(u: &Grib, v: &Grib) -> Result<Grib, Grib1Error> {
+    expect_units(u, "u component", "m/s")?;
+    expect_units(v, "v component", "m/s")?;
+    pointwise(u, v, WIND_SPEED, |u, v| u.hypot(v))
+}
+
+/// Wind chill in K from the temperature in K and the wind speed in m/s, by the formula used in Canada and the US.
+/// Where it isn't defined, above 10 °C or below 4.8 km/h, it is the temperature. The parameter is that of the temperature.
+pub fn wind_chill(temperature: &Grib, wind_speed: &Grib) -> Result<Grib, Grib1Error> {
+    expect_units(temperature, "temperature", "K")?;
+    expect_units(wind_speed, "wind speed", "m/s")?;
+    let parameter = temperature.pds.indicator_of_parameter_and_units;
+    pointwise(temperature, wind_speed, parameter, |t, speed| {
+        let (t, speed) = (t - 273.15, speed * 3.6);
+        if t > 10.0 || speed < 4.8 {
+            return t + 273.15;
+        }
+        let v = speed.powf(0.16);
+        13.12 + 0.6215 * t - 11.37 * v + 0.3965 * t * v + 273.15
+    })
+}
+
+/// Heat index in K from the temperature in K and the relative humidity in %, by the regression of the US National Weather Service
+/// without its adjustments for very dry and very humid air. The parameter is that of the temperature.
+pub fn heat_index(temperature: &Grib, relative_humidity: &Grib) -> Result<Grib, Grib1Error> {
+    expect_units(temperature, "temperature", "K")?;
+    expect_units(relative_humidity, "relative humidity", "%")?;
+    let parameter = temperature.pds.indicator_of_parameter_and_units;
+    pointwise(temperature, relative_humidity, parameter, |t, rh| {
+        // The formulas are in °F
+        let t = (t - 273.15) * 1.8 + 32.0;
+        let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+        let index = if (simple + t) / 2.0 < 80.0 {
+            simple
+        } else {
+            -42.379 + 2.04901523 * t + 10.14333127 * rh - 0.22475541 * t * rh - 6.83783e-3 * t * t - 5.481717e-2 * rh * rh + 1.22874e-3 * t * t * rh + 8.5282e-4 * t * rh * rh - 1.99e-6 * t * t * rh * rh
+        };
+        (index - 32.0) / 1.8 + 273.15
+    })
+}
+
+// Constants of the Magnus formula over water, for temperatures in °C and pressures in hPa
+const MAGNUS_A: f64 = 6.112;
+const MAGNUS_B: f64 = 17.67;
+const MAGNUS_C: f64 = 243.5;
+
+fn saturation_pressure(t: f64) -> f64 {
+    MAGNUS_A * (MAGNUS_B * t / (t + MAGNUS_C)).exp()
+}
+
+// Fail unless the parameter of the field is known and in the units given
+fn expect_units(grib: &Grib, name: &str, units: &str) -> Result<(), Grib1Error> {
+    let indicator = grib.pds.indicator_of_parameter_and_units;
+    match parameter(grib.pds.parameter_table_version_number, indicator) {
+        Some(found) if found.units == units => Ok(()),
+        Some(found) => Err(Grib1Error::IncompatibleFields(format!("the {} is {} in {}, not in {}", name, found.abbreviation, found.units, units))),
+        None => Err(Grib1Error::IncompatibleFields(format!("the {} is parameter {}, whose units aren't known", name, indicator))),
+    }
+}
+
+// Apply the formula to the values of both fields, giving a field of the parameter with the PDS otherwise that of `a`.
+// Points missing from either field are missing.
+fn pointwise<F: Fn(f64, f64) -> f64>(a: &Grib, b: &Grib, parameter: u8, formula: F) -> Result<Grib, Grib1Error> {
+    a.check_same_grid(b)?;
+    let bds = a.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let other = b.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+
+    let data = bds
+        .data
+        .iter()
+        .zip(&other.data)
+        .map(|(x, y)| if bds.is_missing(*x) || other.is_missing(*y) { f32::NAN } else { formula(*x as f64, *y as f64) as f32 })
+        .collect();
+    let mut result = a.with_data(bds, data);
+    result.pds.indicator_of_parameter_and_units = parameter;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::missing::MissingValues;
+    use crate::synthetic::MessageBuilder;
+
+    #[test]
+    fn derive_test() -> Result<(), Grib1Error> {
+        let field = |parameter, values: Vec<f32>| MessageBuilder::new(3, 1).parameter(parameter).decimal_scale_factor(2).values(values).grib();
+        let temperature = field(11, vec![293.15, 303.15, 263.15])?;
+        let dew_point_field = field(17, vec![293.15, 293.15, f32::NAN])?;
+
+        let rh = relative_humidity(&temperature, &dew_point_field)?;
+        assert_eq!(rh.pds.indicator_of_parameter_and_units, 52);
+        let rh_values = &rh.bds.as_ref().unwrap().data;
+        assert!((rh_values[0] - 100.0).abs() < 0.01 && (rh_values[1] - 55.0).abs() < 0.1 && rh_values[2].is_nan());
+        let back = dew_point(&temperature, &rh)?.bds.unwrap().data;
+        assert!((back[1] - 293.15).abs() < 0.01 && back[2].is_nan());
+
+        let speed = wind_speed(&field(33, vec![3.0, 0.0, 6.0])?, &field(34, vec![4.0, 1.0, 8.0])?)?;
+        assert_eq!(speed.bds.as_ref().unwrap().data, vec![5.0, 1.0, 10.0]);
+        // Only the cold, windy point has a wind chill, of about -20.3 °C at -10 °C and 36 km/h
+        let chill = wind_chill(&temperature, &speed)?.bds.unwrap().data;
+        assert!(chill[0] == temperature.bds.as_ref().unwrap().data[0] && (chill[2] - 252.85).abs() < 0.1);
+
+        // 30 °C at 55 % feels like about 31.9 °C, 20 °C at 50 % like about 19.4 °C
+        let index = heat_index(&temperature, &field(52, vec![50.0, 55.0, 50.0])?)?.bds.unwrap().data;
+        assert!((index[1] - 305.04).abs() < 0.1 && (index[0] - 292.51).abs() < 0.1);
+
+        assert!(matches!(relative_humidity(&temperature, &field(52, vec![0.0; 3])?), Err(Grib1Error::IncompatibleFields(_))));
+        assert!(relative_humidity(&temperature, &MessageBuilder::new(1, 3).parameter(17).grib()?).is_err());
+
+        // Missing points are given as the sentinel of the first field, as read with that policy
+        let mut sentinel = field(11, vec![f32::NAN, 303.15, 263.15])?;
+        if let Some(bds) = sentinel.bds.as_mut() {
+            bds.set_missing_values(MissingValues::Sentinel(9999.0));
+        }
+        let rh = relative_humidity(&sentinel, &dew_point_field)?.bds.unwrap();
+        assert_eq!((rh.data[0], rh.data[2]), (9999.0, 9999.0));
+        assert_eq!((rh.optional_values()[0], rh.optional_values()[2]), (None, None));
+        assert!((rh.data[1] - 55.0).abs() < 0.1);
+        Ok(())
+    }
+}
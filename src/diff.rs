@@ -0,0 +1,312 @@
+//! Comparison of messages and files, reporting metadata and grid differences as well as statistics on the decoded values.
+
+#[cfg(feature = "async-tokio")]
+use crate::error::Grib1Error;
+use crate::time::DateTime;
+#[cfg(feature = "async-tokio")]
+use crate::Grib1Reader;
+use crate::{DataRepresentation, Grib, GDS, PDS};
+#[cfg(feature = "async-tokio")]
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A field that has different values in the two messages
+pub struct FieldDifference {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Statistics on the differences between the decoded values of two messages
+pub struct ValueStatistics {
+    /// Number of points compared
+    pub compared: usize,
+    /// Number of points differing by more than the tolerance
+    pub differing: usize,
+    pub max_abs_diff: f32,
+    pub rmse: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Differences between two messages
+pub struct MessageDiff {
+    /// Differences in the PDS
+    pub metadata: Vec<FieldDifference>,
+    /// Differences in the GDS
+    pub grid: Vec<FieldDifference>,
+    /// None if either message lacks decoded data or the number of values differs
+    pub values: Option<ValueStatistics>,
+}
+
+impl MessageDiff {
+    /// True if there are no metadata or grid differences and no values differ by more than the tolerance
+    pub fn is_identical(&self) -> bool {
+        self.metadata.is_empty() && self.grid.is_empty() && self.values.as_ref().map(|v| v.differing == 0).unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The fields used to pair up messages from two files
+pub struct MessageKey {
+    pub param: u8,
+    pub type_of_level: u8,
+    pub level: u16,
+    pub reference_time: DateTime,
+    pub step: u32,
+}
+
+impl MessageKey {
+    pub fn of(pds: &PDS) -> MessageKey {
+        MessageKey {
+            param: pds.indicator_of_parameter_and_units,
+            type_of_level: pds.indicator_of_type_of_level_or_layer,
+            level: pds.level_or_layer_value,
+            reference_time: pds.reference_time(),
+            step: pds.step(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Differences between two files
+pub struct FileDiff {
+    pub only_in_left: Vec<MessageKey>,
+    pub only_in_right: Vec<MessageKey>,
+    pub common: Vec<(MessageKey, MessageDiff)>,
+}
+
+impl FileDiff {
+    /// True if both files contain the same messages and all of them are identical
+    pub fn is_identical(&self) -> bool {
+        self.only_in_left.is_empty() && self.only_in_right.is_empty() && self.common.iter().all(|(_, diff)| diff.is_identical())
+    }
+}
+
+macro_rules! compare {
+    ($out:expr, $left:expr, $right:expr, $($field:ident),+) => {{
+        $(
+            if $left.$field != $right.$field {
+                $out.push(FieldDifference {
+                    field: stringify!($field),
+                    left: format!("{:?}", $left.$field),
+                    right: format!("{:?}", $right.$field),
+                });
+            }
+        )+
+    }};
+}
+
+/// Compare two messages. Values differing by no more than `tolerance` are considered equal.
+pub fn diff_messages(left: &Grib, right: &Grib, tolerance: f32) -> MessageDiff {
+    let mut grid = vec![];
+    match (&left.gds, &right.gds) {
+        (Some(l), Some(r)) => diff_gds(&mut grid, l, r),
+        (None, None) => {}
+        (l, r) => grid.push(FieldDifference {
+            field: "gds",
+            left: if l.is_some() { "present" } else { "absent" }.to_string(),
+            right: if r.is_some() { "present" } else { "absent" }.to_string(),
+        }),
+    }
+
+    let values = match (&left.bds, &right.bds) {
+        (Some(l), Some(r)) if l.data.len() == r.data.len() => Some(value_statistics(&l.nan_values(), &r.nan_values(), tolerance)),
+        _ => None,
+    };
+
+    MessageDiff {
+        metadata: diff_pds(&left.pds, &right.pds),
+        grid,
+        values,
+    }
+}
+
+/// Compare all messages of two files, pairing them up by parameter, level, reference time and step.
+/// Messages occurring more than once with the same key are paired in the order they appear.
+#[cfg(feature = "async-tokio")]
+pub async fn diff_files(left: &mut Grib1Reader, right: &mut Grib1Reader, tolerance: f32) -> Result<FileDiff, Grib1Error> {
+    let mut left_messages = group(left.read_where(|_| true).await?);
+    let mut right_messages = group(right.read_where(|_| true).await?);
+    let mut result = FileDiff::default();
+
+    for (key, lefts) in left_messages.iter_mut() {
+        let rights = right_messages.entry(*key).or_default();
+        let common = lefts.len().min(rights.len());
+
+        for (l, r) in lefts.drain(..common).zip(rights.drain(..common)) {
+            result.common.push((*key, diff_messages(&l, &r, tolerance)));
+        }
+        result.only_in_left.extend(lefts.iter().map(|_| *key));
+    }
+
+    for (key, rights) in right_messages {
+        result.only_in_right.extend(rights.iter().map(|_| key));
+    }
+
+    Ok(result)
+}
+
+#[cfg(feature = "async-tokio")]
+fn group(messages: Vec<Grib>) -> BTreeMap<MessageKey, Vec<Grib>> {
+    let mut result: BTreeMap<MessageKey, Vec<Grib>> = BTreeMap::new();
+    for grib in messages {
+        result.entry(MessageKey::of(&grib.pds)).or_default().push(grib);
+    }
+    result
+}
+
+fn diff_pds(left: &PDS, right: &PDS) -> Vec<FieldDifference> {
+    let mut out = vec![];
+    compare!(
+        out,
+        left,
+        right,
+        parameter_table_version_number,
+        identification_of_center,
+        generating_process_id_number,
+        grid_identification,
+        flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms,
+        indicator_of_parameter_and_units,
+        indicator_of_type_of_level_or_layer,
+        level_or_layer_value,
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        forecast_time_unit,
+        p1_period_of_time,
+        p2_period_of_time,
+        time_range_indicator,
+        number_included_in_average,
+        number_missing_from_averages_or_accumulations,
+        century_of_initial_reference_time,
+        identification_of_sub_center,
+        decimal_scale_factor
+    );
+    out
+}
+
+pub(crate) fn diff_gds(out: &mut Vec<FieldDifference>, left: &GDS, right: &GDS) {
+    compare!(out, left, right, number_of_vertical_coordinate_values, pvl_location, data_representation_type);
+
+    match (&left.data, &right.data) {
+        (DataRepresentation::RotatedLatLon(l), DataRepresentation::RotatedLatLon(r)) => compare!(
+            out,
+            l,
+            r,
+            number_of_lat_values,
+            number_of_lon_values,
+            latitude_of_first_grid_point,
+            longitude_of_first_grid_point,
+            latitude_of_last_grid_point,
+            longitude_of_last_grid_point,
+            resolution_and_component_flags,
+            i_direction_increment,
+            j_direction_increment,
+            scanning_mode,
+            latitude_of_southern_pole,
+            longitude_of_southern_pole,
+            angle_of_rotation
+        ),
+        (DataRepresentation::RegularLatLon(l), DataRepresentation::RegularLatLon(r)) => compare!(
+            out,
+            l,
+            r,
+            number_of_lat_values,
+            number_of_lon_values,
+            latitude_of_first_grid_point,
+            longitude_of_first_grid_point,
+            latitude_of_last_grid_point,
+            longitude_of_last_grid_point,
+            resolution_and_component_flags,
+            i_direction_increment,
+            j_direction_increment,
+            scanning_mode
+        ),
+        (DataRepresentation::AlbersEqualArea(l), DataRepresentation::AlbersEqualArea(r)) | (DataRepresentation::ObliqueLambert(l), DataRepresentation::ObliqueLambert(r)) => compare!(
+            out,
+            l,
+            r,
+            number_of_x_values,
+            number_of_y_values,
+            latitude_of_first_grid_point,
+            longitude_of_first_grid_point,
+            resolution_and_component_flags,
+            orientation_of_the_grid,
+            x_direction_grid_length,
+            y_direction_grid_length,
+            projection_centre_flag,
+            scanning_mode,
+            latin_1,
+            latin_2,
+            latitude_of_southern_pole,
+            longitude_of_southern_pole
+        ),
+        (DataRepresentation::Unhandled, DataRepresentation::Unhandled) => {}
+        (l, r) => out.push(FieldDifference {
+            field: "data",
+            left: format!("{:?}", l),
+            right: format!("{:?}", r),
+        }),
+    }
+}
+
+fn value_statistics(left: &[f32], right: &[f32], tolerance: f32) -> ValueStatistics {
+    let mut result = ValueStatistics::default();
+    let mut sum_of_squares = 0.0;
+    let mut finite = 0;
+
+    for (l, r) in left.iter().zip(right) {
+        result.compared += 1;
+        if l.is_nan() && r.is_nan() {
+            continue;
+        }
+
+        let diff = (l - r).abs();
+        if diff.is_nan() || diff > tolerance {
+            result.differing += 1;
+        }
+        if !diff.is_nan() {
+            result.max_abs_diff = result.max_abs_diff.max(diff);
+            sum_of_squares += diff as f64 * diff as f64;
+            finite += 1;
+        }
+    }
+
+    if finite > 0 {
+        result.rmse = (sum_of_squares / finite as f64).sqrt();
+    }
+    result
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::SearchParams;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn diff_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }, SearchParams { param: 34, level: 700 }]).await?;
+
+        let diff = diff_messages(&result[0], &result[1], 0.0);
+        assert_eq!(diff.metadata.len(), 1);
+        assert_eq!(diff.metadata[0].field, "indicator_of_parameter_and_units");
+        assert!(diff.grid.is_empty());
+        assert!(diff.values.unwrap().differing > 0);
+        assert!(diff_messages(&result[0], &result[0], 0.0).is_identical());
+
+        let f = File::open("data/sample.grib").await?;
+        let mut other = Grib1Reader::new(BufReader::new(f));
+        let diff = diff_files(&mut reader, &mut other, 0.0).await?;
+        assert_eq!(diff.common.len(), 2);
+        assert!(diff.is_identical());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+//! Annotated text rendering of the raw octets of a message, similar to `grib_dump -O`.
+
+use crate::codec::{read_f32_ibm, read_i16_be, read_i24_be, read_u24_be};
+use crate::error::Grib1Error;
+use crate::{edition0, message_length, resolve_lengths};
+use std::fmt::Write;
+
+// How the octets of a field should be interpreted
+enum Kind {
+    Unsigned,
+    Signed,
+    Millidegrees,
+    Ibm,
+    Flags,
+    Text,
+}
+
+/// Produce an octet-by-octet annotated dump of every section in a single raw message, e.g. as returned by `read_binary`.
+/// Octet numbers are relative to the start of each section, as in the WMO documentation.
+/// A message that is cut short is dumped as far as possible. Edition 0 messages are dumped with their own layout.
+pub fn dump(message: &[u8]) -> Result<String, Grib1Error> {
+    if message.len() < 8 || &message[0..4] != b"GRIB" {
+        return Err(Grib1Error::WrongHeader);
+    }
+
+    let mut out = String::new();
+    // Large messages give their length in units of 120 octets, corrected by the BDS length field
+    let length_field = read_u24_be(&message[4..]) as usize;
+    let total_length = message_length(message).unwrap_or(length_field);
+
+    // The indicator section of edition 0 is just the identifier, and the PDS follows it
+    let edition0 = edition0::is_edition0(message);
+    let mut offset = if edition0 { 4 } else { 8 };
+    header(&mut out, 0, "Indicator section", offset);
+    let section = &message[0..offset];
+    field(&mut out, section, 1, 4, "identifier", Kind::Text);
+    if !edition0 {
+        field(&mut out, section, 5, 7, "total_length", Kind::Unsigned);
+        field(&mut out, section, 8, 8, "edition_number", Kind::Unsigned);
+    }
+
+    let pds = match next_section(&mut out, message, &mut offset, 1, "Product definition section", |length| length) {
+        Some(pds) => pds,
+        None => return Ok(out),
+    };
+    dump_pds(&mut out, pds, edition0);
+
+    let flag = pds.get(7).copied().unwrap_or(0);
+    if flag & 128 > 0 {
+        match next_section(&mut out, message, &mut offset, 2, "Grid description section", |length| length) {
+            Some(gds) => dump_gds(&mut out, gds),
+            None => return Ok(out),
+        }
+    }
+
+    if flag & 64 > 0 {
+        match next_section(&mut out, message, &mut offset, 3, "Bit-map section", |length| length) {
+            Some(bms) => dump_bms(&mut out, bms),
+            None => return Ok(out),
+        }
+    }
+
+    let bds_offset = offset;
+    match next_section(
+        &mut out,
+        message,
+        &mut offset,
+        4,
+        "Binary data section",
+        |length| if edition0 { length } else { resolve_lengths(length_field, bds_offset, length).1 },
+    ) {
+        Some(bds) => dump_bds(&mut out, bds),
+        None => return Ok(out),
+    }
+
+    header(&mut out, 5, "End section", 4);
+    field(&mut out, message.get(offset..).unwrap_or(&[]), 1, 4, "end_marker", Kind::Text);
+    if offset + 4 != total_length {
+        let _ = writeln!(out, "!! The sections add up to {} octets, but the indicator section says {}", offset + 4, total_length);
+    }
+
+    Ok(out)
+}
+
+// Find the next section based on its length field, as resolved to the true length, write its header and advance the offset past it
+fn next_section<'a>(out: &mut String, message: &'a [u8], offset: &mut usize, number: u8, name: &str, resolve: impl Fn(usize) -> usize) -> Option<&'a [u8]> {
+    if *offset + 3 > message.len() {
+        let _ = writeln!(out, "!! Message ends before section {}", number);
+        return None;
+    }
+
+    let length = resolve(read_u24_be(&message[*offset..]) as usize);
+    header(out, number, name, length);
+
+    let end = (*offset + length).min(message.len());
+    let section = &message[*offset..end];
+    if end < *offset + length {
+        let _ = writeln!(out, "!! Section is truncated, only {} of {} octets are present", section.len(), length);
+    }
+    *offset += length;
+
+    Some(section)
+}
+
+fn dump_pds(out: &mut String, pds: &[u8], edition0: bool) {
+    field(out, pds, 1, 3, "section_length", Kind::Unsigned);
+    field(out, pds, 4, 4, "parameter_table_version_number", Kind::Unsigned);
+    field(out, pds, 5, 5, "identification_of_center", Kind::Unsigned);
+    field(out, pds, 6, 6, "generating_process_id_number", Kind::Unsigned);
+    field(out, pds, 7, 7, "grid_identification", Kind::Unsigned);
+    field(out, pds, 8, 8, "flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms", Kind::Flags);
+    field(out, pds, 9, 9, "indicator_of_parameter_and_units", Kind::Unsigned);
+    field(out, pds, 10, 10, "indicator_of_type_of_level_or_layer", Kind::Unsigned);
+    field(out, pds, 11, 12, "level_or_layer_value", Kind::Unsigned);
+    field(out, pds, 13, 13, "year", Kind::Unsigned);
+    field(out, pds, 14, 14, "month", Kind::Unsigned);
+    field(out, pds, 15, 15, "day", Kind::Unsigned);
+    field(out, pds, 16, 16, "hour", Kind::Unsigned);
+    field(out, pds, 17, 17, "minute", Kind::Unsigned);
+    field(out, pds, 18, 18, "forecast_time_unit", Kind::Unsigned);
+    field(out, pds, 19, 19, "p1_period_of_time", Kind::Unsigned);
+    field(out, pds, 20, 20, "p2_period_of_time", Kind::Unsigned);
+    field(out, pds, 21, 21, "time_range_indicator", Kind::Unsigned);
+    field(out, pds, 22, 23, "number_included_in_average", Kind::Unsigned);
+    field(out, pds, 24, 24, "number_missing_from_averages_or_accumulations", Kind::Unsigned);
+    // Edition 0 has no century, sub-centre or decimal scale factor
+    if edition0 {
+        octets(out, pds, 25, "reserved");
+        return;
+    }
+    field(out, pds, 25, 25, "century_of_initial_reference_time", Kind::Unsigned);
+    field(out, pds, 26, 26, "identification_of_sub_center", Kind::Unsigned);
+    field(out, pds, 27, 28, "decimal_scale_factor", Kind::Signed);
+    if pds.len() > 28 {
+        field(out, pds, 29, 40.min(pds.len()), "reserved", Kind::Text);
+    }
+    if pds.len() > 40 {
+        octets(out, pds, 41, "local_use");
+    }
+}
+
+fn dump_gds(out: &mut String, gds: &[u8]) {
+    field(out, gds, 1, 3, "section_length", Kind::Unsigned);
+    field(out, gds, 4, 4, "number_of_vertical_coordinate_values", Kind::Unsigned);
+    field(out, gds, 5, 5, "pvl_location", Kind::Unsigned);
+    field(out, gds, 6, 6, "data_representation_type", Kind::Unsigned);
+
+    let representation_type = gds.get(5).copied();
+    if representation_type != Some(0) && representation_type != Some(10) {
+        octets(out, gds, 7, "grid_definition");
+        return;
+    }
+
+    field(out, gds, 7, 8, "number_of_lat_values", Kind::Unsigned);
+    field(out, gds, 9, 10, "number_of_lon_values", Kind::Unsigned);
+    field(out, gds, 11, 13, "latitude_of_first_grid_point", Kind::Millidegrees);
+    field(out, gds, 14, 16, "longitude_of_first_grid_point", Kind::Millidegrees);
+    field(out, gds, 17, 17, "resolution_and_component_flags", Kind::Flags);
+    field(out, gds, 18, 20, "latitude_of_last_grid_point", Kind::Millidegrees);
+    field(out, gds, 21, 23, "longitude_of_last_grid_point", Kind::Millidegrees);
+    field(out, gds, 24, 25, "i_direction_increment", Kind::Unsigned);
+    field(out, gds, 26, 27, "j_direction_increment", Kind::Unsigned);
+    field(out, gds, 28, 28, "scanning_mode", Kind::Flags);
+    field(out, gds, 29, 32, "reserved", Kind::Text);
+    if representation_type == Some(10) {
+        field(out, gds, 33, 35, "latitude_of_southern_pole", Kind::Millidegrees);
+        field(out, gds, 36, 38, "longitude_of_southern_pole", Kind::Millidegrees);
+        field(out, gds, 39, 42, "angle_of_rotation", Kind::Ibm);
+    }
+
+    // The list of vertical coordinate parameters follows if present, at an octet from 1 to 254
+    let count = gds.get(3).copied().unwrap_or(0) as usize;
+    let location = gds.get(4).copied().unwrap_or(255) as usize;
+    if count > 0 && (1..255).contains(&location) {
+        for i in 0..count {
+            let start = location + i * 4;
+            field(out, gds, start, start + 3, &format!("vertical_coordinate_value[{}]", i), Kind::Ibm);
+        }
+    }
+}
+
+fn dump_bms(out: &mut String, bms: &[u8]) {
+    field(out, bms, 1, 3, "section_length", Kind::Unsigned);
+    field(out, bms, 4, 4, "number_of_unused_bits_at_end_of_section3", Kind::Unsigned);
+    field(out, bms, 5, 6, "table_reference", Kind::Unsigned);
+    octets(out, bms, 7, "bitmap");
+}
+
+fn dump_bds(out: &mut String, bds: &[u8]) {
+    field(out, bds, 1, 3, "section_length", Kind::Unsigned);
+    field(out, bds, 4, 4, "data_flag", Kind::Flags);
+    field(out, bds, 5, 6, "binary_scale_factor", Kind::Signed);
+    field(out, bds, 7, 10, "reference_value", Kind::Ibm);
+    field(out, bds, 11, 11, "bits_per_value", Kind::Unsigned);
+    octets(out, bds, 12, "packed_data");
+}
+
+fn header(out: &mut String, number: u8, name: &str, length: usize) {
+    let _ = writeln!(out, "===== Section {}: {} (length {}) =====", number, name, length);
+}
+
+// Write a single annotated field spanning octets `start` to `end` (1-based, inclusive)
+fn field(out: &mut String, section: &[u8], start: usize, end: usize, name: &str, kind: Kind) {
+    let range = if start == end { format!("{}", start) } else { format!("{}-{}", start, end) };
+    let bytes = match start.checked_sub(1).and_then(|first| section.get(first..end)) {
+        Some(bytes) => bytes,
+        None => {
+            let _ = writeln!(out, "{:<9} {} = <missing>", range, name);
+            return;
+        }
+    };
+
+    let value = match kind {
+        Kind::Unsigned => bytes.iter().fold(0u64, |acc, b| (acc << 8) + *b as u64).to_string(),
+        Kind::Signed => read_i16_be(bytes).to_string(),
+        Kind::Millidegrees => format!("{}", read_i24_be(bytes) as f64 * 0.001),
+        Kind::Ibm => format!("{}", read_f32_ibm(bytes)),
+        Kind::Flags => format!("{:08b}", bytes[0]),
+        Kind::Text => bytes.iter().map(|b| if b.is_ascii_graphic() { *b as char } else { '.' }).collect(),
+    };
+
+    let _ = writeln!(out, "{:<9} {} = {} [{}]", range, name, value, hex(bytes));
+}
+
+// Summarise a run of octets from `start` to the end of the section, showing the first few
+fn octets(out: &mut String, section: &[u8], start: usize, name: &str) {
+    let bytes = section.get(start - 1..).unwrap_or(&[]);
+    if bytes.is_empty() {
+        return;
+    }
+
+    let shown = &bytes[..bytes.len().min(16)];
+    let more = if bytes.len() > shown.len() { " ..." } else { "" };
+    let _ = writeln!(out, "{:<9} {} = ({} octets) [{}{}]", format!("{}-{}", start, section.len()), name, bytes.len(), hex(shown), more);
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::{writer, Grib, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn dump_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let bytes = reader.read_binary(vec![SearchParams { param: 34, level: 700 }]).await?;
+
+        let text = dump(&bytes)?;
+        assert!(text.contains("9         indicator_of_parameter_and_units = 34 [22]"));
+        assert!(text.contains("11-12     level_or_layer_value = 700 [02 bc]"));
+        assert!(text.contains("===== Section 5: End section (length 4) ====="));
+        assert!(text.contains("end_marker = 7777"));
+        assert!(!text.contains("!!"));
+
+        // A truncated message is still dumped as far as possible
+        let text = dump(&bytes[..100])?;
+        assert!(text.contains("!! Section is truncated"));
+
+        // The end section of a large message is found from the lengths as resolved
+        let message = Grib::from_bytes(&bytes)?;
+        let pds = writer::encode_pds(&message.pds, true, false);
+        let gds = writer::encode_gds(message.gds.as_ref().unwrap())?;
+        let large = writer::finalize(&pds, Some(&gds), None, &vec![0; 0x80_0000 + 100])?;
+        let text = dump(&large)?;
+        assert!(text.contains(&format!("===== Section 4: Binary data section (length {}) =====", large.len() - 8 - pds.len() - gds.len() - 4)));
+        assert!(text.contains("end_marker = 7777"));
+        assert!(!text.contains("!!"));
+
+        // An edition 0 message, with a 4 octet indicator section and a 24 octet PDS
+        let mut edition0 = b"GRIB".to_vec();
+        edition0.extend_from_slice(&[0, 0, 24]);
+        edition0.extend_from_slice(&bytes[8 + 3..8 + 24]);
+        edition0.extend_from_slice(&bytes[8 + 28..]);
+        edition0[7] = 0;
+        let text = dump(&edition0)?;
+        assert!(text.contains("===== Section 0: Indicator section (length 4) ====="));
+        assert!(text.contains("9         indicator_of_parameter_and_units = 34 [22]"));
+        assert!(text.contains("===== Section 1: Product definition section (length 24) ====="));
+        assert!(!text.contains("total_length") && !text.contains("decimal_scale_factor"));
+        assert!(text.contains("end_marker = 7777"));
+        assert!(!text.contains("!!"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Decoding messages a second time with the ecCodes C library, linked from the system, and comparing the values with those of
+//! this crate, to check that unusual files are read correctly. Only the values are compared, within the quantisation of the packing.
+
+use crate::compare::{missing_as_nan, values_close, FieldMismatch};
+use crate::error::Grib1Error;
+use crate::merge::split_messages;
+use crate::Grib;
+use std::ffi::{c_char, c_int, c_long, c_void, CStr};
+use std::path::Path;
+
+#[link(name = "eccodes")]
+extern "C" {
+    fn codes_handle_new_from_message(context: *mut c_void, data: *const c_void, data_len: usize) -> *mut c_void;
+    fn codes_handle_delete(handle: *mut c_void) -> c_int;
+    fn codes_get_long(handle: *const c_void, key: *const c_char, value: *mut c_long) -> c_int;
+    fn codes_get_double(handle: *const c_void, key: *const c_char, value: *mut f64) -> c_int;
+    fn codes_get_size(handle: *const c_void, key: *const c_char, size: *mut usize) -> c_int;
+    fn codes_get_double_array(handle: *const c_void, key: *const c_char, values: *mut f64, length: *mut usize) -> c_int;
+    fn codes_get_error_message(code: c_int) -> *const c_char;
+}
+
+// A handle of ecCodes on a message it doesn't own, deleted when dropped
+struct Handle<'a> {
+    handle: *mut c_void,
+    _message: &'a [u8],
+}
+
+impl<'a> Handle<'a> {
+    fn new(message: &'a [u8]) -> Result<Handle<'a>, Grib1Error> {
+        // SAFETY: the message outlives the handle, which only reads it
+        let handle = unsafe { codes_handle_new_from_message(std::ptr::null_mut(), message.as_ptr() as *const c_void, message.len()) };
+        if handle.is_null() {
+            return Err(Grib1Error::Eccodes("the message couldn't be opened".to_string()));
+        }
+        Ok(Handle { handle, _message: message })
+    }
+
+    fn long(&self, key: &CStr) -> Result<c_long, Grib1Error> {
+        let mut value = 0;
+        // SAFETY: the handle is valid and the key terminated
+        check(unsafe { codes_get_long(self.handle, key.as_ptr(), &mut value) })?;
+        Ok(value)
+    }
+
+    fn double(&self, key: &CStr) -> Result<f64, Grib1Error> {
+        let mut value = 0.0;
+        // SAFETY: as for `long`
+        check(unsafe { codes_get_double(self.handle, key.as_ptr(), &mut value) })?;
+        Ok(value)
+    }
+
+    fn doubles(&self, key: &CStr) -> Result<Vec<f64>, Grib1Error> {
+        let mut size = 0;
+        // SAFETY: as for `long`, and the array holds `size` values
+        check(unsafe { codes_get_size(self.handle, key.as_ptr(), &mut size) })?;
+        let mut values = vec![0.0; size];
+        check(unsafe { codes_get_double_array(self.handle, key.as_ptr(), values.as_mut_ptr(), &mut size) })?;
+        values.truncate(size);
+        Ok(values)
+    }
+}
+
+impl Drop for Handle<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the handle is valid and deleted once
+        unsafe { codes_handle_delete(self.handle) };
+    }
+}
+
+fn check(code: c_int) -> Result<(), Grib1Error> {
+    if code == 0 {
+        return Ok(());
+    }
+    // SAFETY: ecCodes returns a static, terminated message for every code
+    let message = unsafe { CStr::from_ptr(codes_get_error_message(code)) };
+    Err(Grib1Error::Eccodes(message.to_string_lossy().into_owned()))
+}
+
+/// The values of a single raw message as decoded by ecCodes, in the order of the message with missing points as NaN
+pub fn eccodes_values(message: &[u8]) -> Result<Vec<f32>, Grib1Error> {
+    let handle = Handle::new(message)?;
+    let values = handle.doubles(c"values")?;
+    let missing = match handle.long(c"bitmapPresent")? {
+        0 => None,
+        _ => Some(handle.double(c"missingValue")?),
+    };
+    Ok(values.into_iter().map(|value| if Some(value) == missing { f32::NAN } else { value as f32 }).collect())
+}
+
+/// Decode a single raw message with this crate and with ecCodes, giving the first point where the values differ by more than
+/// the quantisation of the packing, or None if they agree
+pub fn cross_validate(message: &[u8]) -> Result<Option<FieldMismatch>, Grib1Error> {
+    let grib = Grib::from_bytes(message)?;
+    let Some((values, quantisation)) = missing_as_nan(&grib) else {
+        return Ok(Some(FieldMismatch::NoData));
+    };
+    Ok(values_close(&values, &eccodes_values(message)?, quantisation, 1e-6).err())
+}
+
+/// Cross-validate every message of a file, giving the index of each message that doesn't agree with the mismatch found
+pub fn cross_validate_file(path: impl AsRef<Path>) -> Result<Vec<(usize, FieldMismatch)>, Grib1Error> {
+    let bytes = std::fs::read(path)?;
+    let mut result = vec![];
+    for (index, message) in split_messages(&bytes)?.into_iter().enumerate() {
+        if let Some(mismatch) = cross_validate(message)? {
+            result.push((index, mismatch));
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eccodes_test() -> Result<(), Grib1Error> {
+        assert_eq!(cross_validate_file("data/sample.grib")?, vec![]);
+
+        let bytes = std::fs::read("data/sample.grib")?;
+        let message = split_messages(&bytes)?[0];
+        assert_eq!(eccodes_values(message)?.len(), 1189 * 1069);
+        assert!(matches!(eccodes_values(&message[..1000]), Err(Grib1Error::Eccodes(_))));
+        Ok(())
+    }
+}
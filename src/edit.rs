@@ -0,0 +1,110 @@
+//! Editing the PDS of an existing message in place, leaving every other section untouched.
+
+use crate::error::{Grib1Error, PdsError};
+use crate::time::DateTime;
+
+#[derive(Debug, Clone, Default)]
+/// The PDS fields to change. Fields left as None keep their original value.
+pub struct PdsEdit {
+    pub parameter_table_version_number: Option<u8>,
+    pub identification_of_center: Option<u8>,
+    pub identification_of_sub_center: Option<u8>,
+    pub generating_process_id_number: Option<u8>,
+    pub indicator_of_parameter_and_units: Option<u8>,
+    pub indicator_of_type_of_level_or_layer: Option<u8>,
+    pub level_or_layer_value: Option<u16>,
+    pub reference_time: Option<DateTime>,
+    pub forecast_time_unit: Option<u8>,
+    pub p1_period_of_time: Option<u8>,
+    pub p2_period_of_time: Option<u8>,
+    pub time_range_indicator: Option<u8>,
+}
+
+/// Apply the edit to a single raw message and return the corrected bytes. Only the PDS octets are changed.
+pub fn edit_pds(message: &[u8], edit: &PdsEdit) -> Result<Vec<u8>, Grib1Error> {
+    if message.len() < 8 || &message[0..4] != b"GRIB" {
+        return Err(Grib1Error::WrongHeader);
+    }
+    if message[7] != 1 {
+        return Err(Grib1Error::WrongVersion(message[7]));
+    }
+    if message.len() < 8 + 28 || crate::codec::read_u24_be(&message[8..]) < 28 {
+        let got = (crate::codec::read_u24_be(&message[8..]) as usize).min(message.len() - 8);
+        return Err(PdsError::TooShort { needed: 28, got }.into());
+    }
+
+    let mut result = message.to_vec();
+    let pds = &mut result[8..8 + 28];
+
+    let octets = [
+        (3, edit.parameter_table_version_number),
+        (4, edit.identification_of_center),
+        (5, edit.generating_process_id_number),
+        (8, edit.indicator_of_parameter_and_units),
+        (9, edit.indicator_of_type_of_level_or_layer),
+        (17, edit.forecast_time_unit),
+        (18, edit.p1_period_of_time),
+        (19, edit.p2_period_of_time),
+        (20, edit.time_range_indicator),
+        (25, edit.identification_of_sub_center),
+    ];
+    for (index, value) in octets {
+        if let Some(value) = value {
+            pds[index] = value;
+        }
+    }
+
+    if let Some(level) = edit.level_or_layer_value {
+        pds[10..12].copy_from_slice(&level.to_be_bytes());
+    }
+
+    if let Some(time) = edit.reference_time {
+        if time.year < 1 {
+            return Err(PdsError::InvalidField {
+                octet: 13,
+                field: "year",
+                reason: format!("the year {} can't be represented", time.year),
+            }
+            .into());
+        }
+
+        // The year is stored as year of century (1-100) and century
+        let century = (time.year - 1) / 100 + 1;
+        pds[12] = (time.year - (century - 1) * 100) as u8;
+        pds[13..17].copy_from_slice(&[time.month, time.day, time.hour, time.minute]);
+        pds[24] = century as u8;
+    }
+
+    Ok(result)
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::{Grib, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn edit_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let bytes = reader.read_binary(vec![SearchParams { param: 33, level: 700 }]).await?;
+
+        let edit = PdsEdit {
+            indicator_of_parameter_and_units: Some(131),
+            reference_time: Some(DateTime::new(2000, 1, 2, 3, 0)),
+            ..Default::default()
+        };
+        let edited = edit_pds(&bytes, &edit)?;
+        assert_eq!(edited.len(), bytes.len());
+        assert_eq!(edited[8 + 28..], bytes[8 + 28..]);
+
+        let grib = Grib::from_bytes(&edited)?;
+        assert_eq!(grib.pds.indicator_of_parameter_and_units, 131);
+        assert_eq!(grib.pds.level_or_layer_value, 700);
+        assert_eq!(grib.pds.reference_time(), DateTime::new(2000, 1, 2, 3, 0));
+
+        Ok(())
+    }
+}
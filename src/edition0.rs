@@ -0,0 +1,65 @@
+//! Compatibility with GRIB edition 0, used in archives from before 1991.
+//!
+//! Edition 0 messages have a 4 octet indicator section without the total length, so the length is the sum of the sections.
+//! The PDS is 24 octets, lacking the century, sub-center and decimal scale factor of edition 1. It is extended to the edition 1 layout
+//! when read, with the reference time in the 20th century, sub-center 0 and no decimal scaling, so the rest of the library can treat both alike.
+
+/// Octet 8 of a message is the edition number, but in edition 0 it's the fourth octet of the PDS, which is always zero
+pub(crate) fn is_edition0(bytes: &[u8]) -> bool {
+    bytes.get(7) == Some(&0)
+}
+
+/// The PDS of an edition 0 message in the layout of edition 1
+pub(crate) fn to_edition1_pds(pds: &[u8]) -> Vec<u8> {
+    let mut result = pds.to_vec();
+    result.resize(28.max(pds.len()), 0);
+    if pds.len() < 28 {
+        // Only the 24 octets of edition 0 are copied, the length field must agree with the rest
+        result[..3].copy_from_slice(&[0, 0, 28]);
+        result[24] = 20;
+        result[25..28].fill(0);
+    }
+    result
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use crate::error::Grib1Error;
+    use crate::{Grib, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn edition0_test() -> Result<(), Grib1Error> {
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let original = Grib::from_bytes(&sample[..2542704])?;
+
+        // The first message of the sample rewritten as edition 0: no length in the indicator section and a 24 octet PDS
+        let mut message = b"GRIB".to_vec();
+        message.extend_from_slice(&[0, 0, 24]);
+        message.extend_from_slice(&sample[8 + 3..8 + 24]);
+        message.extend_from_slice(&sample[8 + 28..2542704]);
+        message[7] = 0;
+
+        let grib = Grib::from_bytes(&message)?;
+        assert_eq!(grib.length as usize, message.len());
+        assert_eq!(grib.pds.reference_time().year, 1923);
+        assert_eq!(grib.bds.as_ref().unwrap().data, original.bds.as_ref().unwrap().data);
+
+        let mut bytes = message.clone();
+        bytes.extend_from_slice(&sample[2542704..]);
+        let path = std::env::temp_dir().join("grib1_reader_edition0_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?)).strict(true);
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }, SearchParams { param: 34, level: 700 }]).await?;
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].length as usize, message.len());
+        assert_eq!(result[1].offset as usize, message.len());
+        assert_eq!(reader.read_raw(&result[0]).await?, message);
+        assert_eq!(crate::merge::split_messages(&bytes)?.len(), 2);
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
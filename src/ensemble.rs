@@ -0,0 +1,138 @@
+//! Ensemble forecasts: identifying the member a message belongs to from the local extension of its PDS, and statistics of the members
+//! at each grid point as new in-memory fields. The extensions understood are those of NCEP (centre 7) and ECMWF local definition 1 (centre 98).
+
+use crate::error::Grib1Error;
+use crate::sections::sections;
+use crate::Grib;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The member of an ensemble a message belongs to
+pub struct EnsembleMember {
+    /// 0 for the control forecast, otherwise the number of the perturbed forecast
+    pub number: u16,
+    /// Whether the forecast is perturbed negatively. NCEP gives the negatively and positively perturbed forecasts of a pair the same number.
+    pub negative: bool,
+    /// Number of members of the ensemble, if given
+    pub size: Option<u16>,
+}
+
+/// The member a single raw message belongs to, or None if the PDS has no ensemble extension that is understood
+pub fn ensemble_member(message: &[u8]) -> Result<Option<EnsembleMember>, Grib1Error> {
+    let sections = sections(message)?;
+    let Some(pds) = sections.iter().find(|section| section.id == 1) else {
+        return Ok(None);
+    };
+    // Octet 5 is the centre and octet 41 the kind of extension, 1 for ensembles at NCEP and for local definition 1 at ECMWF
+    let member = match (pds.octet(5), pds.octet(41)) {
+        // Octet 42 is 1 for the control forecast and 2 or 3 for forecasts perturbed negatively or positively. Octet 43 tells
+        // the control forecast at high resolution from that at low resolution, and numbers the perturbed forecasts.
+        (Some(7), Some(1)) => match (pds.octet(42), pds.octet(43)) {
+            (Some(1), _) => Some(EnsembleMember { number: 0, negative: false, size: None }),
+            (Some(kind @ (2 | 3)), Some(number)) => Some(EnsembleMember {
+                number: number as u16,
+                negative: kind == 2,
+                size: None,
+            }),
+            _ => None,
+        },
+        // Octet 50 is the number of the member and octet 51 the number of members
+        (Some(98), Some(1)) => match (pds.octet(50), pds.octet(51)) {
+            (Some(number), Some(size)) => Some(EnsembleMember {
+                number: number as u16,
+                negative: false,
+                size: Some(size as u16),
+            }),
+            _ => None,
+        },
+        _ => None,
+    };
+    Ok(member)
+}
+
+/// The mean of the members at each point. Points missing from some members are averaged over the others.
+pub fn ensemble_mean(members: &[Grib]) -> Result<Grib, Grib1Error> {
+    per_point(members, |values| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// The spread of the members at each point, their standard deviation
+pub fn ensemble_spread(members: &[Grib]) -> Result<Grib, Grib1Error> {
+    per_point(members, |values| {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+    })
+}
+
+/// The fraction of members above the threshold at each point, from 0 to 1
+pub fn exceedance_probability(members: &[Grib], threshold: f32) -> Result<Grib, Grib1Error> {
+    per_point(members, |values| values.iter().filter(|value| **value > threshold as f64).count() as f64 / values.len() as f64)
+}
+
+// Apply the statistic to the values present at each point, which is missing if no member has a value. The PDS is that of the first member.
+fn per_point<F: Fn(&[f64]) -> f64>(members: &[Grib], statistic: F) -> Result<Grib, Grib1Error> {
+    let Some(first) = members.first() else {
+        return Err(Grib1Error::IncompatibleFields("there are no members".to_string()));
+    };
+    for member in &members[1..] {
+        first.check_same_grid(member)?;
+    }
+
+    let bds = first.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let mut values = Vec::with_capacity(members.len());
+    let mut data = Vec::with_capacity(bds.data.len());
+    for i in 0..bds.data.len() {
+        values.clear();
+        for member in members {
+            let member_bds = member.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+            let value = member_bds.data[i];
+            if !member_bds.is_missing(value) {
+                values.push(value as f64);
+            }
+        }
+        data.push(if values.is_empty() { f32::NAN } else { statistic(&values) as f32 });
+    }
+    Ok(first.with_data(bds, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::missing::MissingValues;
+    use crate::synthetic::MessageBuilder;
+
+    #[test]
+    fn ensemble_test() -> Result<(), Grib1Error> {
+        let ncep = |kind, number| MessageBuilder::new(2, 1).pds(|pds| pds.identification_of_center = 7).local_extension(vec![1, kind, number, 1, 255]).build();
+        let member = |number, negative, size| Some(EnsembleMember { number, negative, size });
+        assert_eq!(ensemble_member(&ncep(1, 2)?)?, member(0, false, None));
+        assert_eq!(ensemble_member(&ncep(3, 4)?)?, member(4, false, None));
+        // A negatively perturbed forecast isn't the control forecast, nor its positive counterpart
+        assert_eq!(ensemble_member(&ncep(2, 4)?)?, member(4, true, None));
+        let mut ecmwf = vec![1, 1, 11, 3, 243, b'0', b'0', b'0', b'1', 12, 51];
+        ecmwf.resize(12, 0);
+        let message = MessageBuilder::new(2, 1).pds(|pds| pds.identification_of_center = 98).local_extension(ecmwf).build()?;
+        assert_eq!(ensemble_member(&message)?, member(12, false, Some(51)));
+        assert_eq!(ensemble_member(&MessageBuilder::new(2, 1).build()?)?, None);
+
+        let mut members = [vec![1.0, f32::NAN], vec![2.0, f32::NAN], vec![6.0, 4.0]]
+            .into_iter()
+            .map(|values| MessageBuilder::new(2, 1).decimal_scale_factor(1).values(values).grib())
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(ensemble_mean(&members)?.bds.unwrap().data, vec![3.0, 4.0]);
+        let spread = ensemble_spread(&members)?.bds.unwrap().data;
+        assert!((spread[0] - (14f32 / 3.0).sqrt()).abs() < 1e-5 && spread[1] == 0.0);
+        assert_eq!(exceedance_probability(&members, 1.5)?.bds.unwrap().data, vec![2.0 / 3.0, 1.0]);
+
+        assert!(ensemble_mean(&[MessageBuilder::new(2, 1).grib()?, MessageBuilder::new(1, 2).grib()?]).is_err());
+
+        // A point missing from every member read with a sentinel is given as the sentinel
+        for member in &mut members[..2] {
+            if let Some(bds) = member.bds.as_mut() {
+                bds.set_missing_values(MissingValues::Sentinel(9999.0));
+            }
+        }
+        let mean = ensemble_mean(&members[..2])?.bds.unwrap();
+        assert_eq!(mean.data, vec![1.5, 9999.0]);
+        assert_eq!(mean.optional_values(), vec![Some(1.5), None]);
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
+use crate::core::ParseError;
+pub use crate::core::{BdsError, BmsError, GdsError, PdsError};
+use std::io;
 use thiserror::Error;
-use tokio::io;
 
 #[derive(Error, Debug)]
 /// List of errors the library can return when reading a GRIB file
@@ -16,7 +18,152 @@ pub enum Grib1Error {
     /// The contained version number didn't match 0x01
     WrongVersion(u8),
 
-    #[error("Tried to decode more data than we have")]
-    /// The bitstream representing the data didn't have the expected length
-    DataDecodeFailed,
+    #[error("Tried to decode more data than we have, got {decoded} of {expected_points} values")]
+    /// The bitstream representing the data didn't have the expected length, or its packing couldn't be understood
+    DataDecodeFailed { expected_points: usize, decoded: usize },
+
+    #[error("The message doesn't contain a {0} section")]
+    /// A section needed for the operation is missing, or the data wasn't decoded
+    MissingSection(&'static str),
+
+    #[error("Unsupported grid type {0}")]
+    /// The data representation type of the GDS isn't one the library can handle
+    UnsupportedGrid(u8),
+
+    #[error("No GRIB2 equivalent for {0} {1}")]
+    /// A value couldn't be mapped onto the GRIB2 code tables when converting
+    NoGrib2Equivalent(&'static str, u8),
+
+    #[error("Invalid configuration: {0}")]
+    /// A configuration file couldn't be parsed
+    InvalidConfig(String),
+
+    #[error("Invalid naming template: {0}")]
+    /// A template used to name output files couldn't be expanded
+    InvalidTemplate(String),
+
+    #[error("Malformed message: {0}")]
+    /// The bytes given as a message don't form a complete GRIB1 message
+    MalformedMessage(String),
+
+    #[error("Expected {expected} values but got {actual}")]
+    /// The number of values doesn't match the number of points in the grid
+    ValueCountMismatch { expected: usize, actual: usize },
+
+    #[error("Unsupported number of bits per value: {0}")]
+    /// Values can only be packed using 1 to 32 bits
+    UnsupportedBitsPerValue(u8),
+
+    #[error("The value at index {0} is infinite")]
+    /// Infinite values can't be packed, NaN marks a missing value
+    NonFiniteValue(usize),
+
+    #[error("The message would be {0} bytes, which doesn't fit in the indicator section")]
+    /// The encoded message is too large for the length field, even using the large message convention
+    MessageTooLarge(usize),
+
+    #[error("The region doesn't contain any grid points")]
+    /// Cropping to a region would leave nothing
+    RegionOutsideGrid,
+
+    #[error("Invalid grid definition: {0}")]
+    InvalidGrid(String),
+
+    #[error("Unsupported packing, data flag {0:#010b}")]
+    /// The BDS uses a packing method that can't be handled
+    UnsupportedPacking(u8),
+
+    #[error("The value at index {0} is outside the range given for packing")]
+    ValueOutOfRange(usize),
+
+    #[error("Invalid range of values for packing: {0} to {1}")]
+    InvalidRange(f32, f32),
+
+    #[error("The fields are not compatible: {0}")]
+    /// Element-wise operations need fields on the same grid
+    IncompatibleFields(String),
+
+    #[error("The sections of the message at offset {offset} add up to {actual} octets, but the indicator section says {expected}")]
+    /// The section lengths are inconsistent with the total length
+    LengthMismatch { offset: u64, expected: u64, actual: u64 },
+
+    #[error("The message at offset {0} doesn't end with 7777")]
+    MissingEndSection(u64),
+
+    #[error("The message at offset {offset} failed validation of the {section}: {reason}")]
+    /// A check made by the strict reader failed
+    ValidationFailed { offset: u64, section: &'static str, reason: String },
+
+    #[error("The message at offset {offset} is cut short, {missing_bytes} bytes are missing")]
+    /// The file ends before the message does, e.g. because it is still being written or the transfer was interrupted
+    Truncated { offset: u64, missing_bytes: u64 },
+
+    #[error("{0}")]
+    /// A problem with the product definition section
+    Pds(#[from] PdsError),
+
+    #[error("{0}")]
+    /// A problem with the grid description section
+    Gds(#[from] GdsError),
+
+    #[error("{0}")]
+    /// A problem with the bit-map section
+    Bms(#[from] BmsError),
+
+    #[error("{0}")]
+    /// A problem with the binary data section
+    Bds(#[from] BdsError),
+
+    #[error("Message {message_index} at offset {offset}, in the {section}: {source}")]
+    /// An error while reading a message of a file, with where it happened. `message_index` counts every message from the start of the file.
+    InMessage { message_index: usize, offset: u64, section: &'static str, source: Box<Grib1Error> },
+
+    #[error("GRIB edition {0} isn't supported, only edition 1")]
+    /// The message is of another GRIB edition, e.g. 2. The reader can skip GRIB2 messages using `skip_grib2`.
+    UnsupportedEdition(u8),
+
+    #[error("The {what} needs {requested}, more than the limit of {limit}")]
+    /// Reading the message would allocate more than allowed by the `Limits` of the reader
+    ResourceLimitExceeded { what: &'static str, requested: u64, limit: u64 },
+
+    #[error("Invalid index: {0}")]
+    /// An index file couldn't be read, e.g. because it was written by another program
+    InvalidIndex(String),
+
+    #[error("Unknown parameter: {0}")]
+    /// A parameter was given by an abbreviation that isn't in code table 2
+    UnknownParameter(String),
+
+    #[error("{parameter} is in {units}, not in {expected}")]
+    /// The values of a parameter were asked for as a quantity its units in code table 2 aren't
+    UnexpectedUnits { parameter: String, units: &'static str, expected: &'static str },
+
+    #[error("ecCodes: {0}")]
+    /// ecCodes failed to decode a message being cross-validated
+    Eccodes(String),
+}
+
+impl Grib1Error {
+    /// The underlying error, without the location added when reading a file
+    pub fn root_cause(&self) -> &Grib1Error {
+        match self {
+            Grib1Error::InMessage { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+}
+
+impl From<ParseError> for Grib1Error {
+    fn from(error: ParseError) -> Grib1Error {
+        match error {
+            ParseError::Pds(error) => Grib1Error::Pds(error),
+            ParseError::Gds(error) => Grib1Error::Gds(error),
+            ParseError::Bms(error) => Grib1Error::Bms(error),
+            ParseError::Bds(error) => Grib1Error::Bds(error),
+            ParseError::MalformedMessage(reason) => Grib1Error::MalformedMessage(reason),
+            ParseError::ValueCountMismatch { expected, actual } => Grib1Error::ValueCountMismatch { expected, actual },
+            ParseError::DataDecodeFailed { expected_points, decoded } => Grib1Error::DataDecodeFailed { expected_points, decoded },
+            ParseError::ResourceLimitExceeded { what, requested, limit } => Grib1Error::ResourceLimitExceeded { what, requested, limit },
+        }
+    }
 }
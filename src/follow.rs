@@ -0,0 +1,175 @@
+//! Following a file that is being written, e.g. the output of a running model, decoding each message once all of it is there.
+//! The end of the file is polled, so it works on any file system, local or not.
+//!
+//! ```no_run
+//! # async fn example(reader: &mut grib1_reader::Grib1Reader) -> Result<(), grib1_reader::error::Grib1Error> {
+//! let mut follow = reader.follow();
+//! loop {
+//!     let grib = follow.next().await?;
+//!     println!("{} at offset {}", grib.pds.indicator_of_parameter_and_units, grib.offset);
+//! }
+//! # }
+//! ```
+
+use crate::error::Grib1Error;
+use crate::{Grib, Grib1Reader, GribResult, PDS};
+use std::io::SeekFrom;
+use std::time::Duration;
+
+/// How long to wait before looking at the end of the file again unless set otherwise
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The messages of a file as they are written, returned by `Grib1Reader::follow`
+pub struct Follow<'a> {
+    reader: &'a mut Grib1Reader,
+    offset: u64,
+    message_index: usize,
+    poll_interval: Duration,
+}
+
+impl Grib1Reader {
+    /// Go through the messages of the file from the start, waiting for more to be written once the end is reached
+    pub fn follow(&mut self) -> Follow<'_> {
+        Follow {
+            reader: self,
+            offset: 0,
+            message_index: 0,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+}
+
+impl Follow<'_> {
+    /// How long to wait between looking for more of the file
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Start at the offset rather than at the start of the file, e.g. at the end of the messages read before
+    pub fn from_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Position in the file of the message after the last one returned
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The next message, waiting until all of it has been written. GRIB2 messages passed over by `Grib1Reader::skip_grib2` aren't returned,
+    /// and neither is whatever is between the messages of a file read with `Grib1Reader::gts`.
+    pub async fn next(&mut self) -> Result<Grib, Grib1Error> {
+        loop {
+            let length = self.reader.seek(SeekFrom::End(0)).await?;
+            // The indicator section is 16 octets at most, and its length is known once all of it is there
+            if self.offset + 16 <= length {
+                match self.reader.declared_length(self.offset).await {
+                    Ok(Some(message_length)) if self.offset + message_length <= length => {
+                        let (offset, message_index) = (self.offset, self.message_index);
+                        self.reader.seek(SeekFrom::Start(offset)).await?;
+                        let result = self.reader.read_grib(message_index, offset, offset + message_length, &|_: &PDS| true, true).await?;
+                        self.message_index += 1;
+                        self.offset += message_length;
+                        match result {
+                            GribResult::Grib(grib) => return Ok(*grib),
+                            GribResult::Length(_) => continue,
+                        }
+                    }
+                    // The message isn't all there, or not even the headers of its sections
+                    Ok(Some(_)) | Err(Grib1Error::Truncated { .. }) => {}
+                    Ok(None) if self.reader.gts => {
+                        if let Some(next) = self.reader.find_marker(self.offset, length).await? {
+                            self.offset = next;
+                            continue;
+                        }
+                    }
+                    Ok(None) => return Err(Grib1Error::WrongHeader),
+                    Err(error) => return Err(error),
+                }
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn follow_test() -> Result<(), Grib1Error> {
+        let bytes = std::fs::read("data/sample.grib")?;
+        let path = std::env::temp_dir().join("grib1_reader_follow_test.grb");
+        std::fs::write(&path, &bytes[..2542704])?;
+
+        // The second message is written in three parts while the first is being read
+        let writer = {
+            let (path, bytes) = (path.clone(), bytes.clone());
+            tokio::spawn(async move {
+                let mut file = tokio::fs::OpenOptions::new().append(true).open(&path).await?;
+                for range in [2542704..2542712, 2542712..3000000, 3000000..bytes.len()] {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    file.write_all(&bytes[range]).await?;
+                    file.flush().await?;
+                }
+                Ok::<_, std::io::Error>(())
+            })
+        };
+
+        let mut reader = Grib1Reader::open(&path).await?;
+        let mut follow = reader.follow().poll_interval(Duration::from_millis(5));
+        assert_eq!(follow.next().await?.pds.indicator_of_parameter_and_units, 33);
+        let grib = follow.next().await?;
+        assert_eq!((grib.pds.indicator_of_parameter_and_units, grib.offset), (34, 2542704));
+        assert_eq!(grib.bds.unwrap().data.len(), 1271041);
+        assert_eq!(follow.offset(), bytes.len() as u64);
+        writer.await.unwrap()?;
+
+        // Nothing but a message may follow one unless the file is read as GTS bulletins
+        std::fs::write(&path, [&bytes[..], &[0; 16]].concat())?;
+        let mut reader = Grib1Reader::open(&path).await?;
+        assert!(matches!(reader.follow().from_offset(bytes.len() as u64).next().await, Err(Grib1Error::WrongHeader)));
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn follow_edition0_test() -> Result<(), Grib1Error> {
+        // The first message of the sample rewritten as edition 0, whose length is only known from the headers of its sections
+        let sample = std::fs::read("data/sample.grib")?;
+        let mut message = b"GRIB".to_vec();
+        message.extend_from_slice(&[0, 0, 24]);
+        message.extend_from_slice(&sample[8 + 3..8 + 24]);
+        message.extend_from_slice(&sample[8 + 28..2542704]);
+        message[7] = 0;
+        let path = std::env::temp_dir().join("grib1_reader_follow_edition0_test.grb");
+        std::fs::write(&path, &message[..20])?;
+
+        // The pieces end within the length fields of the GDS at octet 29 and of the BDS at octet 71
+        let writer = {
+            let (path, message) = (path.clone(), message.clone());
+            tokio::spawn(async move {
+                let mut file = tokio::fs::OpenOptions::new().append(true).open(&path).await?;
+                for range in [20..29, 29..71, 71..message.len()] {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    file.write_all(&message[range]).await?;
+                    file.flush().await?;
+                }
+                Ok::<_, std::io::Error>(())
+            })
+        };
+
+        let mut reader = Grib1Reader::open(&path).await?;
+        let mut follow = reader.follow().poll_interval(Duration::from_millis(5));
+        let grib = follow.next().await?;
+        assert_eq!((grib.pds.indicator_of_parameter_and_units, grib.length as usize), (33, message.len()));
+        assert_eq!(follow.offset(), message.len() as u64);
+        writer.await.unwrap()?;
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
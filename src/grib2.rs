@@ -0,0 +1,438 @@
+//! Conversion of decoded GRIB1 messages into GRIB2 messages.
+//! The grid is written using template 3.0 (latitude/longitude) or 3.1 (rotated latitude/longitude), the product using template 4.0 or 4.8 (statistically processed) and the data using simple packing (template 5.0).
+
+use crate::error::Grib1Error;
+use crate::{writer, DataRepresentation, Grib, RotatedLatLon, PDS};
+use bitstream_io::{BigEndian, BitWrite, BitWriter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The GRIB2 way of identifying a parameter
+pub struct Grib2Parameter {
+    pub discipline: u8,
+    pub category: u8,
+    pub number: u8,
+}
+
+/// Map a GRIB1 parameter indicator (code table 2) to the equivalent GRIB2 discipline, category and number
+pub fn grib2_parameter(indicator: u8) -> Option<Grib2Parameter> {
+    let (discipline, category, number) = match indicator {
+        1 => (0, 3, 0),
+        2 => (0, 3, 1),
+        3 => (0, 3, 2),
+        6 => (0, 3, 4),
+        7 => (0, 3, 5),
+        8 => (0, 3, 6),
+        11 => (0, 0, 0),
+        12 => (0, 0, 1),
+        13 => (0, 0, 2),
+        14 => (0, 0, 3),
+        15 => (0, 0, 4),
+        16 => (0, 0, 5),
+        17 => (0, 0, 6),
+        18 => (0, 0, 7),
+        19 => (0, 0, 8),
+        20 => (0, 19, 0),
+        31 => (0, 2, 0),
+        32 => (0, 2, 1),
+        33 => (0, 2, 2),
+        34 => (0, 2, 3),
+        35 => (0, 2, 4),
+        36 => (0, 2, 5),
+        39 => (0, 2, 8),
+        40 => (0, 2, 9),
+        41 => (0, 2, 10),
+        43 => (0, 2, 12),
+        51 => (0, 1, 0),
+        52 => (0, 1, 1),
+        53 => (0, 1, 2),
+        54 => (0, 1, 3),
+        57 => (0, 1, 6),
+        59 => (0, 1, 7),
+        61 => (0, 1, 8),
+        62 => (0, 1, 9),
+        63 => (0, 1, 10),
+        65 => (0, 1, 13),
+        66 => (0, 1, 11),
+        71 => (0, 6, 1),
+        73 => (0, 6, 3),
+        74 => (0, 6, 4),
+        75 => (0, 6, 5),
+        76 => (0, 6, 6),
+        80 => (10, 3, 0),
+        81 => (2, 0, 0),
+        83 => (2, 0, 1),
+        84 => (0, 19, 1),
+        85 => (2, 0, 2),
+        86 => (2, 0, 3),
+        87 => (2, 0, 4),
+        91 => (10, 2, 0),
+        92 => (10, 2, 1),
+        100 => (10, 0, 3),
+        111 => (0, 4, 0),
+        112 => (0, 5, 0),
+        121 => (0, 0, 10),
+        122 => (0, 0, 11),
+        124 => (0, 2, 17),
+        125 => (0, 2, 18),
+        _ => return None,
+    };
+
+    Some(Grib2Parameter { discipline, category, number })
+}
+
+// Fixed surface as type, scale factor and scaled value (code table 4.5)
+type Surface = (u8, u8, u32);
+const MISSING_SURFACE: Surface = (255, 255, u32::MAX);
+
+// Map a GRIB1 level (code table 3) to the first and second GRIB2 fixed surface
+fn grib2_surfaces(pds: &PDS) -> Option<(Surface, Surface)> {
+    let value = pds.level_or_layer_value as u32;
+    let (top, bottom) = (value >> 8, value & 0xff);
+
+    let surfaces = match pds.indicator_of_type_of_level_or_layer {
+        1 => ((1, 0, 0), MISSING_SURFACE),
+        2 => ((2, 0, 0), MISSING_SURFACE),
+        3 => ((3, 0, 0), MISSING_SURFACE),
+        4 => ((4, 0, 0), MISSING_SURFACE),
+        8 => ((8, 0, 0), MISSING_SURFACE),
+        // hPa to Pa
+        100 => ((100, 0, value * 100), MISSING_SURFACE),
+        101 => ((100, 0, top * 1000), (100, 0, bottom * 1000)),
+        102 => ((101, 0, 0), MISSING_SURFACE),
+        103 => ((102, 0, value), MISSING_SURFACE),
+        105 => ((103, 0, value), MISSING_SURFACE),
+        106 => ((103, 0, top * 100), (103, 0, bottom * 100)),
+        107 => ((104, 4, value), MISSING_SURFACE),
+        109 => ((105, 0, value), MISSING_SURFACE),
+        110 => ((105, 0, top), (105, 0, bottom)),
+        // cm to m
+        111 => ((106, 2, value), MISSING_SURFACE),
+        112 => ((106, 2, top), (106, 2, bottom)),
+        200 => ((10, 0, 0), MISSING_SURFACE),
+        _ => return None,
+    };
+
+    Some(surfaces)
+}
+
+// Map a GRIB1 time unit (code table 4) to a GRIB2 one (code table 4.4) along with a multiplier for the amount
+fn grib2_time_unit(unit: u8) -> (u8, u32) {
+    match unit {
+        13 => (0, 15),
+        14 => (0, 30),
+        254 => (13, 1),
+        unit => (unit, 1),
+    }
+}
+
+/// Convert a decoded message into a GRIB2 message, using the built-in parameter mapping of the WMO table.
+/// Parameters of local tables (version 128 and up) aren't mapped, use `convert_with_parameter` for those.
+pub fn convert(grib: &Grib) -> Result<Vec<u8>, Grib1Error> {
+    let version = grib.pds.parameter_table_version_number;
+    if version >= 128 {
+        return Err(Grib1Error::NoGrib2Equivalent("parameter table version", version));
+    }
+    let parameter = grib2_parameter(grib.pds.indicator_of_parameter_and_units).ok_or(Grib1Error::NoGrib2Equivalent("parameter", grib.pds.indicator_of_parameter_and_units))?;
+    convert_with_parameter(grib, parameter)
+}
+
+/// Convert a decoded message into a GRIB2 message, using the supplied parameter. Useful for local parameters the built-in mapping doesn't know about.
+pub fn convert_with_parameter(grib: &Grib, parameter: Grib2Parameter) -> Result<Vec<u8>, Grib1Error> {
+    let gds = grib.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+    grib.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+
+    // A regular grid has the layout of a rotated one without the rotation
+    let (grid, rotated) = match gds.data {
+        DataRepresentation::RegularLatLon(grid) => (grid.into(), false),
+        DataRepresentation::RotatedLatLon(grid) => (grid, true),
+        _ => return Err(Grib1Error::UnsupportedGrid(gds.data_representation_type)),
+    };
+
+    let mut sections = vec![];
+    sections.extend(identification_section(&grib.pds));
+    sections.extend(grid_section(&grid, rotated));
+    sections.extend(product_section(&grib.pds, parameter)?);
+    sections.extend(data_sections(grib)?);
+
+    let total_length = 16 + sections.len() as u64 + 4;
+
+    let mut result = Vec::with_capacity(total_length as usize);
+    result.extend(b"GRIB");
+    result.extend([0, 0, parameter.discipline, 2]);
+    result.extend(total_length.to_be_bytes());
+    result.extend(sections);
+    result.extend(b"7777");
+
+    Ok(result)
+}
+
+impl Grib {
+    /// Convert the message into GRIB2 bytes. The message must have been read including its data.
+    pub fn to_grib2(&self) -> Result<Vec<u8>, Grib1Error> {
+        convert(self)
+    }
+}
+
+fn identification_section(pds: &PDS) -> Vec<u8> {
+    let time = pds.reference_time();
+    let is_analysis = pds.valid_time() == Some(time);
+
+    let mut section = vec![];
+    section.extend((pds.identification_of_center as u16).to_be_bytes());
+    section.extend((pds.identification_of_sub_center as u16).to_be_bytes());
+    // Master tables version 4, no local tables, reference time is the start of the forecast
+    section.extend([4, 0, 1]);
+    section.extend((time.year as u16).to_be_bytes());
+    section.extend([time.month, time.day, time.hour, time.minute, 0]);
+    // Operational products, analysis or forecast
+    section.extend([0, if is_analysis { 0 } else { 1 }]);
+
+    with_header(1, section)
+}
+
+fn grid_section(grid: &RotatedLatLon, rotated: bool) -> Vec<u8> {
+    let number_of_points = grid.number_of_lat_values as u32 * grid.number_of_lon_values as u32;
+
+    let mut section = vec![0];
+    section.extend(number_of_points.to_be_bytes());
+    // No optional list of numbers, template 3.1 or 3.0
+    section.extend([0, 0]);
+    section.extend(if rotated { 1u16 } else { 0u16 }.to_be_bytes());
+
+    // Spherical earth with radius 6367.47 km, as assumed by GRIB1
+    section.extend([0, 255]);
+    section.extend(u32::MAX.to_be_bytes());
+    section.push(255);
+    section.extend(u32::MAX.to_be_bytes());
+    section.push(255);
+    section.extend(u32::MAX.to_be_bytes());
+
+    section.extend((grid.number_of_lat_values as u32).to_be_bytes());
+    section.extend((grid.number_of_lon_values as u32).to_be_bytes());
+    // Basic angle and subdivisions, so the angles are in micro degrees
+    section.extend(0u32.to_be_bytes());
+    section.extend(u32::MAX.to_be_bytes());
+
+    section.extend(signed_u32(micro_degrees(grid.latitude_of_first_grid_point)));
+    section.extend(longitude_u32(grid.longitude_of_first_grid_point));
+
+    let mut flags = 0;
+    if grid.resolution_and_component_flags & 0x80 > 0 {
+        flags |= 0x30;
+    }
+    if grid.resolution_and_component_flags & 0x08 > 0 {
+        flags |= 0x08;
+    }
+    section.push(flags);
+
+    section.extend(signed_u32(micro_degrees(grid.latitude_of_last_grid_point)));
+    section.extend(longitude_u32(grid.longitude_of_last_grid_point));
+    section.extend(increment_u32(grid.i_direction_increment));
+    section.extend(increment_u32(grid.j_direction_increment));
+    section.push(grid.scanning_mode);
+
+    if rotated {
+        section.extend(signed_u32(micro_degrees(grid.latitude_of_southern_pole)));
+        section.extend(longitude_u32(grid.longitude_of_southern_pole));
+        section.extend(grid.angle_of_rotation.to_be_bytes());
+    }
+
+    with_header(3, section)
+}
+
+fn product_section(pds: &PDS, parameter: Grib2Parameter) -> Result<Vec<u8>, Grib1Error> {
+    let (first, second) = grib2_surfaces(pds).ok_or(Grib1Error::NoGrib2Equivalent("level type", pds.indicator_of_type_of_level_or_layer))?;
+    let (unit, multiplier) = grib2_time_unit(pds.forecast_time_unit);
+
+    let statistical_process = match pds.time_range_indicator {
+        0 | 1 | 10 => None,
+        3 => Some(0),
+        4 => Some(1),
+        5 => Some(4),
+        other => return Err(Grib1Error::NoGrib2Equivalent("time range indicator", other)),
+    };
+
+    let forecast_time = match pds.time_range_indicator {
+        10 => ((pds.p1_period_of_time as u32) << 8) + pds.p2_period_of_time as u32,
+        _ => pds.p1_period_of_time as u32,
+    };
+
+    let mut section = vec![0, 0];
+    section.extend(if statistical_process.is_some() { 8u16 } else { 0u16 }.to_be_bytes());
+    section.extend([parameter.category, parameter.number, if pds.valid_time() == Some(pds.reference_time()) { 0 } else { 2 }, 255, pds.generating_process_id_number]);
+    // Observational data cut-off is unknown
+    section.extend([255, 255, 255]);
+    section.push(unit);
+    section.extend((forecast_time * multiplier).to_be_bytes());
+    for (surface_type, scale, value) in [first, second] {
+        section.extend([surface_type, scale]);
+        section.extend(value.to_be_bytes());
+    }
+
+    if let Some(process) = statistical_process {
+        let end = pds.valid_time().ok_or(Grib1Error::NoGrib2Equivalent("time unit", pds.forecast_time_unit))?;
+        section.extend((end.year as u16).to_be_bytes());
+        section.extend([end.month, end.day, end.hour, end.minute, 0]);
+        // One time range with no missing values
+        section.push(1);
+        section.extend(0u32.to_be_bytes());
+        // Successive times have their forecast time incremented
+        section.extend([process, 2, unit]);
+        let length = pds.p2_period_of_time.saturating_sub(pds.p1_period_of_time) as u32;
+        section.extend((length * multiplier).to_be_bytes());
+        section.push(255);
+        section.extend(0u32.to_be_bytes());
+    }
+
+    Ok(with_header(4, section))
+}
+
+fn data_sections(grib: &Grib) -> Result<Vec<u8>, Grib1Error> {
+    let bds = grib.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let values = writer::physical_values(grib)?;
+    let present: Vec<f32> = values.iter().copied().filter(|value| !value.is_nan()).collect();
+
+    // Only the values of the points present are packed
+    let mut section = vec![];
+    section.extend((present.len() as u32).to_be_bytes());
+    section.extend(0u16.to_be_bytes());
+    section.extend(bds.reference_value.to_be_bytes());
+    section.extend(signed_u16(bds.binary_scale_factor as i32));
+    section.extend(signed_u16(grib.pds.decimal_scale_factor as i32));
+    // Original values were floating point
+    section.extend([bds.bits_per_value, 0]);
+    let mut result = with_header(5, section);
+
+    // A bit-map included in the section if any point is missing, or none at all
+    if present.len() < values.len() {
+        let mut bitmap = vec![0u8; 1 + values.len().div_ceil(8)];
+        for (i, value) in values.iter().enumerate() {
+            if !value.is_nan() {
+                bitmap[1 + i / 8] |= 0x80 >> (i % 8);
+            }
+        }
+        result.extend(with_header(6, bitmap));
+    } else {
+        result.extend(with_header(6, vec![255]));
+    }
+
+    // Recover the packed integers from the decoded values and pack them again
+    let factor = 2.0f64.powi(-(bds.binary_scale_factor as i32));
+    let decimal_factor = 10.0f64.powi(grib.pds.decimal_scale_factor as i32);
+    let max = if bds.bits_per_value >= 32 { u32::MAX as f64 } else { ((1u64 << bds.bits_per_value) - 1) as f64 };
+    let mut packed_values = BitWriter::endian(Vec::new(), BigEndian);
+    if bds.bits_per_value > 0 {
+        for value in &present {
+            let packed = ((*value as f64 * decimal_factor - bds.reference_value as f64) * factor).round().clamp(0.0, max);
+            packed_values.write(bds.bits_per_value as u32, packed as u32)?;
+        }
+    }
+    packed_values.byte_align()?;
+    result.extend(with_header(7, packed_values.into_writer()));
+
+    Ok(result)
+}
+
+// Prefix the section content with its length and number
+fn with_header(number: u8, content: Vec<u8>) -> Vec<u8> {
+    let mut section = Vec::with_capacity(content.len() + 5);
+    section.extend((content.len() as u32 + 5).to_be_bytes());
+    section.push(number);
+    section.extend(content);
+    section
+}
+
+fn micro_degrees(degrees: f32) -> i32 {
+    (degrees as f64 * 1000.0).round() as i32 * 1000
+}
+
+fn longitude_u32(degrees: f32) -> [u8; 4] {
+    (micro_degrees(degrees).rem_euclid(360_000_000) as u32).to_be_bytes()
+}
+
+fn increment_u32(millidegrees: u16) -> [u8; 4] {
+    if millidegrees == u16::MAX {
+        return u32::MAX.to_be_bytes();
+    }
+    (millidegrees as u32 * 1000).to_be_bytes()
+}
+
+// GRIB2 uses sign and magnitude for negative numbers, just like GRIB1
+fn signed_u32(value: i32) -> [u8; 4] {
+    let magnitude = value.unsigned_abs() & 0x7fff_ffff;
+    (if value < 0 { magnitude | 0x8000_0000 } else { magnitude }).to_be_bytes()
+}
+
+fn signed_u16(value: i32) -> [u8; 2] {
+    let magnitude = value.unsigned_abs() as u16 & 0x7fff;
+    (if value < 0 { magnitude | 0x8000 } else { magnitude }).to_be_bytes()
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::synthetic::MessageBuilder;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn grib2_conversion_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+
+        // The sample uses a local parameter table, so the parameter is given: the u-component of wind
+        let wind = Grib2Parameter { discipline: 0, category: 2, number: 2 };
+        let bytes = convert_with_parameter(&result[0], wind)?;
+        assert_eq!(&bytes[0..4], b"GRIB");
+        assert_eq!(bytes[7], 2);
+        assert_eq!(u64::from_be_bytes(bytes[8..16].try_into().unwrap()), bytes.len() as u64);
+        assert_eq!(&bytes[bytes.len() - 4..], b"7777");
+
+        // Walk the sections and make sure they follow each other in order
+        let mut offset = 16;
+        let mut numbers = vec![];
+        while offset < bytes.len() - 4 {
+            let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+            numbers.push(bytes[offset + 4]);
+            offset += length;
+        }
+        assert_eq!(numbers, vec![1, 3, 4, 5, 6, 7]);
+        assert_eq!(offset, bytes.len() - 4);
+
+        // Missing points are left out of the packed values and marked in a bit-map
+        let bds = result[0].bds.as_ref().unwrap();
+        let mut data = bds.data.clone();
+        data[1] = f32::NAN;
+        data[9] = f32::NAN;
+        let with_bitmap = crate::Grib::from_bytes(&crate::writer::encode(&result[0].pds, result[0].gds.as_ref().unwrap(), &data, bds.bits_per_value)?)?;
+        assert!(with_bitmap.bitmap.is_some());
+        let bytes = convert_with_parameter(&with_bitmap, wind)?;
+        let mut sections = std::collections::HashMap::new();
+        let mut offset = 16;
+        while offset < bytes.len() - 4 {
+            let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+            sections.insert(bytes[offset + 4], &bytes[offset..offset + length]);
+            offset += length;
+        }
+        let present = bds.data.len() - 2;
+        assert_eq!(u32::from_be_bytes(sections[&5][5..9].try_into().unwrap()) as usize, present);
+        assert_eq!(sections[&6].len(), 6 + bds.data.len().div_ceil(8));
+        assert_eq!(sections[&6][5..8], [0, 0b1011_1111, 0b1011_1111]);
+        assert_eq!(sections[&7].len(), 5 + (present * bds.bits_per_value as usize).div_ceil(8));
+
+        // A regular grid is written with template 3.0, which lacks the rotation
+        let regular = MessageBuilder::new(3, 2).parameter(11).grib()?.to_grib2()?;
+        let grid = &regular[16 + 21..];
+        assert_eq!((grid[4], u32::from_be_bytes(grid[0..4].try_into().unwrap())), (3, 72));
+        assert_eq!((u16::from_be_bytes(grid[12..14].try_into().unwrap()), u32::from_be_bytes(grid[30..34].try_into().unwrap())), (0, 3));
+        assert_eq!(grid[72 + 4], 4);
+
+        // Parameters of local tables aren't looked up in the WMO table
+        let local = MessageBuilder::new(3, 2).parameter(11).pds(|pds| pds.parameter_table_version_number = 128).grib()?;
+        assert!(matches!(local.to_grib2(), Err(Grib1Error::NoGrib2Equivalent("parameter table version", 128))));
+
+        Ok(())
+    }
+}
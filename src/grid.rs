@@ -0,0 +1,466 @@
+//! Grid definitions: `GridDefinition` for handling any grid the library parses without matching every representation,
+//! and builders for the grids understood by the writer, validating the shape and increments and deriving the last grid point.
+
+use crate::error::Grib1Error;
+use crate::{BoundingBox, ConicProjection, DataRepresentation, RegularLatLon, RotatedLatLon, GDS};
+
+#[derive(Debug, Clone, Copy)]
+/// Any grid the library parses, with the fields they share available whatever the projection
+pub enum GridDefinition {
+    RegularLatLon(RegularLatLon),
+    RotatedLatLon(RotatedLatLon),
+    AlbersEqualArea(ConicProjection),
+    ObliqueLambert(ConicProjection),
+}
+
+impl GridDefinition {
+    /// The data representation type of the grid (code table 6)
+    pub fn data_representation_type(&self) -> u8 {
+        match self {
+            GridDefinition::RegularLatLon(_) => 0,
+            GridDefinition::AlbersEqualArea(_) => 8,
+            GridDefinition::RotatedLatLon(_) => 10,
+            GridDefinition::ObliqueLambert(_) => 13,
+        }
+    }
+
+    /// Number of points along the i (x) and j (y) axes
+    pub fn shape(&self) -> (usize, usize) {
+        match self {
+            GridDefinition::RegularLatLon(grid) => (grid.number_of_lat_values as usize, grid.number_of_lon_values as usize),
+            GridDefinition::RotatedLatLon(grid) => (grid.number_of_lat_values as usize, grid.number_of_lon_values as usize),
+            GridDefinition::AlbersEqualArea(grid) | GridDefinition::ObliqueLambert(grid) => (grid.number_of_x_values as usize, grid.number_of_y_values as usize),
+        }
+    }
+
+    /// Number of points in the grid
+    pub fn number_of_points(&self) -> usize {
+        let (ni, nj) = self.shape();
+        ni * nj
+    }
+
+    /// Scanning mode flags (code table 8)
+    pub fn scanning_mode(&self) -> u8 {
+        match self {
+            GridDefinition::RegularLatLon(grid) => grid.scanning_mode,
+            GridDefinition::RotatedLatLon(grid) => grid.scanning_mode,
+            GridDefinition::AlbersEqualArea(grid) | GridDefinition::ObliqueLambert(grid) => grid.scanning_mode,
+        }
+    }
+
+    /// Resolution and component flags (code table 7)
+    pub fn resolution_and_component_flags(&self) -> u8 {
+        match self {
+            GridDefinition::RegularLatLon(grid) => grid.resolution_and_component_flags,
+            GridDefinition::RotatedLatLon(grid) => grid.resolution_and_component_flags,
+            GridDefinition::AlbersEqualArea(grid) | GridDefinition::ObliqueLambert(grid) => grid.resolution_and_component_flags,
+        }
+    }
+
+    /// Latitude and longitude of the first grid point. For rotated grids these are in the rotated system.
+    pub fn first_grid_point(&self) -> (f32, f32) {
+        match self {
+            GridDefinition::RegularLatLon(grid) => (grid.latitude_of_first_grid_point, grid.longitude_of_first_grid_point),
+            GridDefinition::RotatedLatLon(grid) => (grid.latitude_of_first_grid_point, grid.longitude_of_first_grid_point),
+            GridDefinition::AlbersEqualArea(grid) | GridDefinition::ObliqueLambert(grid) => (grid.latitude_of_first_grid_point, grid.longitude_of_first_grid_point),
+        }
+    }
+
+    /// The region covered by the points of a latitude/longitude grid, in the rotated system for rotated grids. Projected grids give None,
+    /// as their edges aren't parallels and meridians.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let grid: RotatedLatLon = match *self {
+            GridDefinition::RegularLatLon(grid) => grid.into(),
+            GridDefinition::RotatedLatLon(grid) => grid,
+            GridDefinition::AlbersEqualArea(_) | GridDefinition::ObliqueLambert(_) => return None,
+        };
+        let (first, last) = (grid.longitude_of_first_grid_point, grid.longitude_of_last_grid_point);
+        // Points go from east to west when scanning in the -i direction
+        let (west, east) = if grid.scanning_mode & 0x80 > 0 { (last, first) } else { (first, last) };
+        Some(BoundingBox {
+            north: grid.latitude_of_first_grid_point.max(grid.latitude_of_last_grid_point),
+            south: grid.latitude_of_first_grid_point.min(grid.latitude_of_last_grid_point),
+            west,
+            east,
+        })
+    }
+}
+
+impl GDS {
+    /// The grid, unless it is of a type the library doesn't parse
+    pub fn grid(&self) -> Option<GridDefinition> {
+        match self.data {
+            DataRepresentation::RegularLatLon(grid) => Some(GridDefinition::RegularLatLon(grid)),
+            DataRepresentation::RotatedLatLon(grid) => Some(GridDefinition::RotatedLatLon(grid)),
+            DataRepresentation::AlbersEqualArea(grid) => Some(GridDefinition::AlbersEqualArea(grid)),
+            DataRepresentation::ObliqueLambert(grid) => Some(GridDefinition::ObliqueLambert(grid)),
+            DataRepresentation::Unhandled => None,
+        }
+    }
+}
+
+impl From<GridDefinition> for DataRepresentation {
+    fn from(grid: GridDefinition) -> DataRepresentation {
+        match grid {
+            GridDefinition::RegularLatLon(grid) => DataRepresentation::RegularLatLon(grid),
+            GridDefinition::RotatedLatLon(grid) => DataRepresentation::RotatedLatLon(grid),
+            GridDefinition::AlbersEqualArea(grid) => DataRepresentation::AlbersEqualArea(grid),
+            GridDefinition::ObliqueLambert(grid) => DataRepresentation::ObliqueLambert(grid),
+        }
+    }
+}
+
+impl From<GridDefinition> for GDS {
+    /// A GDS without vertical coordinates
+    fn from(grid: GridDefinition) -> GDS {
+        GDS {
+            number_of_vertical_coordinate_values: 0,
+            pvl_location: 255,
+            data_representation_type: grid.data_representation_type(),
+            data: grid.into(),
+            vertical_coordinates: vec![],
+        }
+    }
+}
+
+impl From<RegularLatLon> for GridDefinition {
+    fn from(grid: RegularLatLon) -> GridDefinition {
+        GridDefinition::RegularLatLon(grid)
+    }
+}
+
+impl From<RotatedLatLon> for GridDefinition {
+    fn from(grid: RotatedLatLon) -> GridDefinition {
+        GridDefinition::RotatedLatLon(grid)
+    }
+}
+
+impl TryFrom<GridDefinition> for RegularLatLon {
+    type Error = Grib1Error;
+
+    fn try_from(grid: GridDefinition) -> Result<RegularLatLon, Grib1Error> {
+        match grid {
+            GridDefinition::RegularLatLon(grid) => Ok(grid),
+            other => Err(Grib1Error::UnsupportedGrid(other.data_representation_type())),
+        }
+    }
+}
+
+impl TryFrom<GridDefinition> for RotatedLatLon {
+    type Error = Grib1Error;
+
+    /// Regular grids are converted as rotated grids with the pole in its usual place
+    fn try_from(grid: GridDefinition) -> Result<RotatedLatLon, Grib1Error> {
+        match grid {
+            GridDefinition::RegularLatLon(grid) => Ok(grid.into()),
+            GridDefinition::RotatedLatLon(grid) => Ok(grid),
+            other => Err(Grib1Error::UnsupportedGrid(other.data_representation_type())),
+        }
+    }
+}
+
+impl TryFrom<GridDefinition> for ConicProjection {
+    type Error = Grib1Error;
+
+    fn try_from(grid: GridDefinition) -> Result<ConicProjection, Grib1Error> {
+        match grid {
+            GridDefinition::AlbersEqualArea(grid) | GridDefinition::ObliqueLambert(grid) => Ok(grid),
+            other => Err(Grib1Error::UnsupportedGrid(other.data_representation_type())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Builds the GDS of a regular latitude/longitude grid (data representation type 0)
+pub struct RegularLatLonBuilder {
+    layout: Layout,
+}
+
+impl RegularLatLonBuilder {
+    /// A grid of `ni` points along each parallel and `nj` points along each meridian
+    pub fn new(ni: u16, nj: u16) -> RegularLatLonBuilder {
+        RegularLatLonBuilder { layout: Layout::new(ni, nj) }
+    }
+
+    /// Position of the first grid point in degrees
+    pub fn first_grid_point(mut self, lat: f32, lon: f32) -> Self {
+        self.layout.first = (lat, lon);
+        self
+    }
+
+    /// Distance between neighbouring points in degrees, along a parallel (i) and a meridian (j)
+    pub fn increments(mut self, i: f32, j: f32) -> Self {
+        self.layout.increments = Some((i, j));
+        self
+    }
+
+    /// Scanning mode flags (code table 8). Defaults to 0, i.e. rows from north to south with points from west to east.
+    pub fn scanning_mode(mut self, scanning_mode: u8) -> Self {
+        self.layout.scanning_mode = scanning_mode;
+        self
+    }
+
+    /// Whether vector components are relative to the grid rather than easterly and northerly
+    pub fn grid_relative_winds(mut self, grid_relative: bool) -> Self {
+        self.layout.grid_relative_winds = grid_relative;
+        self
+    }
+
+    pub fn build(&self) -> Result<GDS, Grib1Error> {
+        Ok(GDS {
+            number_of_vertical_coordinate_values: 0,
+            pvl_location: 255,
+            data_representation_type: 0,
+            data: DataRepresentation::RegularLatLon(self.layout.resolve()?),
+            vertical_coordinates: vec![],
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Builds the GDS of a rotated latitude/longitude grid (data representation type 10). Coordinates are given in the rotated system.
+pub struct RotatedLatLonBuilder {
+    layout: Layout,
+    southern_pole: (f32, f32),
+    angle_of_rotation: f32,
+}
+
+impl RotatedLatLonBuilder {
+    /// A grid of `ni` points along each parallel and `nj` points along each meridian
+    pub fn new(ni: u16, nj: u16) -> RotatedLatLonBuilder {
+        RotatedLatLonBuilder {
+            layout: Layout::new(ni, nj),
+            southern_pole: (-90.0, 0.0),
+            angle_of_rotation: 0.0,
+        }
+    }
+
+    /// Position of the first grid point in rotated degrees
+    pub fn first_grid_point(mut self, lat: f32, lon: f32) -> Self {
+        self.layout.first = (lat, lon);
+        self
+    }
+
+    /// Distance between neighbouring points in degrees, along a parallel (i) and a meridian (j)
+    pub fn increments(mut self, i: f32, j: f32) -> Self {
+        self.layout.increments = Some((i, j));
+        self
+    }
+
+    /// Scanning mode flags (code table 8). Defaults to 0, i.e. rows from north to south with points from west to east.
+    pub fn scanning_mode(mut self, scanning_mode: u8) -> Self {
+        self.layout.scanning_mode = scanning_mode;
+        self
+    }
+
+    /// Whether vector components are relative to the grid rather than easterly and northerly
+    pub fn grid_relative_winds(mut self, grid_relative: bool) -> Self {
+        self.layout.grid_relative_winds = grid_relative;
+        self
+    }
+
+    /// Geographical position of the southern pole of the rotated system. Defaults to (-90, 0), i.e. no rotation.
+    pub fn southern_pole(mut self, lat: f32, lon: f32) -> Self {
+        self.southern_pole = (lat, lon);
+        self
+    }
+
+    pub fn angle_of_rotation(mut self, angle: f32) -> Self {
+        self.angle_of_rotation = angle;
+        self
+    }
+
+    pub fn build(&self) -> Result<GDS, Grib1Error> {
+        let (lat, lon) = self.southern_pole;
+        if !(-90.0..=90.0).contains(&lat) || !(-360.0..=360.0).contains(&lon) {
+            return Err(Grib1Error::InvalidGrid(format!("The southern pole ({}, {}) is not a valid position", lat, lon)));
+        }
+        if !self.angle_of_rotation.is_finite() {
+            return Err(Grib1Error::InvalidGrid("The angle of rotation must be finite".to_string()));
+        }
+
+        Ok(GDS {
+            number_of_vertical_coordinate_values: 0,
+            pvl_location: 255,
+            data_representation_type: 10,
+            data: DataRepresentation::RotatedLatLon(RotatedLatLon {
+                latitude_of_southern_pole: lat,
+                longitude_of_southern_pole: lon,
+                angle_of_rotation: self.angle_of_rotation,
+                ..self.layout.resolve()?.into()
+            }),
+            vertical_coordinates: vec![],
+        })
+    }
+}
+
+// The part shared by both kinds of latitude/longitude grids
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    ni: u16,
+    nj: u16,
+    first: (f32, f32),
+    increments: Option<(f32, f32)>,
+    scanning_mode: u8,
+    grid_relative_winds: bool,
+}
+
+impl Layout {
+    fn new(ni: u16, nj: u16) -> Layout {
+        Layout {
+            ni,
+            nj,
+            first: (0.0, 0.0),
+            increments: None,
+            scanning_mode: 0,
+            grid_relative_winds: false,
+        }
+    }
+
+    fn resolve(&self) -> Result<RegularLatLon, Grib1Error> {
+        if self.ni == 0 || self.nj == 0 {
+            return Err(Grib1Error::InvalidGrid(format!("The grid must have at least one point in each direction, not {}x{}", self.ni, self.nj)));
+        }
+        if self.scanning_mode & 0x1f != 0 {
+            return Err(Grib1Error::InvalidGrid(format!("Unknown scanning mode flags {:08b}", self.scanning_mode)));
+        }
+
+        let (i_increment, j_increment) = self.increments.ok_or_else(|| Grib1Error::InvalidGrid("The increments must be given".to_string()))?;
+        let i_increment = millidegrees_increment(i_increment)?;
+        let j_increment = millidegrees_increment(j_increment)?;
+
+        let (lat, lon) = self.first;
+        let first_lat = millidegrees(lat)?;
+        let first_lon = millidegrees(lon)?;
+
+        // Points go in the negative i direction if bit 1 is set, and in the positive j direction if bit 2 is set
+        let i_sign = if self.scanning_mode & 0x80 > 0 { -1 } else { 1 };
+        let j_sign = if self.scanning_mode & 0x40 > 0 { 1 } else { -1 };
+        let i_span = (self.ni as i32 - 1) * i_increment as i32;
+        let j_span = (self.nj as i32 - 1) * j_increment as i32;
+        let last_lat = first_lat + j_sign * j_span;
+        let mut last_lon = first_lon + i_sign * i_span;
+        if last_lon.abs() > 360_000 {
+            // Wrap around, the longitudes of the grid are unwrapped when reading
+            last_lon -= last_lon.signum() * 360_000;
+        }
+
+        if first_lat.abs() > 90_000 || last_lat.abs() > 90_000 {
+            return Err(Grib1Error::InvalidGrid(format!("The latitudes {} to {} are outside -90 to 90", first_lat as f32 * 0.001, last_lat as f32 * 0.001)));
+        }
+        if i_span >= 360_000 {
+            return Err(Grib1Error::InvalidGrid(format!("The {} points along a parallel span more than 360 degrees", self.ni)));
+        }
+        if first_lon.abs() > 360_000 || last_lon.abs() > 360_000 {
+            return Err(Grib1Error::InvalidGrid(format!("The longitudes {} to {} are outside -360 to 360", first_lon as f32 * 0.001, last_lon as f32 * 0.001)));
+        }
+
+        Ok(RegularLatLon {
+            number_of_lat_values: self.ni,
+            number_of_lon_values: self.nj,
+            latitude_of_first_grid_point: first_lat as f32 * 0.001,
+            longitude_of_first_grid_point: first_lon as f32 * 0.001,
+            latitude_of_last_grid_point: last_lat as f32 * 0.001,
+            longitude_of_last_grid_point: last_lon as f32 * 0.001,
+            // Direction increments given
+            resolution_and_component_flags: 0x80 | if self.grid_relative_winds { 0x08 } else { 0 },
+            i_direction_increment: i_increment,
+            j_direction_increment: j_increment,
+            scanning_mode: self.scanning_mode,
+        })
+    }
+}
+
+fn millidegrees(value: f32) -> Result<i32, Grib1Error> {
+    if !value.is_finite() {
+        return Err(Grib1Error::InvalidGrid(format!("{} is not a valid coordinate", value)));
+    }
+    Ok((value as f64 * 1000.0).round() as i32)
+}
+
+// Increments are stored as whole millidegrees in 16 bits, with all ones meaning not given
+fn millidegrees_increment(value: f32) -> Result<u16, Grib1Error> {
+    let scaled = value as f64 * 1000.0;
+    if !(scaled.round() >= 1.0 && scaled.round() < 65535.0) {
+        return Err(Grib1Error::InvalidGrid(format!("The increment {} must be between 0.001 and 65.534 degrees", value)));
+    }
+    if (scaled - scaled.round()).abs() > 1e-3 {
+        return Err(Grib1Error::InvalidGrid(format!("The increment {} is not a whole number of millidegrees", value)));
+    }
+    Ok(scaled.round() as u16)
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::{writer, Grib, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn grid_definition_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+        let gds = reader.inventory().await?.remove(0).gds.unwrap();
+        let grid = gds.grid().unwrap();
+        assert_eq!((grid.data_representation_type(), grid.shape(), grid.number_of_points()), (10, (1189, 1069), 1271041));
+        let bounds = grid.bounds().unwrap();
+        assert!(bounds.south < bounds.north && bounds.west < bounds.east);
+        assert!(RegularLatLon::try_from(grid).is_err());
+        let rotated = RotatedLatLon::try_from(grid)?;
+        assert_eq!(GDS::from(GridDefinition::from(rotated)).data_representation_type, 10);
+
+        // Going west from 10E to 0E
+        let gds = RegularLatLonBuilder::new(3, 2).first_grid_point(50.0, 10.0).increments(5.0, 1.0).scanning_mode(0x80).build()?;
+        let grid = gds.grid().unwrap();
+        let bounds = grid.bounds().unwrap();
+        assert!((bounds.north - 50.0).abs() < 1e-3 && (bounds.south - 49.0).abs() < 1e-3);
+        assert_eq!((bounds.west, bounds.east), (0.0, 10.0));
+        assert!(matches!(DataRepresentation::from(grid), DataRepresentation::RegularLatLon(_)));
+        assert!(GDS { data: DataRepresentation::Unhandled, ..gds }.grid().is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn grid_builder_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let source = &result[0];
+
+        // The grid of the sample file can be described by its first point and increments
+        let gds = RotatedLatLonBuilder::new(1189, 1069)
+            .first_grid_point(-0.62, -22.75)
+            .increments(0.022, 0.022)
+            .scanning_mode(0x40)
+            .grid_relative_winds(true)
+            .southern_pole(-40.0, 26.5)
+            .build()?;
+        let gds = GDS {
+            vertical_coordinates: source.gds.as_ref().unwrap().vertical_coordinates.clone(),
+            ..gds
+        };
+        let original = writer::encode(&source.pds, source.gds.as_ref().unwrap(), &source.bds.as_ref().unwrap().data, 16)?;
+        let rebuilt = writer::encode(&source.pds, &gds, &source.bds.as_ref().unwrap().data, 16)?;
+        assert_eq!(original, rebuilt);
+
+        // A regular grid is written with a 32 octet GDS and read back
+        let gds = RegularLatLonBuilder::new(4, 3).first_grid_point(60.0, 350.0).increments(5.0, 2.5).build()?;
+        let bytes = writer::encode(&source.pds, &gds, &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0], 8)?;
+        assert_eq!(crate::codec::read_u24_be(&bytes[8 + 28..]), 32);
+        match Grib::from_bytes(&bytes)?.gds.unwrap().data {
+            DataRepresentation::RegularLatLon(grid) => {
+                assert!((grid.latitude_of_last_grid_point - 55.0).abs() < 1e-3);
+                assert!((grid.longitude_of_last_grid_point - 5.0).abs() < 1e-3);
+            }
+            data => panic!("Unexpected grid {:?}", data),
+        }
+
+        assert!(RegularLatLonBuilder::new(4, 3).build().is_err());
+        assert!(RegularLatLonBuilder::new(0, 3).increments(1.0, 1.0).build().is_err());
+        assert!(RegularLatLonBuilder::new(4, 3).increments(0.0001, 1.0).build().is_err());
+        assert!(RegularLatLonBuilder::new(4, 30).first_grid_point(80.0, 0.0).increments(1.0, 1.0).scanning_mode(0x40).build().is_err());
+        assert!(RegularLatLonBuilder::new(721, 3).increments(0.5, 1.0).build().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! WMO bulletin headers wrapping messages sent over the Global Telecommunication System, e.g. `\x01\r\r\n123\r\r\nHTXE85 ECMF 011200\r\r\n` before `GRIB`.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The header of the bulletin a message was sent in, set on messages by readers with `Grib1Reader::gts` enabled
+pub struct BulletinHeader {
+    /// The channel sequence number following the start of heading, if there is one
+    pub sequence_number: Option<u32>,
+    /// The abbreviated heading, e.g. `HTXE85 ECMF 011200` or `HTXE85 ECMF 011200 RRA`
+    pub heading: String,
+}
+
+impl BulletinHeader {
+    /// The data type and area designator, `TTAAii`
+    pub fn designator(&self) -> &str {
+        self.heading.split(' ').next().unwrap_or("")
+    }
+
+    /// The ICAO location indicator of the originating centre, `CCCC`
+    pub fn originator(&self) -> &str {
+        self.heading.split(' ').nth(1).unwrap_or("")
+    }
+}
+
+// The longest stretch before a message that is looked at for a header
+#[cfg(feature = "async-tokio")]
+pub(crate) const MAX_HEADER_LENGTH: u64 = 128;
+
+// Find the header in the bytes leading up to a message, which may also hold the end of the previous bulletin or other junk
+#[cfg(feature = "async-tokio")]
+pub(crate) fn parse_header(bytes: &[u8]) -> Option<BulletinHeader> {
+    let start = bytes.iter().rposition(|b| *b == 0x01).map_or(0, |position| position + 1);
+    let text = std::str::from_utf8(&bytes[start..]).ok()?;
+    let mut lines = text.split(['\r', '\n']).map(str::trim).filter(|line| !line.is_empty());
+
+    let mut line = lines.next()?;
+    let mut sequence_number = None;
+    if line.bytes().all(|b| b.is_ascii_digit()) {
+        sequence_number = line.parse().ok();
+        line = lines.next()?;
+    }
+
+    // The abbreviated heading starts with four letters and two digits
+    let designator = line.as_bytes().get(..6)?;
+    if !designator[..4].iter().all(u8::is_ascii_uppercase) || !designator[4..].iter().all(u8::is_ascii_digit) || lines.next().is_some() {
+        return None;
+    }
+
+    Some(BulletinHeader { sequence_number, heading: line.to_string() })
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn bulletin_test() -> Result<(), Grib1Error> {
+        let header = parse_header(b"\r\r\n\x03\x01\r\r\n123\r\r\nHTXE85 ECMF 011200\r\r\n").unwrap();
+        assert_eq!(header.sequence_number, Some(123));
+        assert_eq!((header.designator(), header.originator()), ("HTXE85", "ECMF"));
+        assert_eq!(parse_header(b"junk"), None);
+
+        // Wrap both messages of the sample in bulletins, the way they arrive from a GTS feed
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let mut bytes = vec![];
+        for (i, message) in sample.chunks(2542704).enumerate() {
+            bytes.extend_from_slice(format!("\x01\r\r\n{:03}\r\r\nHTXE8{} EKMI 011200\r\r\n", i, i + 5).as_bytes());
+            bytes.extend_from_slice(message);
+            bytes.extend_from_slice(b"\r\r\n\x03");
+        }
+        let path = std::env::temp_dir().join("grib1_reader_bulletin_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?)).gts(true);
+        let messages = reader.inventory().await?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].bulletin.as_ref().map(|header| header.heading.as_str()), Some("HTXE86 EKMI 011200"));
+        assert_eq!(messages[1].bulletin.as_ref().and_then(|header| header.sequence_number), Some(1));
+
+        let result = reader.read(vec![SearchParams { param: 34, level: 700 }]).await?;
+        assert_eq!(result[0].bds.as_ref().unwrap().data.len(), 1271041);
+        assert_eq!(reader.read_binary(vec![SearchParams { param: 33, level: 700 }]).await?, sample[..2542704]);
+
+        // Without the option the wrapper is an error
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert!(reader.inventory().await.is_err());
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! Messages received from a GTS feed, e.g. a TCP connection to a telecommunication centre, in the framing of the WMO Manual on the GTS:
+//! every bulletin is preceded by its length in 8 ASCII digits and a 2 digit format identifier. The GRIB1 messages of the bulletins are
+//! decoded with their bulletin header, and anything else, e.g. text bulletins or GRIB2, is passed over. A GRIB1 message that can't be
+//! decoded is skipped and recorded as a `SkippedMessage` warning, so one damaged message doesn't stop the feed.
+//!
+//! ```no_run
+//! # async fn example(connection: tokio::fs::File) -> Result<(), grib1_reader::error::Grib1Error> {
+//! let mut feed = grib1_reader::gts_feed::GtsFeed::new(connection);
+//! while let Some(grib) = feed.next().await? {
+//!     println!("{:?}", grib.bulletin);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Grib1Error;
+use crate::gts::parse_header;
+use crate::warning::Warning;
+use crate::{message_length, Grib};
+use std::collections::VecDeque;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Length of the framing before each bulletin: the length of the bulletin and the format identifier
+const FRAME_HEADER_LENGTH: usize = 10;
+
+/// The GRIB1 messages of a stream of framed bulletins, in the order they arrive
+pub struct GtsFeed<R> {
+    reader: R,
+    pending: VecDeque<Grib>,
+    bulletins: usize,
+    warnings: Vec<Warning>,
+}
+
+impl<R: AsyncRead + Unpin> GtsFeed<R> {
+    pub fn new(reader: R) -> GtsFeed<R> {
+        GtsFeed {
+            reader,
+            pending: VecDeque::new(),
+            bulletins: 0,
+            warnings: vec![],
+        }
+    }
+
+    /// Number of bulletins received so far, including those without GRIB1 messages
+    pub fn bulletins(&self) -> usize {
+        self.bulletins
+    }
+
+    /// The messages skipped so far because they couldn't be decoded. The offset of a warning is the position of the message within its bulletin.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// The next GRIB1 message, waiting for the bulletins holding it to arrive, or None once the stream ends between two bulletins.
+    /// The offset of a message is its position within its bulletin.
+    pub async fn next(&mut self) -> Result<Option<Grib>, Grib1Error> {
+        loop {
+            if let Some(grib) = self.pending.pop_front() {
+                return Ok(Some(grib));
+            }
+            let Some(bulletin) = self.read_bulletin().await? else {
+                return Ok(None);
+            };
+            self.bulletins += 1;
+            self.pending.extend(messages(&bulletin, &mut self.warnings));
+        }
+    }
+
+    // The bytes of the next bulletin without the framing, or None at the end of the stream
+    async fn read_bulletin(&mut self) -> Result<Option<Vec<u8>>, Grib1Error> {
+        let mut header = [0; FRAME_HEADER_LENGTH];
+        let mut read = 0;
+        while read < header.len() {
+            match self.reader.read(&mut header[read..]).await? {
+                0 if read == 0 => return Ok(None),
+                0 => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                count => read += count,
+            }
+        }
+
+        let length = std::str::from_utf8(&header[..8])
+            .ok()
+            .and_then(|digits| digits.parse::<usize>().ok())
+            .filter(|_| matches!(&header[8..], b"00" | b"01"))
+            .ok_or_else(|| Grib1Error::MalformedMessage(format!("{:?} doesn't frame a GTS bulletin", String::from_utf8_lossy(&header))))?;
+        let mut bulletin = vec![0; length];
+        self.reader.read_exact(&mut bulletin).await?;
+        Ok(Some(bulletin))
+    }
+}
+
+// Decode the GRIB1 messages of a bulletin, each with the header found before it, recording those that can't be decoded in `warnings`
+fn messages(bulletin: &[u8], warnings: &mut Vec<Warning>) -> Vec<Grib> {
+    let mut result = vec![];
+    let mut offset = 0;
+    while let Some(start) = bulletin[offset..].windows(4).position(|window| window == b"GRIB").map(|position| offset + position) {
+        let message = &bulletin[start..];
+        let length = match message.get(7) {
+            Some(0 | 1) => message_length(message),
+            Some(2) => message.get(8..16).map(|length| u64::from_be_bytes(length.try_into().unwrap_or_default()) as usize),
+            _ => None,
+        };
+        // A marker that isn't followed by a whole message is part of something else
+        let Some(length) = length.filter(|length| *length >= 8 && *length <= message.len()) else {
+            offset = start + 4;
+            continue;
+        };
+
+        if message[7] != 2 {
+            match Grib::from_bytes(&message[..length]) {
+                Ok(mut grib) => {
+                    grib.offset = start as u64;
+                    grib.bulletin = parse_header(&bulletin[offset..start]);
+                    result.push(grib);
+                }
+                Err(error) => warnings.push(Warning::SkippedMessage {
+                    offset: start as u64,
+                    error: error.to_string(),
+                }),
+            }
+        }
+        offset = start + length;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn gts_feed_test() -> Result<(), Grib1Error> {
+        let sample = std::fs::read("data/sample.grib")?;
+        let mut bulletins = vec![b"\x01\r\r\n001\r\r\nSMXX01 EKMI 011200\r\r\nAAXX 01124\r\r\n\x03".to_vec()];
+        for (i, message) in sample.chunks(2542704).enumerate() {
+            let mut bulletin = format!("\x01\r\r\n{:03}\r\r\nHTXE8{} EKMI 011200\r\r\n", i + 2, i + 5).into_bytes();
+            bulletin.extend_from_slice(message);
+            bulletin.extend_from_slice(b"\r\r\n\x03");
+            bulletins.push(bulletin);
+        }
+        let mut stream = vec![];
+        for bulletin in &bulletins {
+            stream.extend_from_slice(format!("{:08}00", bulletin.len()).as_bytes());
+            stream.extend_from_slice(bulletin);
+        }
+
+        let mut feed = GtsFeed::new(&stream[..]);
+        let first = feed.next().await?.unwrap();
+        assert_eq!(first.pds.indicator_of_parameter_and_units, 33);
+        assert_eq!(first.bulletin.map(|header| (header.sequence_number, header.heading)), Some((Some(2), "HTXE85 EKMI 011200".to_string())));
+        let second = feed.next().await?.unwrap();
+        assert_eq!(second.bds.unwrap().data.len(), 1271041);
+        assert!(feed.next().await?.is_none());
+        assert_eq!(feed.bulletins(), 3);
+
+        // A stream cut within a bulletin or out of step with the framing is an error
+        let mut cut = GtsFeed::new(&stream[..stream.len() - 1]);
+        assert!(cut.next().await?.is_some());
+        assert!(cut.next().await.is_err());
+        assert!(matches!(GtsFeed::new(&stream[1..]).next().await, Err(Grib1Error::MalformedMessage(_))));
+
+        // A damaged message between two good ones is skipped and recorded
+        let good = crate::synthetic::MessageBuilder::new(3, 2).build()?;
+        let mut damaged = good.clone();
+        damaged[8..11].copy_from_slice(&[0, 0, 5]);
+        let mut bulletin = b"\x01\r\r\n002\r\r\nHTXE85 EKMI 011200\r\r\n".to_vec();
+        let header = bulletin.len() as u64;
+        for message in [&good, &damaged, &good] {
+            bulletin.extend_from_slice(message);
+        }
+        let mut stream = format!("{:08}00", bulletin.len()).into_bytes();
+        stream.extend_from_slice(&bulletin);
+        let mut feed = GtsFeed::new(&stream[..]);
+        assert!(feed.next().await?.is_some());
+        assert_eq!(feed.next().await?.map(|grib| grib.offset), Some(header + 2 * good.len() as u64));
+        assert!(feed.next().await?.is_none());
+        assert!(matches!(feed.warnings(), [Warning::SkippedMessage { offset, .. }] if *offset == header + good.len() as u64));
+        Ok(())
+    }
+}
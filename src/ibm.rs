@@ -0,0 +1,105 @@
+//! Conversion between IEEE 754 single precision floats and the IBM System/370 single precision floats used for reference values and angles.
+//!
+//! An IBM float has a sign bit, a 7 bit base 16 exponent biased by 64 and a 24 bit fraction, i.e. `(-1)^s * 0.F * 16^(E - 64)`.
+//! Every finite `f32` lies within its range, but as the exponent is base 16 only 21 to 24 bits of the fraction are significant, so most values must be rounded.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How to round values that can't be represented exactly
+pub enum Rounding {
+    /// To the nearest representable value, ties to even
+    Nearest,
+    /// To the largest representable value that isn't greater, as needed for the reference value of packed data
+    Down,
+}
+
+/// Decode an IBM float from the first four bytes of the slice
+pub fn from_ibm(bytes: &[u8]) -> f32 {
+    crate::codec::read_f32_ibm(bytes)
+}
+
+/// Encode a value as an IBM float, rounding to the nearest representable value. NaN is encoded as zero.
+pub fn to_ibm(value: f32) -> [u8; 4] {
+    to_ibm_rounded(value, Rounding::Nearest)
+}
+
+/// Encode a value as an IBM float using the given rounding. NaN is encoded as zero and infinities as the largest magnitude of the same sign.
+pub fn to_ibm_rounded(value: f32, rounding: Rounding) -> [u8; 4] {
+    if value == 0.0 || value.is_nan() {
+        return [0; 4];
+    }
+
+    let sign = if value < 0.0 { 0x80 } else { 0 };
+    if value.is_infinite() {
+        return [sign | 0x7f, 0xff, 0xff, 0xff];
+    }
+
+    // Split the value into an integer significand and a power of two, value = m * 2^e exactly
+    let bits = value.to_bits();
+    let biased = ((bits >> 23) & 0xff) as i32;
+    let (m, e) = if biased == 0 { (bits & 0x7f_ffff, -149) } else { ((bits & 0x7f_ffff) | 0x80_0000, biased - 150) };
+
+    // Choose the power of sixteen that puts the highest set bit within the top four bits of the 24 bit fraction
+    let top = 31 - m.leading_zeros() as i32 + e;
+    let t = top - 20 - (top - 20).rem_euclid(4);
+    let shift = e - t;
+    let fraction = if shift >= 0 {
+        (m as u64) << shift
+    } else {
+        let dropped = -shift as u32;
+        let kept = (m as u64) >> dropped;
+        let remainder = (m as u64) & ((1 << dropped) - 1);
+        let half = 1u64 << (dropped - 1);
+        // Rounding down a negative value means rounding its magnitude up
+        let round_up = match rounding {
+            Rounding::Nearest => remainder > half || (remainder == half && kept & 1 == 1),
+            Rounding::Down => sign != 0 && remainder > 0,
+        };
+        // The fraction can't overflow, as rounding only happens when the value has fewer than 24 bits in this range
+        kept + round_up as u64
+    };
+
+    // value = fraction * 2^t = 0.F * 16^(E - 64) with F = fraction / 2^24
+    let exponent = (t + 24) / 4 + 64;
+
+    [sign | exponent as u8, (fraction >> 16) as u8, (fraction >> 8) as u8, fraction as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ibm_test() {
+        assert_eq!(to_ibm(0.0), [0, 0, 0, 0]);
+        assert_eq!(to_ibm(-0.0), [0, 0, 0, 0]);
+        assert_eq!(to_ibm(1.0), [0x41, 0x10, 0x00, 0x00]);
+        assert_eq!(to_ibm(-118.625), [0xc2, 0x76, 0xa0, 0x00]);
+        assert_eq!(to_ibm(0.15625), [0x40, 0x28, 0x00, 0x00]);
+
+        // Values representable in both formats survive a round trip
+        for value in [1.0, -1.0, 0.5, 100.0, 2.0f32.powi(-100), 2.0f32.powi(100), 123456.0, f32::MAX, -f32::MAX, f32::MIN_POSITIVE] {
+            assert_eq!(from_ibm(&to_ibm(value)), value);
+        }
+
+        // The smallest subnormal only needs a single bit
+        assert_eq!(to_ibm(f32::from_bits(1)), [0x1b, 0x80, 0, 0]);
+        assert_eq!(from_ibm(&to_ibm(f32::from_bits(1))), f32::from_bits(1));
+
+        // 1 + 2^-23 needs 24 significant bits, but IBM has only 21 below 16, so it's rounded
+        let value = 1.0 + f32::EPSILON;
+        assert_eq!(to_ibm(value), to_ibm(1.0));
+        assert_eq!(to_ibm(1.0 + 5.0 * f32::EPSILON), [0x41, 0x10, 0x00, 0x01]);
+        assert_eq!(to_ibm(1.0 + 4.0 * f32::EPSILON), [0x41, 0x10, 0x00, 0x00]);
+        assert_eq!(to_ibm(1.0 + 12.0 * f32::EPSILON), [0x41, 0x10, 0x00, 0x02]);
+
+        // Rounding down never gives a larger value, whatever the sign
+        for value in [1.0 + f32::EPSILON, -(1.0 + f32::EPSILON), 3.3, -3.3, 271.15, -0.001] {
+            let rounded = from_ibm(&to_ibm_rounded(value, Rounding::Down));
+            assert!(rounded <= value);
+            assert!((value - rounded).abs() <= value.abs() * 2.0f32.powi(-20));
+        }
+
+        assert_eq!(to_ibm(f32::NAN), [0, 0, 0, 0]);
+        assert_eq!(to_ibm(f32::NEG_INFINITY), [0xff, 0xff, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,94 @@
+//! Inventories in the colon separated `.idx` format NCEP publishes next to its files on NOMADS, e.g. `1:0:d=2023100215:UGRD:700 mb:1 hour fcst:`.
+//! Download scripts find the byte range of a message from its offset and the offset of the next line, so the lines must cover every message of the file in order.
+
+use crate::{Grib, PDS};
+
+/// The idx line of a message, numbered from 1
+pub fn idx_line(number: usize, grib: &Grib) -> String {
+    let pds = &grib.pds;
+    let time = pds.reference_time();
+    let parameter = pds.parameter().map_or(format!("var{}", pds.indicator_of_parameter_and_units), |parameter| parameter.abbreviation.to_string());
+
+    format!("{}:{}:d={:04}{:02}{:02}{:02}:{}:{}:{}:", number, grib.offset, time.year, time.month, time.day, time.hour, parameter, level(pds), forecast(pds))
+}
+
+/// The idx inventory of all the messages of a file, as returned by `Grib1Reader::inventory`
+pub fn to_idx(messages: &[Grib]) -> String {
+    messages.iter().enumerate().map(|(i, grib)| idx_line(i + 1, grib) + "\n").collect()
+}
+
+// The level the way wgrib describes it, from code table 3
+fn level(pds: &PDS) -> String {
+    let value = pds.level_or_layer_value;
+    let (top, bottom) = (value >> 8, value & 0xff);
+    match pds.indicator_of_type_of_level_or_layer {
+        1 => "surface".to_string(),
+        2 => "cloud base".to_string(),
+        3 => "cloud top".to_string(),
+        4 => "0C isotherm".to_string(),
+        6 => "max wind".to_string(),
+        7 => "tropopause".to_string(),
+        8 => "nominal top of atmosphere".to_string(),
+        100 => format!("{} mb", value),
+        101 => format!("{}-{} mb", top * 10, bottom * 10),
+        102 => "mean sea level".to_string(),
+        103 => format!("{} m above mean sea level", value),
+        105 => format!("{} m above ground", value),
+        106 => format!("{}-{} m above ground", top * 100, bottom * 100),
+        107 => format!("{} sigma level", value as f32 / 10000.0),
+        109 => format!("{} hybrid level", value),
+        111 => format!("{} cm below ground", value),
+        112 => format!("{}-{} cm below ground", top, bottom),
+        116 => format!("{}-{} mb above ground", top, bottom),
+        200 => "entire atmosphere".to_string(),
+        other => format!("level {} {}", other, value),
+    }
+}
+
+// The forecast time the way wgrib describes it, from code tables 4 and 5
+fn forecast(pds: &PDS) -> String {
+    let unit = match pds.forecast_time_unit {
+        0 => "min",
+        1 => "hour",
+        2 => "day",
+        3 => "month",
+        4 => "year",
+        _ => "unit",
+    };
+    let (p1, p2) = (pds.p1_period_of_time, pds.p2_period_of_time);
+    match pds.time_range_indicator {
+        0 | 1 if p1 == 0 => "anl".to_string(),
+        0 | 1 => format!("{} {} fcst", p1, unit),
+        2 => format!("{}-{} {} fcst", p1, p2, unit),
+        3 => format!("{}-{} {} ave fcst", p1, p2, unit),
+        4 => format!("{}-{} {} acc fcst", p1, p2, unit),
+        5 => format!("{}-{} {} diff fcst", p1, p2, unit),
+        10 => format!("{} {} fcst", pds.step(), unit),
+        other => format!("time range {} {}-{} {}", other, p1, p2, unit),
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+    use crate::Grib1Reader;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn idx_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let mut messages = reader.inventory().await?;
+        assert_eq!(to_idx(&messages), "1:0:d=2023100215:var33:700 mb:1 hour fcst:\n2:2542704:d=2023100215:var34:700 mb:1 hour fcst:\n");
+        for message in &mut messages {
+            message.pds.parameter_table_version_number = 2;
+        }
+        assert_eq!(to_idx(&messages), "1:0:d=2023100215:UGRD:700 mb:1 hour fcst:\n2:2542704:d=2023100215:VGRD:700 mb:1 hour fcst:\n");
+
+        let pds = &mut messages[0].pds;
+        (pds.indicator_of_type_of_level_or_layer, pds.time_range_indicator, pds.p1_period_of_time, pds.p2_period_of_time) = (1, 4, 0, 6);
+        assert_eq!(idx_line(1, &messages[0]), "1:0:d=2023100215:UGRD:surface:0-6 hour acc fcst:");
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+//! Indexes of the messages in a file, saved next to it so later reads can go straight to the messages they need instead of scanning the whole file.
+//!
+//! The index file starts with `G1IX` and a version octet. Version 2 goes on to describe the file indexed: its size in 8 octets, the time it was
+//! modified as seconds since the Unix epoch in 8 octets and nanoseconds in 4 (all ones if unknown), and a SHA-256 digest of its first and last
+//! 64 KiB. Then follows the number of messages in 4 octets. Every message is given by its offset and length in 8 octets each, then its PDS and
+//! GDS, each preceded by its length in 2 octets (0 when there is no GDS). Numbers are big endian. Version 1 indexes lack the description of the file.
+//!
+//! `GribIndex` holds the fields messages are usually looked up by, for answering many questions about a file after a single scan.
+
+use crate::digest::sha256;
+use crate::error::Grib1Error;
+use crate::time::DateTime;
+#[cfg(feature = "async-tokio")]
+use crate::{edition0, Grib1Reader, GribResult, PDS};
+use crate::{parse_gds, parse_pds, Grib};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+#[cfg(feature = "async-tokio")]
+use tokio::io::AsyncReadExt;
+
+const MAGIC: &[u8; 4] = b"G1IX";
+const VERSION: u8 = 2;
+// How much of each end of the file goes into the digest
+const DIGESTED: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where a message is in the file, with the sections describing it
+pub struct IndexEntry {
+    pub offset: u64,
+    pub length: u64,
+    /// The PDS as in an edition 1 message
+    pub pds: Vec<u8>,
+    pub gds: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// What an index records about the file indexed, to tell whether the file has changed since
+pub struct SourceFile {
+    pub size: u64,
+    /// Time since the Unix epoch, if the platform gives it
+    pub modified: Option<Duration>,
+    /// SHA-256 digest of the first and last 64 KiB, or of the whole file if it is shorter
+    pub digest: [u8; 32],
+}
+
+impl SourceFile {
+    /// Describe the file as it is now
+    pub fn of(path: impl AsRef<Path>) -> Result<SourceFile, Grib1Error> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let size = metadata.len();
+
+        let mut bytes = vec![];
+        (&mut file).take(DIGESTED).read_to_end(&mut bytes)?;
+        if size > DIGESTED {
+            file.seek(SeekFrom::Start(size.saturating_sub(DIGESTED).max(DIGESTED)))?;
+            file.read_to_end(&mut bytes)?;
+        }
+
+        Ok(SourceFile {
+            size,
+            modified: metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()),
+            digest: sha256(&bytes),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The messages of a file, in the order they appear
+pub struct Index {
+    pub entries: Vec<IndexEntry>,
+    /// The file indexed, None for indexes of version 1 and those built from a reader
+    pub source: Option<SourceFile>,
+}
+
+/// The path of the index of a file, the path of the file with `.g1idx` appended
+pub fn index_path(path: impl AsRef<Path>) -> PathBuf {
+    let mut result = path.as_ref().as_os_str().to_owned();
+    result.push(".g1idx");
+    PathBuf::from(result)
+}
+
+impl Index {
+    /// The messages as returned by `Grib1Reader::inventory`, without their data
+    pub fn messages(&self) -> Result<Vec<Grib>, Grib1Error> {
+        self.entries
+            .iter()
+            .map(|entry| {
+                Ok(Grib {
+                    offset: entry.offset,
+                    length: entry.length,
+                    pds: parse_pds(&entry.pds)?,
+                    gds: entry.gds.as_deref().map(parse_gds).transpose()?,
+                    bitmap: None,
+                    bds: None,
+                    digest: None,
+                    bulletin: None,
+                    sections: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the index may not describe the file as it is now, i.e. the file has changed since it was indexed or the index doesn't say what it was
+    pub fn is_stale(&self, path: impl AsRef<Path>) -> Result<bool, Grib1Error> {
+        Ok(self.source != Some(SourceFile::of(path)?))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = MAGIC.to_vec();
+        result.push(VERSION);
+        let source = self.source.unwrap_or(SourceFile {
+            size: u64::MAX,
+            modified: None,
+            digest: [0; 32],
+        });
+        let (seconds, nanoseconds) = source.modified.map_or((u64::MAX, u32::MAX), |time| (time.as_secs(), time.subsec_nanos()));
+        result.extend_from_slice(&source.size.to_be_bytes());
+        result.extend_from_slice(&seconds.to_be_bytes());
+        result.extend_from_slice(&nanoseconds.to_be_bytes());
+        result.extend_from_slice(&source.digest);
+        result.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+        for entry in &self.entries {
+            result.extend_from_slice(&entry.offset.to_be_bytes());
+            result.extend_from_slice(&entry.length.to_be_bytes());
+            for section in [Some(&entry.pds), entry.gds.as_ref()] {
+                let section = section.map_or(&[][..], Vec::as_slice);
+                result.extend_from_slice(&(section.len() as u16).to_be_bytes());
+                result.extend_from_slice(section);
+            }
+        }
+        result
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Index, Grib1Error> {
+        if bytes.len() < 9 || &bytes[0..4] != MAGIC {
+            return Err(Grib1Error::InvalidIndex("it doesn't start with G1IX".to_string()));
+        }
+        if !(1..=VERSION).contains(&bytes[4]) {
+            return Err(Grib1Error::InvalidIndex(format!("version {} isn't supported", bytes[4])));
+        }
+
+        let mut rest = &bytes[5..];
+        let mut take = |count: usize| -> Result<&[u8], Grib1Error> {
+            let (taken, remaining) = rest.split_at_checked(count).ok_or_else(|| Grib1Error::InvalidIndex("it is cut short".to_string()))?;
+            rest = remaining;
+            Ok(taken)
+        };
+
+        let mut source = None;
+        if bytes[4] >= 2 {
+            let size = u64::from_be_bytes(take(8)?.try_into().unwrap_or_default());
+            let seconds = u64::from_be_bytes(take(8)?.try_into().unwrap_or_default());
+            let nanoseconds = u32::from_be_bytes(take(4)?.try_into().unwrap_or_default());
+            let digest: [u8; 32] = take(32)?.try_into().unwrap_or_default();
+            source = Some(SourceFile {
+                size,
+                modified: (seconds != u64::MAX).then(|| Duration::new(seconds, nanoseconds.min(999_999_999))),
+                digest,
+            })
+            .filter(|_| size != u64::MAX);
+        }
+
+        let count = u32::from_be_bytes(take(4)?.try_into().unwrap_or_default()) as usize;
+        let mut entries = Vec::with_capacity(count.min(bytes.len() / 20));
+        for _ in 0..count {
+            let offset = u64::from_be_bytes(take(8)?.try_into().unwrap_or_default());
+            let length = u64::from_be_bytes(take(8)?.try_into().unwrap_or_default());
+            let length_of_pds = u16::from_be_bytes(take(2)?.try_into().unwrap_or_default()) as usize;
+            let pds = take(length_of_pds)?.to_vec();
+            let length_of_gds = u16::from_be_bytes(take(2)?.try_into().unwrap_or_default()) as usize;
+            let gds = Some(take(length_of_gds)?.to_vec()).filter(|gds| !gds.is_empty());
+            entries.push(IndexEntry { offset, length, pds, gds });
+        }
+        Ok(Index { entries, source })
+    }
+
+    /// Scan a file and index its messages, recording what the file is like so the index can be found stale later
+    #[cfg(feature = "async-tokio")]
+    pub async fn build(path: impl AsRef<Path>) -> Result<Index, Grib1Error> {
+        let source = SourceFile::of(&path)?;
+        let mut index = Grib1Reader::open(&path).await?.build_index().await?;
+        index.source = Some(source);
+        Ok(index)
+    }
+
+    /// Update an index of a file that is being appended to as `Grib1Reader::rescan_from` does, recording what the file is like now
+    #[cfg(feature = "async-tokio")]
+    pub async fn rescan_from(path: impl AsRef<Path>, previous: &Index) -> Result<Index, Grib1Error> {
+        let source = SourceFile::of(&path)?;
+        let mut index = Grib1Reader::open(&path).await?.rescan_from(previous).await?;
+        index.source = Some(source);
+        Ok(index)
+    }
+
+    #[cfg(feature = "async-tokio")]
+    pub async fn write(&self, path: impl AsRef<Path>) -> Result<(), Grib1Error> {
+        Ok(tokio::fs::write(path, self.to_bytes()).await?)
+    }
+
+    #[cfg(feature = "async-tokio")]
+    pub async fn read(path: impl AsRef<Path>) -> Result<Index, Grib1Error> {
+        Index::from_bytes(&tokio::fs::read(path).await?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The fields a message is looked up by, with where it is in the file
+pub struct IndexedMessage {
+    pub param: u8,
+    pub type_of_level: u8,
+    pub level: u16,
+    pub reference_time: DateTime,
+    pub step: u32,
+    /// The grid identification of the PDS, 255 for grids given by the GDS only
+    pub grid: u8,
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl IndexedMessage {
+    pub fn of(grib: &Grib) -> IndexedMessage {
+        IndexedMessage {
+            param: grib.pds.indicator_of_parameter_and_units,
+            type_of_level: grib.pds.indicator_of_type_of_level_or_layer,
+            level: grib.pds.level_or_layer_value,
+            reference_time: grib.pds.reference_time(),
+            step: grib.pds.step(),
+            grid: grib.pds.grid_identification,
+            offset: grib.offset,
+            length: grib.length,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The messages of a file held in memory, built once by `Grib1Reader::grib_index` and queried without reading the file again
+pub struct GribIndex {
+    /// In the order they appear in the file
+    pub messages: Vec<IndexedMessage>,
+}
+
+impl GribIndex {
+    /// Index messages, e.g. as returned by `Grib1Reader::inventory`
+    pub fn from_messages(messages: &[Grib]) -> GribIndex {
+        GribIndex {
+            messages: messages.iter().map(IndexedMessage::of).collect(),
+        }
+    }
+
+    /// The messages of an index read from disk
+    pub fn from_index(index: &Index) -> Result<GribIndex, Grib1Error> {
+        Ok(GribIndex::from_messages(&index.messages()?))
+    }
+
+    /// The messages accepted by the filter
+    pub fn select<F: Fn(&IndexedMessage) -> bool>(&self, filter: F) -> Vec<&IndexedMessage> {
+        self.messages.iter().filter(|message| filter(message)).collect()
+    }
+
+    /// The message of the parameter at the level, reference time and step, if any. The first one is returned if there are several.
+    pub fn find(&self, param: u8, type_of_level: u8, level: u16, reference_time: DateTime, step: u32) -> Option<&IndexedMessage> {
+        self.messages
+            .iter()
+            .find(|m| (m.param, m.type_of_level, m.level, m.reference_time, m.step) == (param, type_of_level, level, reference_time, step))
+    }
+
+    pub fn by_param(&self, param: u8) -> Vec<&IndexedMessage> {
+        self.select(|message| message.param == param)
+    }
+
+    pub fn by_level(&self, type_of_level: u8, level: u16) -> Vec<&IndexedMessage> {
+        self.select(|message| message.type_of_level == type_of_level && message.level == level)
+    }
+
+    pub fn by_reference_time(&self, reference_time: DateTime) -> Vec<&IndexedMessage> {
+        self.select(|message| message.reference_time == reference_time)
+    }
+
+    pub fn by_step(&self, step: u32) -> Vec<&IndexedMessage> {
+        self.select(|message| message.step == step)
+    }
+
+    /// The distinct parameters, in ascending order
+    pub fn params(&self) -> Vec<u8> {
+        distinct(self.messages.iter().map(|message| message.param))
+    }
+
+    /// The distinct levels of a parameter as type and value, in ascending order
+    pub fn levels(&self, param: u8) -> Vec<(u8, u16)> {
+        distinct(self.by_param(param).into_iter().map(|message| (message.type_of_level, message.level)))
+    }
+
+    /// The distinct reference times, in ascending order
+    pub fn reference_times(&self) -> Vec<DateTime> {
+        distinct(self.messages.iter().map(|message| message.reference_time))
+    }
+
+    /// The distinct steps of a parameter, in ascending order
+    pub fn steps(&self, param: u8) -> Vec<u32> {
+        distinct(self.by_param(param).into_iter().map(|message| message.step))
+    }
+}
+
+pub(crate) fn distinct<T: Ord>(values: impl Iterator<Item = T>) -> Vec<T> {
+    let mut result: Vec<T> = values.collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+#[cfg(feature = "async-tokio")]
+impl Grib1Reader {
+    /// Scan the file once and hold what its messages are looked up by in memory
+    pub async fn grib_index(&mut self) -> Result<GribIndex, Grib1Error> {
+        Ok(GribIndex::from_messages(&self.inventory().await?))
+    }
+
+    /// Read and decode a message found in a `GribIndex` of the file
+    pub async fn read_indexed_message(&mut self, message: &IndexedMessage) -> Result<Grib, Grib1Error> {
+        self.decode_at(message.offset, message.length).await
+    }
+
+    // Decode the message at the offset as the other reads do, so the options and unpackers of the reader apply
+    async fn decode_at(&mut self, offset: u64, length: u64) -> Result<Grib, Grib1Error> {
+        self.seek(SeekFrom::Start(offset)).await?;
+        let mut section = "IS";
+        match self.read_sections(offset, offset + length, &|_: &PDS| true, true, &mut section).await? {
+            GribResult::Grib(grib) => Ok(*grib),
+            GribResult::Length(_) => Err(Grib1Error::WrongHeader),
+        }
+    }
+
+    /// Scan the file and index its messages, which can be saved with `Index::write` for later reads
+    pub async fn build_index(&mut self) -> Result<Index, Grib1Error> {
+        self.index_from(0, vec![]).await
+    }
+
+    /// Bring an index of a file being appended to up to date, scanning only what follows the last message indexed. The messages already
+    /// indexed are kept as they are. A file shorter than the index says has been rewritten rather than appended to, and is indexed from the start.
+    pub async fn rescan_from(&mut self, previous: &Index) -> Result<Index, Grib1Error> {
+        let end = previous.entries.iter().map(|entry| entry.offset + entry.length).max().unwrap_or(0);
+        if end > self.seek(SeekFrom::End(0)).await? {
+            return self.build_index().await;
+        }
+        self.index_from(end, previous.entries.clone()).await
+    }
+
+    // Index the messages from the offset on, after the entries of those before it
+    async fn index_from(&mut self, offset: u64, mut entries: Vec<IndexEntry>) -> Result<Index, Grib1Error> {
+        let message_index = entries.len();
+        for grib in self.scan(offset, message_index, |_| true, false).await? {
+            let mut header = [0; 8];
+            self.seek(SeekFrom::Start(grib.offset)).await?;
+            self.reader.read_exact(&mut header).await?;
+
+            // Edition 0 messages have no length in the indicator section, and a shorter PDS that is stored as in edition 1
+            let edition0 = edition0::is_edition0(&header);
+            self.seek(SeekFrom::Start(grib.offset + if edition0 { 4 } else { 8 })).await?;
+            let mut pds = self.read_section().await?;
+            if edition0 {
+                pds = edition0::to_edition1_pds(&pds);
+            }
+            let gds = match grib.pds.has_gds() {
+                true => Some(self.read_section().await?),
+                false => None,
+            };
+            entries.push(IndexEntry {
+                offset: grib.offset,
+                length: grib.length,
+                pds,
+                gds,
+            });
+        }
+        Ok(Index { entries, source: None })
+    }
+
+    /// Open a file together with its index, saved next to it as given by `index_path`. The index is built and saved if there is none
+    /// or it is stale, i.e. the file has changed since it was indexed, so the offsets of the index always match the file.
+    pub async fn with_index(path: impl AsRef<Path>) -> Result<(Grib1Reader, Index), Grib1Error> {
+        let saved = index_path(&path);
+        let index = match Index::read(&saved).await {
+            Ok(index) if !index.is_stale(&path)? => index,
+            _ => {
+                let index = Index::build(&path).await?;
+                index.write(&saved).await?;
+                index
+            }
+        };
+        Ok((Grib1Reader::open(path).await?, index))
+    }
+
+    /// Like `read_where`, but only the messages the index says are accepted by the filter are read
+    pub async fn read_indexed<F: Fn(&PDS) -> bool>(&mut self, index: &Index, filter: F) -> Result<Vec<Grib>, Grib1Error> {
+        let mut result = vec![];
+        for grib in index.messages()? {
+            if filter(&grib.pds) {
+                result.push(self.decode_at(grib.offset, grib.length).await?);
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn index_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let index = reader.build_index().await?;
+        assert_eq!(index.entries.len(), 2);
+        assert_eq!((index.entries[1].offset, index.entries[1].length), (2542704, 2542704));
+        assert_eq!(index.entries[0].gds.as_ref().map(Vec::len), Some(570));
+
+        let path = std::env::temp_dir().join("grib1_reader_index_test.g1idx");
+        index.write(&path).await?;
+        let index = Index::read(&path).await?;
+        let messages = index.messages()?;
+        assert_eq!(messages[1].pds.indicator_of_parameter_and_units, 34);
+        assert_eq!(messages[1].gds.as_ref().map(|gds| gds.number_of_points()), Some(1271041));
+
+        let result = reader.read_indexed(&index, |pds| pds.indicator_of_parameter_and_units == 34).await?;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].offset, 2542704);
+        assert_eq!(result[0].bds.as_ref().unwrap().data.len(), 1271041);
+
+        let bytes = index.to_bytes();
+        assert!(Index::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Index::from_bytes(b"GRIB").is_err());
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn with_index_test() -> Result<(), Grib1Error> {
+        let path = std::env::temp_dir().join("grib1_reader_with_index_test.grb");
+        let bytes = std::fs::read("data/sample.grib")?;
+        std::fs::write(&path, &bytes)?;
+        let _ = std::fs::remove_file(index_path(&path));
+
+        let (_, index) = Grib1Reader::with_index(&path).await?;
+        assert_eq!(index.entries.len(), 2);
+        assert_eq!(Index::read(index_path(&path)).await?, index);
+        assert!(!index.is_stale(&path)?);
+
+        // Indexes without a description of the file are stale, as are those of files that have changed
+        let version1 = Index { source: None, ..index.clone() };
+        assert!(version1.is_stale(&path)?);
+        std::fs::write(&path, &bytes[2542704..])?;
+        assert!(index.is_stale(&path)?);
+        let (mut reader, index) = Grib1Reader::with_index(&path).await?;
+        assert_eq!(index.entries.len(), 1);
+        let result = reader.read_indexed(&index, |_| true).await?;
+        assert_eq!(result[0].pds.indicator_of_parameter_and_units, 34);
+
+        std::fs::remove_file(index_path(&path))?;
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rescan_from_test() -> Result<(), Grib1Error> {
+        let path = std::env::temp_dir().join("grib1_reader_rescan_from_test.grb");
+        let bytes = std::fs::read("data/sample.grib")?;
+        std::fs::write(&path, &bytes[..2542704])?;
+        let first = Index::build(&path).await?;
+
+        // Only the appended message is scanned, the first being kept from the old index even if it has since been overwritten
+        let mut appended = vec![0; 2542704];
+        appended.extend_from_slice(&bytes[2542704..]);
+        std::fs::write(&path, &appended)?;
+        let index = Index::rescan_from(&path, &first).await?;
+        assert_eq!(index.entries, Index::build("data/sample.grib").await?.entries);
+        assert!(!index.is_stale(&path)?);
+
+        // A file shorter than the index is indexed again
+        std::fs::write(&path, &bytes[2542704..])?;
+        let index = Index::rescan_from(&path, &index).await?;
+        assert_eq!((index.entries.len(), index.entries[0].offset), (1, 0));
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn grib_index_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+        let index = reader.grib_index().await?;
+        assert_eq!(index, GribIndex::from_index(&reader.build_index().await?)?);
+
+        let reference_time = DateTime::new(2023, 10, 2, 15, 0);
+        assert_eq!(index.params(), vec![33, 34]);
+        assert_eq!(index.levels(34), vec![(100, 700)]);
+        assert_eq!(index.reference_times(), vec![reference_time]);
+        assert_eq!(index.steps(33), vec![1]);
+        assert_eq!(index.by_level(100, 700).len(), 2);
+        assert!(index.by_step(0).is_empty());
+
+        let message = index.find(34, 100, 700, reference_time, 1).unwrap();
+        assert_eq!((message.offset, message.length), (2542704, 2542704));
+        assert!(index.find(34, 100, 850, reference_time, 1).is_none());
+
+        let decoded = reader.read_indexed_message(message).await?;
+        assert_eq!(decoded.pds.indicator_of_parameter_and_units, 34);
+        assert_eq!(decoded.offset, 2542704);
+
+        // The options of the reader apply as they do to any other read
+        let limits = crate::limits::Limits { max_points: 1000, ..Default::default() };
+        let mut limited = Grib1Reader::open("data/sample.grib").await?.limits(limits);
+        assert!(limited.read_indexed_message(message).await.is_err());
+        let index = Index::build("data/sample.grib").await?;
+        assert!(limited.read_indexed(&index, |_| true).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+//! Interpolation between fields, e.g. to the times between the steps of 3-hourly forcing, and from a stack of levels to other levels:
+//! in the logarithm of pressure for isobaric and hybrid model levels, and linearly for heights. The fields must be on the same grid.
+
+use crate::aggregate::period;
+use crate::codec::read_f32_ibm;
+use crate::error::Grib1Error;
+use crate::sections::sections;
+use crate::time::{time_unit_minutes, DateTime};
+use crate::Grib;
+
+/// Blend two fields valid at different times linearly to a time between them, the ends included. The PDS is that of `a`
+/// with the step changed to give the time, which must be a whole number of its forecast time unit. Points missing from either field stay missing.
+pub fn interpolate_time(a: &Grib, b: &Grib, time: DateTime) -> Result<Grib, Grib1Error> {
+    check_same_field(a, b)?;
+    let (ta, tb) = (valid_at(a)?.to_minutes(), valid_at(b)?.to_minutes());
+    let t = time.to_minutes();
+    if ta == tb {
+        return Err(Grib1Error::IncompatibleFields("the fields are valid at the same time".to_string()));
+    }
+    if t < ta.min(tb) || t > ta.max(tb) {
+        return Err(Grib1Error::IncompatibleFields(format!("{} isn't between the times of the fields", time)));
+    }
+
+    let weight = ((t - ta) as f64 / (tb - ta) as f64) as f32;
+    let bds = a.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let other = b.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let data = bds.nan_values().iter().zip(other.nan_values().iter()).map(|(x, y)| x + (y - x) * weight).collect();
+
+    let mut result = a.with_data(bds, data);
+    let unit = time_unit_minutes(a.pds.forecast_time_unit).ok_or_else(|| Grib1Error::IncompatibleFields(format!("the forecast time unit {} isn't of fixed length", a.pds.forecast_time_unit)))?;
+    let minutes = t - a.pds.reference_time().to_minutes();
+    let step = match minutes / unit {
+        step if minutes % unit != 0 || !(0..=0xffff).contains(&step) => return Err(Grib1Error::IncompatibleFields(format!("{} can't be given as a step from the reference time", time))),
+        step => step as u16,
+    };
+    // Steps that don't fit P1 use both octets
+    (result.pds.p1_period_of_time, result.pds.p2_period_of_time, result.pds.time_range_indicator) = match step {
+        0..=255 => (step as u8, 0, 0),
+        _ => ((step >> 8) as u8, step as u8, 10),
+    };
+    Ok(result)
+}
+
+/// Interpolate fields on isobaric levels (level type 100) to the target pressures in hPa, linearly in the logarithm of pressure.
+/// Points where a target lies outside the levels given, or where a level is missing, are missing.
+pub fn interpolate_pressure(levels: &[Grib], targets: &[u16]) -> Result<Vec<Grib>, Grib1Error> {
+    check_stack(levels, &[100])?;
+    vertical(levels, |level, _| (levels[level].pds.level_or_layer_value as f64).ln(), targets, |target| (target as f64).ln(), 100)
+}
+
+/// Interpolate fields on height levels, above sea level (level type 103) or above ground (105), linearly to the target heights in metres
+pub fn interpolate_height(levels: &[Grib], targets: &[u16]) -> Result<Vec<Grib>, Grib1Error> {
+    let type_of_level = check_stack(levels, &[103, 105])?;
+    vertical(levels, |level, _| levels[level].pds.level_or_layer_value as f64, targets, |target| target as f64, type_of_level)
+}
+
+/// Interpolate fields on hybrid model levels (level type 109) to the target pressures in hPa, linearly in the logarithm of pressure.
+/// The pressure of each level is found from the surface pressure in Pa and the coefficients as given by `vertical_coordinates`:
+/// A and then B for each half level, the full level k lying halfway between half levels k - 1 and k.
+pub fn interpolate_hybrid(levels: &[Grib], surface_pressure: &Grib, coefficients: &[f32], targets: &[u16]) -> Result<Vec<Grib>, Grib1Error> {
+    check_stack(levels, &[109])?;
+    if let Some(level) = levels.first() {
+        level.check_same_grid(surface_pressure)?;
+    }
+    let surface = surface_pressure.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?.nan_values();
+    let half_levels = coefficients.len() / 2;
+    let (a, b) = coefficients.split_at(half_levels);
+    if let Some(level) = levels.iter().find(|level| !(1..half_levels).contains(&(level.pds.level_or_layer_value as usize))) {
+        return Err(Grib1Error::IncompatibleFields(format!("there are no coefficients for model level {}", level.pds.level_or_layer_value)));
+    }
+
+    let pressure = |level: usize, point: usize| {
+        let k = levels[level].pds.level_or_layer_value as usize;
+        let half = |k: usize| a[k] as f64 + b[k] as f64 * surface[point] as f64;
+        ((half(k - 1) + half(k)) / 2.0).ln()
+    };
+    vertical(levels, pressure, targets, |target| (target as f64 * 100.0).ln(), 100)
+}
+
+/// The vertical coordinate parameters of the GDS of a single raw message, e.g. the coefficients of hybrid levels. Empty if there are none.
+pub fn vertical_coordinates(message: &[u8]) -> Result<Vec<f32>, Grib1Error> {
+    let sections = sections(message)?;
+    let Some(gds) = sections.iter().find(|section| section.id == 2) else {
+        return Ok(vec![]);
+    };
+    let (count, location) = (gds.octet(4).unwrap_or(0) as usize, gds.octet(5).unwrap_or(255) as usize);
+    if count == 0 || location == 255 {
+        return Ok(vec![]);
+    }
+    let octets = gds
+        .octets(location, location + count * 4 - 1)
+        .ok_or_else(|| Grib1Error::MalformedMessage("The vertical coordinates extend past the GDS".to_string()))?;
+    Ok(octets.chunks_exact(4).map(read_f32_ibm).collect())
+}
+
+// Fail unless the levels are of the same parameter and one of the types of level on the same grid, returning the type
+fn check_stack(levels: &[Grib], types_of_level: &[u8]) -> Result<u8, Grib1Error> {
+    let Some(first) = levels.first() else {
+        return Err(Grib1Error::IncompatibleFields("there are no levels".to_string()));
+    };
+    let type_of_level = first.pds.indicator_of_type_of_level_or_layer;
+    for level in levels {
+        if !types_of_level.contains(&level.pds.indicator_of_type_of_level_or_layer) || level.pds.indicator_of_type_of_level_or_layer != type_of_level {
+            return Err(Grib1Error::IncompatibleFields(format!("level type {} isn't expected", level.pds.indicator_of_type_of_level_or_layer)));
+        }
+        if level.pds.indicator_of_parameter_and_units != first.pds.indicator_of_parameter_and_units {
+            return Err(Grib1Error::IncompatibleFields("the parameters differ".to_string()));
+        }
+        first.check_same_grid(level)?;
+    }
+    Ok(type_of_level)
+}
+
+// Interpolate each column linearly in the vertical coordinate, given for each level and point, to each target. The results are copies
+// of the first level with the target as level.
+fn vertical<C, T>(levels: &[Grib], coordinate: C, targets: &[u16], target_coordinate: T, type_of_level: u8) -> Result<Vec<Grib>, Grib1Error>
+where
+    C: Fn(usize, usize) -> f64,
+    T: Fn(u16) -> f64,
+{
+    let data = levels
+        .iter()
+        .map(|level| level.bds.as_ref().map(|bds| bds.nan_values()).ok_or(Grib1Error::MissingSection("BDS")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = &levels[0];
+    let points = data[0].len();
+
+    let mut results = targets.iter().map(|_| Vec::with_capacity(points)).collect::<Vec<_>>();
+    let mut column = Vec::with_capacity(levels.len());
+    for point in 0..points {
+        column.clear();
+        column.extend(data.iter().enumerate().map(|(level, values)| (coordinate(level, point), values[point] as f64)));
+        column.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (target, result) in targets.iter().zip(&mut results) {
+            let x = target_coordinate(*target);
+            let value = column.windows(2).find(|pair| pair[0].0 <= x && x <= pair[1].0).map_or(f64::NAN, |pair| {
+                let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                if x1 == x0 {
+                    y0
+                } else {
+                    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+                }
+            });
+            result.push(value as f32);
+        }
+    }
+
+    let bds = first.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    Ok(targets
+        .iter()
+        .zip(results)
+        .map(|(target, data)| {
+            let mut result = first.with_data(bds, data);
+            result.pds.indicator_of_type_of_level_or_layer = type_of_level;
+            result.pds.level_or_layer_value = *target;
+            result
+        })
+        .collect())
+}
+
+// Fail unless the fields are of the same parameter and level on the same grid
+pub(crate) fn check_same_field(a: &Grib, b: &Grib) -> Result<(), Grib1Error> {
+    let field = |grib: &Grib| (grib.pds.indicator_of_parameter_and_units, grib.pds.indicator_of_type_of_level_or_layer, grib.pds.level_or_layer_value);
+    if field(a) != field(b) {
+        return Err(Grib1Error::IncompatibleFields("the parameters or levels differ".to_string()));
+    }
+    a.check_same_grid(b)
+}
+
+// The time a field valid at a single time is valid at
+fn valid_at(grib: &Grib) -> Result<DateTime, Grib1Error> {
+    match period(&grib.pds)? {
+        (start, end) if start == end => Ok(start),
+        _ => Err(Grib1Error::IncompatibleFields("accumulations and averages can't be interpolated in time".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::missing::MissingValues;
+    use crate::synthetic::MessageBuilder;
+
+    #[test]
+    fn interpolate_time_test() -> Result<(), Grib1Error> {
+        let reference = DateTime::new(2024, 3, 1, 0, 0);
+        let at = |step, values: Vec<f32>| MessageBuilder::new(2, 1).reference_time(reference).step(step).decimal_scale_factor(2).values(values).grib();
+        let (a, b) = (at(3, vec![1.0, f32::NAN])?, at(6, vec![4.0, 2.0])?);
+
+        let result = interpolate_time(&a, &b, DateTime::new(2024, 3, 1, 4, 0))?;
+        assert_eq!(result.pds.step(), 4);
+        assert_eq!(result.pds.valid_time(), Some(DateTime::new(2024, 3, 1, 4, 0)));
+        let data = &result.bds.as_ref().unwrap().data;
+        assert!((data[0] - 2.0).abs() < 1e-5 && data[1].is_nan());
+        // The order of the fields doesn't matter
+        assert!((interpolate_time(&b, &a, DateTime::new(2024, 3, 1, 5, 0))?.bds.unwrap().data[0] - 3.0).abs() < 1e-5);
+
+        assert!(interpolate_time(&a, &b, DateTime::new(2024, 3, 1, 7, 0)).is_err());
+        assert!(interpolate_time(&a, &b, DateTime::new(2024, 3, 1, 4, 30)).is_err());
+        assert!(interpolate_time(&a, &at(3, vec![0.0, 0.0])?, DateTime::new(2024, 3, 1, 3, 0)).is_err());
+        assert!(interpolate_time(&a, &MessageBuilder::new(2, 1).reference_time(reference).step(6).parameter(33).grib()?, DateTime::new(2024, 3, 1, 4, 0)).is_err());
+
+        // Sentinels aren't blended with the values, and stay missing
+        let mut sentinel = at(3, vec![1.0, f32::NAN])?;
+        if let Some(bds) = sentinel.bds.as_mut() {
+            bds.set_missing_values(MissingValues::Sentinel(9999.0));
+        }
+        let result = interpolate_time(&sentinel, &b, DateTime::new(2024, 3, 1, 4, 0))?.bds.unwrap();
+        assert_eq!(result.data[1], 9999.0);
+        assert!((result.data[0] - 2.0).abs() < 1e-5 && result.optional_values()[1].is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn vertical_test() -> Result<(), Grib1Error> {
+        // Values that are linear in the logarithm of pressure are interpolated exactly
+        let mut isobaric = [1000, 850, 500]
+            .into_iter()
+            .map(|hpa| {
+                MessageBuilder::new(2, 1)
+                    .level(100, hpa)
+                    .decimal_scale_factor(4)
+                    .values(vec![(hpa as f32).ln(), if hpa == 850 { f32::NAN } else { 1.0 }])
+                    .grib()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let result = interpolate_pressure(&isobaric, &[700, 1100])?;
+        assert_eq!(result[0].pds.level_or_layer_value, 700);
+        let data = &result[0].bds.as_ref().unwrap().data;
+        assert!((data[0] - 700f32.ln()).abs() < 1e-3 && data[1].is_nan());
+        assert!(result[1].bds.as_ref().unwrap().data[0].is_nan());
+
+        // Levels read with a sentinel: the sentinel isn't taken for a value, and points out of range are given as the sentinel too
+        for level in &mut isobaric {
+            if let Some(bds) = level.bds.as_mut() {
+                bds.set_missing_values(MissingValues::Sentinel(9999.0));
+            }
+        }
+        let result = interpolate_pressure(&isobaric, &[700, 1100])?;
+        let (data, out_of_range) = (result[0].bds.as_ref().unwrap(), result[1].bds.as_ref().unwrap());
+        assert!((data.data[0] - 700f32.ln()).abs() < 1e-3 && data.data[1] == 9999.0);
+        assert_eq!(out_of_range.optional_values(), vec![None, None]);
+
+        let heights = [(0, 10.0), (1000, 0.0)].map(|(height, value)| MessageBuilder::new(2, 1).level(103, height).values(vec![value, value]).grib());
+        let heights = heights.into_iter().collect::<Result<Vec<_>, _>>()?;
+        let result = interpolate_height(&heights, &[250])?;
+        assert_eq!((result[0].pds.indicator_of_type_of_level_or_layer, result[0].bds.as_ref().unwrap().data[0]), (103, 7.5));
+        assert!(interpolate_pressure(&heights, &[500]).is_err());
+
+        // Three half levels at 0, half and all of the surface pressure, the full levels at a quarter and three quarters of it
+        let coefficients = vertical_coordinates(&with_vertical_coordinates(&[0.0, 0.0, 0.0, 0.0, 0.5, 1.0])?)?;
+        assert_eq!(coefficients, vec![0.0, 0.0, 0.0, 0.0, 0.5, 1.0]);
+        let surface = MessageBuilder::new(2, 1).level(1, 0).values(vec![100000.0, 100000.0]).grib()?;
+        let model = [(1, 25000f32), (2, 75000f32)]
+            .into_iter()
+            .map(|(k, pa)| MessageBuilder::new(2, 1).level(109, k).decimal_scale_factor(4).values(vec![pa.ln(), pa.ln()]).grib())
+            .collect::<Result<Vec<_>, _>>()?;
+        let result = interpolate_hybrid(&model, &surface, &coefficients, &[500])?;
+        assert_eq!(result[0].pds.indicator_of_type_of_level_or_layer, 100);
+        assert!((result[0].bds.as_ref().unwrap().data[0] - 50000f32.ln()).abs() < 1e-3);
+        assert!(interpolate_hybrid(&model, &surface, &coefficients[..4], &[500]).is_err());
+        let mut surface = MessageBuilder::new(2, 1).level(1, 0).values(vec![100000.0, f32::NAN]).grib()?;
+        if let Some(bds) = surface.bds.as_mut() {
+            bds.set_missing_values(MissingValues::Sentinel(9999.0));
+        }
+        // 50 hPa would lie between the levels were the sentinel taken for the surface pressure
+        let result = interpolate_hybrid(&model, &surface, &coefficients, &[50])?;
+        assert!(result[0].bds.as_ref().unwrap().data[1].is_nan());
+        assert!(vertical_coordinates(&MessageBuilder::new(2, 1).build()?)?.is_empty());
+        Ok(())
+    }
+
+    // A message with the vertical coordinate parameters appended to its GDS
+    fn with_vertical_coordinates(values: &[f32]) -> Result<Vec<u8>, Grib1Error> {
+        let message = MessageBuilder::new(2, 1).build()?;
+        let sections = sections(&message)?;
+        let mut gds = sections[2].bytes.to_vec();
+        (gds[3], gds[4]) = (values.len() as u8, gds.len() as u8 + 1);
+        gds.extend(values.iter().flat_map(|value| crate::ibm::to_ibm(*value)));
+        let length = crate::codec::write_u24_be(gds.len() as u32);
+        gds[0..3].copy_from_slice(&length);
+        crate::writer::finalize(sections[1].bytes, Some(&gds), None, sections[3].bytes)
+    }
+}
@@ -0,0 +1,96 @@
+//! Reading damaged files, where a message that can't be parsed is skipped instead of failing the whole read.
+//! When a message doesn't start where the previous one ended, the reader searches forward for the next `GRIB` marker.
+
+use crate::error::Grib1Error;
+use crate::{Grib, Grib1Reader, SearchParams, PDS};
+use std::ops::Range;
+
+#[derive(Debug)]
+/// A message that couldn't be read
+pub struct SkippedMessage {
+    /// Position of the message within the file
+    pub offset: u64,
+    pub error: Grib1Error,
+}
+
+#[derive(Debug, Default)]
+/// The messages that could be read, and the ones that were skipped in the order they were found
+pub struct LenientRead {
+    pub messages: Vec<Grib>,
+    pub skipped: Vec<SkippedMessage>,
+    /// Byte ranges passed over while searching for the next message, including those of skipped messages whose length couldn't be trusted
+    pub skipped_ranges: Vec<Range<u64>>,
+}
+
+impl Grib1Reader {
+    /// Like `read`, but a message that fails to parse is skipped and recorded rather than aborting the read.
+    pub async fn read_lenient(&mut self, search: Vec<SearchParams>) -> Result<LenientRead, Grib1Error> {
+        self.read_lenient_where(|pds| search.iter().any(|item| item.matches(pds))).await
+    }
+
+    /// Like `read_where`, but a message that fails to parse is skipped and recorded rather than aborting the read.
+    /// Only errors reading the file itself are returned, e.g. when it can't be seeked.
+    pub async fn read_lenient_where<F: Fn(&PDS) -> bool>(&mut self, filter: F) -> Result<LenientRead, Grib1Error> {
+        let mut result = LenientRead::default();
+        result.messages = self.collect(0, 0, filter, true, Some(&mut result)).await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn lenient_test() -> Result<(), Grib1Error> {
+        // Break the bit-map flag of the first message, so its sections no longer add up
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        bytes[8 + 7] |= 0x40;
+        let path = std::env::temp_dir().join("grib1_reader_lenient_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert!(reader.read(vec![SearchParams { param: 34, level: 700 }]).await.is_err());
+
+        let result = reader.read_lenient(vec![SearchParams { param: 34, level: 700 }]).await?;
+        assert_eq!(result.messages.len(), 1);
+        assert_eq!(result.messages[0].offset, 2542704);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].offset, 0);
+        assert!(result.skipped_ranges.is_empty());
+
+        // A lenient reader passes over the message wherever it reads one message at a time
+        let mut reader = Grib1Reader::open(&path).await?.lenient(true);
+        let mut messages = reader.messages().await?;
+        assert_eq!(messages.next().await?.unwrap().offset, 2542704);
+        assert!(messages.next().await?.is_none());
+        assert_eq!(reader.warnings().len(), 1);
+        let result = reader.read_with(vec![], |_| crate::messages::Decision::Decode).await?;
+        assert_eq!(result.len(), 1);
+        let mut matches = reader.into_matches(|_| true).prefetch(true);
+        assert_eq!(matches.next().await?.unwrap().offset, 2542704);
+        assert!(matches.next().await?.is_none());
+
+        // Garbage before and between the messages, and a length that doesn't lead to the next message
+        let mut damaged = b"junk".to_vec();
+        let mut first = tokio::fs::read("data/sample.grib").await?;
+        let second = first.split_off(2542704);
+        first[6] = 0;
+        damaged.extend_from_slice(&first);
+        damaged.extend_from_slice(b"more junk");
+        damaged.extend_from_slice(&second);
+        tokio::fs::write(&path, &damaged).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        let result = reader.read_lenient(vec![SearchParams { param: 34, level: 700 }]).await?;
+        assert_eq!(result.messages.len(), 1);
+        assert_eq!(result.messages[0].offset, 4 + 2542704 + 9);
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped_ranges, vec![0..4, 4..4 + 2542704 + 9]);
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -1,178 +1,735 @@
 //! Read a GRIB1 file and search for data based on parameter and level values. The results can either be decoded or extracted as a binary blob so it can be saved to a separate file.
-//! Currently only the Grid 10 (RotatedLatLon) data representation type is supported.
+//! Currently the Grid 0 (RegularLatLon) and Grid 10 (RotatedLatLon) data representation types are supported.
 
-use bitstream_io::{BigEndian, BitRead, BitReader};
-use error::Grib1Error;
-use std::io::Cursor;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use codec::read_u24_be;
+#[cfg(feature = "std")]
+use error::{BdsError, Grib1Error};
+#[cfg(feature = "async-tokio")]
 use std::io::SeekFrom;
+#[cfg(feature = "std")]
+use time::DateTime;
+#[cfg(feature = "async-tokio")]
 use tokio::fs::File;
+#[cfg(feature = "async-tokio")]
 use tokio::io::{AsyncReadExt, AsyncSeekExt, BufReader};
 
+#[cfg(feature = "std")]
+pub mod aggregate;
+#[cfg(feature = "std")]
+pub mod arithmetic;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "async-tokio")]
+pub mod catalog;
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod core;
+#[cfg(feature = "async-tokio")]
+pub mod cube;
+#[cfg(feature = "std")]
+pub mod data_array;
+#[cfg(feature = "std")]
+pub mod derive;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod digest;
+#[cfg(feature = "std")]
+pub mod dump;
+#[cfg(feature = "eccodes")]
+pub mod eccodes;
+#[cfg(feature = "std")]
+pub mod edit;
+#[cfg(feature = "std")]
+mod edition0;
+#[cfg(feature = "std")]
+pub mod ensemble;
+#[cfg(feature = "std")]
 pub mod error;
-
+#[cfg(feature = "async-tokio")]
+pub mod follow;
+#[cfg(feature = "std")]
+pub mod grib2;
+#[cfg(feature = "std")]
+pub mod grid;
+#[cfg(feature = "std")]
+pub mod gts;
+#[cfg(feature = "async-tokio")]
+pub mod gts_feed;
+#[cfg(feature = "std")]
+pub mod ibm;
+#[cfg(feature = "std")]
+pub mod idx;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "std")]
+pub mod interpolate;
+#[cfg(feature = "async-tokio")]
+pub mod lenient;
+#[cfg(feature = "std")]
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod lossless;
+#[cfg(feature = "std")]
+pub mod merge;
+#[cfg(feature = "async-tokio")]
+pub mod messages;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod missing;
+#[cfg(feature = "async-tokio")]
+pub mod options;
+#[cfg(feature = "std")]
+pub mod predefined;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "async-tokio")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod region;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod sample;
+#[cfg(feature = "std")]
+pub mod sanity;
+#[cfg(feature = "std")]
+pub mod sections;
+#[cfg(feature = "async-tokio")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod split;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "async-tokio")]
+pub mod streaming;
+#[cfg(feature = "std")]
+mod strict;
+#[cfg(feature = "std")]
+pub mod sync;
+#[cfg(all(feature = "std", any(test, feature = "test-util")))]
+pub mod synthetic;
+#[cfg(feature = "std")]
+pub mod tables;
+#[cfg(feature = "std")]
+pub mod time;
+#[cfg(feature = "async-tokio")]
+mod trace;
+#[cfg(feature = "uom")]
+pub mod units;
+#[cfg(feature = "std")]
+pub mod unpacker;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod values;
+#[cfg(feature = "std")]
+pub mod warning;
+#[cfg(feature = "std")]
+pub mod writer;
+
+#[cfg(feature = "std")]
+use crate::core::{decode_data, next_section, parse_bitmap, parse_gds, parse_pds};
+pub use crate::core::{Bitmap, ConicProjection, DataRepresentation, MatrixValues, PackingInfo, PackingKind, RegularLatLon, RotatedLatLon, BDS, GDS, PDS};
+#[cfg(feature = "std")]
+pub use dump::dump;
+
+#[cfg(feature = "async-tokio")]
 /// The star of the show
 pub struct Grib1Reader {
     pub reader: BufReader<File>,
+    strict: bool,
+    lenient: bool,
+    skip_grib2: bool,
+    gts: bool,
+    decimal_scaling: bool,
+    missing_values: missing::MissingValues,
+    digest: bool,
+    warnings: Vec<warning::Warning>,
+    report: report::ScanReport,
+    metrics: metrics::Metrics,
+    limits: limits::Limits,
+    progress: Option<progress::Callback>,
+    unpackers: Vec<Box<dyn unpacker::BdsUnpacker>>,
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 /// Grib file representation
 pub struct Grib {
+    /// Position of the message within the file
+    pub offset: u64,
     pub length: u64,
     pub pds: PDS,
     pub gds: Option<GDS>,
+    /// The bit-map section as read, if the message has one. Messages derived from others leave it out, as their missing values are NaN.
+    pub bitmap: Option<Bitmap>,
     pub bds: Option<BDS>,
+    /// SHA-256 digest of the raw bytes of the message, set by readers with `Grib1Reader::digest` enabled
+    pub digest: Option<[u8; 32]>,
+    /// The header of the GTS bulletin wrapping the message, set by readers with `Grib1Reader::gts` enabled
+    pub bulletin: Option<gts::BulletinHeader>,
+    // Where the sections are from the start of the message, if it was read from one
+    pub(crate) sections: Option<sections::SectionRanges>,
 }
 
-#[derive(Debug, Clone, Copy)]
-/// Rotated latitude/longitude
-pub struct RotatedLatLon {
-    pub number_of_lat_values: u16,
-    pub number_of_lon_values: u16,
-    pub latitude_of_first_grid_point: f32,
-    pub longitude_of_first_grid_point: f32,
-    pub latitude_of_last_grid_point: f32,
-    pub longitude_of_last_grid_point: f32,
-    pub latitude_of_southern_pole: f32,
-    pub longitude_of_southern_pole: f32,
+#[cfg(feature = "std")]
+impl RotatedLatLon {
+    /// The (rotated) latitude of every row, in the order they are stored
+    pub fn latitudes(&self) -> Vec<f32> {
+        spread(self.latitude_of_first_grid_point, self.latitude_of_last_grid_point, self.number_of_lon_values as usize)
+    }
+
+    /// The (rotated) longitude of every column, in the order they are stored
+    pub fn longitudes(&self) -> Vec<f32> {
+        let mut last = self.longitude_of_last_grid_point;
+        if last < self.longitude_of_first_grid_point {
+            // The grid crosses the dateline
+            last += 360.0;
+        }
+        spread(self.longitude_of_first_grid_point, last, self.number_of_lat_values as usize)
+    }
+
+    /// Convert rotated coordinates into geographical latitude and longitude
+    pub fn to_geographic(&self, lat: f32, lon: f32) -> (f32, f32) {
+        let (x, y, z) = to_cartesian(lat as f64, lon as f64 + self.angle_of_rotation as f64);
+
+        // Tilt the pole, then turn it to the longitude of the southern pole
+        let theta = -(90.0 + self.latitude_of_southern_pole as f64).to_radians();
+        let (x, z) = (theta.cos() * x + theta.sin() * z, -theta.sin() * x + theta.cos() * z);
+        let (lat, lon) = from_cartesian(x, y, z);
+
+        (lat as f32, normalize_longitude(lon + self.longitude_of_southern_pole as f64) as f32)
+    }
+
+    /// Convert geographical latitude and longitude into rotated coordinates
+    pub fn from_geographic(&self, lat: f32, lon: f32) -> (f32, f32) {
+        let (x, y, z) = to_cartesian(lat as f64, lon as f64 - self.longitude_of_southern_pole as f64);
+
+        let theta = (90.0 + self.latitude_of_southern_pole as f64).to_radians();
+        let (x, z) = (theta.cos() * x + theta.sin() * z, -theta.sin() * x + theta.cos() * z);
+        let (lat, lon) = from_cartesian(x, y, z);
+
+        (lat as f32, normalize_longitude(lon - self.angle_of_rotation as f64) as f32)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-/// List of data types the library supports (is able to decode)
-pub enum DataRepresentation {
-    Unhandled,
-    RotatedLatLon(RotatedLatLon),
+#[cfg(feature = "std")]
+fn to_cartesian(lat: f64, lon: f64) -> (f64, f64, f64) {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    (lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
 }
 
-#[derive(Debug)]
-enum GribResult {
-    Length(u64),
-    Grib(Grib),
+#[cfg(feature = "std")]
+fn from_cartesian(x: f64, y: f64, z: f64) -> (f64, f64) {
+    (z.clamp(-1.0, 1.0).asin().to_degrees(), y.atan2(x).to_degrees())
 }
 
-#[derive(Debug, Clone)]
-/// Grid description section
-pub struct GDS {
-    pub number_of_vertical_coordinate_values: u8,
-    pub pvl_location: u8,
-    pub data_representation_type: u8,
-    pub data: DataRepresentation,
+// Bring a longitude into the range -180 to 180
+#[cfg(feature = "std")]
+fn normalize_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
 }
 
-#[derive(Debug, Clone)]
-/// Product definition section
-pub struct PDS {
-    pub parameter_table_version_number: u8,
-    pub identification_of_center: u8,
-    pub generating_process_id_number: u8,
-    pub grid_identification: u8,
-    pub flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms: u8,
-    pub indicator_of_parameter_and_units: u8,
-    pub indicator_of_type_of_level_or_layer: u8,
-    pub level_or_layer_value: u16,
-    pub year: u8,
-    pub month: u8,
-    pub day: u8,
-    pub hour: u8,
-    pub minute: u8,
-    pub forecast_time_unit: u8,
-    pub p1_period_of_time: u8,
-    pub p2_period_of_time: u8,
-    pub time_range_indicator: u8,
-    pub number_missing_from_averages_or_accumulations: u8,
-    pub century_of_initial_reference_time: u8,
-    pub identification_of_sub_center: u8,
-    pub decimal_scale_factor: i16,
+#[cfg(feature = "async-tokio")]
+#[derive(Debug)]
+enum GribResult {
+    Length(u64),
+    Grib(Box<Grib>),
 }
 
+#[cfg(feature = "std")]
 impl PDS {
-    pub fn has_gds(&self) -> bool {
-        self.flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms & 128 > 0
+    /// Look up the name and units of the parameter in code table 2
+    pub fn parameter(&self) -> Option<&'static tables::Parameter> {
+        tables::parameter(self.parameter_table_version_number, self.indicator_of_parameter_and_units)
+    }
+
+    /// The reference time (analysis or start of forecast) of the data
+    pub fn reference_time(&self) -> DateTime {
+        let year = (self.century_of_initial_reference_time as i32 - 1) * 100 + self.year as i32;
+        DateTime::new(year, self.month, self.day, self.hour, self.minute)
     }
 
-    pub fn has_bmp(&self) -> bool {
-        self.flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms & 64 > 0
+    /// The time the data is valid for. For accumulations and averages this is the end of the period.
+    /// Returns None if the forecast time unit is unknown.
+    pub fn valid_time(&self) -> Option<DateTime> {
+        self.reference_time().add_time_unit(self.forecast_time_unit, self.step() as i64)
     }
 }
 
-#[derive(Debug)]
-///Bit-map section
-pub struct Bitmap {
-    pub number_of_unused_bits_at_end_of_section3: u8,
-    pub table_reference: u16,
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
+/// A geographical region given by its edges in degrees. The west edge may be larger than the east edge if the region crosses the dateline.
+pub struct BoundingBox {
+    pub north: f32,
+    pub south: f32,
+    pub west: f32,
+    pub east: f32,
 }
 
-#[derive(Debug, Clone)]
-/// Binary data section
-pub struct BDS {
-    pub data_flag: u8,
-    pub binary_scale_factor: i16,
-    pub reference_value: f32,
-    pub bits_per_value: u8,
-    pub data: Vec<f32>,
+#[cfg(feature = "std")]
+impl BoundingBox {
+    /// Check whether a point lies within the region
+    pub fn contains(&self, lat: f32, lon: f32) -> bool {
+        if lat < self.south || lat > self.north {
+            return false;
+        }
+
+        let lon = lon.rem_euclid(360.0);
+        let west = self.west.rem_euclid(360.0);
+        let east = self.east.rem_euclid(360.0);
+        if west <= east {
+            lon >= west && lon <= east
+        } else {
+            lon >= west || lon <= east
+        }
+    }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize))]
 /// Search parameters for when reading the file
 pub struct SearchParams {
     pub param: u32,
     pub level: u32,
 }
 
+#[cfg(feature = "std")]
+impl SearchParams {
+    /// Check whether a message with the given PDS matches the search parameters
+    pub fn matches(&self, pds: &PDS) -> bool {
+        pds.indicator_of_parameter_and_units == self.param as u8 && pds.level_or_layer_value == self.level as u16
+    }
+}
+
+#[cfg(feature = "async-tokio")]
 impl Grib1Reader {
     /// Create a new instance of the GRIB1 reader by specifying the BufReader wrapping the file to read.
     pub fn new(buf_reader: BufReader<File>) -> Grib1Reader {
-        Grib1Reader { reader: buf_reader }
+        Grib1Reader {
+            reader: buf_reader,
+            strict: false,
+            lenient: false,
+            skip_grib2: false,
+            gts: false,
+            decimal_scaling: true,
+            missing_values: missing::MissingValues::Nan,
+            digest: false,
+            warnings: vec![],
+            report: report::ScanReport::default(),
+            metrics: metrics::Metrics::default(),
+            limits: limits::Limits::default(),
+            progress: None,
+            unpackers: vec![],
+        }
+    }
+
+    /// In strict mode every message is checked for consistency: the section lengths against their minimum sizes, the flags of the PDS,
+    /// and the number of packed values against the grid and bit-map. Any problem fails the read with a `ValidationFailed` error.
+    pub fn strict(mut self, strict: bool) -> Grib1Reader {
+        self.strict = strict;
+        self
+    }
+
+    /// Skip messages that can't be parsed in `read`, `read_where` and `inventory` instead of failing, searching forward for the next message like `read_lenient_where`.
+    /// Each skipped message is recorded as a `SkippedMessage` warning.
+    pub fn lenient(mut self, lenient: bool) -> Grib1Reader {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Skip GRIB2 messages in files mixing editions instead of failing with `UnsupportedEdition`. They are passed over using their length.
+    pub fn skip_grib2(mut self, skip: bool) -> Grib1Reader {
+        self.skip_grib2 = skip;
+        self
+    }
+
+    /// Accept messages wrapped in GTS bulletins, or preceded by other junk, as they arrive from operational feeds. Whatever is between the messages is skipped,
+    /// and the bulletin header is set on the message following it.
+    pub fn gts(mut self, gts: bool) -> Grib1Reader {
+        self.gts = gts;
+        self
+    }
+
+    /// Compute the SHA-256 digest of every message returned, e.g. to find duplicates across mirrored feeds. This reads each message in full, even for an inventory.
+    pub fn digest(mut self, digest: bool) -> Grib1Reader {
+        self.digest = digest;
+        self
+    }
+
+    /// Whether decoded values are divided by ten to the power of the decimal scale factor, on by default.
+    /// Without it the values are those the encoder packed, e.g. to compare them with a product defined in scaled units.
+    pub fn decimal_scaling(mut self, decimal_scaling: bool) -> Grib1Reader {
+        self.decimal_scaling = decimal_scaling;
+        self
+    }
+
+    /// What points missing from the bit-map are given as in decoded data, NaN by default
+    pub fn missing_values(mut self, missing_values: missing::MissingValues) -> Grib1Reader {
+        self.missing_values = missing_values;
+        self
+    }
+
+    /// Limit what is allocated when reading a message, e.g. when reading files from untrusted sources
+    pub fn limits(mut self, limits: limits::Limits) -> Grib1Reader {
+        self.limits = limits;
+        self
+    }
+
+    /// Call back after every message with how far the read has got, e.g. to show a progress bar while scanning a large archive
+    pub fn progress<P: FnMut(&progress::Progress) + Send + 'static>(mut self, callback: P) -> Grib1Reader {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Register a decoder for packings the library doesn't handle. Unpackers are asked in the order they are registered, before the built-in decoding.
+    pub fn unpacker<U: unpacker::BdsUnpacker + 'static>(mut self, unpacker: U) -> Grib1Reader {
+        self.unpackers.push(Box::new(unpacker));
+        self
+    }
+
+    /// The anomalies found in the messages read so far that didn't prevent them from being read
+    pub fn warnings(&self) -> &[warning::Warning] {
+        &self.warnings
+    }
+
+    /// Hand back the warnings collected so far and start over
+    pub fn take_warnings(&mut self) -> Vec<warning::Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Statistics on the last read of the file
+    pub fn scan_report(&self) -> &report::ScanReport {
+        &self.report
+    }
+
+    /// Counters of the work done by the reader, adding up across reads
+    pub fn metrics(&self) -> &metrics::Metrics {
+        &self.metrics
+    }
+
+    /// Hand back the metrics collected so far and start counting from zero
+    pub fn take_metrics(&mut self) -> metrics::Metrics {
+        std::mem::take(&mut self.metrics)
     }
 
     /// Read the file looking for data matching the specified search parameters and return the decoded result.
     pub async fn read(&mut self, search: Vec<SearchParams>) -> Result<Vec<Grib>, Grib1Error> {
+        self.read_where(|pds| search.iter().any(|item| item.matches(pds))).await
+    }
+
+    /// Read the file looking for data matching the specified search parameters, returning the messages found for each entry at the same position as the entry.
+    /// A message matching several entries is returned for the first of them only.
+    pub async fn read_grouped(&mut self, search: &[SearchParams]) -> Result<Vec<Vec<Grib>>, Grib1Error> {
+        let mut result: Vec<Vec<Grib>> = search.iter().map(|_| vec![]).collect();
+        for grib in self.read_where(|pds| search.iter().any(|item| item.matches(pds))).await? {
+            if let Some(position) = search.iter().position(|item| item.matches(&grib.pds)) {
+                result[position].push(grib);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Read the file looking for data matching the specified search parameters and return the binary blob representing the file.
+    pub async fn read_binary(&mut self, search: Vec<SearchParams>) -> Result<Vec<u8>, Grib1Error> {
+        self.read_binary_where(|pds| search.iter().any(|item| item.matches(pds))).await
+    }
+
+    /// Read the file and return the decoded messages whose PDS is accepted by the filter.
+    pub async fn read_where<F: Fn(&PDS) -> bool>(&mut self, filter: F) -> Result<Vec<Grib>, Grib1Error> {
+        self.scan(0, 0, filter, true).await
+    }
+
+    /// Read the header sections of every message in the file without decoding the data.
+    pub async fn inventory(&mut self) -> Result<Vec<Grib>, Grib1Error> {
+        self.scan(0, 0, |_| true, false).await
+    }
+
+    /// Read the raw bytes of a message previously returned by the reader.
+    pub async fn read_raw(&mut self, grib: &Grib) -> Result<Vec<u8>, Grib1Error> {
+        self.seek(SeekFrom::Start(grib.offset)).await?;
+
+        let mut buffer = vec![0; grib.length as usize];
+        self.reader.read_exact(&mut buffer).await?;
+
+        Ok(buffer)
+    }
+
+    /// Check every message of the file against the GRIB1 specification, reporting all the deviations found rather than failing on the first.
+    /// Anything between the messages is reported and skipped.
+    pub async fn validate(&mut self) -> Result<validate::ValidationReport, Grib1Error> {
+        let mut report = validate::ValidationReport::default();
+        let length = self.seek(SeekFrom::End(0)).await?;
+
         let mut offset = 0;
+        while offset < length {
+            let Some(message_length) = self.declared_length(offset).await? else {
+                let next = self.find_marker(offset + 1, length).await?.unwrap_or(length);
+                report.deviations.push(validate::Deviation {
+                    offset,
+                    section: "IS",
+                    octet: None,
+                    severity: validate::Severity::Error,
+                    description: format!("{} octets before the next message aren't part of one", next - offset),
+                });
+                offset = next;
+                continue;
+            };
+
+            // Read no further than the end of the file, which validation reports as a truncated message
+            let message_length = message_length.clamp(8, length - offset);
+            check_limit("message", message_length, self.limits.max_message_size)?;
+            self.seek(SeekFrom::Start(offset)).await?;
+            let mut buffer = vec![0; message_length as usize];
+            self.reader.read_exact(&mut buffer).await?;
+            self.count_read(message_length);
+
+            report.deviations.extend(validate::validate_message(&buffer, offset));
+            report.messages += 1;
+            offset += message_length;
+        }
+
+        Ok(report)
+    }
+
+    // Read every message from the one at the offset, the `message_index`th of the file, skipping the ones that can't be parsed if the reader is lenient
+    pub(crate) async fn scan<F: Fn(&PDS) -> bool>(&mut self, offset: u64, message_index: usize, filter: F, read_bds: bool) -> Result<Vec<Grib>, Grib1Error> {
+        if !self.lenient {
+            return self.collect(offset, message_index, filter, read_bds, None).await;
+        }
+
+        let mut recovered = lenient::LenientRead::default();
+        let messages = self.collect(offset, message_index, filter, read_bds, Some(&mut recovered)).await?;
+        self.warn_skipped(&mut recovered);
+        Ok(messages)
+    }
+
+    // Move the messages skipped by a lenient read to the warnings of the reader
+    pub(crate) fn warn_skipped(&mut self, recovered: &mut lenient::LenientRead) {
+        self.warnings.extend(recovered.skipped.drain(..).map(|skipped| warning::Warning::SkippedMessage {
+            offset: skipped.offset,
+            error: skipped.error.to_string(),
+        }));
+    }
+
+    // Read every message from the offset, either failing on the first that can't be parsed, or recording it in `recovered` and moving on to the next
+    async fn collect<F: Fn(&PDS) -> bool>(&mut self, mut offset: u64, mut message_index: usize, filter: F, read_bds: bool, mut recovered: Option<&mut lenient::LenientRead>) -> Result<Vec<Grib>, Grib1Error> {
         let mut result = vec![];
+        self.report = report::ScanReport::default();
 
         // We need to know how large the file is, so we know when to stop
-        let length = self.reader.seek(SeekFrom::End(0)).await?;
+        let length = self.seek(SeekFrom::End(0)).await?;
 
         while offset < length {
-            self.reader.seek(SeekFrom::Start(offset)).await?;
+            let Some((grib, next)) = self.scan_step(offset, message_index, length, &filter, read_bds, recovered.as_deref_mut()).await? else {
+                break;
+            };
+            result.extend(grib);
+            offset = next;
+            message_index += 1;
+        }
 
-            let grib_result = self.read_grib(&search, true).await?;
-            let length = match grib_result {
-                GribResult::Grib(grib) => {
-                    let length = grib.length;
-                    result.push(grib);
+        Ok(result)
+    }
 
-                    length
+    // Take a scan one message further from the offset, the `message_index`th of the file, in a file of `length` bytes. Gives the message if the
+    // filter accepts it, with the header of its bulletin, and the offset to go on from, or None if only skipped bytes are left.
+    // A message that can't be parsed fails the scan, or is recorded in `recovered` and passed over.
+    pub(crate) async fn scan_step<F: Fn(&PDS) -> bool>(
+        &mut self,
+        mut offset: u64,
+        message_index: usize,
+        length: u64,
+        filter: &F,
+        read_bds: bool,
+        mut recovered: Option<&mut lenient::LenientRead>,
+    ) -> Result<Option<(Option<Grib>, u64)>, Grib1Error> {
+        let mut bulletin = None;
+        if self.gts || recovered.is_some() {
+            // Whatever is between the messages is skipped up to the next marker
+            if self.declared_length(offset).await?.is_none() {
+                let next = self.find_marker(offset, length).await?;
+                trace::event!(debug, offset, end = next.unwrap_or(length), "skipped bytes between messages");
+                if let Some(recovered) = recovered.as_deref_mut() {
+                    recovered.skipped_ranges.push(offset..next.unwrap_or(length));
                 }
-                GribResult::Length(length) => length,
-            };
+                match next {
+                    Some(next) => {
+                        if self.gts {
+                            bulletin = self.bulletin_header(offset, next).await?;
+                        }
+                        offset = next;
+                    }
+                    None => {
+                        self.report_progress(length, length);
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+
+        self.seek(SeekFrom::Start(offset)).await?;
+
+        let grib_result = match (self.read_grib(message_index, offset, length, filter, read_bds).await, recovered) {
+            (Ok(grib_result), _) => grib_result,
+            (Err(error), None) => return Err(error),
+            (Err(error), Some(recovered)) => {
+                self.report.skipped_errors += 1;
+                recovered.skipped.push(lenient::SkippedMessage { offset, error });
+
+                // Trust the length in the indicator section if it leads to another message or the end of the file,
+                // otherwise it may be corrupt and the next message is searched for after this one's marker
+                let next = match self.declared_length(offset).await? {
+                    Some(message_length) if offset + message_length == length => length,
+                    Some(message_length) if offset + message_length < length && self.declared_length(offset + message_length).await?.is_some() => offset + message_length,
+                    _ => {
+                        let next = self.find_marker(offset + 4, length).await?.unwrap_or(length);
+                        trace::event!(debug, offset, end = next, "searched for the message following a corrupt one");
+                        recovered.skipped_ranges.push(offset..next);
+                        next
+                    }
+                };
+                GribResult::Length(next - offset)
+            }
+        };
+        let (grib, message_length) = match grib_result {
+            GribResult::Grib(mut grib) => {
+                grib.bulletin = bulletin;
+                let length = grib.length;
+                (Some(*grib), length)
+            }
+            GribResult::Length(length) => (None, length),
+        };
+
+        offset += message_length;
+        self.report_progress(offset, length);
+        Ok(Some((grib, offset)))
+    }
+
+    // The bulletin header in the bytes skipped before the message at `end`, if they hold one
+    async fn bulletin_header(&mut self, start: u64, end: u64) -> Result<Option<gts::BulletinHeader>, Grib1Error> {
+        let start = start.max(end.saturating_sub(gts::MAX_HEADER_LENGTH));
+        self.seek(SeekFrom::Start(start)).await?;
+        let mut buffer = vec![0; (end - start) as usize];
+        self.reader.read_exact(&mut buffer).await?;
+        self.count_read(buffer.len() as u64);
+
+        Ok(gts::parse_header(&buffer))
+    }
 
-            offset += length;
+    // The length of the message at the offset according to its indicator section, if there is one
+    async fn declared_length(&mut self, offset: u64) -> Result<Option<u64>, Grib1Error> {
+        self.seek(SeekFrom::Start(offset)).await?;
+        let mut buffer = [0; 16];
+        if self.reader.read_exact(&mut buffer).await.is_err() || &buffer[0..4] != b"GRIB" {
+            return Ok(None);
         }
 
-        Ok(result)
+        if buffer[7] == 2 {
+            // GRIB2 gives the total length in the last 8 octets of its 16 octet indicator section
+            return Ok(Some(u64::from_be_bytes(buffer[8..16].try_into().unwrap_or_default()).max(16)));
+        }
+
+        let edition0 = edition0::is_edition0(&buffer);
+        let length_field = read_u24_be(&buffer[4..]) as usize;
+        let length = if length_field & 0x80_0000 == 0 && !edition0 {
+            length_field
+        } else {
+            // Large messages need the length of the BDS, and edition 0 messages have no length at all, so follow the sections up to the BDS.
+            // The file may end within them while it's being written.
+            let end = self.seek(SeekFrom::End(0)).await?;
+            let start = if edition0 { 4 } else { 8 };
+            let flags = buffer[start + 7];
+            let mut position = start as u64 + read_u24_be(&buffer[start..]) as u64;
+            for present in [flags & 0x80 > 0, flags & 0x40 > 0] {
+                if present {
+                    position += self.section_length(offset, position, end).await? as u64;
+                }
+            }
+            match self.section_length(offset, position, end).await? {
+                bds_length if edition0 => position as usize + bds_length + 4,
+                bds_length => resolve_lengths(length_field, position as usize, bds_length).0,
+            }
+        };
+        Ok(if length >= 8 { Some(length as u64) } else { None })
     }
 
-    /// Read the file looking for data matching the specified search parameters and return the binary blob representing the file.
-    pub async fn read_binary(&mut self, search: Vec<SearchParams>) -> Result<Vec<u8>, Grib1Error> {
+    // The length of the section at `position` within the message at `offset`, which fails as truncated if the file ends within its length field
+    async fn section_length(&mut self, offset: u64, position: u64, end: u64) -> Result<usize, Grib1Error> {
+        if offset + position + 3 > end {
+            return Err(Grib1Error::Truncated {
+                offset,
+                missing_bytes: offset + position + 3 - end,
+            });
+        }
+        self.seek(SeekFrom::Start(offset + position)).await?;
+        self.get_length().await
+    }
+
+    // The position of the next GRIB marker at or after `from`
+    async fn find_marker(&mut self, from: u64, end: u64) -> Result<Option<u64>, Grib1Error> {
+        let mut position = from;
+        let mut buffer = vec![0; 64 * 1024];
+
+        while position + 4 <= end {
+            self.seek(SeekFrom::Start(position)).await?;
+            let count = (end - position).min(buffer.len() as u64) as usize;
+            self.reader.read_exact(&mut buffer[..count]).await?;
+
+            if let Some(index) = buffer[..count].windows(4).position(|window| window == b"GRIB") {
+                return Ok(Some(position + index as u64));
+            }
+            // Keep the last three bytes, as the marker may straddle two reads
+            position += count.saturating_sub(3).max(1) as u64;
+        }
+
+        Ok(None)
+    }
+
+    /// Read the file and return the binary blob of the messages whose PDS is accepted by the filter.
+    pub async fn read_binary_where<F: Fn(&PDS) -> bool>(&mut self, filter: F) -> Result<Vec<u8>, Grib1Error> {
         let mut offset = 0;
         let mut result = vec![];
+        self.report = report::ScanReport::default();
 
         // We need to know how large the file is, so we know when to stop
-        let length = self.reader.seek(SeekFrom::End(0)).await?;
+        let length = self.seek(SeekFrom::End(0)).await?;
 
+        let mut message_index = 0;
         while offset < length {
-            self.reader.seek(SeekFrom::Start(offset)).await?;
+            if self.gts && self.declared_length(offset).await?.is_none() {
+                match self.find_marker(offset, length).await? {
+                    Some(next) => offset = next,
+                    None => break,
+                }
+            }
+            self.seek(SeekFrom::Start(offset)).await?;
 
-            let grib_result = self.read_grib(&search, false).await?;
+            let grib_result = self.read_grib(message_index, offset, length, &filter, false).await?;
+            message_index += 1;
 
-            let length = match grib_result {
+            let message_length = match grib_result {
                 GribResult::Grib(grib) => {
                     // Go back to the start of the block and read all of it into a buffer we can return
-                    self.reader.seek(SeekFrom::Start(offset)).await?;
+                    self.seek(SeekFrom::Start(offset)).await?;
 
                     let mut buffer = vec![0; grib.length as usize];
                     self.reader.read_exact(&mut buffer).await?;
+                    self.count_read(grib.length);
                     result.append(&mut buffer);
 
                     grib.length
@@ -180,180 +737,288 @@ impl Grib1Reader {
                 GribResult::Length(length) => length,
             };
 
-            offset += length;
+            offset += message_length;
+            self.report_progress(offset, length);
         }
 
         Ok(result)
     }
 
-    async fn read_grib(&mut self, search_list: &Vec<SearchParams>, read_bds: bool) -> Result<GribResult, Grib1Error> {
+    async fn read_grib<F: Fn(&PDS) -> bool>(&mut self, message_index: usize, offset: u64, end: u64, filter: &F, read_bds: bool) -> Result<GribResult, Grib1Error> {
+        let mut section = "IS";
+        self.report.messages_seen += 1;
+        self.metrics.messages_scanned += 1;
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let sections = self.read_sections(offset, end, filter, read_bds, &mut section);
+        #[cfg(feature = "tracing")]
+        let sections = tracing::Instrument::instrument(sections, tracing::debug_span!("message", message_index, offset));
+        let result = sections.await.map_err(|error| {
+            trace::event!(warn, message_index, offset, section, error = %error, "failed to read message");
+            Grib1Error::InMessage {
+                message_index,
+                offset,
+                section,
+                source: Box::new(error),
+            }
+        })?;
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            GribResult::Grib(grib) => tracing::debug!(message_index, offset, length = grib.length, decoded = grib.bds.is_some(), elapsed_us = started.elapsed().as_micros() as u64, "read message"),
+            GribResult::Length(length) => tracing::trace!(message_index, offset, length, elapsed_us = started.elapsed().as_micros() as u64, "skipped message"),
+        }
+
+        if let GribResult::Grib(grib) = &result {
+            self.report.matched += 1;
+            if read_bds && grib.gds.as_ref().is_some_and(|gds| gds.row_length().is_none()) {
+                self.report.skipped_unhandled_grid += 1;
+            }
+        }
+        Ok(result)
+    }
+
+    // Read the message section by section, keeping track of the section being read so errors can say where they happened
+    async fn read_sections<F: Fn(&PDS) -> bool>(&mut self, offset: u64, end: u64, filter: &F, read_bds: bool, section: &mut &'static str) -> Result<GribResult, Grib1Error> {
         // The first 8 bytes describes the header of the grib1 file
         let mut buffer = [0; 8];
-        let _ = self.reader.read(&mut buffer).await?;
+        let available = (end - offset).min(8) as usize;
+        self.reader.read_exact(&mut buffer[..available]).await?;
+        self.count_read(available as u64);
 
         // Look for the letters GRIB that indicate this is indeed the kind of file we can read
         let header: [u8; 4] = [0x47, 0x52, 0x49, 0x42];
-        if header != buffer[0..4] {
+        if header[..available.min(4)] != buffer[..available.min(4)] {
             return Err(Grib1Error::WrongHeader);
         }
-
-        // We use the length of the section to skip to the next one if we aren't interested in it
-        let length_of_grib_section = read_u24_be(&buffer[4..]);
+        if available < 8 {
+            return Err(Grib1Error::Truncated { offset, missing_bytes: 8 - available as u64 });
+        }
 
         // Make sure this is indeed a version we can understand
         let version = buffer[7];
-        if version != 1 {
+        if version == 2 {
+            if !self.skip_grib2 {
+                return Err(Grib1Error::UnsupportedEdition(version));
+            }
+
+            // The indicator section of GRIB2 is 16 octets, ending with the total length in 8 octets
+            let mut length = [0; 8];
+            if (end - offset) < 16 {
+                return Err(Grib1Error::Truncated { offset, missing_bytes: offset + 16 - end });
+            }
+            self.reader.read_exact(&mut length).await?;
+            self.count_read(8);
+            let length = u64::from_be_bytes(length);
+            if offset + length > end {
+                return Err(Grib1Error::Truncated {
+                    offset,
+                    missing_bytes: offset + length - end,
+                });
+            }
+            trace::event!(debug, offset, length, "skipped GRIB2 message");
+            return Ok(GribResult::Length(length.max(16)));
+        }
+        if version > 1 {
             return Err(Grib1Error::WrongVersion(version));
         }
 
-        let pds = self.read_pds().await?;
+        // Edition 0 has no length in its indicator section, so the PDS starts right after GRIB and the length follows from the sections
+        let edition0 = edition0::is_edition0(&buffer);
+        if edition0 {
+            self.seek(SeekFrom::Start(offset + 4)).await?;
+        }
+
+        // We use the length of the section to skip to the next one if we aren't interested in it. For large messages it's only known once the BDS is found.
+        let length_field = if edition0 { 0 } else { read_u24_be(&buffer[4..]) as usize };
+        check_limit("message", length_field as u64 & 0x7f_ffff, self.limits.max_message_size)?;
+        if length_field & 0x80_0000 == 0 && offset + length_field as u64 > end {
+            return Err(Grib1Error::Truncated {
+                offset,
+                missing_bytes: offset + length_field as u64 - end,
+            });
+        }
+
+        *section = "PDS";
+        let mut pds_bytes = self.read_section().await?;
+        trace::event!(trace, offset, section = "PDS", length = pds_bytes.len(), "read section");
+        let pds_length = pds_bytes.len() as u64;
+        if edition0 {
+            pds_bytes = edition0::to_edition1_pds(&pds_bytes);
+        }
+        let pds = parse_pds(&pds_bytes)?;
 
         let mut result = Grib {
-            length: length_of_grib_section as u64,
+            offset,
+            length: length_field as u64,
             pds,
             gds: None,
+            bitmap: None,
             bds: None,
+            digest: None,
+            bulletin: None,
+            sections: None,
         };
 
-        let mut number_of_lat_values = 0;
-        let mut number_of_lon_values = 0;
+        let mut gds_bytes = None;
         if result.pds.has_gds() {
-            let gds = self.read_gds().await?;
-
-            // If we found a rotated lat/lon scheme grab the values we need
-            if let DataRepresentation::RotatedLatLon(value) = gds.data {
-                number_of_lat_values = value.number_of_lat_values;
-                number_of_lon_values = value.number_of_lon_values;
-            }
-            result.gds = Some(gds);
+            *section = "GDS";
+            let bytes = self.read_section().await?;
+            trace::event!(trace, offset, section = "GDS", length = bytes.len(), "read section");
+            result.gds = Some(parse_gds(&bytes)?);
+            gds_bytes = Some(bytes);
         }
 
+        let mut bms = None;
         if result.pds.has_bmp() {
-            // The data this library is written for doesn't contain bitmaps, so this is more here for show.
-            let _bitmap = self.read_bitmap().await?;
+            *section = "BMS";
+            let bytes = self.read_section().await?;
+            trace::event!(trace, offset, section = "BMS", length = bytes.len(), "read section");
+            result.bitmap = Some(parse_bitmap(&bytes)?);
+            bms = Some(bytes);
         }
 
+        *section = "BDS";
+        let bds_start = self.reader.stream_position().await?;
+        let (total_length, bds_length) = match self.get_length().await? {
+            bds_length if edition0 => ((bds_start - offset) as usize + bds_length + 4, bds_length),
+            bds_length => resolve_lengths(length_field, (bds_start - offset) as usize, bds_length),
+        };
+        let (length_of_grib_section, bds_length) = (total_length as u64, bds_length as u64);
+        check_limit("message", length_of_grib_section, self.limits.max_message_size)?;
+        if offset + length_of_grib_section > end {
+            return Err(Grib1Error::Truncated {
+                offset,
+                missing_bytes: offset + length_of_grib_section - end,
+            });
+        }
+        result.length = length_of_grib_section;
+        let length_of = |bytes: &Option<Vec<u8>>| bytes.as_ref().map(|bytes| bytes.len() as u64);
+        result.sections = Some(sections::SectionRanges::of_lengths(if edition0 { 4 } else { 8 }, pds_length, length_of(&gds_bytes), length_of(&bms), bds_length));
+        *section = "ES";
+        self.check_end(offset, length_of_grib_section, bds_start - offset + bds_length).await?;
+
+        let mut bds_header = vec![0; 11.min(bds_length as usize)];
+        self.reader.read_exact(&mut bds_header).await?;
+        self.count_read(bds_header.len() as u64);
+        self.seek(SeekFrom::Start(bds_start)).await?;
+        *section = "BDS";
+
+        let sections = strict::Sections {
+            offset,
+            pds: &pds_bytes,
+            gds: gds_bytes.as_deref(),
+            bms: bms.as_deref(),
+            bds_header: &bds_header,
+            bds_length: bds_length as usize,
+        };
+        if self.strict {
+            strict::check(&sections)?;
+        }
+        self.warnings.extend(warning::check(&sections));
+
         // Check to see if this is the data we are interested in
-        for seach_item in search_list {
-            if result.pds.indicator_of_parameter_and_units == seach_item.param as u8 && result.pds.level_or_layer_value == seach_item.level as u16 {
-                // If we are just interested in the binary blob we don't need to read and unpack the actual contained data
-                if read_bds {
-                    let bds = self.read_bds(number_of_lat_values as usize * number_of_lon_values as usize).await?;
-                    result.bds = Some(bds);
-                }
+        if filter(&result.pds) {
+            // If we are just interested in the binary blob we don't need to read and unpack the actual contained data
+            if read_bds {
+                *section = "BDS";
+                let mut bds = vec![0; bds_length as usize];
+                self.reader.read_exact(&mut bds).await?;
+                self.count_read(bds_length);
+                let started = std::time::Instant::now();
+                let decimal_scale_factor = if self.decimal_scaling { result.pds.decimal_scale_factor } else { 0 };
+                let mut bds = match self.unpackers.iter().find(|unpacker| unpacker.accepts(&result.pds, &bds)) {
+                    Some(unpacker) => {
+                        let unpacked = unpacker.unpack(&bds, bms.as_deref(), result.gds.as_ref(), decimal_scale_factor)?;
+                        let expected = result.gds.as_ref().map_or(unpacked.data.len(), |gds| gds.number_of_points());
+                        if unpacked.data.len() != expected {
+                            return Err(Grib1Error::ValueCountMismatch { expected, actual: unpacked.data.len() });
+                        }
+                        unpacked
+                    }
+                    None => decode_data(&bds, bms.as_deref(), result.gds.as_ref(), decimal_scale_factor, self.limits.max_points)?,
+                };
+                bds.set_missing_values(self.missing_values);
+                result.bds = Some(bds);
+                self.metrics.messages_decoded += 1;
+                self.metrics.decode_time += started.elapsed();
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    offset,
+                    length = bds_length,
+                    points = result.bds.as_ref().map_or(0, |bds| bds.data.len()),
+                    bits_per_value = bds_header.get(10).copied().unwrap_or(0),
+                    elapsed_us = started.elapsed().as_micros() as u64,
+                    "decoded BDS"
+                );
+            }
 
-                return Ok(GribResult::Grib(result));
+            if self.digest {
+                let raw = self.read_raw(&result).await?;
+                self.count_read(raw.len() as u64);
+                result.digest = Some(digest::sha256(&raw));
             }
+
+            return Ok(GribResult::Grib(Box::new(result)));
         }
 
-        Ok(GribResult::Length(length_of_grib_section as u64))
+        Ok(GribResult::Length(length_of_grib_section))
     }
 
-    async fn read_gds(&mut self) -> Result<GDS, Grib1Error> {
-        let len = self.get_length().await?;
-
-        let mut buffer = vec![0; len];
-        self.reader.read_exact(&mut buffer).await?;
-
-        let data_representation_type = buffer[5];
-
-        let mut data = DataRepresentation::Unhandled;
-        if data_representation_type == 10 {
-            data = DataRepresentation::RotatedLatLon(RotatedLatLon {
-                number_of_lat_values: read_u16_be(&buffer[6..]),
-                number_of_lon_values: read_u16_be(&buffer[8..]),
-                latitude_of_first_grid_point: read_i24_be(&buffer[10..]) as f32 * 0.001,
-                longitude_of_first_grid_point: read_i24_be(&buffer[13..]) as f32 * 0.001,
-                latitude_of_last_grid_point: read_i24_be(&buffer[17..]) as f32 * 0.001,
-                longitude_of_last_grid_point: read_i24_be(&buffer[20..]) as f32 * 0.001,
-                latitude_of_southern_pole: read_i24_be(&buffer[32..]) as f32 * 0.001,
-                longitude_of_southern_pole: read_i24_be(&buffer[35..]) as f32 * 0.001,
+    // The sections must add up to the length given in the indicator section, and be followed by the end section
+    async fn check_end(&mut self, offset: u64, length: u64, sections: u64) -> Result<(), Grib1Error> {
+        if sections + 4 != length {
+            return Err(Grib1Error::LengthMismatch {
+                offset,
+                expected: length,
+                actual: sections + 4,
             });
         }
 
-        Ok(GDS {
-            number_of_vertical_coordinate_values: buffer[3],
-            pvl_location: buffer[4],
-            data_representation_type: buffer[5],
-            data,
-        })
+        let position = self.reader.stream_position().await?;
+        self.seek(SeekFrom::Start(offset + length - 4)).await?;
+        let mut end = [0; 4];
+        let complete = self.reader.read_exact(&mut end).await.is_ok();
+        self.count_read(4);
+        self.seek(SeekFrom::Start(position)).await?;
+
+        if !complete || &end != b"7777" {
+            return Err(Grib1Error::MissingEndSection(offset));
+        }
+        Ok(())
     }
 
-    async fn read_pds(&mut self) -> Result<PDS, Grib1Error> {
+    async fn read_section(&mut self) -> Result<Vec<u8>, Grib1Error> {
         let len = self.get_length().await?;
+        check_limit("section", len as u64, self.limits.max_section_size as u64)?;
 
         let mut buffer = vec![0; len];
         self.reader.read_exact(&mut buffer).await?;
+        self.count_read(len as u64);
 
-        Ok(PDS {
-            parameter_table_version_number: buffer[3],
-            identification_of_center: buffer[4],
-            generating_process_id_number: buffer[5],
-            grid_identification: buffer[6],
-            flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms: buffer[7],
-            indicator_of_parameter_and_units: buffer[8],
-            indicator_of_type_of_level_or_layer: buffer[9],
-            level_or_layer_value: read_u16_be(&buffer[10..]),
-            year: buffer[12],
-            month: buffer[13],
-            day: buffer[14],
-            hour: buffer[15],
-            minute: buffer[16],
-            forecast_time_unit: buffer[17],
-            p1_period_of_time: buffer[18],
-            p2_period_of_time: buffer[19],
-            time_range_indicator: buffer[20],
-            number_missing_from_averages_or_accumulations: buffer[23],
-            century_of_initial_reference_time: buffer[24],
-            identification_of_sub_center: buffer[25],
-            decimal_scale_factor: read_i16_be(&buffer[26..]),
-        })
+        Ok(buffer)
     }
 
-    async fn read_bitmap(&mut self) -> Result<Bitmap, Grib1Error> {
-        let len = self.get_length().await?;
-        let mut buffer = vec![0; len];
-        self.reader.read_exact(&mut buffer).await?;
-
-        Ok(Bitmap {
-            number_of_unused_bits_at_end_of_section3: buffer[3],
-            table_reference: read_u16_be(&buffer[4..]),
-        })
+    async fn seek(&mut self, position: SeekFrom) -> Result<u64, Grib1Error> {
+        self.metrics.seeks += 1;
+        Ok(self.reader.seek(position).await?)
     }
 
-    async fn read_bds(&mut self, number_of_data_points: usize) -> Result<BDS, Grib1Error> {
-        let len = self.get_length().await?;
-        let mut buffer = vec![0; len];
-        self.reader.read_exact(&mut buffer).await?;
-
-        let binary_scale = read_i16_be(&buffer[4..]);
-        let ref_value = read_f32_ibm(&buffer[6..]);
-        let bit_count = buffer[10];
-
-        let mut r = BitReader::endian(Cursor::new(&buffer[11..]), BigEndian);
-        let mut result = vec![];
-        let mut iterations = 0;
-        let base: f32 = 2.0;
-        let factor = base.powf(binary_scale as f32);
-
-        // Convert all the packed data into f32 values
-        while iterations < number_of_data_points {
-            if let Ok(x) = r.read::<u32>(bit_count as u32) {
-                let y = ref_value + (x as f32) * factor;
-                result.push(y);
-            } else {
-                return Err(Grib1Error::DataDecodeFailed);
-            }
-            iterations += 1;
+    fn report_progress(&mut self, offset: u64, total: u64) {
+        let messages_found = self.report.messages_seen;
+        if let Some(callback) = self.progress.as_mut() {
+            callback(&progress::Progress {
+                bytes_processed: offset.min(total),
+                total_bytes: total,
+                messages_found,
+            });
         }
+    }
 
-        Ok(BDS {
-            data_flag: buffer[3],
-            binary_scale_factor: binary_scale,
-            reference_value: ref_value,
-            bits_per_value: bit_count,
-            data: result,
-        })
+    fn count_read(&mut self, bytes: u64) {
+        self.report.bytes_read += bytes;
+        self.metrics.bytes_read += bytes;
     }
 
     async fn get_length(&mut self) -> Result<usize, Grib1Error> {
@@ -361,51 +1026,164 @@ impl Grib1Reader {
         let mut buffer = [0; 3];
         self.reader.read_exact(&mut buffer).await?;
         let len = read_u24_be(&buffer[..]) as usize;
-        self.reader.seek(SeekFrom::Current(-3)).await?;
+        self.seek(SeekFrom::Current(-3)).await?;
 
         Ok(len)
     }
 }
 
-//
-// Utility funtions to convert slices of memory into the value types we want
-//
+#[cfg(feature = "std")]
+impl Grib {
+    /// Decode a single complete message held in memory, e.g. one returned by `read_binary`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Grib, Grib1Error> {
+        if bytes.len() < 8 || bytes[0..4] != *b"GRIB" {
+            return Err(Grib1Error::WrongHeader);
+        }
+        match bytes[7] {
+            0 | 1 => {}
+            2 => return Err(Grib1Error::UnsupportedEdition(2)),
+            version => return Err(Grib1Error::WrongVersion(version)),
+        }
+        let declared = message_length(bytes).ok_or_else(|| Grib1Error::MalformedMessage("The sections before the BDS are incomplete".to_string()))? as u64;
+        if declared > bytes.len() as u64 {
+            return Err(Grib1Error::Truncated {
+                offset: 0,
+                missing_bytes: declared - bytes.len() as u64,
+            });
+        }
+
+        let edition0 = edition0::is_edition0(bytes);
+        let mut offset = if edition0 { 4 } else { 8 };
+        let indicator_length = offset;
+        let pds = match next_section(bytes, &mut offset)? {
+            section if edition0 => parse_pds(&edition0::to_edition1_pds(section))?,
+            section => parse_pds(section)?,
+        };
+
+        let pds_length = offset - indicator_length;
+        let (mut gds, mut gds_length) = (None, None);
+        if pds.has_gds() {
+            let section = next_section(bytes, &mut offset)?;
+            gds = Some(parse_gds(section)?);
+            gds_length = Some(section.len() as u64);
+        }
+
+        let (mut bms, mut bitmap) = (None, None);
+        if pds.has_bmp() {
+            let section = next_section(bytes, &mut offset)?;
+            bitmap = Some(parse_bitmap(section)?);
+            bms = Some(section);
+        }
+
+        let bds_start = offset;
+        let mut bds = next_section(bytes, &mut offset)?;
+        let (length, bds_length) = match bds.len() {
+            bds_length if edition0 => (bds_start + bds_length + 4, bds_length),
+            bds_length => resolve_lengths(read_u24_be(&bytes[4..]) as usize, bds_start, bds_length),
+        };
+        if bds_length != bds.len() {
+            bds = bytes.get(bds_start..bds_start + bds_length).ok_or(BdsError::TooShort {
+                needed: bds_length,
+                got: bytes.len() - bds_start,
+            })?;
+            offset = bds_start + bds_length;
+        }
+        let bds = decode_data(bds, bms, gds.as_ref(), pds.decimal_scale_factor, limits::Limits::default().max_points)?;
+
+        let length = length as u64;
+        if offset as u64 + 4 != length {
+            return Err(Grib1Error::LengthMismatch {
+                offset: 0,
+                expected: length,
+                actual: offset as u64 + 4,
+            });
+        }
+        if bytes.get(offset..offset + 4) != Some(b"7777") {
+            return Err(Grib1Error::MissingEndSection(0));
+        }
+
+        let sections = sections::SectionRanges::of_lengths(indicator_length as u64, pds_length as u64, gds_length, bms.map(|bms| bms.len() as u64), bds_length as u64);
+        Ok(Grib {
+            offset: 0,
+            length,
+            pds,
+            gds,
+            bitmap,
+            bds: Some(bds),
+            digest: None,
+            bulletin: None,
+            sections: Some(sections),
+        })
+    }
+}
 
-fn read_f32_ibm(data: &[u8]) -> f32 {
-    let sign = if (data[0] & 0x80) > 0 { -1.0 } else { 1.0 };
-    let a = (data[0] & 0x7f) as i32;
-    let b = (((data[1] as i32) << 16) + ((data[2] as i32) << 8) + data[3] as i32) as f32;
+// Messages longer than 0x7fffff octets follow the ECMWF convention: the top bit of the length in the indicator section is set and the rest
+// gives the length in units of 120 octets, while the length field of the BDS holds the difference to the true length (always less than 120).
+// Returns the true lengths of the message and of its BDS.
+#[cfg(feature = "std")]
+pub(crate) fn resolve_lengths(length_field: usize, bds_offset: usize, bds_length_field: usize) -> (usize, usize) {
+    if length_field & 0x80_0000 == 0 || bds_length_field >= 120 {
+        return (length_field, bds_length_field);
+    }
 
-    sign * 2.0f32.powi(-24) * b * 16.0f32.powi(a - 64)
+    let total = ((length_field & 0x7f_ffff) * 120 + 4).saturating_sub(bds_length_field);
+    (total, total.saturating_sub(bds_offset + 4))
 }
 
-fn read_i16_be(array: &[u8]) -> i16 {
-    let mut val = (array[1] as i16) + (((array[0] & 127) as i16) << 8);
-    if array[0] & 0x80 > 0 {
-        val = -val;
+// The true length of the message at the start of `bytes`, or None if its header is incomplete
+#[cfg(feature = "std")]
+pub(crate) fn message_length(bytes: &[u8]) -> Option<usize> {
+    let length_field = read_u24_be(bytes.get(4..8)?) as usize;
+    let edition0 = edition0::is_edition0(bytes);
+    if length_field & 0x80_0000 == 0 && !edition0 {
+        return Some(length_field);
+    }
+
+    let bds_offset = bds_offset(bytes)?;
+    let bds_length = read_u24_be(bytes.get(bds_offset..bds_offset + 3)?) as usize;
+    if edition0 {
+        return Some(bds_offset + bds_length + 4);
     }
-    val
+    Some(resolve_lengths(length_field, bds_offset, bds_length).0)
 }
 
-fn read_i24_be(array: &[u8]) -> i32 {
-    let mut val = (array[2] as i32) + ((array[1] as i32) << 8) + (((array[0] & 127) as i32) << 16);
-    if array[0] & 0x80 > 0 {
-        val = -val;
+// The position of the BDS within a message, found from the lengths of the sections before it
+#[cfg(feature = "std")]
+pub(crate) fn bds_offset(bytes: &[u8]) -> Option<usize> {
+    let start = if edition0::is_edition0(bytes) { 4 } else { 8 };
+    let flags = *bytes.get(start + 7)?;
+    let mut offset = start + read_u24_be(bytes.get(start..start + 3)?) as usize;
+    for present in [flags & 0x80 > 0, flags & 0x40 > 0] {
+        if present {
+            offset += read_u24_be(bytes.get(offset..offset + 3)?) as usize;
+        }
     }
-    val
+    Some(offset)
 }
 
-fn read_u16_be(array: &[u8]) -> u16 {
-    (array[1] as u16) + ((array[0] as u16) << 8)
+#[cfg(feature = "async-tokio")]
+fn check_limit(what: &'static str, requested: u64, limit: u64) -> Result<(), Grib1Error> {
+    if requested > limit {
+        return Err(Grib1Error::ResourceLimitExceeded { what, requested, limit });
+    }
+    Ok(())
 }
 
-fn read_u24_be(array: &[u8]) -> u32 {
-    (array[2] as u32) + ((array[1] as u32) << 8) + ((array[0] as u32) << 16)
+// Evenly distribute `count` values from `first` to `last`, both included
+#[cfg(feature = "std")]
+fn spread(first: f32, last: f32, count: usize) -> Vec<f32> {
+    if count < 2 {
+        return vec![first; count];
+    }
+
+    let step = (last - first) / (count - 1) as f32;
+    (0..count).map(|i| first + step * i as f32).collect()
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "async-tokio"))]
 mod tests {
     use super::*;
+    use crate::core::{parse_bds, BmsError, GdsError, ParseError, PdsError};
 
     #[tokio::test]
     async fn read_test() -> Result<(), Grib1Error> {
@@ -433,6 +1211,20 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn read_grouped_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+
+        // Grouped by the entry matched, in the order of the query
+        let search = [SearchParams { param: 34, level: 700 }, SearchParams { param: 33, level: 850 }, SearchParams { param: 33, level: 700 }];
+        let grouped = reader.read_grouped(&search).await?;
+        assert_eq!(grouped.iter().map(Vec::len).collect::<Vec<_>>(), vec![1, 0, 1]);
+        assert_eq!(grouped[2][0].pds.indicator_of_parameter_and_units, 33);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn read_binary_test() -> Result<(), Grib1Error> {
         let f = File::open("data/sample.grib").await?;
@@ -445,4 +1237,278 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn end_section_test() -> Result<(), Grib1Error> {
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        let length = read_u24_be(&bytes[4..]) as usize;
+        assert!(Grib::from_bytes(&bytes[..length]).is_ok());
+
+        // Corrupt the end section of the first message
+        bytes[length - 1] = b'X';
+        assert!(matches!(Grib::from_bytes(&bytes[..length]), Err(Grib1Error::MissingEndSection(0))));
+
+        let path = std::env::temp_dir().join("grib1_reader_end_section_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert!(matches!(reader.inventory().await.unwrap_err().root_cause(), Grib1Error::MissingEndSection(0)));
+
+        // Claim the first message is longer than its sections
+        bytes[length - 1] = b'7';
+        bytes[6] += 2;
+        tokio::fs::write(&path, &bytes).await?;
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert!(matches!(reader.inventory().await.unwrap_err().root_cause(), Grib1Error::LengthMismatch { offset: 0, .. }));
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn strict_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?)).strict(true);
+        assert_eq!(reader.inventory().await?.len(), 2);
+
+        // Claim there are more bits per value than the section holds
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        bytes[8 + 28 + 570 + 10] = 17;
+        let path = std::env::temp_dir().join("grib1_reader_strict_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert_eq!(reader.inventory().await?.len(), 2);
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?)).strict(true);
+        assert!(matches!(reader.inventory().await.unwrap_err().root_cause(), Grib1Error::ValidationFailed { offset: 0, section: "BDS", .. }));
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn truncated_test() -> Result<(), Grib1Error> {
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        bytes.truncate(bytes.len() - 1000);
+        let path = std::env::temp_dir().join("grib1_reader_truncated_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        let error = reader.inventory().await.unwrap_err();
+        assert!(matches!(
+            error,
+            Grib1Error::InMessage {
+                message_index: 1,
+                offset: 2542704,
+                section: "IS",
+                ..
+            }
+        ));
+        assert!(matches!(error.root_cause(), Grib1Error::Truncated { offset: 2542704, missing_bytes: 1000 }));
+
+        // Read leniently, the complete message is still returned
+        let result = reader.read_lenient(vec![SearchParams { param: 33, level: 700 }]).await?;
+        assert_eq!(result.messages.len(), 1);
+        assert!(matches!(result.skipped[0].error.root_cause(), Grib1Error::Truncated { offset: 2542704, missing_bytes: 1000 }));
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn large_message_test() -> Result<(), Grib1Error> {
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let source = Grib::from_bytes(&sample[..2542704])?;
+
+        // A constant field with a BDS padded past the 0x7fffff octets the length field can hold
+        let pds = writer::encode_pds(&source.pds, true, false);
+        let gds = writer::encode_gds(source.gds.as_ref().unwrap())?;
+        let bds = vec![0; 0x80_0000 + 100];
+        let large = writer::finalize(&pds, Some(&gds), None, &bds)?;
+        assert!(read_u24_be(&large[4..]) & 0x80_0000 > 0);
+
+        let grib = Grib::from_bytes(&large)?;
+        assert_eq!(grib.length as usize, large.len());
+        assert_eq!(grib.bds.unwrap().data.len(), 1189 * 1069);
+
+        let mut bytes = large.clone();
+        bytes.extend_from_slice(&sample);
+        let path = std::env::temp_dir().join("grib1_reader_large_message_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?)).strict(true);
+        let inventory = reader.inventory().await?;
+        assert_eq!(inventory.iter().map(|grib| grib.offset).collect::<Vec<_>>(), vec![0, large.len() as u64, large.len() as u64 + 2542704]);
+        assert_eq!(reader.read_raw(&inventory[0]).await?, large);
+
+        // The lenient reader finds the message after a damaged large message from its length
+        bytes[8 + 28 + gds.len() + 10] = 40;
+        tokio::fs::write(&path, &bytes).await?;
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?)).strict(true);
+        let result = reader.read_lenient_where(|_| true).await?;
+        assert_eq!(result.messages.len(), 2);
+        assert_eq!(result.skipped.len(), 1);
+        assert!(result.skipped_ranges.is_empty());
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn parse_fuzz_test() -> Result<(), Grib1Error> {
+        // Short sections give an error instead of a panic
+        assert!(matches!(parse_pds(&[0; 20]), Err(ParseError::Pds(PdsError::TooShort { needed: 28, got: 20 }))));
+        assert!(matches!(parse_gds(&[0, 0, 40, 0, 255, 10, 0]), Err(ParseError::Gds(GdsError::TooShort { needed: 42, got: 7 }))));
+        assert!(matches!(parse_bitmap(&[0; 4]), Err(ParseError::Bms(BmsError::TooShort { needed: 6, got: 4 }))));
+        assert!(matches!(
+            parse_bds(&[0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 40], 1, 0),
+            Err(ParseError::Bds(BdsError::InvalidField { octet: 11, field: "bits_per_value", .. }))
+        ));
+
+        let sample = std::fs::read("data/sample.grib")?;
+        let source = Grib::from_bytes(&sample[..2542704])?;
+        let gds = grid::RegularLatLonBuilder::new(4, 3).first_grid_point(60.0, 0.0).increments(5.0, 2.5).build()?;
+        let data = [1.0, 2.0, f32::NAN, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0];
+        let messages = [
+            writer::encode(&source.pds, &gds, &data, 12)?,
+            writer::encode_packed(&source.pds, &gds, &data[3..].repeat(2)[..12], 12, writer::Packing::SecondOrderRowByRow { fallback: false })?,
+        ];
+
+        // Vertical coordinates said to be at octet 0 of the GDS
+        let mut bytes = messages[0].clone();
+        (bytes[8 + 28 + 3], bytes[8 + 28 + 4]) = (1, 0);
+        assert!(dump::dump(&bytes)?.contains("Section 2"));
+        assert!(Grib::from_bytes(&bytes)?.gds.unwrap().vertical_coordinates.is_empty());
+
+        // Damage random octets of a small message and cut it short, the parsers must never panic
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for i in 0..5000 {
+            let mut bytes = messages[i % 2].clone();
+            for _ in 0..1 + random() % 4 {
+                let index = (random() % bytes.len() as u64) as usize;
+                bytes[index] = random() as u8;
+            }
+            if random() % 4 == 0 {
+                bytes.truncate((random() % bytes.len() as u64) as usize);
+            }
+
+            let _ = Grib::from_bytes(&bytes);
+            let _ = dump::dump(&bytes);
+            let _ = validate::validate_message(&bytes, 0);
+            let _ = merge::split_messages(&bytes);
+            let start = (random() % bytes.len().max(1) as u64) as usize;
+            let slice = bytes.get(start..).unwrap_or(&[]);
+            let _ = parse_pds(slice);
+            let _ = parse_gds(slice);
+            let _ = parse_bitmap(slice);
+            let _ = decode_data(slice, None, None, 0, 1 << 20);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mixed_editions_test() -> Result<(), Grib1Error> {
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let wind = grib2::Grib2Parameter { discipline: 0, category: 2, number: 2 };
+        let grib2 = grib2::convert_with_parameter(&Grib::from_bytes(&sample[..2542704])?, wind)?;
+        assert!(matches!(Grib::from_bytes(&grib2), Err(Grib1Error::UnsupportedEdition(2))));
+
+        let mut bytes = sample[..2542704].to_vec();
+        bytes.extend_from_slice(&grib2);
+        bytes.extend_from_slice(&sample[2542704..]);
+        let path = std::env::temp_dir().join("grib1_reader_mixed_editions_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        let error = reader.inventory().await.unwrap_err();
+        assert!(matches!(error, Grib1Error::InMessage { message_index: 1, offset: 2542704, .. }));
+        assert!(matches!(error.root_cause(), Grib1Error::UnsupportedEdition(2)));
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?)).skip_grib2(true);
+        let result = reader.read(vec![SearchParams { param: 34, level: 700 }]).await?;
+        assert_eq!(result[0].offset, 2542704 + grib2.len() as u64);
+        assert_eq!(reader.read_binary_where(|_| true).await?.len(), 2 * 2542704);
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn value_count_test() -> Result<(), Grib1Error> {
+        let sample = tokio::fs::read("data/sample.grib").await?;
+
+        // One more point along each row than there are values
+        let mut bytes = sample[..2542704].to_vec();
+        bytes[8 + 28 + 7] += 1;
+        assert!(matches!(Grib::from_bytes(&bytes), Err(Grib1Error::ValueCountMismatch { expected, actual: 1271041 }) if expected == 1190 * 1069));
+
+        // One point fewer leaves values over, which the reader warns about
+        bytes[8 + 28 + 7] -= 2;
+        let path = std::env::temp_dir().join("grib1_reader_value_count_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert_eq!(reader.read_where(|_| true).await?[0].bds.as_ref().unwrap().data.len(), 1188 * 1069);
+        assert_eq!(
+            reader.take_warnings(),
+            vec![warning::Warning::ValueCountMismatch {
+                offset: 0,
+                expected: 1188 * 1069,
+                packed: 1189 * 1069
+            }]
+        );
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decimal_scale_test() -> Result<(), Grib1Error> {
+        // The values are packed as hundredths, so the reference value is the scaled minimum
+        let bytes = crate::synthetic::MessageBuilder::new(2, 2).decimal_scale_factor(2).values(vec![1.25, 2.5, -3.75, 10.0]).build()?;
+        let grib = Grib::from_bytes(&bytes)?;
+        assert_eq!((grib.pds.decimal_scale_factor, grib.bds.as_ref().unwrap().reference_value), (2, -375.0));
+        assert_eq!(grib.bds.unwrap().data, [1.25, 2.5, -3.75, 10.0]);
+
+        let path = std::env::temp_dir().join("grib1_reader_decimal_scale_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        let result = Grib1Reader::open(&path).await?.read_where(|_| true).await?;
+        tokio::fs::remove_file(&path).await?;
+        assert_eq!(result[0].bds.as_ref().unwrap().data, [1.25, 2.5, -3.75, 10.0]);
+        Ok(())
+    }
+
+    #[test]
+    fn conic_grid_test() -> Result<(), Grib1Error> {
+        // An Albers equal-area grid of 100x80 points 5 km apart, cutting at 29.5N and 45.5N, with the south pole in its usual place
+        let mut gds = vec![0, 0, 42, 0, 255, 8, 0, 100, 0, 80, 0x00, 0x4e, 0x20, 0x81, 0xd4, 0xc0, 0x80, 0x81, 0x5f, 0x90];
+        gds.extend([0x00, 0x13, 0x88, 0x00, 0x13, 0x88, 0, 0x40, 0x00, 0x73, 0x3c, 0x00, 0xb1, 0xbc, 0x81, 0x5f, 0x90, 0, 0, 0, 0, 0]);
+        let parsed = parse_gds(&gds)?;
+        let DataRepresentation::AlbersEqualArea(grid) = parsed.data else {
+            panic!("Unexpected grid {:?}", parsed.data);
+        };
+        assert_eq!((grid.number_of_x_values, grid.number_of_y_values), (100, 80));
+        assert_eq!((grid.x_direction_grid_length, grid.y_direction_grid_length, grid.scanning_mode), (5000, 5000, 0x40));
+        let angles = [
+            grid.latitude_of_first_grid_point,
+            grid.longitude_of_first_grid_point,
+            grid.orientation_of_the_grid,
+            grid.latin_1,
+            grid.latin_2,
+            grid.latitude_of_southern_pole,
+        ];
+        for (angle, expected) in angles.into_iter().zip([20.0, -120.0, -90.0, 29.5, 45.5, -90.0]) {
+            assert!((angle - expected).abs() < 1e-3, "{} != {}", angle, expected);
+        }
+        assert_eq!((parsed.number_of_points(), parsed.row_length()), (8000, Some(100)));
+
+        gds[5] = 13;
+        assert!(matches!(parse_gds(&gds)?.data, DataRepresentation::ObliqueLambert(_)));
+        assert!(matches!(parse_gds(&gds[..30]), Err(ParseError::Gds(GdsError::TooShort { needed: 40, got: 30 }))));
+        Ok(())
+    }
 }
@@ -0,0 +1,72 @@
+//! Caps on what the reader allocates from lengths and counts given in the file, so hostile or corrupt input can't exhaust memory.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Limits on the allocations made when reading a message. Exceeding one fails the message with `ResourceLimitExceeded`.
+pub struct Limits {
+    /// Largest message in octets. Its data section is read into memory when decoding.
+    pub max_message_size: u64,
+    /// Largest PDS, GDS or bit-map section in octets
+    pub max_section_size: usize,
+    /// Most points in a decoded field, each taking four octets
+    pub max_points: usize,
+}
+
+impl Default for Limits {
+    /// Generous limits that no real message comes close to: 1 GiB messages, 16 MiB sections and 256 million points
+    fn default() -> Limits {
+        Limits {
+            max_message_size: 1 << 30,
+            max_section_size: 1 << 24,
+            max_points: 1 << 28,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+    use crate::{Grib, Grib1Reader};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn limits_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        assert_eq!(reader.read_where(|_| true).await?.len(), 2);
+
+        let limits = Limits { max_section_size: 100, ..Limits::default() };
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?)).limits(limits);
+        let error = reader.inventory().await.unwrap_err();
+        assert!(matches!(error.root_cause(), Grib1Error::ResourceLimitExceeded { what: "section", requested: 570, limit: 100 }));
+
+        let limits = Limits { max_points: 1000, ..Limits::default() };
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?)).limits(limits);
+        assert_eq!(reader.inventory().await?.len(), 2);
+        let error = reader.read_where(|_| true).await.unwrap_err();
+        assert!(matches!(
+            error.root_cause(),
+            Grib1Error::ResourceLimitExceeded {
+                what: "grid",
+                requested: 1271041,
+                limit: 1000
+            }
+        ));
+
+        let limits = Limits {
+            max_message_size: 1 << 20,
+            ..Limits::default()
+        };
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?)).limits(limits);
+        assert!(matches!(reader.inventory().await.unwrap_err().root_cause(), Grib1Error::ResourceLimitExceeded { what: "message", .. }));
+
+        // A grid of 65535 x 65535 points packed with 0 bits per value is tiny, but would decode to 16 GiB
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        bytes.truncate(2542704);
+        bytes[8 + 28 + 6..8 + 28 + 10].fill(0xff);
+        bytes[8 + 28 + 570 + 10] = 0;
+        assert!(matches!(Grib::from_bytes(&bytes), Err(Grib1Error::ResourceLimitExceeded { what: "grid", .. })));
+
+        Ok(())
+    }
+}
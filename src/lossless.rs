@@ -0,0 +1,204 @@
+//! Lossless re-encoding: the packed integers, scales and reference value of a message are kept exactly as stored,
+//! so writing them again reproduces the original BDS byte for byte, e.g. after editing the metadata.
+
+use crate::codec::{read_i16_be, write_i16_be, write_u24_be};
+use crate::core::{number_of_data_points, parse_gds, present_points};
+use crate::error::{BdsError, BmsError, Grib1Error, PdsError};
+use crate::writer;
+use crate::{next_section, GDS, PDS};
+use bitstream_io::{BigEndian, BitRead, BitReader, BitWrite, BitWriter};
+use std::io::Cursor;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The contents of a simple packed BDS exactly as stored, together with the bit-map section if there is one
+pub struct PackedData {
+    /// The data flag including the number of unused bits
+    pub data_flag: u8,
+    pub binary_scale_factor: i16,
+    /// The reference value as an IBM float, see `ibm::from_ibm`
+    pub reference_value: [u8; 4],
+    pub bits_per_value: u8,
+    /// The packed integers, one per value present. They may be changed, but not their number.
+    pub values: Vec<u32>,
+    /// The bit-map section as stored, if any
+    pub bitmap: Option<Vec<u8>>,
+    // Everything from the octet holding the end of the packed integers, i.e. the unused bits and any padding
+    tail: Vec<u8>,
+    count: usize,
+}
+
+impl PackedData {
+    /// Extract the packed data from a single raw message, e.g. one returned by `read_binary`
+    pub fn from_message(message: &[u8]) -> Result<PackedData, Grib1Error> {
+        if message.len() < 8 || &message[0..4] != b"GRIB" {
+            return Err(Grib1Error::WrongHeader);
+        }
+        if message[7] != 1 {
+            return Err(Grib1Error::WrongVersion(message[7]));
+        }
+
+        let mut offset = 8;
+        let pds = next_section(message, &mut offset)?;
+        let flag = *pds.get(7).ok_or(PdsError::TooShort { needed: 28, got: pds.len() })?;
+        let gds = if flag & 128 > 0 { Some(parse_gds(next_section(message, &mut offset)?)?) } else { None };
+        let bitmap = if flag & 64 > 0 { Some(next_section(message, &mut offset)?) } else { None };
+
+        PackedData::from_sections(gds.as_ref(), bitmap, next_section(message, &mut offset)?)
+    }
+
+    /// Unpack the integers of a BDS without scaling them. There is one for each point of the grid,
+    /// or for each point present in the bit-map if there is one.
+    pub fn from_sections(gds: Option<&GDS>, bitmap: Option<&[u8]>, bds: &[u8]) -> Result<PackedData, Grib1Error> {
+        if bds.len() < 11 {
+            return Err(BdsError::TooShort { needed: 11, got: bds.len() }.into());
+        }
+        let data_flag = bds[3];
+        if data_flag & 0xc0 != 0 {
+            return Err(Grib1Error::UnsupportedPacking(data_flag));
+        }
+
+        let bits_per_value = bds[10];
+        if bits_per_value > 32 {
+            return Err(Grib1Error::UnsupportedBitsPerValue(bits_per_value));
+        }
+        let packed = &bds[11..];
+        let points = number_of_data_points(bds, bitmap, gds);
+        let count = match bitmap {
+            Some(bitmap) => present_points(bitmap, points)?.iter().filter(|p| **p).count(),
+            None => points,
+        };
+
+        let mut reader = BitReader::endian(Cursor::new(packed), BigEndian);
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            if bits_per_value == 0 {
+                values.push(0);
+                continue;
+            }
+            values.push(reader.read::<u32>(bits_per_value as u32).map_err(|_| Grib1Error::DataDecodeFailed {
+                expected_points: count,
+                decoded: values.len(),
+            })?);
+        }
+
+        Ok(PackedData {
+            data_flag,
+            binary_scale_factor: read_i16_be(&bds[4..]),
+            reference_value: [bds[6], bds[7], bds[8], bds[9]],
+            bits_per_value,
+            values,
+            bitmap: bitmap.map(|b| b.to_vec()),
+            tail: packed[count * bits_per_value as usize / 8..].to_vec(),
+            count,
+        })
+    }
+
+    /// The BDS with the current integers, identical to the original if they haven't been changed
+    pub fn to_section(&self) -> Result<Vec<u8>, Grib1Error> {
+        if self.values.len() != self.count {
+            return Err(Grib1Error::ValueCountMismatch {
+                expected: self.count,
+                actual: self.values.len(),
+            });
+        }
+
+        let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+        for value in self.values.iter().filter(|_| self.bits_per_value > 0) {
+            writer.write(self.bits_per_value as u32, *value)?;
+        }
+        writer.byte_align()?;
+        let mut packed = writer.into_writer();
+
+        // The octet holding the end of the integers keeps its original unused bits
+        let bits = self.count * self.bits_per_value as usize;
+        let whole = bits / 8;
+        let mut tail = self.tail.clone();
+        if !bits.is_multiple_of(8) {
+            let mask = 0xffu8 << (8 - bits % 8);
+            tail[0] = (packed[whole] & mask) | (tail[0] & !mask);
+        }
+        packed.truncate(whole);
+        packed.extend(tail);
+
+        let mut section = vec![];
+        section.extend(write_u24_be(11 + packed.len() as u32));
+        section.push(self.data_flag);
+        section.extend(write_i16_be(self.binary_scale_factor));
+        section.extend(self.reference_value);
+        section.push(self.bits_per_value);
+        section.extend(packed);
+
+        Ok(section)
+    }
+}
+
+/// Encode a message from a PDS and GDS around the packed data, which is written exactly as stored.
+/// The presence flags of the PDS are set to reflect the sections actually written.
+pub fn encode_lossless(pds: &PDS, gds: &GDS, packed: &PackedData) -> Result<Vec<u8>, Grib1Error> {
+    let expected = match &packed.bitmap {
+        Some(bitmap) if bitmap.len() >= 6 => {
+            let points = gds.number_of_points();
+            (0..points).filter(|i| bitmap.get(6 + i / 8).map(|b| b & (0x80 >> (i % 8)) > 0).unwrap_or(false)).count()
+        }
+        Some(bitmap) => return Err(BmsError::TooShort { needed: 6, got: bitmap.len() }.into()),
+        None => gds.number_of_points(),
+    };
+    if packed.bits_per_value > 0 && packed.values.len() != expected {
+        return Err(Grib1Error::ValueCountMismatch { expected, actual: packed.values.len() });
+    }
+
+    let bds = packed.to_section()?;
+    writer::finalize(&writer::encode_pds(pds, true, packed.bitmap.is_some()), Some(&writer::encode_gds(gds)?), packed.bitmap.as_deref(), &bds)
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::{Grib, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn lossless_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let bytes = reader.read_binary(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let original = Grib::from_bytes(&bytes)?;
+        let packed = PackedData::from_message(&bytes)?;
+
+        // The BDS is the last section before 7777
+        let bds_length = packed.to_section()?.len();
+        assert_eq!(packed.to_section()?, bytes[bytes.len() - 4 - bds_length..bytes.len() - 4]);
+
+        let mut pds = original.pds.clone();
+        pds.indicator_of_parameter_and_units = 131;
+        let encoded = encode_lossless(&pds, original.gds.as_ref().unwrap(), &packed)?;
+        assert_eq!(encoded[encoded.len() - 4 - bds_length..], bytes[bytes.len() - 4 - bds_length..]);
+
+        let decoded = Grib::from_bytes(&encoded)?;
+        assert_eq!(decoded.pds.indicator_of_parameter_and_units, 131);
+        assert_eq!(decoded.bds.unwrap().data, original.bds.unwrap().data);
+
+        let mut changed = packed.clone();
+        changed.values.pop();
+        assert!(changed.to_section().is_err());
+        let mut wide = bytes[bytes.len() - 4 - bds_length..bytes.len() - 4].to_vec();
+        wide[10] = 33;
+        assert!(matches!(PackedData::from_sections(None, None, &wide), Err(Grib1Error::UnsupportedBitsPerValue(33))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn padded_section_test() -> Result<(), Grib1Error> {
+        // Three values present out of five points, followed by two octets of padding
+        let bitmap = [0, 0, 7, 3, 0, 0, 0b1011_0000];
+        let bds = [0, 0, 16, 0, 0, 0, 0x42, 0x10, 0, 0, 8, 1, 2, 3, 0, 0];
+        let packed = PackedData::from_sections(None, Some(&bitmap), &bds)?;
+        assert_eq!(packed.values, vec![1, 2, 3]);
+        assert_eq!(packed.to_section()?, bds);
+        assert_eq!(PackedData::from_sections(None, None, &bds)?.values, vec![1, 2, 3, 0, 0]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Building a multi-message file from messages taken from several sources, preserving their original encoding.
+
+use crate::error::Grib1Error;
+use crate::message_length;
+#[cfg(feature = "async-tokio")]
+use crate::{Grib, Grib1Reader};
+#[cfg(feature = "async-tokio")]
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Split a blob of concatenated messages, e.g. as returned by `read_binary`, into the individual messages.
+/// Every message is checked to start with `GRIB`, be edition 1 and end with `7777`.
+pub fn split_messages(bytes: &[u8]) -> Result<Vec<&[u8]>, Grib1Error> {
+    let mut result = vec![];
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+        if rest.len() < 8 || &rest[0..4] != b"GRIB" {
+            return Err(Grib1Error::WrongHeader);
+        }
+        match rest[7] {
+            0 | 1 => {}
+            2 => return Err(Grib1Error::UnsupportedEdition(2)),
+            version => return Err(Grib1Error::WrongVersion(version)),
+        }
+
+        let length = message_length(rest).unwrap_or(0);
+        if length < 12 || length > rest.len() {
+            return Err(Grib1Error::MalformedMessage(format!("Message at offset {} claims a length of {} but only {} bytes are available", offset, length, rest.len())));
+        }
+        if &rest[length - 4..length] != b"7777" {
+            return Err(Grib1Error::MalformedMessage(format!("Message at offset {} doesn't end with 7777", offset)));
+        }
+
+        result.push(&rest[..length]);
+        offset += length;
+    }
+
+    Ok(result)
+}
+
+#[cfg(feature = "async-tokio")]
+/// Writes messages one after another into a single well-formed multi-message GRIB1 stream
+pub struct Merger<W> {
+    writer: W,
+    messages: usize,
+    bytes: u64,
+}
+
+#[cfg(feature = "async-tokio")]
+impl<W: AsyncWrite + Unpin> Merger<W> {
+    pub fn new(writer: W) -> Merger<W> {
+        Merger { writer, messages: 0, bytes: 0 }
+    }
+
+    /// Add one or more complete messages given as raw bytes. Nothing is written if any of them is malformed.
+    pub async fn add_bytes(&mut self, bytes: &[u8]) -> Result<(), Grib1Error> {
+        let messages = split_messages(bytes)?;
+
+        self.writer.write_all(bytes).await?;
+        self.messages += messages.len();
+        self.bytes += bytes.len() as u64;
+
+        Ok(())
+    }
+
+    /// Copy a message previously returned by `reader` verbatim
+    pub async fn add_message(&mut self, reader: &mut Grib1Reader, grib: &Grib) -> Result<(), Grib1Error> {
+        let bytes = reader.read_raw(grib).await?;
+        self.add_bytes(&bytes).await
+    }
+
+    /// Number of messages written so far
+    pub fn message_count(&self) -> usize {
+        self.messages
+    }
+
+    /// Number of bytes written so far
+    pub fn byte_count(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Flush the output and hand back the writer
+    pub async fn finish(mut self) -> Result<W, Grib1Error> {
+        self.writer.flush().await?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::SearchParams;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn merge_test() -> Result<(), Grib1Error> {
+        let path = std::env::temp_dir().join("grib1_reader_merge_test.grb");
+
+        let mut first = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let mut second = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let messages = first.inventory().await?;
+        let v_wind = second.read_binary(vec![SearchParams { param: 34, level: 700 }]).await?;
+
+        let mut merger = Merger::new(File::create(&path).await?);
+        merger.add_bytes(&v_wind).await?;
+        merger.add_message(&mut first, &messages[0]).await?;
+        assert!(merger.add_bytes(&v_wind[..100]).await.is_err());
+        assert_eq!(merger.message_count(), 2);
+        merger.finish().await?;
+
+        let mut merged = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        let result = merged.inventory().await?;
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].pds.indicator_of_parameter_and_units, 34);
+        assert_eq!(result[1].pds.indicator_of_parameter_and_units, 33);
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+//! A single pass over the messages of a file where each message's headers are seen before deciding whether to decode it, copy it or pass it over.
+//!
+//! ```no_run
+//! # async fn example(reader: &mut grib1_reader::Grib1Reader) -> Result<(), grib1_reader::error::Grib1Error> {
+//! let mut messages = reader.messages().await?;
+//! while let Some(message) = messages.next().await? {
+//!     if message.pds.indicator_of_parameter_and_units == 33 {
+//!         let grib = message.decode().await?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Grib1Error;
+use crate::lenient::LenientRead;
+use crate::{gts, Grib, Grib1Reader, GribResult, SearchParams, GDS, PDS};
+use std::io::SeekFrom;
+use tokio::io::AsyncReadExt;
+use tokio::task::JoinHandle;
+
+/// The messages of a file in order, returned by `Grib1Reader::messages`
+pub struct Messages<'a> {
+    reader: &'a mut Grib1Reader,
+    offset: u64,
+    length: u64,
+    message_index: usize,
+    // The messages skipped by a lenient reader, until they are moved to its warnings
+    recovered: LenientRead,
+}
+
+/// The headers of a message, with the means to read the rest of it
+pub struct MessageHeader<'a> {
+    reader: &'a mut Grib1Reader,
+    message_index: usize,
+    /// Position of the message within the file
+    pub offset: u64,
+    pub length: u64,
+    pub pds: PDS,
+    pub gds: Option<GDS>,
+    /// The header of the GTS bulletin wrapping the message, if the reader has `Grib1Reader::gts` enabled
+    pub bulletin: Option<gts::BulletinHeader>,
+}
+
+/// The decoded messages of a file accepted by a filter, returned by `Grib1Reader::into_matches`
+pub struct Matches {
+    scan: Option<Scan>,
+    prefetch: bool,
+    // The next match being read and decoded in the background
+    pending: Option<JoinHandle<ScanStep>>,
+}
+
+// A scan handed back by the task that took it a match further
+type ScanStep = (Scan, Result<Option<Grib>, Grib1Error>);
+
+// Where a scan for matches has got to, moved to the task reading the next match when prefetching
+struct Scan {
+    reader: Grib1Reader,
+    filter: Box<dyn Fn(&PDS) -> bool + Send + Sync>,
+    offset: u64,
+    length: Option<u64>,
+    message_index: usize,
+    recovered: LenientRead,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What `Grib1Reader::read_with` does with a message after its headers are seen
+pub enum Decision {
+    /// Decode the message and return it
+    Decode,
+    /// Pass over the message without reading its data
+    Skip,
+    /// End the read, returning the messages decoded so far
+    Stop,
+}
+
+impl Grib1Reader {
+    /// Go through the messages of the file one at a time, reading only their headers until asked for more
+    pub async fn messages(&mut self) -> Result<Messages<'_>, Grib1Error> {
+        let length = self.seek(SeekFrom::End(0)).await?;
+        self.report = crate::report::ScanReport::default();
+        Ok(Messages {
+            reader: self,
+            offset: 0,
+            length,
+            message_index: 0,
+            recovered: LenientRead::default(),
+        })
+    }
+
+    /// Go through the messages accepted by the filter one at a time, decoding each when it is asked for unless `Matches::prefetch` is set
+    pub fn into_matches<F: Fn(&PDS) -> bool + Send + Sync + 'static>(self, filter: F) -> Matches {
+        Matches {
+            scan: Some(Scan {
+                reader: self,
+                filter: Box::new(filter),
+                offset: 0,
+                length: None,
+                message_index: 0,
+                recovered: LenientRead::default(),
+            }),
+            prefetch: false,
+            pending: None,
+        }
+    }
+
+    /// Read the file in a single pass, calling `decide` with the headers of every message matching any of the search parameters,
+    /// or of every message if there are none. Only the messages it decides to decode are returned.
+    /// The callback can keep state of its own, e.g. to count or log the messages, or to stop once it has found what it needs.
+    pub async fn read_with<F: FnMut(&MessageHeader) -> Decision>(&mut self, search: Vec<SearchParams>, mut decide: F) -> Result<Vec<Grib>, Grib1Error> {
+        let mut result = vec![];
+        let mut messages = self.messages().await?;
+        while let Some(message) = messages.next().await? {
+            if !search.is_empty() && !search.iter().any(|params| params.matches(&message.pds)) {
+                continue;
+            }
+            match decide(&message) {
+                Decision::Decode => result.push(message.decode().await?),
+                Decision::Skip => {}
+                Decision::Stop => break,
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Messages<'_> {
+    /// The next message, or None at the end of the file. GRIB2 messages passed over by `Grib1Reader::skip_grib2` aren't returned,
+    /// and neither are those a `Grib1Reader::lenient` reader can't parse.
+    pub async fn next(&mut self) -> Result<Option<MessageHeader<'_>>, Grib1Error> {
+        while self.offset < self.length {
+            let message_index = self.message_index;
+            let recovered = self.reader.lenient.then_some(&mut self.recovered);
+            let step = self.reader.scan_step(self.offset, message_index, self.length, &|_: &PDS| true, false, recovered).await?;
+            self.reader.warn_skipped(&mut self.recovered);
+            let Some((grib, next)) = step else {
+                self.offset = self.length;
+                break;
+            };
+            self.offset = next;
+            self.message_index += 1;
+            if let Some(grib) = grib {
+                return Ok(Some(MessageHeader {
+                    reader: &mut *self.reader,
+                    message_index,
+                    offset: grib.offset,
+                    length: grib.length,
+                    pds: grib.pds,
+                    gds: grib.gds,
+                    bulletin: grib.bulletin,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Matches {
+    /// Read and decode the next match on a background task while the caller works on the one returned, so reading overlaps with that work.
+    /// At most one match is decoded ahead.
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// The next match, or None at the end of the file
+    pub async fn next(&mut self) -> Result<Option<Grib>, Grib1Error> {
+        let result = match (self.pending.take(), self.scan.take()) {
+            (Some(pending), _) => {
+                let (scan, result) = pending.await.map_err(std::io::Error::other)?;
+                self.scan = Some(scan);
+                result
+            }
+            (None, Some(mut scan)) => {
+                let result = scan.next().await;
+                self.scan = Some(scan);
+                result
+            }
+            (None, None) => return Ok(None),
+        };
+
+        match (&result, self.scan.take()) {
+            (Ok(Some(_)), Some(mut scan)) if self.prefetch => {
+                self.pending = Some(tokio::spawn(async move {
+                    let result = scan.next().await;
+                    (scan, result)
+                }))
+            }
+            // A scan that has ended or failed isn't taken further
+            (Ok(Some(_)), scan) => self.scan = scan,
+            _ => {}
+        }
+        result
+    }
+}
+
+impl Scan {
+    async fn next(&mut self) -> Result<Option<Grib>, Grib1Error> {
+        let length = match self.length {
+            Some(length) => length,
+            None => {
+                self.reader.report = crate::report::ScanReport::default();
+                *self.length.insert(self.reader.seek(SeekFrom::End(0)).await?)
+            }
+        };
+        while self.offset < length {
+            let recovered = self.reader.lenient.then_some(&mut self.recovered);
+            let step = self.reader.scan_step(self.offset, self.message_index, length, &self.filter, true, recovered).await?;
+            self.reader.warn_skipped(&mut self.recovered);
+            let Some((grib, next)) = step else {
+                self.offset = length;
+                break;
+            };
+            self.offset = next;
+            self.message_index += 1;
+            if grib.is_some() {
+                return Ok(grib);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl MessageHeader<'_> {
+    /// Read and decode the whole message, as `Grib1Reader::read_where` would
+    pub async fn decode(self) -> Result<Grib, Grib1Error> {
+        self.reader.seek(SeekFrom::Start(self.offset)).await?;
+        match self.reader.read_grib(self.message_index, self.offset, self.offset + self.length, &|_: &PDS| true, true).await? {
+            GribResult::Grib(mut grib) => {
+                grib.bulletin = self.bulletin;
+                Ok(*grib)
+            }
+            GribResult::Length(_) => Err(Grib1Error::WrongHeader),
+        }
+    }
+
+    /// The raw bytes of the message, as `Grib1Reader::read_raw` would return them
+    pub async fn raw_bytes(&mut self) -> Result<Vec<u8>, Grib1Error> {
+        self.reader.seek(SeekFrom::Start(self.offset)).await?;
+        let mut buffer = vec![0; self.length as usize];
+        self.reader.reader.read_exact(&mut buffer).await?;
+        self.reader.count_read(self.length);
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn messages_test() -> Result<(), Grib1Error> {
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+
+        let mut messages = reader.messages().await?;
+        let mut message = messages.next().await?.unwrap();
+        assert_eq!((message.offset, message.length, message.pds.indicator_of_parameter_and_units), (0, 2542704, 33));
+        assert_eq!(message.raw_bytes().await?, sample[..2542704]);
+
+        let message = messages.next().await?.unwrap();
+        assert_eq!(message.offset, 2542704);
+        let grib = message.decode().await?;
+        assert_eq!(grib.pds.indicator_of_parameter_and_units, 34);
+        assert_eq!(grib.bds.unwrap().data.len(), 1271041);
+
+        assert!(messages.next().await?.is_none());
+        assert_eq!(reader.metrics().messages_decoded, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_with_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+
+        let mut seen = vec![];
+        let result = reader
+            .read_with(vec![], |message| {
+                seen.push(message.offset);
+                if message.pds.indicator_of_parameter_and_units == 34 {
+                    Decision::Decode
+                } else {
+                    Decision::Skip
+                }
+            })
+            .await?;
+        assert_eq!(seen, [0, 2542704]);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].offset, 2542704);
+        assert_eq!(result[0].bds.as_ref().unwrap().data.len(), 1271041);
+
+        // Stopping at the first message leaves the second one unseen
+        let mut count = 0;
+        let result = reader
+            .read_with(vec![], |_| {
+                count += 1;
+                Decision::Stop
+            })
+            .await?;
+        assert!(result.is_empty());
+        assert_eq!(count, 1);
+
+        // Only the messages matching the search are seen
+        let result = reader.read_with(vec![SearchParams { param: 33, level: 700 }], |_| Decision::Decode).await?;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].pds.indicator_of_parameter_and_units, 33);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn matches_test() -> Result<(), Grib1Error> {
+        for prefetch in [false, true] {
+            let mut matches = Grib1Reader::open("data/sample.grib").await?.into_matches(|_| true).prefetch(prefetch);
+            let first = matches.next().await?.unwrap();
+            assert_eq!((first.offset, first.pds.indicator_of_parameter_and_units), (0, 33));
+            // While prefetching the second message is already being read
+            assert_eq!(matches.pending.is_some(), prefetch);
+            let second = matches.next().await?.unwrap();
+            assert_eq!((second.offset, second.bds.unwrap().data.len()), (2542704, 1271041));
+            assert!(matches.next().await?.is_none());
+            assert!(matches.next().await?.is_none());
+        }
+
+        let mut matches = Grib1Reader::open("data/sample.grib").await?.into_matches(|pds| pds.indicator_of_parameter_and_units == 34).prefetch(true);
+        assert_eq!(matches.next().await?.unwrap().offset, 2542704);
+        assert!(matches.next().await?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! Counters kept by the reader over its whole lifetime, for long-running services to chart how much work the decoder does.
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// What a reader has done since it was created or its metrics were last taken. Unlike the `ScanReport` these add up across reads.
+pub struct Metrics {
+    /// Octets read from the file
+    pub bytes_read: u64,
+    /// Seeks performed on the file
+    pub seeks: u64,
+    /// Messages looked at, whether they matched or not
+    pub messages_scanned: u64,
+    /// Messages whose data section was decoded
+    pub messages_decoded: u64,
+    /// Time spent unpacking data sections
+    pub decode_time: Duration,
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use crate::error::Grib1Error;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn metrics_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        reader.read(vec![SearchParams { param: 34, level: 700 }]).await?;
+        let metrics = *reader.metrics();
+        assert_eq!((metrics.messages_scanned, metrics.messages_decoded), (2, 1));
+        assert_eq!(metrics.bytes_read, reader.scan_report().bytes_read);
+        assert!(metrics.seeks > 0);
+        assert!(!metrics.decode_time.is_zero());
+
+        // The counters keep adding up over reads until they're taken
+        reader.inventory().await?;
+        assert_eq!(reader.metrics().messages_scanned, 4);
+        assert_eq!(reader.metrics().messages_decoded, 1);
+        assert!(reader.metrics().bytes_read > metrics.bytes_read);
+        assert_eq!(reader.take_metrics().messages_scanned, 4);
+        assert_eq!(*reader.metrics(), Default::default());
+
+        Ok(())
+    }
+}
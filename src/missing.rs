@@ -0,0 +1,82 @@
+//! What missing points are given as in decoded data. NaN is the default, which most of the library relies on,
+//! but systems downstream may expect a sentinel such as 9999 instead.
+
+pub use crate::core::MissingValues;
+use crate::BDS;
+use std::borrow::Cow;
+
+impl BDS {
+    /// Whether a value of the data stands for a missing point
+    pub fn is_missing(&self, value: f32) -> bool {
+        match self.missing_values {
+            MissingValues::Nan => value.is_nan(),
+            MissingValues::Sentinel(sentinel) => value == sentinel,
+        }
+    }
+
+    /// The values with the missing points as None, whatever they are given as
+    pub fn optional_values(&self) -> Vec<Option<f32>> {
+        self.data.iter().map(|value| Some(*value).filter(|value| !self.is_missing(*value))).collect()
+    }
+
+    // The values with the missing points as NaN, which the encoders and computations of the library expect
+    pub(crate) fn nan_values(&self) -> Cow<'_, [f32]> {
+        match self.missing_values {
+            MissingValues::Nan => Cow::Borrowed(&self.data),
+            MissingValues::Sentinel(_) => Cow::Owned(self.data.iter().map(|value| if self.is_missing(*value) { f32::NAN } else { *value }).collect()),
+        }
+    }
+
+    /// Change what the missing points are given as
+    pub fn set_missing_values(&mut self, missing_values: MissingValues) {
+        let replacement = match missing_values {
+            MissingValues::Nan => f32::NAN,
+            MissingValues::Sentinel(sentinel) => sentinel,
+        };
+        for i in 0..self.data.len() {
+            if self.is_missing(self.data[i]) {
+                self.data[i] = replacement;
+            }
+        }
+        self.missing_values = missing_values;
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+    use crate::options::Grib1ReaderOptions;
+    use crate::{writer, Grib, Grib1Reader};
+
+    #[tokio::test]
+    async fn missing_values_test() -> Result<(), Grib1Error> {
+        let source = Grib1Reader::open("data/sample.grib").await?.read_where(|_| true).await?.remove(0);
+        let data = [1.0, f32::NAN, 3.0, 4.0];
+        let gds = crate::grid::RegularLatLonBuilder::new(2, 2).first_grid_point(50.0, 0.0).increments(1.0, 1.0).build()?;
+        let path = std::env::temp_dir().join("grib1_reader_missing_values_test.grb");
+        tokio::fs::write(&path, writer::encode(&source.pds, &gds, &data, 16)?).await?;
+
+        let mut reader = Grib1Reader::open_with(&path, Grib1ReaderOptions::new().missing_values(MissingValues::Sentinel(9999.0))).await?;
+        let grib = reader.read_where(|_| true).await?.remove(0);
+        let mut bds = grib.bds.clone().unwrap();
+        assert_eq!(bds.data[1], 9999.0);
+
+        // The sentinel is still a missing point when the message is encoded again or computed with
+        let repacked = Grib::from_bytes(&grib.repack(16)?)?.bds.unwrap();
+        assert!(repacked.data[1].is_nan() && repacked.data[3] == 4.0);
+        assert_eq!(grib.minimum(&grib.scale(2.0, 0.0)?)?.bds.unwrap().data[..2], [1.0, 9999.0]);
+        let mut missing = bds.clone();
+        missing.data = vec![9999.0; 4];
+        assert!(missing.sanity_flags().all_missing);
+        assert_eq!(bds.optional_values()[..2], [Some(1.0), None]);
+
+        bds.set_missing_values(MissingValues::Nan);
+        assert!(bds.data[1].is_nan());
+        assert_eq!(bds.optional_values()[1], None);
+        assert_eq!(Grib::from_bytes(&tokio::fs::read(&path).await?)?.bds.unwrap().missing_values, MissingValues::Nan);
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+//! Reader-wide configuration given when the reader is created, with `Grib1Reader::with_options` or `Grib1Reader::open_with`.
+
+use crate::error::Grib1Error;
+use crate::limits::Limits;
+use crate::missing::MissingValues;
+use crate::Grib1Reader;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::BufReader;
+
+// The default of BufReader
+const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+/// How a reader behaves, built up from the defaults of `Grib1Reader::new`, e.g. `Grib1ReaderOptions::new().strict(true).buffer_capacity(1 << 20)`.
+/// Each option is described by the `Grib1Reader` method of the same name.
+pub struct Grib1ReaderOptions {
+    strict: bool,
+    lenient: bool,
+    skip_grib2: bool,
+    gts: bool,
+    digest: bool,
+    decimal_scaling: bool,
+    missing_values: MissingValues,
+    limits: Limits,
+    buffer_capacity: usize,
+}
+
+impl Default for Grib1ReaderOptions {
+    fn default() -> Grib1ReaderOptions {
+        Grib1ReaderOptions {
+            strict: false,
+            lenient: false,
+            skip_grib2: false,
+            gts: false,
+            digest: false,
+            decimal_scaling: true,
+            missing_values: MissingValues::Nan,
+            limits: Limits::default(),
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+        }
+    }
+}
+
+impl Grib1ReaderOptions {
+    pub fn new() -> Grib1ReaderOptions {
+        Grib1ReaderOptions::default()
+    }
+
+    /// Check every message for consistency, failing the read on the first problem
+    pub fn strict(mut self, strict: bool) -> Grib1ReaderOptions {
+        self.strict = strict;
+        self
+    }
+
+    /// Skip messages that can't be parsed and resynchronise on the next `GRIB` marker, recording them as warnings
+    pub fn lenient(mut self, lenient: bool) -> Grib1ReaderOptions {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Pass over GRIB2 messages in files mixing editions
+    pub fn skip_grib2(mut self, skip: bool) -> Grib1ReaderOptions {
+        self.skip_grib2 = skip;
+        self
+    }
+
+    /// Skip GTS bulletin headers and other junk between messages
+    pub fn gts(mut self, gts: bool) -> Grib1ReaderOptions {
+        self.gts = gts;
+        self
+    }
+
+    /// Compute the SHA-256 digest of every message returned
+    pub fn digest(mut self, digest: bool) -> Grib1ReaderOptions {
+        self.digest = digest;
+        self
+    }
+
+    /// Apply the decimal scale factor to decoded values
+    pub fn decimal_scaling(mut self, decimal_scaling: bool) -> Grib1ReaderOptions {
+        self.decimal_scaling = decimal_scaling;
+        self
+    }
+
+    /// Give missing points as NaN or a sentinel value
+    pub fn missing_values(mut self, missing_values: MissingValues) -> Grib1ReaderOptions {
+        self.missing_values = missing_values;
+        self
+    }
+
+    /// Limit what is allocated when reading a message
+    pub fn limits(mut self, limits: Limits) -> Grib1ReaderOptions {
+        self.limits = limits;
+        self
+    }
+
+    /// Size in octets of the buffer the file is read through by `Grib1Reader::open_with`, 8 KiB by default. Larger buffers help sequential scans over network file systems.
+    pub fn buffer_capacity(mut self, capacity: usize) -> Grib1ReaderOptions {
+        self.buffer_capacity = capacity;
+        self
+    }
+}
+
+impl Grib1Reader {
+    /// Create a reader of the file configured by the options. The buffer capacity is that of the BufReader given.
+    pub fn with_options(buf_reader: BufReader<File>, options: Grib1ReaderOptions) -> Grib1Reader {
+        Grib1Reader::new(buf_reader)
+            .strict(options.strict)
+            .lenient(options.lenient)
+            .skip_grib2(options.skip_grib2)
+            .gts(options.gts)
+            .digest(options.digest)
+            .decimal_scaling(options.decimal_scaling)
+            .missing_values(options.missing_values)
+            .limits(options.limits)
+    }
+
+    /// Open a file for reading, e.g. `Grib1Reader::open("data/sample.grib").await?`
+    pub async fn open(path: impl AsRef<Path>) -> Result<Grib1Reader, Grib1Error> {
+        Grib1Reader::open_with(path, Grib1ReaderOptions::default()).await
+    }
+
+    /// Open a file for reading, configured by the options
+    pub async fn open_with(path: impl AsRef<Path>, options: Grib1ReaderOptions) -> Result<Grib1Reader, Grib1Error> {
+        let file = File::open(path).await?;
+        Ok(Grib1Reader::with_options(BufReader::with_capacity(options.buffer_capacity, file), options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warning::Warning;
+
+    #[tokio::test]
+    async fn options_test() -> Result<(), Grib1Error> {
+        // Values packed as hundredths, so turning the scaling off gives them 100 times larger
+        let bytes = crate::synthetic::MessageBuilder::new(2, 2).decimal_scale_factor(2).values(vec![1.25, 2.5, -3.75, 10.0]).build()?;
+        let path = std::env::temp_dir().join("grib1_reader_options_scaling_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        let scaled = Grib1Reader::open(&path).await?.read_where(|_| true).await?.remove(0);
+        let options = Grib1ReaderOptions::new().buffer_capacity(1 << 20).decimal_scaling(false);
+        let unscaled = Grib1Reader::open_with(path.clone(), options).await?.read_where(|_| true).await?.remove(0);
+        tokio::fs::remove_file(&path).await?;
+        assert_eq!(scaled.bds.unwrap().data, [1.25, 2.5, -3.75, 10.0]);
+        assert_eq!(unscaled.bds.unwrap().data, [125.0, 250.0, -375.0, 1000.0]);
+
+        // Break the bit-map flag of the first message, which a lenient reader skips
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        bytes[8 + 7] |= 0x40;
+        let path = std::env::temp_dir().join("grib1_reader_options_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        assert!(Grib1Reader::open(&path).await?.inventory().await.is_err());
+        let mut reader = Grib1Reader::open_with(&path, Grib1ReaderOptions::new().lenient(true)).await?;
+        assert_eq!(reader.read_where(|_| true).await?.len(), 1);
+        assert!(matches!(reader.warnings(), [Warning::SkippedMessage { offset: 0, .. }]));
+
+        assert!(matches!(Grib1Reader::open("data/missing.grib").await, Err(Grib1Error::IoError(_))));
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
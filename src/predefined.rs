@@ -0,0 +1,26 @@
+//! Catalogue of commonly used NCEP predefined grids, identified by the `grid_identification` of the PDS when a message has no GDS.
+
+/// Number of points along a parallel (Ni) and along a meridian (Nj) of a catalogued grid
+pub fn grid_size(grid_identification: u8) -> Option<(u16, u16)> {
+    match grid_identification {
+        // Global latitude/longitude grids with 2.5, 1, 0.5 and 1.25 degree spacing
+        2 => Some((144, 73)),
+        3 => Some((360, 181)),
+        4 => Some((720, 361)),
+        45 => Some((288, 145)),
+        // Lambert conformal grids over North America
+        211 => Some((93, 65)),
+        212 => Some((185, 129)),
+        215 => Some((369, 257)),
+        218 => Some((614, 428)),
+        221 => Some((349, 277)),
+        236 => Some((151, 113)),
+        252 => Some((301, 225)),
+        _ => None,
+    }
+}
+
+/// Number of points of a catalogued grid
+pub fn number_of_points(grid_identification: u8) -> Option<usize> {
+    grid_size(grid_identification).map(|(ni, nj)| ni as usize * nj as usize)
+}
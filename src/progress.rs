@@ -0,0 +1,59 @@
+//! Progress of a read through a file, for interactive tools to show while scanning large archives.
+
+// The callback kept by the reader
+#[cfg(feature = "async-tokio")]
+pub(crate) type Callback = Box<dyn FnMut(&Progress) + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Where a read has got to, passed to the callback given to `Grib1Reader::progress` after every message
+pub struct Progress {
+    /// Octets of the file scanned so far, including the messages that were passed over
+    pub bytes_processed: u64,
+    /// Size of the file
+    pub total_bytes: u64,
+    /// Messages found so far
+    pub messages_found: usize,
+}
+
+impl Progress {
+    /// The part of the file done, from 0 to 1
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+        self.bytes_processed as f64 / self.total_bytes as f64
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+    use crate::Grib1Reader;
+    use std::sync::{Arc, Mutex};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn progress_test() -> Result<(), Grib1Error> {
+        let updates = Arc::new(Mutex::new(vec![]));
+        let collected = updates.clone();
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?)).progress(move |progress| collected.lock().unwrap().push(*progress));
+        reader.inventory().await?;
+
+        let updates = updates.lock().unwrap().clone();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(
+            updates[0],
+            Progress {
+                bytes_processed: 2542704,
+                total_bytes: 2 * 2542704,
+                messages_found: 1
+            }
+        );
+        assert_eq!(updates[1].fraction(), 1.0);
+        assert_eq!(updates[1].messages_found, 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+//! Chainable queries of a `Catalog` reading like the question asked, e.g.
+//!
+//! ```no_run
+//! # async fn example(catalog: grib1_reader::catalog::Catalog) -> Result<(), grib1_reader::error::Grib1Error> {
+//! let region = grib1_reader::BoundingBox { north: 58.0, south: 54.0, west: 8.0, east: 13.0 };
+//! let fields = catalog.select().param("TMP").level_type(100).levels(&[850, 700]).steps(0..=48).bbox(region).run().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::catalog::{Catalog, MessageHandle};
+use crate::error::Grib1Error;
+use crate::index::IndexedMessage;
+use crate::tables;
+use crate::time::DateTime;
+use crate::{BoundingBox, Grib, Grib1Reader, GribResult, PDS};
+use std::io::SeekFrom;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone)]
+/// The messages of a catalogue to select, every one unless narrowed down
+pub struct Query<'a> {
+    catalog: &'a Catalog,
+    params: Vec<u8>,
+    unknown_param: Option<String>,
+    type_of_level: Option<u8>,
+    levels: Vec<u16>,
+    steps: Option<RangeInclusive<u32>>,
+    reference_times: Option<RangeInclusive<DateTime>>,
+    bbox: Option<BoundingBox>,
+}
+
+#[derive(Debug)]
+/// A message selected by `Query::run_within`, either decoded or left to be read later
+pub enum BatchItem {
+    Decoded(Box<Grib>),
+    /// A message that would have taken the decoded data over the budget, cropped to the region of the query when it is read
+    Deferred {
+        handle: MessageHandle,
+        region: Option<BoundingBox>,
+    },
+}
+
+impl BatchItem {
+    /// The decoded message, reading it if it was deferred
+    pub async fn into_grib(self) -> Result<Grib, Grib1Error> {
+        match self {
+            BatchItem::Decoded(grib) => Ok(*grib),
+            BatchItem::Deferred { handle, region } => {
+                let grib = handle.read().await?;
+                match region {
+                    Some(region) => grib.crop(&region),
+                    None => Ok(grib),
+                }
+            }
+        }
+    }
+}
+
+impl Catalog {
+    /// Start a query of the messages of the catalogue
+    pub fn select(&self) -> Query<'_> {
+        Query {
+            catalog: self,
+            params: vec![],
+            unknown_param: None,
+            type_of_level: None,
+            levels: vec![],
+            steps: None,
+            reference_times: None,
+            bbox: None,
+        }
+    }
+}
+
+impl<'a> Query<'a> {
+    /// Select a parameter by its abbreviation in code table 2, e.g. "TMP". Parameters given in turn are all selected.
+    pub fn param(mut self, abbreviation: &str) -> Self {
+        match tables::parameter_by_abbreviation(abbreviation) {
+            Some(parameter) => self.params.push(parameter.indicator),
+            None => self.unknown_param = Some(abbreviation.to_string()),
+        }
+        self
+    }
+
+    /// Select a parameter by its indicator
+    pub fn param_number(mut self, indicator: u8) -> Self {
+        self.params.push(indicator);
+        self
+    }
+
+    /// Select the type of level (code table 3), e.g. 100 for isobaric levels
+    pub fn level_type(mut self, type_of_level: u8) -> Self {
+        self.type_of_level = Some(type_of_level);
+        self
+    }
+
+    pub fn levels(mut self, levels: &[u16]) -> Self {
+        self.levels = levels.to_vec();
+        self
+    }
+
+    pub fn steps(mut self, steps: RangeInclusive<u32>) -> Self {
+        self.steps = Some(steps);
+        self
+    }
+
+    pub fn reference_times(mut self, reference_times: RangeInclusive<DateTime>) -> Self {
+        self.reference_times = Some(reference_times);
+        self
+    }
+
+    /// Crop the decoded fields to the region
+    pub fn bbox(mut self, bbox: BoundingBox) -> Self {
+        self.bbox = Some(bbox);
+        self
+    }
+
+    /// Whether the message is selected
+    pub fn matches(&self, message: &IndexedMessage) -> bool {
+        (self.params.is_empty() || self.params.contains(&message.param))
+            && self.type_of_level.is_none_or(|type_of_level| type_of_level == message.type_of_level)
+            && (self.levels.is_empty() || self.levels.contains(&message.level))
+            && self.steps.as_ref().is_none_or(|steps| steps.contains(&message.step))
+            && self.reference_times.as_ref().is_none_or(|times| times.contains(&message.reference_time))
+    }
+
+    /// The messages selected, without reading them. See `Catalog::query` for their order and how duplicates are resolved.
+    pub fn handles(&self) -> Result<Vec<MessageHandle>, Grib1Error> {
+        if let Some(abbreviation) = &self.unknown_param {
+            return Err(Grib1Error::UnknownParameter(abbreviation.clone()));
+        }
+        Ok(self.catalog.query(|message| self.matches(message)))
+    }
+
+    /// Read and decode the messages selected, cropped to the region if one is given
+    pub async fn run(&self) -> Result<Vec<Grib>, Grib1Error> {
+        let mut result = vec![];
+        let mut open: Option<(&std::path::Path, Grib1Reader)> = None;
+        let handles = self.handles()?;
+        for handle in &handles {
+            // Consecutive messages of the same file are read without opening it again
+            let reader = match &mut open {
+                Some((path, reader)) if *path == handle.path => reader,
+                _ => &mut open.insert((&handle.path, Grib1Reader::open(&handle.path).await?)).1,
+            };
+            let grib = reader.read_indexed_message(&handle.message).await?;
+            result.push(match &self.bbox {
+                Some(bbox) => grib.crop(bbox)?,
+                None => grib,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Like `run`, but messages are only decoded while their values take no more than `budget` bytes in all. The size of a field is
+    /// known from its grid before it is decoded, and those that don't fit are deferred, so later smaller fields may still be decoded.
+    pub async fn run_within(&self, budget: u64) -> Result<Vec<BatchItem>, Grib1Error> {
+        let mut result = vec![];
+        let mut used = 0;
+        let mut open: Option<(&std::path::Path, Grib1Reader)> = None;
+        let handles = self.handles()?;
+        for handle in &handles {
+            let reader = match &mut open {
+                Some((path, reader)) if *path == handle.path => reader,
+                _ => &mut open.insert((&handle.path, Grib1Reader::open(&handle.path).await?)).1,
+            };
+            let size = decoded_size(reader, &handle.message).await?;
+            if used + size > budget {
+                result.push(BatchItem::Deferred { handle: handle.clone(), region: self.bbox });
+                continue;
+            }
+
+            let grib = reader.read_indexed_message(&handle.message).await?;
+            let grib = match &self.bbox {
+                Some(bbox) => grib.crop(bbox)?,
+                None => grib,
+            };
+            used += grib.bds.as_ref().map_or(0, |bds| bds.data.len() as u64 * 4);
+            result.push(BatchItem::Decoded(Box::new(grib)));
+        }
+        Ok(result)
+    }
+}
+
+// The bytes the values of a message take when decoded, from the points of its grid. Without a GDS it is bounded by the length of the
+// message, as every point takes at least a bit of either the packed values or the bit-map.
+async fn decoded_size(reader: &mut Grib1Reader, message: &IndexedMessage) -> Result<u64, Grib1Error> {
+    reader.seek(SeekFrom::Start(message.offset)).await?;
+    let points = match reader.read_grib(0, message.offset, message.offset + message.length, &|_: &PDS| true, false).await? {
+        GribResult::Grib(grib) => grib.gds.map_or(message.length * 8, |gds| gds.number_of_points() as u64),
+        GribResult::Length(_) => return Err(Grib1Error::UnsupportedEdition(2)),
+    };
+    Ok(points * 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn query_test() -> Result<(), Grib1Error> {
+        let mut catalog = Catalog::new();
+        let index = Grib1Reader::open("data/sample.grib").await?.grib_index().await?;
+        catalog.add_index("data/sample.grib", index, None);
+
+        assert_eq!(catalog.select().handles()?.len(), 2);
+        assert_eq!(catalog.select().param("ugrd").param("VGRD").level_type(100).levels(&[850, 700]).handles()?.len(), 2);
+        assert!(catalog.select().param("VGRD").steps(2..=48).handles()?.is_empty());
+        assert!(catalog.select().levels(&[850]).handles()?.is_empty());
+        let time = DateTime::new(2023, 10, 2, 15, 0);
+        assert_eq!(catalog.select().reference_times(time..=time).handles()?.len(), 2);
+        assert!(matches!(catalog.select().param("t2m").handles(), Err(Grib1Error::UnknownParameter(_))));
+
+        let region = BoundingBox {
+            north: 58.0,
+            south: 54.0,
+            west: 8.0,
+            east: 13.0,
+        };
+        let fields = catalog.select().param("VGRD").steps(0..=48).bbox(region).run().await?;
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].pds.indicator_of_parameter_and_units, 34);
+        assert!(fields[0].bds.as_ref().unwrap().data.len() < 1271041 / 10);
+
+        // Only one whole field fits, while both cropped ones do as the budget is spent by what is decoded
+        let budget = 1271041 * 4 * 11 / 10;
+        let batch = catalog.select().run_within(budget).await?;
+        assert!(matches!(&batch[..], [BatchItem::Decoded(_), BatchItem::Deferred { .. }]));
+        let deferred = batch.into_iter().nth(1).unwrap().into_grib().await?;
+        assert_eq!(deferred.pds.indicator_of_parameter_and_units, 34);
+        let batch = catalog.select().bbox(region).run_within(budget).await?;
+        assert!(matches!(&batch[..], [BatchItem::Decoded(_), BatchItem::Decoded(_)]));
+        assert!(catalog.select().run_within(0).await?.iter().all(|item| matches!(item, BatchItem::Deferred { .. })));
+
+        // Deferred fields are cropped as the decoded ones are
+        let batch = catalog.select().param("VGRD").bbox(region).run_within(0).await?;
+        let deferred = batch.into_iter().next().unwrap().into_grib().await?;
+        assert_eq!(deferred.bds.unwrap().data.len(), fields[0].bds.as_ref().unwrap().data.len());
+        Ok(())
+    }
+}
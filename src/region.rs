@@ -0,0 +1,200 @@
+//! Cropping decoded messages to a region, producing new messages with an updated GDS that can be encoded again.
+
+use crate::error::Grib1Error;
+use crate::writer;
+use crate::{BoundingBox, DataRepresentation, Grib, RegularLatLon, BDS, GDS};
+use std::ops::RangeInclusive;
+
+impl Grib {
+    /// Crop the message to the smallest block of grid points containing every point within the geographical region.
+    /// The message must have been read including its data.
+    pub fn crop(&self, region: &BoundingBox) -> Result<Grib, Grib1Error> {
+        let grid = self.rotated_grid()?;
+        let latitudes = grid.latitudes();
+        let longitudes = grid.longitudes();
+
+        let mut columns: Option<RangeInclusive<usize>> = None;
+        let mut rows: Option<RangeInclusive<usize>> = None;
+        for (j, lat) in latitudes.iter().enumerate() {
+            for (i, lon) in longitudes.iter().enumerate() {
+                let (lat, lon) = grid.to_geographic(*lat, *lon);
+                if region.contains(lat, lon) {
+                    columns = Some(columns.map(|c| *c.start().min(&i)..=*c.end().max(&i)).unwrap_or(i..=i));
+                    rows = Some(rows.map(|r| *r.start().min(&j)..=*r.end().max(&j)).unwrap_or(j..=j));
+                }
+            }
+        }
+
+        match (columns, rows) {
+            (Some(columns), Some(rows)) => self.subset(columns, rows),
+            _ => Err(Grib1Error::RegionOutsideGrid),
+        }
+    }
+
+    /// Extract a block of grid points given by column (i) and row (j) indices.
+    /// The message must have been read including its data.
+    pub fn subset(&self, columns: RangeInclusive<usize>, rows: RangeInclusive<usize>) -> Result<Grib, Grib1Error> {
+        let gds = self.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        let grid = self.rotated_grid()?;
+
+        let ni = grid.number_of_lat_values as usize;
+        let nj = grid.number_of_lon_values as usize;
+        if columns.is_empty() || rows.is_empty() || *columns.end() >= ni || *rows.end() >= nj {
+            return Err(Grib1Error::RegionOutsideGrid);
+        }
+
+        // Adjacent points are either along a row (the default) or along a column
+        let along_columns = grid.scanning_mode & 0x20 > 0;
+        let mut data = Vec::with_capacity(columns.clone().count() * rows.clone().count());
+        if along_columns {
+            for i in columns.clone() {
+                data.extend_from_slice(&bds.data[i * nj + rows.start()..=i * nj + rows.end()]);
+            }
+        } else {
+            for j in rows.clone() {
+                data.extend_from_slice(&bds.data[j * ni + columns.start()..=j * ni + columns.end()]);
+            }
+        }
+
+        let latitudes = grid.latitudes();
+        let longitudes = grid.longitudes();
+        let mut cropped = grid;
+        cropped.number_of_lat_values = columns.clone().count() as u16;
+        cropped.number_of_lon_values = rows.clone().count() as u16;
+        cropped.latitude_of_first_grid_point = latitudes[*rows.start()];
+        cropped.latitude_of_last_grid_point = latitudes[*rows.end()];
+        cropped.longitude_of_first_grid_point = longitudes[*columns.start()];
+        cropped.longitude_of_last_grid_point = longitudes[*columns.end()];
+        // A regular grid stays regular, so the GDS is written back as type 0
+        let representation = match gds.data {
+            DataRepresentation::RegularLatLon(_) => DataRepresentation::RegularLatLon(RegularLatLon {
+                number_of_lat_values: cropped.number_of_lat_values,
+                number_of_lon_values: cropped.number_of_lon_values,
+                latitude_of_first_grid_point: cropped.latitude_of_first_grid_point,
+                longitude_of_first_grid_point: cropped.longitude_of_first_grid_point,
+                latitude_of_last_grid_point: cropped.latitude_of_last_grid_point,
+                longitude_of_last_grid_point: cropped.longitude_of_last_grid_point,
+                resolution_and_component_flags: cropped.resolution_and_component_flags,
+                i_direction_increment: cropped.i_direction_increment,
+                j_direction_increment: cropped.j_direction_increment,
+                scanning_mode: cropped.scanning_mode,
+            }),
+            _ => DataRepresentation::RotatedLatLon(cropped),
+        };
+
+        Ok(Grib {
+            offset: self.offset,
+            length: 0,
+            pds: self.pds.clone(),
+            gds: Some(GDS { data: representation, ..gds.clone() }),
+            bitmap: None,
+            bds: Some(BDS { data, ..bds.clone() }),
+            digest: None,
+            bulletin: None,
+            sections: None,
+        })
+    }
+
+    fn rotated_grid(&self) -> Result<crate::RotatedLatLon, Grib1Error> {
+        let gds = self.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+        match gds.data {
+            DataRepresentation::RotatedLatLon(grid) => Ok(grid),
+            DataRepresentation::RegularLatLon(grid) => Ok(grid.into()),
+            _ => Err(Grib1Error::UnsupportedGrid(gds.data_representation_type)),
+        }
+    }
+}
+
+/// Crop a decoded message to a region and encode the result as a new message, using the same number of bits per value as the original
+pub fn crop_message(grib: &Grib, region: &BoundingBox) -> Result<Vec<u8>, Grib1Error> {
+    let bits_per_value = grib.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?.bits_per_value;
+    writer::repack(&grib.crop(region)?, bits_per_value)
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn crop_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let grib = &result[0];
+
+        let region = BoundingBox {
+            north: 58.0,
+            south: 54.0,
+            west: 8.0,
+            east: 13.0,
+        };
+        let bytes = crop_message(grib, &region)?;
+        let cropped = Grib::from_bytes(&bytes)?;
+        let grid = cropped.rotated_grid()?;
+        assert!(bytes.len() < grib.length as usize / 10);
+        assert_eq!(cropped.bds.as_ref().unwrap().data.len(), grid.number_of_lat_values as usize * grid.number_of_lon_values as usize);
+        // The vertical coordinates are kept
+        assert_eq!(cropped.gds.as_ref().unwrap().vertical_coordinates, grib.gds.as_ref().unwrap().vertical_coordinates);
+
+        // The corners of the region must be covered by the new grid
+        for (lat, lon) in [(58.0, 8.0), (54.0, 13.0)] {
+            let (rlat, rlon) = grid.from_geographic(lat, lon);
+            assert!(rlat >= grid.latitude_of_first_grid_point - 0.03 && rlat <= grid.latitude_of_last_grid_point + 0.03);
+            assert!(rlon >= grid.longitude_of_first_grid_point - 0.03 && rlon <= grid.longitude_of_last_grid_point + 0.03);
+        }
+
+        let outside = BoundingBox {
+            north: -50.0,
+            south: -60.0,
+            west: 8.0,
+            east: 13.0,
+        };
+        assert!(grib.crop(&outside).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn crop_regular_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+
+        // Rows from 60N to 53N with points from 0E to 9E, each value being its index, with a local extension of the PDS
+        let gds = crate::grid::RegularLatLonBuilder::new(10, 8).first_grid_point(60.0, 0.0).increments(1.0, 1.0).build()?;
+        let data: Vec<f32> = (0..80).map(|i| i as f32).collect();
+        let mut pds = result[0].pds.clone();
+        pds.local_extension = vec![1, 2, 3];
+        let grib = Grib::from_bytes(&writer::encode(&pds, &gds, &data, 8)?)?;
+
+        let region = BoundingBox {
+            north: 58.5,
+            south: 55.5,
+            west: 1.5,
+            east: 4.5,
+        };
+        let cropped = Grib::from_bytes(&crop_message(&grib, &region)?)?;
+        assert_eq!(cropped.pds.local_extension, vec![1, 2, 3]);
+        let gds = cropped.gds.unwrap();
+        assert_eq!(gds.data_representation_type, 0);
+        let DataRepresentation::RegularLatLon(grid) = gds.data else { panic!("expected a regular grid") };
+        assert_eq!((grid.number_of_lat_values, grid.number_of_lon_values), (3, 3));
+        let corners = [
+            (grid.latitude_of_first_grid_point, 58.0),
+            (grid.longitude_of_first_grid_point, 2.0),
+            (grid.latitude_of_last_grid_point, 56.0),
+            (grid.longitude_of_last_grid_point, 4.0),
+        ];
+        assert!(corners.iter().all(|(value, expected)| (value - expected).abs() < 0.001));
+        let expected = [22.0, 23.0, 24.0, 32.0, 33.0, 34.0, 42.0, 43.0, 44.0];
+        let data = cropped.bds.unwrap().data;
+        assert_eq!(data.len(), 9);
+        assert!(data.iter().zip(expected).all(|(value, expected)| (value - expected).abs() < 0.5));
+
+        Ok(())
+    }
+}
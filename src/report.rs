@@ -0,0 +1,50 @@
+//! Statistics on what a scan of a file came across, for batch jobs to log and to notice when a producer changes its output.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// What happened during the last read of a file. Available from `Grib1Reader::scan_report` once the read is done.
+pub struct ScanReport {
+    /// Every message found, including GRIB2 messages passed over and the ones that couldn't be read
+    pub messages_seen: usize,
+    /// Messages accepted by the search or filter
+    pub matched: usize,
+    /// Matched messages on a grid type the library can't decode, so their values were skipped
+    pub skipped_unhandled_grid: usize,
+    /// Messages skipped because of an error when reading leniently
+    pub skipped_errors: usize,
+    /// Octets read from the file, which is less than its size when data sections are skipped
+    pub bytes_read: u64,
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use crate::error::Grib1Error;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn scan_report_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        reader.read(vec![SearchParams { param: 34, level: 700 }]).await?;
+        let report = *reader.scan_report();
+        assert_eq!((report.messages_seen, report.matched, report.skipped_unhandled_grid, report.skipped_errors), (2, 1, 0, 0));
+        // The data section of the matching message is read, but only the headers of the other
+        assert!(report.bytes_read > 2542094 && report.bytes_read < 2 * 2542094);
+
+        reader.inventory().await?;
+        assert_eq!(reader.scan_report().matched, 2);
+        assert!(reader.scan_report().bytes_read < 2000);
+
+        // Damage the first message, which the lenient read skips
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        bytes[8 + 7] |= 0x40;
+        let path = std::env::temp_dir().join("grib1_reader_scan_report_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        reader.read_lenient_where(|_| false).await?;
+        assert_eq!((reader.scan_report().messages_seen, reader.scan_report().matched, reader.scan_report().skipped_errors), (2, 0, 1));
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Sampling decoded messages at geographical positions, e.g. to extract the values at weather stations.
+
+use crate::error::Grib1Error;
+use crate::{DataRepresentation, Grib, RotatedLatLon};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How the value at a position between grid points is found
+pub enum Interpolation {
+    /// The value of the closest grid point
+    #[default]
+    Nearest,
+    /// Weighted by the distance to the four surrounding grid points, in grid coordinates
+    Bilinear,
+}
+
+impl Grib {
+    /// The value at a geographical position, or None if it is outside the grid or the grid points needed are missing.
+    /// The message must have been read including its data.
+    pub fn value_at(&self, lat: f32, lon: f32, interpolation: Interpolation) -> Result<Option<f32>, Grib1Error> {
+        let gds = self.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        let grid: RotatedLatLon = match gds.data {
+            DataRepresentation::RotatedLatLon(grid) => grid,
+            DataRepresentation::RegularLatLon(grid) => grid.into(),
+            _ => return Err(Grib1Error::UnsupportedGrid(gds.data_representation_type)),
+        };
+
+        let ni = grid.number_of_lat_values as usize;
+        let nj = grid.number_of_lon_values as usize;
+        let (lat, mut lon) = grid.from_geographic(lat, lon);
+        let longitudes = grid.longitudes();
+        let first_lon = longitudes.first().copied().unwrap_or_default();
+        while lon < first_lon {
+            lon += 360.0;
+        }
+        while lon >= first_lon + 360.0 {
+            lon -= 360.0;
+        }
+        let (Some(i), Some(j)) = (fraction(lon, &longitudes), fraction(lat, &grid.latitudes())) else {
+            return Ok(None);
+        };
+
+        let value = |i: usize, j: usize| self.get(i, j).filter(|value| !bds.is_missing(*value)).unwrap_or(f32::NAN);
+        let value = match interpolation {
+            Interpolation::Nearest => value(i.round() as usize, j.round() as usize),
+            Interpolation::Bilinear => {
+                let (i0, j0) = ((i.floor() as usize).min(ni.saturating_sub(2)), (j.floor() as usize).min(nj.saturating_sub(2)));
+                let (t, u) = (i - i0 as f32, j - j0 as f32);
+                let (i1, j1) = ((i0 + 1).min(ni - 1), (j0 + 1).min(nj - 1));
+
+                let mut sum = 0.0;
+                for (weight, i, j) in [((1.0 - t) * (1.0 - u), i0, j0), (t * (1.0 - u), i1, j0), ((1.0 - t) * u, i0, j1), (t * u, i1, j1)] {
+                    if weight > 0.0 {
+                        sum += weight * value(i, j);
+                    }
+                }
+                sum
+            }
+        };
+
+        Ok(Some(value).filter(|value| !value.is_nan()))
+    }
+}
+
+// The position of a coordinate as a fractional index into the evenly spaced coordinates of the grid, if it's within them
+fn fraction(value: f32, coordinates: &[f32]) -> Option<f32> {
+    let (first, last) = (*coordinates.first()?, *coordinates.last()?);
+    let last_index = (coordinates.len() - 1) as f32;
+    let index = if coordinates.len() == 1 { 0.0 } else { (value - first) / (last - first) * last_index };
+
+    // Allow for the rounding of the coordinates to millidegrees
+    let tolerance = if coordinates.len() == 1 { 0.0 } else { 1e-3 / ((last - first).abs() / last_index) };
+    if !(index >= -tolerance && index <= last_index + tolerance) || (coordinates.len() == 1 && (value - first).abs() > 1e-3) {
+        return None;
+    }
+    Some(index.clamp(0.0, last_index))
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::grid::RegularLatLonBuilder;
+    use crate::writer;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn value_at_test() -> Result<(), Grib1Error> {
+        let mut reader = crate::Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let source = reader.read_where(|pds| pds.indicator_of_parameter_and_units == 33).await?.remove(0);
+
+        // A 4x3 grid from 60N 350E, with values increasing by 1 along rows and 10 between them
+        let gds = RegularLatLonBuilder::new(4, 3).first_grid_point(60.0, 350.0).increments(5.0, 2.5).build()?;
+        let values = [0.0, 1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 13.0, 20.0, 21.0, 22.0, 23.0];
+        let grib = Grib::from_bytes(&writer::encode(&source.pds, &gds, &values, 16)?)?;
+
+        assert_eq!(grib.value_at(57.5, 5.0, Interpolation::Nearest)?, Some(13.0));
+        assert_eq!(grib.value_at(57.5, -5.0, Interpolation::Nearest)?, Some(11.0));
+        let value = grib.value_at(58.75, 357.5, Interpolation::Bilinear)?.unwrap();
+        assert!((value - 6.5).abs() < 1e-3, "{}", value);
+        assert_eq!(grib.value_at(50.0, 0.0, Interpolation::Nearest)?, None);
+        assert_eq!(grib.value_at(60.0, 20.0, Interpolation::Bilinear)?, None);
+
+        // On the rotated grid of the sample the value at a grid point is found again
+        let grid = match source.gds.as_ref().unwrap().data {
+            DataRepresentation::RotatedLatLon(grid) => grid,
+            _ => unreachable!(),
+        };
+        let (lat, lon) = grid.to_geographic(grid.latitudes()[100], grid.longitudes()[200]);
+        let expected = source.bds.as_ref().unwrap().data[100 * 1189 + 200];
+        assert_eq!(source.value_at(lat, lon, Interpolation::Nearest)?, Some(expected));
+        Ok(())
+    }
+}
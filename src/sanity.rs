@@ -0,0 +1,61 @@
+//! Cheap checks for obviously broken fields, so ingest pipelines can quarantine them without looking at the values.
+
+use crate::{Grib, BDS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Signs that a decoded field carries no information
+pub struct SanityFlags {
+    /// Every point is missing, e.g. because the bit-map has no bits set
+    pub all_missing: bool,
+    /// Every point present has the same value, e.g. because the values were packed with 0 bits
+    pub constant: bool,
+}
+
+impl SanityFlags {
+    pub fn is_suspect(&self) -> bool {
+        self.all_missing || self.constant
+    }
+}
+
+impl BDS {
+    pub fn sanity_flags(&self) -> SanityFlags {
+        let mut present = self.data.iter().filter(|v| !self.is_missing(**v));
+        let Some(first) = present.next() else {
+            return SanityFlags { all_missing: true, constant: false };
+        };
+
+        SanityFlags {
+            all_missing: false,
+            constant: self.bits_per_value == 0 || present.all(|v| v == first),
+        }
+    }
+}
+
+impl Grib {
+    /// The sanity flags of the decoded data, or None if the data wasn't decoded
+    pub fn sanity_flags(&self) -> Option<SanityFlags> {
+        self.bds.as_ref().map(|bds| bds.sanity_flags())
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+
+    #[tokio::test]
+    async fn sanity_test() -> Result<(), Grib1Error> {
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let grib = Grib::from_bytes(&sample[..2542704])?;
+        assert_eq!(grib.sanity_flags(), Some(SanityFlags::default()));
+        assert!(!grib.sanity_flags().unwrap().is_suspect());
+
+        let flags = grib.scale(0.0, 273.15)?.sanity_flags().unwrap();
+        assert!(flags.constant && !flags.all_missing);
+
+        let flags = grib.scale(f32::NAN, 0.0)?.sanity_flags().unwrap();
+        assert!(flags.all_missing && flags.is_suspect());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+//! The raw octets of each section of a message, for inspecting fields the parsed structs don't cover, e.g. the local extension of
+//! the PDS or the vertical coordinates of the GDS. Nothing but the section lengths and the flags for the optional sections is parsed.
+
+use crate::codec::read_u24_be;
+use crate::error::Grib1Error;
+use crate::{bds_offset, edition0, message_length, resolve_lengths, Grib};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A section of a message
+pub struct Section<'a> {
+    /// The section number: 0 for the indicator section, 1 PDS, 2 GDS, 3 BMS, 4 BDS and 5 for the end section
+    pub id: u8,
+    /// Position of the first octet within the message
+    pub offset: usize,
+    pub len: usize,
+    pub bytes: &'a [u8],
+}
+
+impl<'a> Section<'a> {
+    /// The octet numbered as in the WMO documentation, starting from 1, or None if the section is shorter
+    pub fn octet(&self, number: usize) -> Option<u8> {
+        self.bytes.get(number.checked_sub(1)?).copied()
+    }
+
+    /// The octets from `first` to `last`, both included and numbered from 1, or None if the section is shorter
+    pub fn octets(&self, first: usize, last: usize) -> Option<&'a [u8]> {
+        self.bytes.get(first.checked_sub(1)?..last)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.id {
+            0 => "Indicator section",
+            1 => "Product definition section",
+            2 => "Grid description section",
+            3 => "Bit-map section",
+            4 => "Binary data section",
+            _ => "End section",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where the sections of a message are within the file, e.g. to read, patch or dump one of them later
+pub struct SectionRanges {
+    pub indicator: Range<u64>,
+    pub pds: Range<u64>,
+    pub gds: Option<Range<u64>>,
+    pub bms: Option<Range<u64>>,
+    pub bds: Range<u64>,
+    pub end: Range<u64>,
+}
+
+impl SectionRanges {
+    // The ranges of consecutive sections of the lengths, from the start of the message
+    pub(crate) fn of_lengths(indicator: u64, pds: u64, gds: Option<u64>, bms: Option<u64>, bds: u64) -> SectionRanges {
+        let mut offset = 0;
+        let mut next = |length: u64| {
+            offset += length;
+            offset - length..offset
+        };
+        SectionRanges {
+            indicator: next(indicator),
+            pds: next(pds),
+            gds: gds.map(&mut next),
+            bms: bms.map(&mut next),
+            bds: next(bds),
+            end: next(4),
+        }
+    }
+
+    fn shifted(&self, by: u64) -> SectionRanges {
+        let shift = |range: &Range<u64>| range.start + by..range.end + by;
+        SectionRanges {
+            indicator: shift(&self.indicator),
+            pds: shift(&self.pds),
+            gds: self.gds.as_ref().map(shift),
+            bms: self.bms.as_ref().map(shift),
+            bds: shift(&self.bds),
+            end: shift(&self.end),
+        }
+    }
+}
+
+impl Grib {
+    /// The byte ranges of the sections within the file, or None for messages that weren't read from one, e.g. those of `Grib::crop`
+    /// or built from an index. Messages decoded by `Grib::from_bytes` are at offset 0 unless their offset is set.
+    pub fn section_ranges(&self) -> Option<SectionRanges> {
+        self.sections.as_ref().map(|sections| sections.shifted(self.offset))
+    }
+}
+
+/// Split a single raw message, e.g. as returned by `read_binary`, into its sections. The optional sections are included if the
+/// flags of the PDS say they are present. The length of the BDS of messages longer than 0x7fffff octets is resolved as when decoding.
+pub fn sections(message: &[u8]) -> Result<Vec<Section<'_>>, Grib1Error> {
+    if message.len() < 8 || message[0..4] != *b"GRIB" {
+        return Err(Grib1Error::WrongHeader);
+    }
+    let edition0 = edition0::is_edition0(message);
+    let incomplete = || Grib1Error::MalformedMessage("The sections before the BDS are incomplete".to_string());
+    let length = message_length(message).ok_or_else(incomplete)?;
+    if length > message.len() {
+        return Err(Grib1Error::Truncated {
+            offset: 0,
+            missing_bytes: (length - message.len()) as u64,
+        });
+    }
+
+    // The sections before the BDS lie within the message if the BDS does
+    let bds_start = bds_offset(message).filter(|offset| offset + 3 <= length).ok_or_else(incomplete)?;
+
+    let mut result = vec![];
+    let mut push = |id, offset: usize, len: usize| {
+        result.push(Section {
+            id,
+            offset,
+            len,
+            bytes: &message[offset..offset + len],
+        })
+    };
+
+    let start = if edition0 { 4 } else { 8 };
+    push(0, 0, start);
+    let flags = message[start + 7];
+    let mut offset = start;
+    for (id, present) in [(1, true), (2, flags & 0x80 > 0), (3, flags & 0x40 > 0)] {
+        if present {
+            let len = read_u24_be(&message[offset..]) as usize;
+            push(id, offset, len);
+            offset += len;
+        }
+    }
+
+    let bds_length = match read_u24_be(&message[bds_start..]) as usize {
+        bds_length if edition0 => bds_length,
+        bds_length => resolve_lengths(read_u24_be(&message[4..]) as usize, bds_start, bds_length).1,
+    };
+    if bds_start + bds_length + 4 != length {
+        return Err(Grib1Error::LengthMismatch {
+            offset: 0,
+            expected: length as u64,
+            actual: (bds_start + bds_length + 4) as u64,
+        });
+    }
+    push(4, bds_start, bds_length);
+    push(5, length - 4, 4);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::split_messages;
+
+    #[test]
+    fn sections_test() -> Result<(), Grib1Error> {
+        let bytes = std::fs::read("data/sample.grib")?;
+        let message = split_messages(&bytes)?[1];
+        let sections = sections(message)?;
+
+        let ids: Vec<u8> = sections.iter().map(|section| section.id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 4, 5]);
+        assert!(sections.windows(2).all(|pair| pair[0].offset + pair[0].len == pair[1].offset));
+        assert_eq!(sections[4].offset + 4, message.len());
+        assert_eq!(sections[4].bytes, b"7777");
+
+        // Octet 9 of the PDS is the parameter, octets 1 to 3 of every section its length
+        assert_eq!(sections[1].octet(9), Some(34));
+        assert_eq!(sections[2].octets(1, 3).map(read_u24_be), Some(sections[2].len as u32));
+        assert_eq!((sections[1].octet(0), sections[1].octet(sections[1].len + 1)), (None, None));
+
+        assert!(matches!(super::sections(&message[..1000]), Err(Grib1Error::Truncated { .. })));
+        let mut broken = message.to_vec();
+        broken[8..11].copy_from_slice(&[0xff; 3]);
+        assert!(matches!(super::sections(&broken), Err(Grib1Error::MalformedMessage(_))));
+
+        // The ranges of a message found in a file are those of its sections shifted by its offset
+        let grib = crate::sync::read_file("data/sample.grib")?.remove(1);
+        let ranges = grib.section_ranges().unwrap();
+        let range = |section: &Section| 2542704 + section.offset as u64..2542704 + (section.offset + section.len) as u64;
+        assert_eq!((ranges.indicator, ranges.pds, ranges.gds), (range(&sections[0]), range(&sections[1]), Some(range(&sections[2]))));
+        assert_eq!((ranges.bms, ranges.bds, ranges.end), (None, range(&sections[3]), range(&sections[4])));
+        assert_eq!(Grib::from_bytes(message)?.section_ranges().unwrap().end, message.len() as u64 - 4..message.len() as u64);
+        assert_eq!(grib.scale(1.0, 0.0)?.section_ranges(), None);
+        Ok(())
+    }
+}
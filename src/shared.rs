@@ -0,0 +1,117 @@
+//! A reader handle that can be cloned and used from several tasks at once, e.g. by a server decoding messages for concurrent requests.
+//! Each read borrows an open file from a pool, so reads never wait for each other to seek.
+
+use crate::error::Grib1Error;
+use crate::options::Grib1ReaderOptions;
+use crate::{Grib, Grib1Reader, GribResult, PDS};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+impl Grib1Reader {
+    /// Read and decode the message starting at the offset, e.g. one found earlier by an inventory or an index.
+    /// Errors give the message index as 0, as the position of the message in the file isn't known.
+    pub async fn read_message_at(&mut self, offset: u64) -> Result<Grib, Grib1Error> {
+        let length = self.seek(SeekFrom::End(0)).await?;
+        self.seek(SeekFrom::Start(offset)).await?;
+        match self.read_grib(0, offset, length, &|_: &PDS| true, true).await? {
+            GribResult::Grib(grib) => Ok(*grib),
+            // A GRIB2 message passed over with `skip_grib2`
+            GribResult::Length(_) => Err(Grib1Error::UnsupportedEdition(2)),
+        }
+    }
+}
+
+#[derive(Clone)]
+/// A handle to a file that can be cloned, sent to other tasks and read through concurrently. Every clone shares the same pool of open files,
+/// which grows to the number of reads made at the same time.
+pub struct SharedGrib1Reader {
+    path: Arc<PathBuf>,
+    options: Grib1ReaderOptions,
+    idle: Arc<Mutex<Vec<Grib1Reader>>>,
+}
+
+impl SharedGrib1Reader {
+    /// Open a file for shared reading
+    pub async fn open(path: impl AsRef<Path>) -> Result<SharedGrib1Reader, Grib1Error> {
+        SharedGrib1Reader::open_with(path, Grib1ReaderOptions::default()).await
+    }
+
+    /// Open a file for shared reading, every read being configured by the options
+    pub async fn open_with(path: impl AsRef<Path>, options: Grib1ReaderOptions) -> Result<SharedGrib1Reader, Grib1Error> {
+        // Open the first file straight away, so a missing file is an error here rather than on the first read
+        let reader = Grib1Reader::open_with(&path, options.clone()).await?;
+        Ok(SharedGrib1Reader {
+            path: Arc::new(path.as_ref().to_path_buf()),
+            options,
+            idle: Arc::new(Mutex::new(vec![reader])),
+        })
+    }
+
+    /// Read and decode the message starting at the offset
+    pub async fn read_message_at(&self, offset: u64) -> Result<Grib, Grib1Error> {
+        let mut reader = self.checkout().await?;
+        let result = reader.read_message_at(offset).await;
+        self.checkin(reader);
+        result
+    }
+
+    /// Read the raw bytes of a message previously returned by a reader of the file
+    pub async fn read_raw(&self, grib: &Grib) -> Result<Vec<u8>, Grib1Error> {
+        let mut reader = self.checkout().await?;
+        let result = reader.read_raw(grib).await;
+        self.checkin(reader);
+        result
+    }
+
+    /// Number of files currently open and not being read from
+    pub fn idle_handles(&self) -> usize {
+        self.idle.lock().map_or(0, |idle| idle.len())
+    }
+
+    async fn checkout(&self) -> Result<Grib1Reader, Grib1Error> {
+        let idle = self.idle.lock().ok().and_then(|mut idle| idle.pop());
+        match idle {
+            Some(reader) => Ok(reader),
+            None => Grib1Reader::open_with(self.path.as_ref(), self.options.clone()).await,
+        }
+    }
+
+    fn checkin(&self, mut reader: Grib1Reader) {
+        // Warnings have nowhere to go, and would otherwise pile up in the pooled readers
+        reader.take_warnings();
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(reader);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shared_reader_test() -> Result<(), Grib1Error> {
+        let reader = SharedGrib1Reader::open("data/sample.grib").await?;
+
+        let tasks: Vec<_> = [2542704, 0, 2542704]
+            .into_iter()
+            .map(|offset| {
+                let reader = reader.clone();
+                tokio::spawn(async move { reader.read_message_at(offset).await })
+            })
+            .collect();
+        let mut parameters = vec![];
+        for task in tasks {
+            let grib = task.await.unwrap()?;
+            assert_eq!(grib.values().len(), 1271041);
+            parameters.push(grib.pds.indicator_of_parameter_and_units);
+        }
+        assert_eq!(parameters, vec![34, 33, 34]);
+        assert!(reader.idle_handles() >= 1);
+
+        assert!(reader.read_message_at(100).await.is_err());
+        assert!(SharedGrib1Reader::open("data/missing.grib").await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! Splitting a multi-message file into one file per message, named from the metadata of each message, and extracting the messages
+//! matching a search into a single file.
+
+use crate::error::Grib1Error;
+use crate::Grib;
+#[cfg(feature = "async-tokio")]
+use crate::{Grib1Reader, SearchParams, PDS};
+#[cfg(feature = "async-tokio")]
+use std::collections::HashSet;
+#[cfg(feature = "async-tokio")]
+use std::io::SeekFrom;
+#[cfg(feature = "async-tokio")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "async-tokio")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+// Size of the blocks messages are copied in by `extract_to_file`
+#[cfg(feature = "async-tokio")]
+const COPY_BLOCK: usize = 1 << 20;
+
+/// Expand a naming template for a message. The following placeholders are supported:
+///
+/// * `{index}` - position of the message in the file, starting at 0
+/// * `{offset}` - byte offset of the message in the file
+/// * `{param}` - parameter indicator
+/// * `{name}` - parameter abbreviation, or the indicator if it is unknown
+/// * `{level_type}` - type of level
+/// * `{level}` - level value
+/// * `{step}` - forecast step
+/// * `{reftime}` - reference time as YYYYMMDDHHMM
+/// * `{validtime}` - valid time as YYYYMMDDHHMM
+/// * `{center}` - originating center
+pub fn message_name(template: &str, index: usize, grib: &Grib) -> Result<String, Grib1Error> {
+    let mut result = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| Grib1Error::InvalidTemplate(format!("Unclosed placeholder in {}", template)))? + start;
+
+        let pds = &grib.pds;
+        let value = match &rest[start + 1..end] {
+            "index" => index.to_string(),
+            "offset" => grib.offset.to_string(),
+            "param" => pds.indicator_of_parameter_and_units.to_string(),
+            "name" => pds.parameter().map(|p| p.abbreviation.to_string()).unwrap_or_else(|| pds.indicator_of_parameter_and_units.to_string()),
+            "level_type" => pds.indicator_of_type_of_level_or_layer.to_string(),
+            "level" => pds.level_or_layer_value.to_string(),
+            "step" => pds.step().to_string(),
+            "reftime" => compact_time(Some(pds.reference_time())),
+            "validtime" => compact_time(pds.valid_time()),
+            "center" => pds.identification_of_center.to_string(),
+            other => return Err(Grib1Error::InvalidTemplate(format!("Unknown placeholder {{{}}}", other))),
+        };
+
+        result.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+fn compact_time(time: Option<crate::time::DateTime>) -> String {
+    match time {
+        Some(t) => format!("{:04}{:02}{:02}{:02}{:02}", t.year, t.month, t.day, t.hour, t.minute),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl Grib1Reader {
+    /// Write every message of the file to its own file in `dir`, named using `naming_template` (see `message_name`), e.g. `{param}_{level}_{validtime}.grb`.
+    /// Fails before writing anything if the template gives several messages the same name. Returns the paths of the written files.
+    pub async fn split_to_dir(&mut self, dir: impl AsRef<Path>, naming_template: &str) -> Result<Vec<PathBuf>, Grib1Error> {
+        self.split_to_dir_where(dir, naming_template, |_| true).await
+    }
+
+    /// Like `split_to_dir`, but only the messages whose PDS passes the filter are written. `{index}` is still the position in the whole file.
+    pub async fn split_to_dir_where<F: Fn(&PDS) -> bool>(&mut self, dir: impl AsRef<Path>, naming_template: &str, filter: F) -> Result<Vec<PathBuf>, Grib1Error> {
+        let messages: Vec<_> = self.inventory().await?.into_iter().enumerate().filter(|(_, grib)| filter(&grib.pds)).collect();
+
+        let mut paths = vec![];
+        let mut seen = HashSet::new();
+        for (index, grib) in &messages {
+            let index = *index;
+            let path = dir.as_ref().join(message_name(naming_template, index, grib)?);
+            if !seen.insert(path.clone()) {
+                return Err(Grib1Error::InvalidTemplate(format!("{} is used for more than one message, consider adding {{index}}", path.display())));
+            }
+            paths.push(path);
+        }
+
+        for ((_, grib), path) in messages.iter().zip(&paths) {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            let bytes = self.read_raw(grib).await?;
+            tokio::fs::write(path, bytes).await?;
+        }
+
+        Ok(paths)
+    }
+
+    /// Write the messages matching any of the search parameters to a new file, e.g. `reader.extract_to_file(search, "subset.grb")`,
+    /// returning how many were written. The messages are copied a block at a time, so only a block is held in memory.
+    pub async fn extract_to_file(&mut self, search: Vec<SearchParams>, path: impl AsRef<Path>) -> Result<usize, Grib1Error> {
+        self.extract_to_file_where(|pds| search.iter().any(|item| item.matches(pds)), path).await
+    }
+
+    /// Like `extract_to_file`, but the messages whose PDS passes the filter are written
+    pub async fn extract_to_file_where<F: Fn(&PDS) -> bool>(&mut self, filter: F, path: impl AsRef<Path>) -> Result<usize, Grib1Error> {
+        let messages: Vec<_> = self.inventory().await?.into_iter().filter(|grib| filter(&grib.pds)).collect();
+
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut block = vec![0; COPY_BLOCK];
+        for grib in &messages {
+            self.seek(SeekFrom::Start(grib.offset)).await?;
+            let mut remaining = grib.length as usize;
+            while remaining > 0 {
+                let count = remaining.min(block.len());
+                self.reader.read_exact(&mut block[..count]).await?;
+                file.write_all(&block[..count]).await?;
+                remaining -= count;
+            }
+            self.count_read(grib.length);
+        }
+        file.flush().await?;
+
+        Ok(messages.len())
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn split_test() -> Result<(), Grib1Error> {
+        let dir = std::env::temp_dir().join("grib1_reader_split_test");
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+
+        let paths = reader.split_to_dir(&dir, "{name}_{level}_{validtime}.grb").await?;
+        // The parameters of the local table of the sample are named by their indicators
+        assert_eq!(paths, vec![dir.join("33_700_202310021600.grb"), dir.join("34_700_202310021600.grb")]);
+        assert_eq!(tokio::fs::metadata(&paths[0]).await?.len(), 2542704);
+
+        assert!(reader.split_to_dir(&dir, "{level}.grb").await.is_err());
+        let paths = reader.split_to_dir_where(&dir, "{index}.grb", |pds| pds.indicator_of_parameter_and_units == 34).await?;
+        assert_eq!(paths, vec![dir.join("1.grb")]);
+        assert!(reader.split_to_dir(&dir, "{unknown}.grb").await.is_err());
+
+        let sample = tokio::fs::read("data/sample.grib").await?;
+        let subset = dir.join("subset.grb");
+        assert_eq!(reader.extract_to_file(vec![SearchParams { param: 34, level: 700 }], &subset).await?, 1);
+        assert_eq!(tokio::fs::read(&subset).await?, sample[2542704..]);
+        assert_eq!(reader.extract_to_file_where(|_| true, &subset).await?, 2);
+        assert_eq!(tokio::fs::read(&subset).await?, sample);
+        assert_eq!(reader.extract_to_file(vec![SearchParams { param: 11, level: 2 }], &subset).await?, 0);
+        assert!(tokio::fs::read(&subset).await?.is_empty());
+
+        tokio::fs::remove_dir_all(&dir).await?;
+        Ok(())
+    }
+}
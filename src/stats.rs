@@ -0,0 +1,370 @@
+//! Statistics of the values of a field, as wanted for quality control of every field read, and their distribution:
+//! histograms and streamed quantile estimates, e.g. for choosing colour scales or finding outliers.
+
+use crate::error::Grib1Error;
+use crate::Grib;
+#[cfg(feature = "async-tokio")]
+use crate::{Grib1Reader, PDS};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Minimum, maximum, mean and standard deviation of the values present, and the number missing. Accumulated in a single pass using
+/// Welford's method, so values can be added one at a time without being kept.
+pub struct Statistics {
+    /// Number of values present
+    pub count: usize,
+    pub missing: usize,
+    /// NaN if no value is present
+    pub min: f32,
+    /// NaN if no value is present
+    pub max: f32,
+    /// NaN if no value is present
+    pub mean: f64,
+    sum_of_squares: f64,
+}
+
+impl Default for Statistics {
+    fn default() -> Statistics {
+        Statistics::new()
+    }
+}
+
+impl Statistics {
+    pub fn new() -> Statistics {
+        Statistics {
+            count: 0,
+            missing: 0,
+            min: f32::NAN,
+            max: f32::NAN,
+            mean: f64::NAN,
+            sum_of_squares: 0.0,
+        }
+    }
+
+    /// The statistics of the values, with NaN as missing
+    pub fn of(values: &[f32]) -> Statistics {
+        let mut result = Statistics::new();
+        values.iter().for_each(|value| result.push(*value));
+        result
+    }
+
+    /// Add a value, NaN being a missing one
+    pub fn push(&mut self, value: f32) {
+        if value.is_nan() {
+            self.missing += 1;
+            return;
+        }
+
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.count == 1 {
+            self.mean = 0.0;
+        }
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.count as f64;
+        self.sum_of_squares += delta * (value as f64 - self.mean);
+    }
+
+    /// Count a missing value given as something other than NaN, e.g. a sentinel
+    pub fn push_missing(&mut self) {
+        self.missing += 1;
+    }
+
+    /// The population standard deviation, NaN if no value is present
+    pub fn standard_deviation(&self) -> f64 {
+        (self.sum_of_squares / self.count as f64).sqrt()
+    }
+}
+
+impl Grib {
+    /// The statistics of the decoded values, counting the points that are missing whatever they are given as. Fails if the data isn't decoded.
+    pub fn stats(&self) -> Result<Statistics, Grib1Error> {
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+
+        let mut result = Statistics::new();
+        for value in &bds.data {
+            match bds.is_missing(*value) {
+                true => result.push_missing(),
+                false => result.push(*value),
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Counts of the values falling in equally wide bins between `min` and `max`, both included
+pub struct Histogram {
+    pub min: f32,
+    pub max: f32,
+    pub counts: Vec<usize>,
+    /// Values below `min`
+    pub below: usize,
+    /// Values above `max`
+    pub above: usize,
+    pub missing: usize,
+}
+
+impl Histogram {
+    /// An empty histogram of `bins` bins, at least one, covering `min` to `max`
+    pub fn new(min: f32, max: f32, bins: usize) -> Histogram {
+        Histogram {
+            min,
+            max,
+            counts: vec![0; bins.max(1)],
+            below: 0,
+            above: 0,
+            missing: 0,
+        }
+    }
+
+    /// The histogram of the values over their whole range, with NaN as missing
+    pub fn of(values: &[f32], bins: usize) -> Histogram {
+        let stats = Statistics::of(values);
+        let mut result = Histogram::new(stats.min, stats.max, bins);
+        values.iter().for_each(|value| result.push(*value));
+        result
+    }
+
+    /// Add a value, NaN being a missing one
+    pub fn push(&mut self, value: f32) {
+        if value.is_nan() {
+            self.missing += 1;
+        } else if value < self.min {
+            self.below += 1;
+        } else if value > self.max {
+            self.above += 1;
+        } else {
+            // The maximum goes in the last bin, as do all values if the range is empty
+            let bins = self.counts.len();
+            let bin = if self.max > self.min {
+                ((value - self.min) as f64 / (self.max - self.min) as f64 * bins as f64) as usize
+            } else {
+                bins - 1
+            };
+            self.counts[bin.min(bins - 1)] += 1;
+        }
+    }
+
+    /// The edges of the bins, one more than there are bins
+    pub fn edges(&self) -> Vec<f32> {
+        let bins = self.counts.len();
+        (0..=bins).map(|i| self.min + (self.max - self.min) * i as f32 / bins as f32).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An estimate of a quantile of streamed values using the P² algorithm of Jain and Chlamtac, keeping five markers instead of the values
+pub struct P2Quantile {
+    p: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    /// Estimate the quantile `p`, between 0 and 1, e.g. 0.5 for the median
+    pub fn new(p: f64) -> P2Quantile {
+        let p = p.clamp(0.0, 1.0);
+        P2Quantile {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    /// Add a value. NaN values are left out.
+    pub fn push(&mut self, value: f32) {
+        if value.is_nan() {
+            return;
+        }
+        let value = value as f64;
+
+        // The first five values are the initial markers
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            self.heights[..self.count].sort_by(f64::total_cmp);
+            return;
+        }
+        self.count += 1;
+
+        let q = &mut self.heights;
+        let cell = if value < q[0] {
+            q[0] = value;
+            0
+        } else if value > q[4] {
+            q[4] = value;
+            3
+        } else {
+            (1..4).find(|i| value < q[*i]).map_or(3, |i| i - 1)
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        // Move the middle markers towards their desired positions, adjusting their heights
+        let n = &mut self.positions;
+        for i in 1..4 {
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = q[i] + d / (n[i + 1] - n[i - 1]) * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i]) + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+                q[i] = if q[i - 1] < parabolic && parabolic < q[i + 1] {
+                    parabolic
+                } else {
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        }
+    }
+
+    /// The estimate, exact while fewer than six values have been added, or None before any
+    pub fn estimate(&self) -> Option<f32> {
+        match self.count {
+            0 => None,
+            1..=5 => Some(self.heights[((self.count - 1) as f64 * self.p).round() as usize] as f32),
+            _ => Some(self.heights[2] as f32),
+        }
+    }
+}
+
+impl Grib {
+    /// The histogram of the decoded values over their whole range, counting the points that are missing whatever they are given as
+    pub fn histogram(&self, bins: usize) -> Result<Histogram, Grib1Error> {
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        let stats = self.stats()?;
+
+        let mut result = Histogram::new(stats.min, stats.max, bins);
+        for value in &bds.data {
+            match bds.is_missing(*value) {
+                true => result.missing += 1,
+                false => result.push(*value),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Estimates of the quantiles, e.g. `[0.05, 0.95]`, of the decoded values present, NaN if there are none
+    pub fn quantiles(&self, quantiles: &[f64]) -> Result<Vec<f32>, Grib1Error> {
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+
+        let mut estimators: Vec<_> = quantiles.iter().map(|p| P2Quantile::new(*p)).collect();
+        for value in bds.data.iter().filter(|value| !bds.is_missing(**value)) {
+            estimators.iter_mut().for_each(|estimator| estimator.push(*value));
+        }
+        Ok(estimators.iter().map(|estimator| estimator.estimate().unwrap_or(f32::NAN)).collect())
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl Grib1Reader {
+    /// Like `read_where`, but the statistics of each message are computed as it is decoded and its values are then dropped,
+    /// so no more than one field is held at a time. The messages are returned with an empty `BDS::data`.
+    pub async fn read_stats_where<F: Fn(&PDS) -> bool>(&mut self, filter: F) -> Result<Vec<(Grib, Statistics)>, Grib1Error> {
+        let mut result = vec![];
+        let mut messages = self.messages().await?;
+        while let Some(message) = messages.next().await? {
+            if !filter(&message.pds) {
+                continue;
+            }
+            let mut grib = message.decode().await?;
+            let stats = grib.stats()?;
+            if let Some(bds) = grib.bds.as_mut() {
+                bds.data = vec![];
+            }
+            result.push((grib, stats));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_test() {
+        let stats = Statistics::of(&[1.0, f32::NAN, 3.0, 5.0]);
+        assert_eq!((stats.count, stats.missing, stats.min, stats.max, stats.mean), (3, 1, 1.0, 5.0, 3.0));
+        assert!((stats.standard_deviation() - (8.0f64 / 3.0).sqrt()).abs() < 1e-12);
+
+        let stats = Statistics::of(&[f32::NAN]);
+        assert!(stats.mean.is_nan() && stats.min.is_nan() && stats.standard_deviation().is_nan());
+        assert_eq!(stats.missing, 1);
+    }
+
+    #[test]
+    fn distribution_test() {
+        let values: Vec<f32> = (0..10007).map(|i| ((i * 7919) % 10007) as f32).chain([f32::NAN]).collect();
+        let histogram = Histogram::of(&values, 10);
+        assert_eq!((histogram.min, histogram.max, histogram.missing), (0.0, 10006.0, 1));
+        assert_eq!(histogram.counts.iter().sum::<usize>(), 10007);
+        assert!(histogram.counts.iter().all(|count| (1000..=1001).contains(count)), "{:?}", histogram.counts);
+        assert_eq!(histogram.edges().len(), 11);
+
+        let mut histogram = Histogram::new(0.0, 1.0, 2);
+        [-1.0, 0.0, 0.5, 1.0, 2.0, 2.0].into_iter().for_each(|value| histogram.push(value));
+        assert_eq!((histogram.counts.as_slice(), histogram.below, histogram.above), (&[1, 2][..], 1, 2));
+
+        // A constant field has an empty range, so every value goes in the last bin
+        let histogram = Histogram::of(&[4.0, 4.0, f32::NAN, 4.0], 3);
+        assert_eq!((histogram.counts.as_slice(), histogram.missing), (&[0, 0, 3][..], 1));
+
+        for p in [0.05, 0.5, 0.95] {
+            let mut quantile = P2Quantile::new(p);
+            values.iter().for_each(|value| quantile.push(*value));
+            let estimate = quantile.estimate().unwrap();
+            assert!((estimate - p as f32 * 10006.0).abs() < 50.0, "{} {}", p, estimate);
+        }
+
+        let mut quantile = P2Quantile::new(0.5);
+        assert_eq!(quantile.estimate(), None);
+        [3.0, 1.0, 2.0].into_iter().for_each(|value| quantile.push(value));
+        assert_eq!(quantile.estimate(), Some(2.0));
+    }
+
+    #[cfg(feature = "async-tokio")]
+    #[tokio::test]
+    async fn read_stats_test() -> Result<(), Grib1Error> {
+        use crate::missing::MissingValues;
+
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+        let mut decoded = reader.read_where(|pds| pds.indicator_of_parameter_and_units == 34).await?.remove(0);
+        let result = reader.read_stats_where(|pds| pds.indicator_of_parameter_and_units == 34).await?;
+        assert_eq!(result.len(), 1);
+        assert!(result[0].0.bds.as_ref().unwrap().data.is_empty());
+        assert_eq!(result[0].1, decoded.stats()?);
+        assert_eq!(result[0].1.count, 1271041);
+
+        let mut sorted = decoded.bds.as_ref().unwrap().data.clone();
+        sorted.sort_by(f32::total_cmp);
+        let quantiles = decoded.quantiles(&[0.1, 0.5, 0.9])?;
+        for (p, estimate) in [0.1, 0.5, 0.9].into_iter().zip(quantiles) {
+            // The values are ordered by the grid, so the estimate is judged by its rank
+            let rank = sorted.partition_point(|value| *value < estimate) as f64 / sorted.len() as f64;
+            assert!((rank - p).abs() < 0.03, "{} {} {}", p, estimate, rank);
+        }
+        let histogram = decoded.histogram(20)?;
+        assert_eq!(histogram.counts.iter().sum::<usize>(), 1271041);
+
+        // Sentinels are counted as missing
+        let bds = decoded.bds.as_mut().unwrap();
+        bds.data[0] = f32::NAN;
+        bds.set_missing_values(MissingValues::Sentinel(9999.0));
+        let stats = decoded.stats()?;
+        assert_eq!((stats.count, stats.missing), (1271040, 1));
+        assert!(stats.max < 9999.0);
+        Ok(())
+    }
+}
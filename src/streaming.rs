@@ -0,0 +1,206 @@
+//! Encoding of very large fields a row at a time, so the values never have to be held in memory all at once.
+//! As simple packing needs the range of the values before the first one is packed, it's either given up front or found by a first pass.
+
+use crate::codec::{write_i16_be, write_u24_be};
+use crate::error::Grib1Error;
+use crate::writer::{encode_gds, encode_pds, length_fields, packing_parameters_for_range};
+use crate::{GDS, PDS};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Writes a single message with simple packing, packing the values as they are supplied
+pub struct StreamingEncoder<W> {
+    writer: W,
+    bits_per_value: u8,
+    decimal_factor: f64,
+    reference: f64,
+    factor: f64,
+    range: (f32, f32),
+    expected: usize,
+    written: usize,
+    // Bits not yet making up a whole octet
+    pending: u64,
+    pending_bits: u32,
+    // Octets following the packed values
+    padding: usize,
+}
+
+impl<W: AsyncWrite + Unpin> StreamingEncoder<W> {
+    /// Write everything up to the packed values. All values must be finite and within `range`, given as (min, max) before decimal scaling.
+    pub async fn start(mut writer: W, pds: &PDS, gds: &GDS, bits_per_value: u8, range: (f32, f32)) -> Result<StreamingEncoder<W>, Grib1Error> {
+        if bits_per_value == 0 || bits_per_value > 32 {
+            return Err(Grib1Error::UnsupportedBitsPerValue(bits_per_value));
+        }
+        if !range.0.is_finite() || !range.1.is_finite() || range.0 > range.1 {
+            return Err(Grib1Error::InvalidRange(range.0, range.1));
+        }
+
+        let pds_bytes = encode_pds(pds, true, false);
+        let gds_bytes = encode_gds(gds)?;
+        let expected = gds.number_of_points();
+
+        // Sections have an even number of octets
+        let packed_length = (expected * bits_per_value as usize).div_ceil(8);
+        let bds_length = 11 + packed_length + (11 + packed_length) % 2;
+        let unused_bits = ((bds_length - 11) * 8 - expected * bits_per_value as usize) as u8;
+        let total_length = 8 + pds_bytes.len() + gds_bytes.len() + bds_length + 4;
+        let (length_field, bds_length_field, padding) = length_fields(total_length, bds_length)?;
+
+        let decimal_factor = 10f64.powi(pds.decimal_scale_factor as i32);
+        let (reference_bytes, reference, binary_scale) = packing_parameters_for_range(range.0 as f64 * decimal_factor, range.1 as f64 * decimal_factor, bits_per_value);
+
+        let mut header = vec![];
+        header.extend(b"GRIB");
+        header.extend(write_u24_be(length_field as u32));
+        header.push(1);
+        header.extend(pds_bytes);
+        header.extend(gds_bytes);
+        header.extend(write_u24_be(bds_length_field as u32));
+        // Grid point data, simple packing, floating point values
+        header.push(unused_bits);
+        header.extend(write_i16_be(binary_scale as i16));
+        header.extend(reference_bytes);
+        header.push(bits_per_value);
+        writer.write_all(&header).await?;
+
+        Ok(StreamingEncoder {
+            writer,
+            bits_per_value,
+            decimal_factor,
+            reference,
+            factor: 2f64.powi(-binary_scale),
+            range,
+            expected,
+            written: 0,
+            pending: 0,
+            pending_bits: 0,
+            padding: bds_length - 11 - packed_length + padding,
+        })
+    }
+
+    /// Pack and write the next values, in the order given by the scanning mode. Rows may be of any length.
+    pub async fn write_row(&mut self, row: &[f32]) -> Result<(), Grib1Error> {
+        if self.written + row.len() > self.expected {
+            return Err(Grib1Error::ValueCountMismatch {
+                expected: self.expected,
+                actual: self.written + row.len(),
+            });
+        }
+
+        let max_packed = ((1u64 << self.bits_per_value) - 1) as f64;
+        let mut bytes = Vec::with_capacity(row.len() * self.bits_per_value as usize / 8 + 1);
+        for (i, value) in row.iter().enumerate() {
+            if !value.is_finite() {
+                return Err(Grib1Error::NonFiniteValue(self.written + i));
+            }
+            if *value < self.range.0 || *value > self.range.1 {
+                return Err(Grib1Error::ValueOutOfRange(self.written + i));
+            }
+
+            let packed = ((*value as f64 * self.decimal_factor - self.reference) * self.factor).round().clamp(0.0, max_packed) as u64;
+            self.pending = (self.pending << self.bits_per_value) | packed;
+            self.pending_bits += self.bits_per_value as u32;
+            while self.pending_bits >= 8 {
+                self.pending_bits -= 8;
+                bytes.push((self.pending >> self.pending_bits) as u8);
+            }
+            self.pending &= (1 << self.pending_bits) - 1;
+        }
+
+        self.writer.write_all(&bytes).await?;
+        self.written += row.len();
+        Ok(())
+    }
+
+    /// Write the rest of the message once every value has been written, and hand back the writer
+    pub async fn finish(mut self) -> Result<W, Grib1Error> {
+        if self.written != self.expected {
+            return Err(Grib1Error::ValueCountMismatch {
+                expected: self.expected,
+                actual: self.written,
+            });
+        }
+
+        let mut bytes = vec![];
+        if self.pending_bits > 0 {
+            bytes.push((self.pending << (8 - self.pending_bits)) as u8);
+        }
+        bytes.extend(vec![0; self.padding]);
+        bytes.extend(b"7777");
+        self.writer.write_all(&bytes).await?;
+        self.writer.flush().await?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Encode a message from values supplied by an iterator that can be traversed twice: once to find the range and once to pack the values
+pub async fn encode_iter<W, I>(writer: W, pds: &PDS, gds: &GDS, values: I, bits_per_value: u8) -> Result<W, Grib1Error>
+where
+    W: AsyncWrite + Unpin,
+    I: IntoIterator<Item = f32> + Clone,
+{
+    let mut range = (f32::INFINITY, f32::NEG_INFINITY);
+    for (i, value) in values.clone().into_iter().enumerate() {
+        if !value.is_finite() {
+            return Err(Grib1Error::NonFiniteValue(i));
+        }
+        range = (range.0.min(value), range.1.max(value));
+    }
+    if range.0 > range.1 {
+        range = (0.0, 0.0);
+    }
+
+    let mut encoder = StreamingEncoder::start(writer, pds, gds, bits_per_value, range).await?;
+    let mut row = Vec::with_capacity(4096);
+    for value in values {
+        row.push(value);
+        if row.len() == row.capacity() {
+            encoder.write_row(&row).await?;
+            row.clear();
+        }
+    }
+    encoder.write_row(&row).await?;
+
+    encoder.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{writer, Grib, Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn streaming_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let source = &result[0];
+        let gds = source.gds.as_ref().unwrap();
+        let data = &source.bds.as_ref().unwrap().data;
+
+        // Packing the values as a stream gives the same message as packing them all at once
+        for bits_per_value in [12, 16, 17] {
+            let streamed = encode_iter(Vec::new(), &source.pds, gds, data.iter().copied(), bits_per_value).await?;
+            assert_eq!(streamed, writer::encode(&source.pds, gds, data, bits_per_value)?);
+        }
+
+        // Row by row with a given range
+        let ni = gds.row_length().unwrap();
+        let mut encoder = StreamingEncoder::start(Vec::new(), &source.pds, gds, 16, (-100.0, 100.0)).await?;
+        for row in data.chunks(ni) {
+            encoder.write_row(row).await?;
+        }
+        assert!(encoder.write_row(&[0.0]).await.is_err());
+        let bytes = encoder.finish().await?;
+        let decoded = Grib::from_bytes(&bytes)?.bds.unwrap();
+        assert!(decoded.data.iter().zip(data).all(|(a, b)| (a - b).abs() < 0.01));
+
+        let mut encoder = StreamingEncoder::start(Vec::new(), &source.pds, gds, 16, (0.0, 1.0)).await?;
+        assert!(encoder.write_row(&[2.0]).await.is_err());
+        assert!(encoder.finish().await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! Consistency checks made on every message by the strict reader, for quality control of produced files.
+
+use crate::codec::read_u16_be;
+use crate::error::Grib1Error;
+use crate::parse_gds;
+
+// The raw sections of a message, with only the header of the BDS
+pub(crate) struct Sections<'a> {
+    pub offset: u64,
+    pub pds: &'a [u8],
+    pub gds: Option<&'a [u8]>,
+    pub bms: Option<&'a [u8]>,
+    pub bds_header: &'a [u8],
+    /// The true length of the BDS, which differs from its length field for large messages
+    pub bds_length: usize,
+}
+
+/// Check the section lengths, the flags and that the number of packed values matches the grid
+pub(crate) fn check(sections: &Sections) -> Result<(), Grib1Error> {
+    let fail = |section: &'static str, reason: String| Grib1Error::ValidationFailed { offset: sections.offset, section, reason };
+
+    let pds = sections.pds;
+    if pds.len() < 28 {
+        return Err(fail("PDS", format!("it is {} octets, but must have at least 28", pds.len())));
+    }
+    if pds[7] & 0x3f != 0 {
+        return Err(fail("PDS", format!("the flag {:08b} has reserved bits set", pds[7])));
+    }
+    if sections.gds.is_none() && pds[6] == 255 {
+        return Err(fail("PDS", "there is no GDS, but grid 255 means the grid is defined by one".to_string()));
+    }
+
+    let mut number_of_points = None;
+    if let Some(gds) = sections.gds {
+        let minimum = if gds.get(5) == Some(&10) { 42 } else { 32 };
+        if gds.len() < minimum {
+            return Err(fail("GDS", format!("it is {} octets, but must have at least {}", gds.len(), minimum)));
+        }
+
+        let (count, location) = (gds[3] as usize, gds[4] as usize);
+        if count > 0 && (location == 255 || location == 0 || location - 1 + count * 4 > gds.len()) {
+            return Err(fail("GDS", format!("{} vertical coordinate values at octet {} don't fit in {} octets", count, location, gds.len())));
+        }
+
+        let grid = parse_gds(gds)?;
+        if grid.row_length().is_some() {
+            if grid.number_of_points() == 0 {
+                return Err(fail("GDS", "the grid has no points".to_string()));
+            }
+            number_of_points = Some(grid.number_of_points());
+        }
+    }
+
+    let mut number_of_values = number_of_points;
+    if let Some(bms) = sections.bms {
+        if bms.len() < 6 {
+            return Err(fail("BMS", format!("it is {} octets, but must have at least 6", bms.len())));
+        }
+
+        if read_u16_be(&bms[4..]) == 0 {
+            let bits = ((bms.len() - 6) * 8).saturating_sub(bms[3] as usize);
+            if let Some(points) = number_of_points {
+                if bits != points {
+                    return Err(fail("BMS", format!("it has {} bits for a grid of {} points", bits, points)));
+                }
+            }
+            number_of_values = Some((0..bits).filter(|i| bms[6 + i / 8] & (0x80 >> (i % 8)) > 0).count());
+        } else {
+            number_of_values = None;
+        }
+    }
+
+    let bds = sections.bds_header;
+    if bds.len() < 11 {
+        return Err(fail("BDS", format!("it is {} octets, but must have at least 11", bds.len())));
+    }
+    let bits_per_value = bds[10] as usize;
+    if bits_per_value > 32 {
+        return Err(fail("BDS", format!("{} bits per value is more than the 32 supported", bits_per_value)));
+    }
+
+    // The number of values packed with simple packing follows from the length of the section and the unused bits at its end
+    if let (Some(expected), 0, true) = (number_of_values, bds[3] & 0xc0, bits_per_value > 0) {
+        let bits = (sections.bds_length.saturating_sub(11) * 8).saturating_sub((bds[3] & 0x0f) as usize);
+        if bits != expected * bits_per_value {
+            return Err(fail("BDS", format!("it holds {} bits, but {} values of {} bits are expected", bits, expected, bits_per_value)));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Reading files using std IO only, available without the `async-tokio` feature. The whole file is read into memory and the messages are decoded from there.
+
+use crate::error::Grib1Error;
+use crate::merge::split_messages;
+use crate::{edition0, next_section, parse_pds, Grib, PDS};
+use std::path::Path;
+
+/// Read and decode every message of a file
+pub fn read_file(path: impl AsRef<Path>) -> Result<Vec<Grib>, Grib1Error> {
+    read_file_where(path, |_| true)
+}
+
+/// Read a file and decode the messages whose PDS passes the filter. The offsets of the messages are set as `Grib1Reader::read_where` would.
+pub fn read_file_where<F: Fn(&PDS) -> bool>(path: impl AsRef<Path>, filter: F) -> Result<Vec<Grib>, Grib1Error> {
+    let bytes = std::fs::read(path)?;
+
+    let mut result = vec![];
+    let mut offset = 0;
+    for message in split_messages(&bytes)? {
+        if filter(&message_pds(message)?) {
+            let mut grib = Grib::from_bytes(message)?;
+            grib.offset = offset;
+            result.push(grib);
+        }
+        offset += message.len() as u64;
+    }
+
+    Ok(result)
+}
+
+// Only the PDS is parsed, so messages that are filtered out aren't decoded
+fn message_pds(message: &[u8]) -> Result<PDS, Grib1Error> {
+    let edition0 = edition0::is_edition0(message);
+    let mut offset = if edition0 { 4 } else { 8 };
+    let pds = match next_section(message, &mut offset)? {
+        section if edition0 => parse_pds(&edition0::to_edition1_pds(section))?,
+        section => parse_pds(section)?,
+    };
+    Ok(pds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_file_test() -> Result<(), Grib1Error> {
+        assert_eq!(read_file("data/sample.grib")?.len(), 2);
+
+        let result = read_file_where("data/sample.grib", |pds| pds.indicator_of_parameter_and_units == 34)?;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].offset, 2542704);
+        assert_eq!(result[0].bds.as_ref().unwrap().data.len(), 1271041);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+//! Small, valid messages built in code, for unit tests that shouldn't depend on binary fixtures. Enabled by the `test-util` feature.
+//!
+//! ```
+//! use grib1_reader::synthetic::MessageBuilder;
+//!
+//! let bytes = MessageBuilder::new(4, 3).parameter(11).values_with(|i, j| if i == 0 && j == 0 { f32::NAN } else { (i + j) as f32 }).build()?;
+//! let grib = grib1_reader::Grib::from_bytes(&bytes)?;
+//! assert_eq!(grib.bds.unwrap().data.len(), 12);
+//! # Ok::<(), grib1_reader::error::Grib1Error>(())
+//! ```
+
+use crate::error::Grib1Error;
+use crate::time::DateTime;
+use crate::writer::{encode_packed, Packing};
+use crate::{DataRepresentation, Grib, RegularLatLon, GDS, PDS};
+
+#[derive(Debug, Clone)]
+/// Builds a message on a regular latitude/longitude grid by default, with values `i + j` at column `i` and row `j` packed using 16 bits
+pub struct MessageBuilder {
+    pds: PDS,
+    gds: GDS,
+    data: Option<Vec<f32>>,
+    bits_per_value: u8,
+    packing: Packing,
+}
+
+impl MessageBuilder {
+    /// A grid of `ni` by `nj` points one degree apart, starting at 0°N 0°E and scanning west to east and south to north
+    pub fn new(ni: u16, nj: u16) -> MessageBuilder {
+        MessageBuilder {
+            pds: PDS {
+                parameter_table_version_number: 3,
+                identification_of_center: 255,
+                generating_process_id_number: 255,
+                grid_identification: 255,
+                flag_specifying_the_presence_or_absence_of_a_gds_or_a_bms: 128,
+                indicator_of_parameter_and_units: 11,
+                indicator_of_type_of_level_or_layer: 1,
+                level_or_layer_value: 0,
+                year: 24,
+                month: 1,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                forecast_time_unit: 1,
+                p1_period_of_time: 0,
+                p2_period_of_time: 0,
+                time_range_indicator: 0,
+                number_included_in_average: 0,
+                number_missing_from_averages_or_accumulations: 0,
+                century_of_initial_reference_time: 21,
+                identification_of_sub_center: 0,
+                decimal_scale_factor: 0,
+                local_extension: vec![],
+            },
+            gds: GDS {
+                number_of_vertical_coordinate_values: 0,
+                pvl_location: 255,
+                data_representation_type: 0,
+                data: DataRepresentation::RegularLatLon(RegularLatLon {
+                    number_of_lat_values: ni,
+                    number_of_lon_values: nj,
+                    latitude_of_first_grid_point: 0.0,
+                    longitude_of_first_grid_point: 0.0,
+                    latitude_of_last_grid_point: nj.saturating_sub(1) as f32,
+                    longitude_of_last_grid_point: ni.saturating_sub(1) as f32,
+                    resolution_and_component_flags: 128,
+                    i_direction_increment: 1000,
+                    j_direction_increment: 1000,
+                    scanning_mode: 0x40,
+                }),
+                vertical_coordinates: vec![],
+            },
+            data: None,
+            bits_per_value: 16,
+            packing: Packing::Simple,
+        }
+    }
+
+    /// Use another grid, e.g. from `grid::RotatedLatLonBuilder`, which the values must fill. Only regular and rotated latitude/longitude grids can be encoded.
+    pub fn grid(mut self, gds: GDS) -> MessageBuilder {
+        self.gds = gds;
+        self
+    }
+
+    pub fn parameter(mut self, indicator_of_parameter_and_units: u8) -> MessageBuilder {
+        self.pds.indicator_of_parameter_and_units = indicator_of_parameter_and_units;
+        self
+    }
+
+    pub fn level(mut self, type_of_level: u8, level: u16) -> MessageBuilder {
+        self.pds.indicator_of_type_of_level_or_layer = type_of_level;
+        self.pds.level_or_layer_value = level;
+        self
+    }
+
+    pub fn reference_time(mut self, time: DateTime) -> MessageBuilder {
+        self.pds.century_of_initial_reference_time = ((time.year - 1) / 100 + 1) as u8;
+        self.pds.year = ((time.year - 1) % 100 + 1) as u8;
+        (self.pds.month, self.pds.day, self.pds.hour, self.pds.minute) = (time.month, time.day, time.hour, time.minute);
+        self
+    }
+
+    /// The forecast step in hours, for a forecast valid at a single time
+    pub fn step(mut self, hours: u8) -> MessageBuilder {
+        (self.pds.forecast_time_unit, self.pds.p1_period_of_time, self.pds.time_range_indicator) = (1, hours, 0);
+        self
+    }
+
+    pub fn decimal_scale_factor(mut self, decimal_scale_factor: i16) -> MessageBuilder {
+        self.pds.decimal_scale_factor = decimal_scale_factor;
+        self
+    }
+
+    /// Change any other field of the PDS. The flags for the GDS and the bit-map are set when building.
+    pub fn pds(mut self, change: impl FnOnce(&mut PDS)) -> MessageBuilder {
+        change(&mut self.pds);
+        self
+    }
+
+    /// The values in scanning order, NaN for the points missing from the bit-map
+    pub fn values(mut self, data: Vec<f32>) -> MessageBuilder {
+        self.data = Some(data);
+        self
+    }
+
+    /// The value at column `i` and row `j`, NaN for the points missing from the bit-map
+    pub fn values_with(mut self, value: impl Fn(usize, usize) -> f32) -> MessageBuilder {
+        let ni = self.gds.row_length().unwrap_or(0).max(1);
+        self.data = Some((0..self.gds.number_of_points()).map(|index| value(index % ni, index / ni)).collect());
+        self
+    }
+
+    pub fn bits_per_value(mut self, bits_per_value: u8) -> MessageBuilder {
+        self.bits_per_value = bits_per_value;
+        self
+    }
+
+    pub fn packing(mut self, packing: Packing) -> MessageBuilder {
+        self.packing = packing;
+        self
+    }
+
+    /// Octets appended to the PDS from octet 41 on, as centres do for their local definitions. Octets 29 to 40 are set to zero.
+    pub fn local_extension(mut self, octets: Vec<u8>) -> MessageBuilder {
+        self.pds.local_extension = [vec![0; 12], octets].concat();
+        self
+    }
+
+    /// Encode the message
+    pub fn build(&self) -> Result<Vec<u8>, Grib1Error> {
+        match &self.data {
+            Some(data) if data.len() != self.gds.number_of_points() => Err(Grib1Error::ValueCountMismatch {
+                expected: self.gds.number_of_points(),
+                actual: data.len(),
+            }),
+            Some(data) => encode_packed(&self.pds, &self.gds, data, self.bits_per_value, self.packing),
+            None => self.clone().values_with(|i, j| (i + j) as f32).build(),
+        }
+    }
+
+    /// Encode and decode the message, as it would be read
+    pub fn grib(&self) -> Result<Grib, Grib1Error> {
+        Grib::from_bytes(&self.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::RotatedLatLonBuilder;
+
+    #[test]
+    fn builder_test() -> Result<(), Grib1Error> {
+        let grib = MessageBuilder::new(3, 2).parameter(33).level(100, 850).reference_time(DateTime::new(2000, 6, 1, 12, 0)).step(6).grib()?;
+        assert_eq!((grib.pds.indicator_of_parameter_and_units, grib.pds.level_or_layer_value), (33, 850));
+        assert_eq!(grib.pds.reference_time(), DateTime::new(2000, 6, 1, 12, 0));
+        assert_eq!(grib.pds.step(), 6);
+        assert_eq!(grib.bds.unwrap().data, vec![0.0, 1.0, 2.0, 1.0, 2.0, 3.0]);
+
+        let values = vec![1.5, f32::NAN, -2.25, 8.0];
+        let grib = MessageBuilder::new(2, 2)
+            .grid(RotatedLatLonBuilder::new(2, 2).increments(1.0, 1.0).southern_pole(-30.0, 10.0).build()?)
+            .decimal_scale_factor(2)
+            .values(values.clone())
+            .grib()?;
+        assert!(grib.pds.has_bmp());
+        assert!(matches!(grib.gds.unwrap().data, DataRepresentation::RotatedLatLon(grid) if (grid.latitude_of_southern_pole + 30.0).abs() < 1e-3));
+        crate::compare::assert_values_close(&grib.bds.unwrap().data, &values, 1e-3, 0.0);
+
+        // The values must fill the grid
+        assert!(MessageBuilder::new(2, 2).values(vec![1.0]).build().is_err());
+        Ok(())
+    }
+}
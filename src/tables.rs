@@ -0,0 +1,158 @@
+//! Lookup tables for the WMO code tables referenced from the PDS.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Description of a parameter from code table 2
+pub struct Parameter {
+    pub indicator: u8,
+    pub abbreviation: &'static str,
+    pub name: &'static str,
+    pub units: &'static str,
+}
+
+/// Look up a parameter by its indicator in a table version. Only the internationally agreed range 1-127 of the WMO versions (below 128) is known.
+/// Local tables, e.g. 253, may give these indicators other meanings, so nothing is found in them.
+pub fn parameter(table_version: u8, indicator: u8) -> Option<&'static Parameter> {
+    if table_version >= 128 {
+        return None;
+    }
+    PARAMETERS.get((indicator as usize).checked_sub(1)?)
+}
+
+/// Look up a parameter by its abbreviation (case insensitive), e.g. "TMP" or "ugrd"
+pub fn parameter_by_abbreviation(abbreviation: &str) -> Option<&'static Parameter> {
+    PARAMETERS.iter().find(|p| p.abbreviation.eq_ignore_ascii_case(abbreviation))
+}
+
+const fn param(indicator: u8, abbreviation: &'static str, name: &'static str, units: &'static str) -> Parameter {
+    Parameter { indicator, abbreviation, name, units }
+}
+
+static PARAMETERS: [Parameter; 127] = [
+    param(1, "PRES", "Pressure", "Pa"),
+    param(2, "PRMSL", "Pressure reduced to MSL", "Pa"),
+    param(3, "PTEND", "Pressure tendency", "Pa/s"),
+    param(4, "PVORT", "Potential vorticity", "K m2/kg/s"),
+    param(5, "ICAHT", "ICAO standard atmosphere reference height", "m"),
+    param(6, "GP", "Geopotential", "m2/s2"),
+    param(7, "HGT", "Geopotential height", "gpm"),
+    param(8, "DIST", "Geometric height", "m"),
+    param(9, "HSTDV", "Standard deviation of height", "m"),
+    param(10, "TOZNE", "Total ozone", "Dobson"),
+    param(11, "TMP", "Temperature", "K"),
+    param(12, "VTMP", "Virtual temperature", "K"),
+    param(13, "POT", "Potential temperature", "K"),
+    param(14, "EPOT", "Pseudo-adiabatic potential temperature", "K"),
+    param(15, "TMAX", "Maximum temperature", "K"),
+    param(16, "TMIN", "Minimum temperature", "K"),
+    param(17, "DPT", "Dew point temperature", "K"),
+    param(18, "DEPR", "Dew point depression", "K"),
+    param(19, "LAPR", "Lapse rate", "K/m"),
+    param(20, "VIS", "Visibility", "m"),
+    param(21, "RDSP1", "Radar spectra (1)", "-"),
+    param(22, "RDSP2", "Radar spectra (2)", "-"),
+    param(23, "RDSP3", "Radar spectra (3)", "-"),
+    param(24, "PLI", "Parcel lifted index (to 500 hPa)", "K"),
+    param(25, "TMPA", "Temperature anomaly", "K"),
+    param(26, "PRESA", "Pressure anomaly", "Pa"),
+    param(27, "GPA", "Geopotential height anomaly", "gpm"),
+    param(28, "WVSP1", "Wave spectra (1)", "-"),
+    param(29, "WVSP2", "Wave spectra (2)", "-"),
+    param(30, "WVSP3", "Wave spectra (3)", "-"),
+    param(31, "WDIR", "Wind direction", "deg"),
+    param(32, "WIND", "Wind speed", "m/s"),
+    param(33, "UGRD", "u-component of wind", "m/s"),
+    param(34, "VGRD", "v-component of wind", "m/s"),
+    param(35, "STRM", "Stream function", "m2/s"),
+    param(36, "VPOT", "Velocity potential", "m2/s"),
+    param(37, "MNTSF", "Montgomery stream function", "m2/s2"),
+    param(38, "SGCVV", "Sigma coordinate vertical velocity", "1/s"),
+    param(39, "VVEL", "Vertical velocity (pressure)", "Pa/s"),
+    param(40, "DZDT", "Vertical velocity (geometric)", "m/s"),
+    param(41, "ABSV", "Absolute vorticity", "1/s"),
+    param(42, "ABSD", "Absolute divergence", "1/s"),
+    param(43, "RELV", "Relative vorticity", "1/s"),
+    param(44, "RELD", "Relative divergence", "1/s"),
+    param(45, "VUCSH", "Vertical u-component shear", "1/s"),
+    param(46, "VVCSH", "Vertical v-component shear", "1/s"),
+    param(47, "DIRC", "Direction of current", "deg"),
+    param(48, "SPC", "Speed of current", "m/s"),
+    param(49, "UOGRD", "u-component of current", "m/s"),
+    param(50, "VOGRD", "v-component of current", "m/s"),
+    param(51, "SPFH", "Specific humidity", "kg/kg"),
+    param(52, "RH", "Relative humidity", "%"),
+    param(53, "MIXR", "Humidity mixing ratio", "kg/kg"),
+    param(54, "PWAT", "Precipitable water", "kg/m2"),
+    param(55, "VAPP", "Vapour pressure", "Pa"),
+    param(56, "SATD", "Saturation deficit", "Pa"),
+    param(57, "EVP", "Evaporation", "kg/m2"),
+    param(58, "CICE", "Cloud ice", "kg/m2"),
+    param(59, "PRATE", "Precipitation rate", "kg/m2/s"),
+    param(60, "TSTM", "Thunderstorm probability", "%"),
+    param(61, "APCP", "Total precipitation", "kg/m2"),
+    param(62, "NCPCP", "Large scale precipitation", "kg/m2"),
+    param(63, "ACPCP", "Convective precipitation", "kg/m2"),
+    param(64, "SRWEQ", "Snowfall rate water equivalent", "kg/m2/s"),
+    param(65, "WEASD", "Water equivalent of accumulated snow depth", "kg/m2"),
+    param(66, "SNOD", "Snow depth", "m"),
+    param(67, "MIXHT", "Mixed layer depth", "m"),
+    param(68, "TTHDP", "Transient thermocline depth", "m"),
+    param(69, "MTHD", "Main thermocline depth", "m"),
+    param(70, "MTHA", "Main thermocline anomaly", "m"),
+    param(71, "TCDC", "Total cloud cover", "%"),
+    param(72, "CDCON", "Convective cloud cover", "%"),
+    param(73, "LCDC", "Low cloud cover", "%"),
+    param(74, "MCDC", "Medium cloud cover", "%"),
+    param(75, "HCDC", "High cloud cover", "%"),
+    param(76, "CWAT", "Cloud water", "kg/m2"),
+    param(77, "BLI", "Best lifted index (to 500 hPa)", "K"),
+    param(78, "SNOC", "Convective snow", "kg/m2"),
+    param(79, "SNOL", "Large scale snow", "kg/m2"),
+    param(80, "WTMP", "Water temperature", "K"),
+    param(81, "LAND", "Land cover (1 = land, 0 = sea)", "proportion"),
+    param(82, "DSLM", "Deviation of sea level from mean", "m"),
+    param(83, "SFCR", "Surface roughness", "m"),
+    param(84, "ALBDO", "Albedo", "%"),
+    param(85, "TSOIL", "Soil temperature", "K"),
+    param(86, "SOILM", "Soil moisture content", "kg/m2"),
+    param(87, "VEG", "Vegetation", "%"),
+    param(88, "SALTY", "Salinity", "kg/kg"),
+    param(89, "DEN", "Density", "kg/m3"),
+    param(90, "WATR", "Water run-off", "kg/m2"),
+    param(91, "ICEC", "Ice cover (1 = ice, 0 = no ice)", "proportion"),
+    param(92, "ICETK", "Ice thickness", "m"),
+    param(93, "DICED", "Direction of ice drift", "deg"),
+    param(94, "SICED", "Speed of ice drift", "m/s"),
+    param(95, "UICE", "u-component of ice drift", "m/s"),
+    param(96, "VICE", "v-component of ice drift", "m/s"),
+    param(97, "ICEG", "Ice growth rate", "m/s"),
+    param(98, "ICED", "Ice divergence", "1/s"),
+    param(99, "SNOM", "Snow melt", "kg/m2"),
+    param(100, "HTSGW", "Significant height of combined wind waves and swell", "m"),
+    param(101, "WVDIR", "Direction of wind waves", "deg"),
+    param(102, "WVHGT", "Significant height of wind waves", "m"),
+    param(103, "WVPER", "Mean period of wind waves", "s"),
+    param(104, "SWDIR", "Direction of swell waves", "deg"),
+    param(105, "SWELL", "Significant height of swell waves", "m"),
+    param(106, "SWPER", "Mean period of swell waves", "s"),
+    param(107, "DIRPW", "Primary wave direction", "deg"),
+    param(108, "PERPW", "Primary wave mean period", "s"),
+    param(109, "DIRSW", "Secondary wave direction", "deg"),
+    param(110, "PERSW", "Secondary wave mean period", "s"),
+    param(111, "NSWRS", "Net short-wave radiation flux (surface)", "W/m2"),
+    param(112, "NLWRS", "Net long-wave radiation flux (surface)", "W/m2"),
+    param(113, "NSWRT", "Net short-wave radiation flux (top of atmosphere)", "W/m2"),
+    param(114, "NLWRT", "Net long-wave radiation flux (top of atmosphere)", "W/m2"),
+    param(115, "LWAVR", "Long-wave radiation flux", "W/m2"),
+    param(116, "SWAVR", "Short-wave radiation flux", "W/m2"),
+    param(117, "GRAD", "Global radiation flux", "W/m2"),
+    param(118, "BRTMP", "Brightness temperature", "K"),
+    param(119, "LWRAD", "Radiance (with respect to wave number)", "W/m/sr"),
+    param(120, "SWRAD", "Radiance (with respect to wave length)", "W/m3/sr"),
+    param(121, "LHTFL", "Latent heat flux", "W/m2"),
+    param(122, "SHTFL", "Sensible heat flux", "W/m2"),
+    param(123, "BLYDP", "Boundary layer dissipation", "W/m2"),
+    param(124, "UFLX", "Momentum flux, u-component", "N/m2"),
+    param(125, "VFLX", "Momentum flux, v-component", "N/m2"),
+    param(126, "WMIXE", "Wind mixing energy", "J"),
+    param(127, "IMGD", "Image data", "-"),
+];
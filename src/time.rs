@@ -0,0 +1,125 @@
+//! Minimal calendar handling for the reference and valid times found in the PDS.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A UTC date and time with minute resolution, which is all GRIB1 can express
+pub struct DateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl DateTime {
+    /// Create a new date and time. No validation is done on the individual fields.
+    pub fn new(year: i32, month: u8, day: u8, hour: u8, minute: u8) -> DateTime {
+        DateTime { year, month, day, hour, minute }
+    }
+
+    /// Number of minutes since 1970-01-01 00:00, handy for ordering and computing differences
+    pub fn to_minutes(&self) -> i64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        days * 1440 + self.hour as i64 * 60 + self.minute as i64
+    }
+
+    /// Inverse of `to_minutes`
+    pub fn from_minutes(minutes: i64) -> DateTime {
+        let days = minutes.div_euclid(1440);
+        let rest = minutes.rem_euclid(1440);
+        let (year, month, day) = civil_from_days(days);
+        DateTime::new(year as i32, month as u8, day as u8, (rest / 60) as u8, (rest % 60) as u8)
+    }
+
+    /// Add a number of minutes, which may be negative
+    pub fn add_minutes(&self, minutes: i64) -> DateTime {
+        DateTime::from_minutes(self.to_minutes() + minutes)
+    }
+
+    /// Add a number of calendar months, clamping the day to the length of the resulting month
+    pub fn add_months(&self, months: i64) -> DateTime {
+        let total = self.year as i64 * 12 + (self.month as i64 - 1) + months;
+        let year = total.div_euclid(12);
+        let month = total.rem_euclid(12) + 1;
+        let last_day = days_from_civil(if month == 12 { year + 1 } else { year }, if month == 12 { 1 } else { month + 1 }, 1) - days_from_civil(year, month, 1);
+        let day = (self.day as i64).min(last_day);
+        DateTime::new(year as i32, month as u8, day as u8, self.hour, self.minute)
+    }
+
+    /// Add `amount` of the given forecast time unit (code table 4). Returns None for unknown units.
+    pub fn add_time_unit(&self, unit: u8, amount: i64) -> Option<DateTime> {
+        if let Some(minutes) = time_unit_minutes(unit) {
+            return Some(self.add_minutes(amount * minutes));
+        }
+
+        let months = match unit {
+            3 => 1,
+            4 => 12,
+            5 => 120,
+            6 => 360,
+            7 => 1200,
+            _ => return None,
+        };
+        Some(self.add_months(amount * months))
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}Z", self.year, self.month, self.day, self.hour, self.minute)
+    }
+}
+
+/// Length in minutes of a fixed-length forecast time unit (code table 4). Calendar based units return None.
+pub fn time_unit_minutes(unit: u8) -> Option<i64> {
+    match unit {
+        0 => Some(1),
+        1 => Some(60),
+        2 => Some(1440),
+        10 => Some(180),
+        11 => Some(360),
+        12 => Some(720),
+        13 => Some(15),
+        14 => Some(30),
+        _ => None,
+    }
+}
+
+// Day counting algorithms from http://howardhinnant.github.io/date_algorithms.html
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calendar_arithmetic_test() {
+        let time = DateTime::new(2023, 12, 31, 18, 0);
+        assert_eq!(time.add_minutes(12 * 60), DateTime::new(2024, 1, 1, 6, 0));
+        assert_eq!(DateTime::new(2024, 1, 31, 0, 0).add_months(1), DateTime::new(2024, 2, 29, 0, 0));
+        assert_eq!(DateTime::from_minutes(time.to_minutes()), time);
+        assert_eq!(DateTime::new(1970, 1, 1, 0, 0).to_minutes(), 0);
+    }
+}
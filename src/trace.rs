@@ -0,0 +1,12 @@
+//! Instrumentation of the reader with the `tracing` crate. Enable the `tracing` feature to get spans and events for scanning, section parsing and decoding,
+//! without it the macros below expand to nothing.
+
+// Emit an event at the given level, e.g. `event!(debug, offset, length, "read section")`
+macro_rules! event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)*);
+    };
+}
+
+pub(crate) use event;
@@ -0,0 +1,76 @@
+//! Decoded values as `uom` quantities, so computations with them are checked for their dimensions at compile time.
+//! The units of a parameter are those of code table 2, and asking for a quantity of other units is an error rather than a conversion.
+
+use crate::error::Grib1Error;
+use crate::tables::parameter;
+use crate::Grib;
+use uom::si::f32::{Length, Pressure, Ratio, ThermodynamicTemperature, Velocity};
+use uom::si::length::meter;
+use uom::si::pressure::pascal;
+use uom::si::ratio::percent;
+use uom::si::thermodynamic_temperature::kelvin;
+use uom::si::velocity::meter_per_second;
+
+impl Grib {
+    /// The values of a parameter in K, e.g. temperature or dew point
+    pub fn temperatures(&self) -> Result<Vec<ThermodynamicTemperature>, Grib1Error> {
+        self.quantities("K", ThermodynamicTemperature::new::<kelvin>)
+    }
+
+    /// The values of a parameter in Pa, e.g. surface or mean sea level pressure
+    pub fn pressures(&self) -> Result<Vec<Pressure>, Grib1Error> {
+        self.quantities("Pa", Pressure::new::<pascal>)
+    }
+
+    /// The values of a parameter in m/s, e.g. the components of the wind
+    pub fn velocities(&self) -> Result<Vec<Velocity>, Grib1Error> {
+        self.quantities("m/s", Velocity::new::<meter_per_second>)
+    }
+
+    /// The values of a parameter in m, e.g. geometric height or snow depth
+    pub fn lengths(&self) -> Result<Vec<Length>, Grib1Error> {
+        self.quantities("m", Length::new::<meter>)
+    }
+
+    /// The values of a parameter in %, e.g. relative humidity or cloud cover
+    pub fn ratios(&self) -> Result<Vec<Ratio>, Grib1Error> {
+        self.quantities("%", Ratio::new::<percent>)
+    }
+
+    // The values as quantities if the parameter is in the units given. Missing points are NaN.
+    fn quantities<Q, F: Fn(f32) -> Q>(&self, units: &'static str, quantity: F) -> Result<Vec<Q>, Grib1Error> {
+        let indicator = self.pds.indicator_of_parameter_and_units;
+        match parameter(self.pds.parameter_table_version_number, indicator) {
+            Some(found) if found.units == units => {}
+            found => {
+                return Err(Grib1Error::UnexpectedUnits {
+                    parameter: found.map_or_else(|| format!("Parameter {}", indicator), |p| p.abbreviation.to_string()),
+                    units: found.map_or("unknown units", |p| p.units),
+                    expected: units,
+                })
+            }
+        }
+
+        let bds = self.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+        Ok(bds.optional_values().into_iter().map(|value| quantity(value.unwrap_or(f32::NAN))).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::MessageBuilder;
+    use uom::si::thermodynamic_temperature::degree_celsius;
+
+    #[test]
+    fn units_test() -> Result<(), Grib1Error> {
+        let temperature = MessageBuilder::new(2, 1).parameter(11).decimal_scale_factor(2).values(vec![273.15, f32::NAN]).grib()?;
+        let values = temperature.temperatures()?;
+        assert!(values[0].get::<degree_celsius>().abs() < 0.01 && values[1].value.is_nan());
+
+        let wind = MessageBuilder::new(2, 1).parameter(33).values(vec![3.0, 4.0]).grib()?;
+        assert_eq!(wind.velocities()?[1], Velocity::new::<meter_per_second>(4.0));
+        assert!(matches!(wind.temperatures(), Err(Grib1Error::UnexpectedUnits { units: "m/s", expected: "K", .. })));
+        Ok(())
+    }
+}
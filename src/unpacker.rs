@@ -0,0 +1,64 @@
+//! Decoders for packings the library doesn't handle itself, e.g. the local spectral packings of some centres. Unpackers are registered
+//! with `Grib1Reader::unpacker` and asked in turn whether they take a BDS before the built-in decoding is used, leaving the scanning
+//! and the other sections to the reader.
+
+use crate::error::Grib1Error;
+use crate::{BDS, GDS, PDS};
+
+/// A decoder for the BDS of some messages
+pub trait BdsUnpacker: Send + Sync {
+    /// Whether the BDS is unpacked by this unpacker, e.g. judging from its flags (octet 4) and the centre of the PDS
+    fn accepts(&self, pds: &PDS, bds: &[u8]) -> bool;
+
+    /// Unpack the values of every point of the grid, with the missing points of the bit-map as NaN. The values are divided
+    /// by ten to the power of `decimal_scale_factor`, which is 0 if the reader has `decimal_scaling` turned off.
+    fn unpack(&self, bds: &[u8], bms: Option<&[u8]>, gds: Option<&GDS>, decimal_scale_factor: i16) -> Result<BDS, Grib1Error>;
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::missing::MissingValues;
+    use crate::{Grib1Reader, PackingInfo, SearchParams};
+
+    // Gives every point of the V component the same value, or no values at all without one
+    struct Constant(Option<f32>);
+
+    impl BdsUnpacker for Constant {
+        fn accepts(&self, pds: &PDS, _bds: &[u8]) -> bool {
+            pds.indicator_of_parameter_and_units == 34
+        }
+
+        fn unpack(&self, bds: &[u8], _bms: Option<&[u8]>, gds: Option<&GDS>, _decimal_scale_factor: i16) -> Result<BDS, Grib1Error> {
+            Ok(BDS {
+                data_flag: bds[3],
+                binary_scale_factor: 0,
+                decimal_scale_factor: 0,
+                reference_value: self.0.unwrap_or(0.0),
+                bits_per_value: 0,
+                data: self.0.map_or(vec![], |value| vec![value; gds.map_or(0, |gds| gds.number_of_points())]),
+                missing_values: MissingValues::Nan,
+                packing: PackingInfo::of(bds),
+                matrix: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn unpacker_test() -> Result<(), Grib1Error> {
+        let search = vec![SearchParams { param: 33, level: 700 }, SearchParams { param: 34, level: 700 }];
+        let plain = Grib1Reader::open("data/sample.grib").await?.read(search.clone()).await?;
+        let result = Grib1Reader::open("data/sample.grib").await?.unpacker(Constant(Some(1.5))).read(search.clone()).await?;
+
+        // Messages the unpacker doesn't accept are decoded as usual
+        assert_eq!(result[0].bds.as_ref().unwrap().data, plain[0].bds.as_ref().unwrap().data);
+        let data = &result[1].bds.as_ref().unwrap().data;
+        assert_eq!(data.len(), 1271041);
+        assert!(data.iter().all(|value| *value == 1.5));
+
+        // The values must fill the grid
+        let error = Grib1Reader::open("data/sample.grib").await?.unpacker(Constant(None)).read(search).await.unwrap_err();
+        assert!(matches!(error.root_cause(), Grib1Error::ValueCountMismatch { expected: 1271041, actual: 0 }));
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+//! Checks of messages against the GRIB1 specification, for producers to test their own output. Unlike the strict reader, which stops at
+//! the first problem, every deviation found is reported with how serious it is.
+
+use crate::codec::{read_i24_be, read_u16_be, read_u24_be};
+use crate::error::Grib1Error;
+use crate::strict::{self, Sections};
+use crate::warning::{self, Warning};
+use crate::{bds_offset, edition0, message_length, resolve_lengths, tables};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// How far a deviation is from the specification
+pub enum Severity {
+    /// Allowed, but worth knowing about, e.g. a local table version or a grid this library doesn't decode
+    Info,
+    /// A value outside the code tables, or an inconsistency readers generally cope with
+    Warning,
+    /// Breaks the specification, so readers may fail or decode the wrong values
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A single deviation from the specification
+pub struct Deviation {
+    /// Position of the message within the file
+    pub offset: u64,
+    pub section: &'static str,
+    /// Octet within the section the deviation is about, numbered from 1 as in the specification
+    pub octet: Option<usize>,
+    pub severity: Severity,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// The deviations found in a file, in the order of the messages
+pub struct ValidationReport {
+    /// Number of messages checked
+    pub messages: usize,
+    pub deviations: Vec<Deviation>,
+}
+
+impl ValidationReport {
+    /// The deviations of at least the given severity
+    pub fn at_least(&self, severity: Severity) -> impl Iterator<Item = &Deviation> {
+        self.deviations.iter().filter(move |deviation| deviation.severity >= severity)
+    }
+
+    /// Whether no deviations worse than `Info` were found
+    pub fn is_compliant(&self) -> bool {
+        self.at_least(Severity::Warning).next().is_none()
+    }
+}
+
+// Code table 4, the unit of time
+const TIME_UNITS: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 10, 11, 12, 254];
+
+// Code table 5, the time range indicator, apart from the local range 128-254
+const TIME_RANGES: [u8; 22] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 51, 113, 114, 115, 116, 117, 118, 119, 123, 124, 125];
+
+// Code table 3, the fixed levels and layers, apart from the local range 200-254
+const LEVEL_TYPES: [u8; 37] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 20, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117, 119, 120, 121, 124, 125, 126, 128, 141, 160,
+];
+
+/// Check a complete message held in memory, e.g. one returned by `read_raw`. The offset is only used to say where the deviations are.
+pub fn validate_message(bytes: &[u8], offset: u64) -> Vec<Deviation> {
+    let mut result = vec![];
+    let mut report = |section: &'static str, octet: Option<usize>, severity: Severity, description: String| {
+        result.push(Deviation {
+            offset,
+            section,
+            octet,
+            severity,
+            description,
+        })
+    };
+
+    if bytes.len() < 8 || bytes[0..4] != *b"GRIB" {
+        report("IS", Some(1), Severity::Error, "the message doesn't start with GRIB".to_string());
+        return result;
+    }
+    match bytes[7] {
+        0 => report("IS", Some(8), Severity::Warning, "edition 0 is obsolete".to_string()),
+        1 => {}
+        edition => {
+            report("IS", Some(8), Severity::Error, format!("edition {} is not GRIB1", edition));
+            return result;
+        }
+    }
+
+    // The sections are needed for the rest of the checks, so give up if they can't be found
+    let edition0 = edition0::is_edition0(bytes);
+    let (Some(length), Some(bds_start)) = (message_length(bytes), bds_offset(bytes)) else {
+        report("IS", None, Severity::Error, "the sections before the BDS are incomplete".to_string());
+        return result;
+    };
+    if length > bytes.len() {
+        report("IS", Some(5), Severity::Error, format!("the message is {} octets, but {} are given", bytes.len(), length));
+        return result;
+    }
+    if length < bds_start + 4 {
+        report("IS", Some(5), Severity::Error, format!("the message is {} octets, which ends before its BDS", length));
+        return result;
+    }
+    if length < bytes.len() {
+        report("IS", Some(5), Severity::Warning, format!("the message is {} octets, but only {} are given", bytes.len(), length));
+    }
+    if bytes[length - 4..length] != *b"7777" {
+        report("ES", Some(1), Severity::Error, "the message doesn't end with 7777".to_string());
+    }
+
+    let start = if edition0 { 4 } else { 8 };
+    let pds_length = read_u24_be(&bytes[start..]) as usize;
+    let pds = match &bytes[start..(start + pds_length).min(bds_start)] {
+        pds if edition0 && pds.len() >= 24 => edition0::to_edition1_pds(pds),
+        pds => pds.to_vec(),
+    };
+    if pds.len() < 28 {
+        report("PDS", Some(1), Severity::Error, format!("it is {} octets, but must have at least 28", pds.len()));
+        return result;
+    }
+    check_pds(&pds, &mut report);
+
+    let mut position = start + pds_length;
+    let mut gds = None;
+    if pds[7] & 0x80 > 0 {
+        let gds_length = read_u24_be(&bytes[position..]) as usize;
+        let section = &bytes[position..position + gds_length];
+        if check_gds(section, &mut report) {
+            gds = Some(section);
+        }
+        position += gds_length;
+    }
+    let mut bms = None;
+    if pds[7] & 0x40 > 0 {
+        let bms_length = read_u24_be(&bytes[position..]) as usize;
+        let section = &bytes[position..position + bms_length];
+        if section.len() >= 6 && read_u16_be(&section[4..]) != 0 {
+            report("BMS", Some(5), Severity::Info, format!("the predefined bit-map {} isn't included", read_u16_be(&section[4..])));
+        }
+        bms = Some(section);
+        position += bms_length;
+    }
+    debug_assert_eq!(position, bds_start);
+
+    // The BDS header and the consistency of the sections are checked as by the strict reader and the warnings of the reader
+    let bds_length = match read_u24_be(&bytes[bds_start..]) as usize {
+        bds_length if edition0 => bds_length,
+        bds_length => resolve_lengths(read_u24_be(&bytes[4..]) as usize, bds_start, bds_length).1,
+    };
+    if bds_start + bds_length + 4 != length {
+        report("BDS", Some(1), Severity::Error, format!("it is {} octets, but {} are left before the end section", bds_length, length - 4 - bds_start));
+    }
+    let bds_header = &bytes[bds_start..(bds_start + 11).min(length - 4)];
+    if bds_header.len() > 3 && bds_header[3] & 0x10 > 0 && bds_header[3] & 0x40 == 0 {
+        report("BDS", Some(4), Severity::Warning, "additional flags are given for simple packing".to_string());
+    }
+
+    // The strict checks stop at the first problem, so the ones of the PDS and GDS reported above are masked for those of the bit-map and BDS to run
+    let mut masked = pds.clone();
+    masked[7] &= 0xc0;
+    masked[6] = 0;
+    let sections = Sections {
+        offset,
+        pds: &masked,
+        gds,
+        bms,
+        bds_header,
+        bds_length,
+    };
+    match strict::check(&sections) {
+        Ok(()) => {}
+        Err(Grib1Error::ValidationFailed { section, reason, .. }) => report(section, None, Severity::Error, reason),
+        Err(error) => report("GDS", None, Severity::Error, error.to_string()),
+    }
+    for warning in warning::check(&sections) {
+        match warning {
+            Warning::UnknownGrid { data_representation_type, .. } => report("GDS", Some(6), Severity::Info, format!("data representation type {} isn't decoded by this library", data_representation_type)),
+            Warning::UnusedBitsMismatch { expected, actual, .. } => report("BDS", Some(4), Severity::Warning, format!("{} unused bits are given, but {} are left after the values", actual, expected)),
+            Warning::ValueCountMismatch { expected, packed, .. } => report("BDS", None, Severity::Warning, format!("{} values are packed for {} points", packed, expected)),
+            Warning::SuspiciousReferenceValue { value, .. } => report("BDS", Some(7), Severity::Warning, format!("the reference value {} isn't a normalised IBM float", value)),
+            Warning::SkippedMessage { error, .. } => report("IS", None, Severity::Error, error),
+        }
+    }
+
+    result
+}
+
+fn check_pds(pds: &[u8], report: &mut impl FnMut(&'static str, Option<usize>, Severity, String)) {
+    let mut range = |octet: usize, value: u8, valid: std::ops::RangeInclusive<u8>, name: &str| {
+        if !valid.contains(&value) {
+            report("PDS", Some(octet), Severity::Error, format!("the {} {} is outside {} to {}", name, value, valid.start(), valid.end()));
+        }
+    };
+    range(14, pds[13], 1..=12, "month");
+    range(15, pds[14], 1..=31, "day");
+    range(16, pds[15], 0..=23, "hour");
+    range(17, pds[16], 0..=59, "minute");
+    range(13, pds[12], 1..=100, "year of the century");
+    range(25, pds[24], 1..=255, "century");
+
+    match pds[3] {
+        1..=3 => {}
+        128..=254 => report("PDS", Some(4), Severity::Info, format!("the local parameter table {} is used", pds[3])),
+        version => report("PDS", Some(4), Severity::Warning, format!("the parameter table version {} is reserved", version)),
+    }
+    if pds[4] == 0 || pds[4] == 255 {
+        report("PDS", Some(5), Severity::Warning, format!("the originating center {} is reserved", pds[4]));
+    }
+    if pds[7] & 0x3f != 0 {
+        report("PDS", Some(8), Severity::Error, format!("the flag {:08b} has reserved bits set", pds[7]));
+    }
+    if pds[7] & 0x80 == 0 && pds[6] == 255 {
+        report("PDS", Some(7), Severity::Error, "there is no GDS, but grid 255 means the grid is defined by one".to_string());
+    }
+    if pds[3] <= 3 && tables::parameter(pds[3], pds[8]).is_none() {
+        report("PDS", Some(9), Severity::Warning, format!("the parameter {} isn't in table 2", pds[8]));
+    }
+    if !LEVEL_TYPES.contains(&pds[9]) && !(200..=254).contains(&pds[9]) {
+        report("PDS", Some(10), Severity::Warning, format!("the level type {} isn't in code table 3", pds[9]));
+    }
+    if !TIME_UNITS.contains(&pds[17]) {
+        report("PDS", Some(18), Severity::Warning, format!("the time unit {} isn't in code table 4", pds[17]));
+    }
+    if !TIME_RANGES.contains(&pds[20]) && !(128..=254).contains(&pds[20]) {
+        report("PDS", Some(21), Severity::Warning, format!("the time range indicator {} isn't in code table 5", pds[20]));
+    }
+}
+
+// Whether the GDS is sound enough for the strict checks to use it
+fn check_gds(gds: &[u8], report: &mut impl FnMut(&'static str, Option<usize>, Severity, String)) -> bool {
+    if gds.len() < 6 {
+        report("GDS", Some(1), Severity::Error, format!("it is {} octets, but must have at least 6", gds.len()));
+        return false;
+    }
+    let minimum = match gds[5] {
+        0 => 32,
+        10 => 42,
+        _ => return true,
+    };
+    if gds.len() < minimum {
+        report("GDS", Some(1), Severity::Error, format!("it is {} octets, but must have at least {}", gds.len(), minimum));
+        return false;
+    }
+
+    let mut sound = true;
+    let (count, location) = (gds[3] as usize, gds[4] as usize);
+    if count > 0 && (location == 255 || location == 0 || location - 1 + count * 4 > gds.len()) {
+        report("GDS", Some(4), Severity::Error, format!("{} vertical coordinate values at octet {} don't fit in {} octets", count, location, gds.len()));
+        sound = false;
+    }
+    if gds[6..8] == [0, 0] || gds[8..10] == [0, 0] {
+        report("GDS", Some(7), Severity::Error, "the grid has no points".to_string());
+        sound = false;
+    }
+    for (octet, name) in [(11, "first"), (24, "last")] {
+        let latitude = read_i24_be(&gds[octet - 1..]);
+        if latitude.abs() > 90_000 {
+            report("GDS", Some(octet), Severity::Error, format!("the latitude of the {} grid point {} is outside -90 to 90", name, latitude as f32 * 0.001));
+        }
+    }
+    // Table 7, only the direction increments, earth shape and component flags are defined
+    if gds[16] & 0x37 != 0 {
+        report("GDS", Some(17), Severity::Warning, format!("the resolution flags {:08b} have reserved bits set", gds[16]));
+    }
+    // Table 8, only the three scanning directions are defined
+    if gds[27] & 0x1f != 0 {
+        report("GDS", Some(28), Severity::Error, format!("the scanning mode {:08b} has reserved bits set", gds[27]));
+    }
+    sound
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::Grib1Reader;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn validate_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let report = reader.validate().await?;
+        assert_eq!(report.messages, 2);
+        assert!(report.is_compliant(), "{:?}", report);
+        // The sample uses the local table 253
+        assert!(report.deviations.iter().all(|deviation| deviation.section == "PDS" && deviation.octet == Some(4)));
+
+        // Every problem is found, not just the first
+        let bytes = tokio::fs::read("data/sample.grib").await?;
+        let mut message = bytes[..2542704].to_vec();
+        message[8 + 13] = 13;
+        message[8 + 7] |= 0x01;
+        message[8 + 28 + 27] |= 0x01;
+        message[2542704 - 1] = b'6';
+        let deviations = validate_message(&message, 0);
+        let errors: Vec<_> = deviations.iter().filter(|deviation| deviation.severity == Severity::Error).map(|deviation| (deviation.section, deviation.octet)).collect();
+        assert_eq!(errors, vec![("ES", Some(1)), ("PDS", Some(14)), ("PDS", Some(8)), ("GDS", Some(28))]);
+
+        Ok(())
+    }
+}
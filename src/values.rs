@@ -0,0 +1,123 @@
+//! Access to decoded values by their position in the grid, so callers don't need to know how the scanning mode orders them.
+
+use crate::Grib;
+
+#[derive(Debug, Clone, Copy)]
+/// A row of the grid from west to east, viewing the decoded values without copying them
+pub struct Row<'a> {
+    values: &'a [f32],
+    start: usize,
+    step: isize,
+    len: usize,
+}
+
+impl<'a> Row<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The x-th value from the west
+    pub fn get(&self, x: usize) -> Option<f32> {
+        (x < self.len).then(|| self.values[(self.start as isize + x as isize * self.step) as usize])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + 'a {
+        let row = *self;
+        (0..row.len).map(move |x| row.values[(row.start as isize + x as isize * row.step) as usize])
+    }
+
+    /// The values as a slice, if they are stored one after the other from west to east, which is the case for the default scanning mode
+    pub fn as_slice(&self) -> Option<&'a [f32]> {
+        (self.step == 1).then(|| &self.values[self.start..self.start + self.len])
+    }
+}
+
+impl Grib {
+    /// The decoded values in the order they are stored, or nothing if the data wasn't read
+    pub fn values(&self) -> &[f32] {
+        self.bds.as_ref().map_or(&[], |bds| &bds.data)
+    }
+
+    /// Number of points along the i and j axes of the grid, e.g. along a parallel and along a meridian for latitude/longitude grids.
+    /// Messages without a grid the library parses give (0, 0).
+    pub fn shape(&self) -> (usize, usize) {
+        self.gds.as_ref().and_then(|gds| gds.grid()).map_or((0, 0), |grid| grid.shape())
+    }
+
+    /// The value of the i-th point along the i axis in the j-th row along the j axis, counted from the first grid point.
+    /// Consecutive values are along the i axis unless the scanning mode says they are along the j axis.
+    pub fn get(&self, i: usize, j: usize) -> Option<f32> {
+        let (ni, nj) = self.shape();
+        if i >= ni || j >= nj {
+            return None;
+        }
+        let along_j = self.gds.as_ref().and_then(|gds| gds.grid()).is_some_and(|grid| grid.scanning_mode() & 0x20 > 0);
+        self.values().get(if along_j { i * nj + j } else { j * ni + i }).copied()
+    }
+}
+
+impl Grib {
+    /// The rows of the grid from north to south, each from west to east, whatever order the scanning mode stores the values in.
+    /// Gives no rows if the data wasn't read or doesn't fill the grid.
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        let (ni, nj) = self.shape();
+        let scanning_mode = self.gds.as_ref().and_then(|gds| gds.grid()).map_or(0, |grid| grid.scanning_mode());
+        let values = self.values();
+        let count = if values.len() >= ni * nj { nj } else { 0 };
+
+        // Points go from east to west with the first flag, rows from south to north with the second, and the third stores columns one after the other
+        let (west_to_east, north_to_south, along_j) = (scanning_mode & 0x80 == 0, scanning_mode & 0x40 == 0, scanning_mode & 0x20 > 0);
+        let index = move |i: usize, j: usize| if along_j { i * nj + j } else { j * ni + i };
+        (0..count).map(move |y| {
+            let j = if north_to_south { y } else { nj - 1 - y };
+            let step = if along_j { nj as isize } else { 1 };
+            let (start, step) = if west_to_east { (index(0, j), step) } else { (index(ni - 1, j), -step) };
+            Row { values, start, step, len: ni }
+        })
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use crate::error::Grib1Error;
+    use crate::grid::RegularLatLonBuilder;
+    use crate::{writer, Grib, Grib1Reader};
+
+    #[tokio::test]
+    async fn values_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::open("data/sample.grib").await?;
+        let source = reader.read_where(|_| true).await?.remove(0);
+        assert_eq!(source.shape(), (1189, 1069));
+        assert_eq!(source.get(5, 2), Some(source.values()[2 * 1189 + 5]));
+        assert_eq!(source.get(1189, 0), None);
+
+        // The same field stored along columns gives the same values by position
+        let values = [0.0, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let gds = RegularLatLonBuilder::new(3, 2).first_grid_point(50.0, 0.0).increments(1.0, 1.0).build()?;
+        let by_rows = Grib::from_bytes(&writer::encode(&source.pds, &gds, &values, 16)?)?;
+        let gds = RegularLatLonBuilder::new(3, 2).first_grid_point(50.0, 0.0).increments(1.0, 1.0).scanning_mode(0x20).build()?;
+        let by_columns = Grib::from_bytes(&writer::encode(&source.pds, &gds, &[0.0, 10.0, 1.0, 11.0, 2.0, 12.0], 16)?)?;
+        for (i, j) in [(0, 0), (2, 0), (1, 1)] {
+            assert_eq!(by_rows.get(i, j), by_columns.get(i, j));
+        }
+        assert_eq!(by_rows.get(2, 1), Some(12.0));
+
+        // Rows come out from north to south and west to east in every scanning mode
+        let rows: Vec<Vec<f32>> = by_columns.rows().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, vec![vec![0.0, 1.0, 2.0], vec![10.0, 11.0, 12.0]]);
+        assert_eq!(by_rows.rows().next().unwrap().as_slice(), Some(&[0.0, 1.0, 2.0][..]));
+        let gds = RegularLatLonBuilder::new(3, 2).first_grid_point(49.0, 2.0).increments(1.0, 1.0).scanning_mode(0xc0).build()?;
+        let reversed = Grib::from_bytes(&writer::encode(&source.pds, &gds, &[12.0, 11.0, 10.0, 2.0, 1.0, 0.0], 16)?)?;
+        let rows: Vec<Vec<f32>> = reversed.rows().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, vec![vec![0.0, 1.0, 2.0], vec![10.0, 11.0, 12.0]]);
+        assert_eq!((reversed.rows().next().unwrap().get(2), reversed.rows().next().unwrap().as_slice()), (Some(2.0), None));
+        assert_eq!(source.rows().count(), 1069);
+
+        reader.inventory().await?.iter().for_each(|grib| assert!(grib.values().is_empty() && grib.rows().next().is_none()));
+        Ok(())
+    }
+}
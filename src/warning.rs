@@ -0,0 +1,115 @@
+//! Anomalies that don't prevent a message from being decoded, collected by the reader so data quality can be monitored without failing.
+
+use crate::codec::{read_f32_ibm, read_u16_be, read_u24_be};
+use crate::strict::Sections;
+use crate::{parse_gds, DataRepresentation};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Something odd about a message that was read anyway. The offset is the position of the message within the file.
+pub enum Warning {
+    /// The GDS describes a grid the library doesn't understand, so the data can't be placed on it
+    UnknownGrid { offset: u64, data_representation_type: u8 },
+    /// The number of unused bits at the end of the BDS doesn't match the number of values packed in it
+    UnusedBitsMismatch { offset: u64, expected: usize, actual: u8 },
+    /// More values are packed than there are points in the grid, or present in the bit-map. Only the first ones are decoded.
+    ValueCountMismatch { offset: u64, expected: usize, packed: usize },
+    /// The reference value isn't normalised or is implausibly large, which often means the encoder got the IBM float wrong
+    SuspiciousReferenceValue { offset: u64, value: f32 },
+    /// The message couldn't be parsed and was skipped by a lenient reader
+    SkippedMessage { offset: u64, error: String },
+}
+
+pub(crate) fn check(sections: &Sections) -> Vec<Warning> {
+    let mut result = vec![];
+    let offset = sections.offset;
+
+    let grid = sections.gds.and_then(|gds| parse_gds(gds).ok());
+    if let Some(grid) = &grid {
+        if let DataRepresentation::Unhandled = grid.data {
+            result.push(Warning::UnknownGrid {
+                offset,
+                data_representation_type: grid.data_representation_type,
+            });
+        }
+    }
+
+    let bds = sections.bds_header;
+    if bds.len() < 11 {
+        return result;
+    }
+
+    // IBM floats have at least one of the top four bits of the fraction set, unless they are zero
+    let value = read_f32_ibm(&bds[6..]);
+    if (bds[7] & 0xf0 == 0 && bds[7..10] != [0, 0, 0]) || value.abs() > 1e20 {
+        result.push(Warning::SuspiciousReferenceValue { offset, value });
+    }
+
+    // With simple packing the unused bits are whatever is left after the values, which are at the points present in the bit-map if there is one
+    let points = grid.filter(|grid| grid.row_length().is_some()).map(|grid| grid.number_of_points());
+    let values = match (sections.bms, points) {
+        (None, points) => points,
+        (Some(bms), Some(points)) if bms.len() >= 6 && read_u16_be(&bms[4..]) == 0 && (bms.len() - 6) * 8 >= points => Some((0..points).filter(|i| bms[6 + i / 8] & (0x80 >> (i % 8)) > 0).count()),
+        _ => None,
+    };
+    let bits_per_value = bds[10] as usize;
+    if let (Some(values), 0, 1..=32) = (values, bds[3] & 0xc0, bits_per_value) {
+        let available = sections.bds_length.saturating_sub(11) * 8;
+        let unused = (bds[3] & 0x0f) as usize;
+        // Large messages may have four octets of padding after the unused bits
+        let large = read_u24_be(bds) as usize != sections.bds_length;
+        if available >= values * bits_per_value {
+            let extra = available - values * bits_per_value;
+            let expected = extra == unused || (large && extra == unused + 32);
+            if !expected && extra.saturating_sub(unused) >= bits_per_value {
+                result.push(Warning::ValueCountMismatch {
+                    offset,
+                    expected: values,
+                    packed: (available - unused) / bits_per_value,
+                });
+            } else if !expected {
+                result.push(Warning::UnusedBitsMismatch {
+                    offset,
+                    expected: extra,
+                    actual: bds[3] & 0x0f,
+                });
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::error::Grib1Error;
+    use crate::Grib1Reader;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn warning_test() -> Result<(), Grib1Error> {
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        reader.inventory().await?;
+        assert!(reader.take_warnings().is_empty());
+
+        // Claim there are no unused bits in the first message and make its reference value unnormalised
+        let mut bytes = tokio::fs::read("data/sample.grib").await?;
+        let bds = 8 + 28 + 570;
+        bytes[bds + 3] &= 0xf0;
+        bytes[bds + 7] = 0x01;
+        let path = std::env::temp_dir().join("grib1_reader_warning_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+
+        let mut reader = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        assert_eq!(reader.inventory().await?.len(), 2);
+        let warnings = reader.take_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(warnings[0], Warning::SuspiciousReferenceValue { offset: 0, .. }));
+        assert_eq!(warnings[1], Warning::UnusedBitsMismatch { offset: 0, expected: 8, actual: 0 });
+        assert!(reader.take_warnings().is_empty());
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,821 @@
+//! Encoding of complete GRIB1 messages from a PDS, a grid definition and the values, using simple or second-order packing.
+
+use crate::codec::{read_i16_be, read_u24_be, write_i16_be, write_i24_be, write_u24_be};
+use crate::error::Grib1Error;
+use crate::ibm::{self, Rounding};
+#[cfg(feature = "async-tokio")]
+use crate::merge::split_messages;
+use crate::predefined;
+#[cfg(feature = "async-tokio")]
+use crate::Grib1Reader;
+use crate::{next_section, parse_gds, DataRepresentation, Grib, GDS, PDS};
+use bitstream_io::{BigEndian, BitWrite, BitWriter};
+use std::borrow::Cow;
+#[cfg(feature = "async-tokio")]
+use std::path::Path;
+#[cfg(feature = "async-tokio")]
+use tokio::fs::{File, OpenOptions};
+#[cfg(feature = "async-tokio")]
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, SeekFrom};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How the values are packed in the BDS
+pub enum Packing {
+    #[default]
+    Simple,
+    /// Second-order packing with one group per row, each packed relative to its smallest value using just enough bits for its range.
+    /// Simple packing is used if the field has missing values or the layout doesn't fit, and also if `fallback` is set and simple packing turns out smaller.
+    SecondOrderRowByRow { fallback: bool },
+}
+
+#[cfg(feature = "async-tokio")]
+/// Writes encoded messages to any `AsyncWrite`, e.g. a file
+pub struct Grib1Writer<W> {
+    writer: W,
+    packing: Packing,
+}
+
+#[cfg(feature = "async-tokio")]
+impl<W: AsyncWrite + Unpin> Grib1Writer<W> {
+    pub fn new(writer: W) -> Grib1Writer<W> {
+        Grib1Writer { writer, packing: Packing::Simple }
+    }
+
+    /// Use this packing for the messages written from now on
+    pub fn with_packing(mut self, packing: Packing) -> Grib1Writer<W> {
+        self.packing = packing;
+        self
+    }
+
+    /// Encode a message and write it. Returns the number of bytes written.
+    pub async fn write(&mut self, pds: &PDS, gds: &GDS, data: &[f32], bits_per_value: u8) -> Result<usize, Grib1Error> {
+        let bytes = encode_packed(pds, gds, data, bits_per_value, self.packing)?;
+        self.writer.write_all(&bytes).await?;
+
+        Ok(bytes.len())
+    }
+
+    /// Encode a message where missing values are given as None and write it. Returns the number of bytes written.
+    pub async fn write_optional(&mut self, pds: &PDS, gds: &GDS, data: &[Option<f32>], bits_per_value: u8) -> Result<usize, Grib1Error> {
+        let data: Vec<f32> = data.iter().map(|v| v.unwrap_or(f32::NAN)).collect();
+        let bytes = encode_packed(pds, gds, &data, bits_per_value, self.packing)?;
+        self.writer.write_all(&bytes).await?;
+
+        Ok(bytes.len())
+    }
+
+    /// Append one or more complete messages already encoded, e.g. by `encode` or `read_binary`. Returns the number of bytes written.
+    /// Nothing is written if any of them is malformed.
+    pub async fn append(&mut self, bytes: &[u8]) -> Result<usize, Grib1Error> {
+        split_messages(bytes)?;
+        self.writer.write_all(bytes).await?;
+
+        Ok(bytes.len())
+    }
+
+    /// Flush the output and hand back the writer
+    pub async fn into_inner(mut self) -> Result<W, Grib1Error> {
+        self.writer.flush().await?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl Grib1Writer<File> {
+    /// Open a GRIB file so that the messages written are added to its end. The file is created if it doesn't exist.
+    /// An existing file must end with a complete message, so nothing is appended to a file that was cut short.
+    pub async fn append_to_file(path: impl AsRef<Path>) -> Result<Grib1Writer<File>, Grib1Error> {
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path).await?;
+
+        let length = file.seek(SeekFrom::End(0)).await?;
+        if length > 0 {
+            let mut end = [0; 4];
+            if length >= 4 {
+                file.seek(SeekFrom::End(-4)).await?;
+                file.read_exact(&mut end).await?;
+            }
+            if &end != b"7777" {
+                return Err(Grib1Error::MalformedMessage("The existing file doesn't end with a complete message".to_string()));
+            }
+        }
+
+        Ok(Grib1Writer::new(file))
+    }
+}
+
+/// Encode a complete message. The grid must be a regular or rotated latitude/longitude grid, see the builders in `grid`. The values are scaled by the decimal scale factor of the PDS and packed using `bits_per_value` bits each.
+/// NaN values are missing; if there are any, a bit-map is written and only the present values are packed.
+/// The flags of the PDS are set to reflect the sections actually written. The local extension of the PDS and the vertical coordinates of the GDS are written as they are.
+pub fn encode(pds: &PDS, gds: &GDS, data: &[f32], bits_per_value: u8) -> Result<Vec<u8>, Grib1Error> {
+    encode_packed(pds, gds, data, bits_per_value, Packing::Simple)
+}
+
+/// Encode a complete message like `encode`, choosing how the values are packed
+pub fn encode_packed(pds: &PDS, gds: &GDS, data: &[f32], bits_per_value: u8, packing: Packing) -> Result<Vec<u8>, Grib1Error> {
+    let expected = gds.number_of_points();
+    if data.len() != expected {
+        return Err(Grib1Error::ValueCountMismatch { expected, actual: data.len() });
+    }
+
+    encode_sections(pds, Some(gds), data, bits_per_value, packing)
+}
+
+/// Encode a message without a GDS, for a grid identified by the `grid_identification` of the PDS as catalogued by the originating centre.
+/// If the grid is one of the NCEP grids in `predefined`, the number of values is checked against it.
+pub fn encode_predefined(pds: &PDS, data: &[f32], bits_per_value: u8) -> Result<Vec<u8>, Grib1Error> {
+    if pds.grid_identification == 255 {
+        return Err(Grib1Error::InvalidGrid("Grid 255 means the grid is only defined by the GDS".to_string()));
+    }
+    if let Some(expected) = predefined::number_of_points(pds.grid_identification) {
+        if data.len() != expected {
+            return Err(Grib1Error::ValueCountMismatch { expected, actual: data.len() });
+        }
+    }
+
+    encode_sections(pds, None, data, bits_per_value, Packing::Simple)
+}
+
+fn encode_sections(pds: &PDS, gds: Option<&GDS>, data: &[f32], bits_per_value: u8, packing: Packing) -> Result<Vec<u8>, Grib1Error> {
+    if let Some(index) = data.iter().position(|v| v.is_infinite()) {
+        return Err(Grib1Error::NonFiniteValue(index));
+    }
+
+    let has_missing = data.iter().any(|v| v.is_nan());
+    let present: Vec<f32>;
+    let values = if has_missing {
+        present = data.iter().copied().filter(|v| !v.is_nan()).collect();
+        &present[..]
+    } else {
+        data
+    };
+
+    let pds_bytes = encode_pds(pds, gds.is_some(), has_missing);
+    let gds_bytes = gds.map(encode_gds).transpose()?;
+    let bms_bytes = if has_missing { Some(encode_bms(data)) } else { None };
+    let simple = || encode_bds(values, pds.decimal_scale_factor, bits_per_value);
+    let bds_bytes = match (packing, gds.and_then(|gds| gds.row_length())) {
+        (Packing::SecondOrderRowByRow { fallback }, Some(row_length)) if !has_missing => match encode_second_order_bds(values, row_length, pds.decimal_scale_factor, bits_per_value)? {
+            Some(second_order) if fallback => {
+                let simple = simple()?;
+                if simple.len() <= second_order.len() {
+                    simple
+                } else {
+                    second_order
+                }
+            }
+            Some(second_order) => second_order,
+            None => simple()?,
+        },
+        _ => simple()?,
+    };
+
+    finalize(&pds_bytes, gds_bytes.as_deref(), bms_bytes.as_deref(), &bds_bytes)
+}
+
+/// Put encoded sections together into a complete message: the indicator section with the total length, the sections and the end section.
+/// The length fields of the PDS, GDS and BMS and the presence flags of the PDS must agree with the sections given. The length field of the BDS is filled in.
+///
+/// Messages longer than 0x7fffff octets use the ECMWF convention: the total length is given in units of 120 octets with the top bit set,
+/// and the BDS length field holds the difference needed to get the true length. The BDS is padded if needed to make that work.
+pub fn finalize(pds: &[u8], gds: Option<&[u8]>, bms: Option<&[u8]>, bds: &[u8]) -> Result<Vec<u8>, Grib1Error> {
+    check_section("PDS", pds, 28)?;
+    if let Some(gds) = gds {
+        check_section("GDS", gds, 32)?;
+    }
+    if let Some(bms) = bms {
+        check_section("BMS", bms, 6)?;
+    }
+    if bds.len() < 11 {
+        return Err(Grib1Error::MalformedMessage(format!("The BDS is {} octets, it must have at least 11", bds.len())));
+    }
+    if (pds[7] & 128 > 0) != gds.is_some() || (pds[7] & 64 > 0) != bms.is_some() {
+        return Err(Grib1Error::MalformedMessage(format!("The PDS flag {:08b} doesn't match the sections given", pds[7])));
+    }
+
+    let gds = gds.unwrap_or(&[]);
+    let bms = bms.unwrap_or(&[]);
+    let mut bds = bds.to_vec();
+    let total_length = 8 + pds.len() + gds.len() + bms.len() + bds.len() + 4;
+    let (length_field, bds_length_field, padding) = length_fields(total_length, bds.len())?;
+    bds.extend(vec![0; padding]);
+    bds[0..3].copy_from_slice(&write_u24_be(bds_length_field as u32));
+
+    let mut result = Vec::with_capacity(total_length);
+    result.extend(b"GRIB");
+    result.extend(write_u24_be(length_field as u32));
+    result.push(1);
+    result.extend(pds);
+    result.extend(gds);
+    result.extend(bms);
+    result.extend(bds);
+    result.extend(b"7777");
+
+    Ok(result)
+}
+
+// The values of the length fields of the indicator section and the BDS, and the number of octets of padding to add to the BDS
+pub(crate) fn length_fields(total_length: usize, bds_length: usize) -> Result<(usize, usize, usize), Grib1Error> {
+    if total_length <= 0x7f_ffff {
+        return Ok((total_length, bds_length, 0));
+    }
+
+    // The BDS length field must come out below 120, so pad the section if the total falls just short of a multiple of 120
+    let padding = if total_length.div_ceil(120) * 120 - total_length > 115 { 4 } else { 0 };
+    let total_length = total_length + padding;
+    let units = total_length.div_ceil(120);
+    if units > 0x7f_ffff {
+        return Err(Grib1Error::MessageTooLarge(total_length));
+    }
+
+    Ok((0x80_0000 | units, units * 120 - total_length + 4, padding))
+}
+
+fn check_section(name: &str, section: &[u8], minimum: usize) -> Result<(), Grib1Error> {
+    if section.len() < minimum {
+        return Err(Grib1Error::MalformedMessage(format!("The {} is {} octets, it must have at least {}", name, section.len(), minimum)));
+    }
+    if read_u24_be(section) as usize != section.len() {
+        return Err(Grib1Error::MalformedMessage(format!("The length field of the {} says {} octets, but it has {}", name, read_u24_be(section), section.len())));
+    }
+    Ok(())
+}
+
+/// Encode a complete message where missing values are given as None. See `encode`.
+pub fn encode_optional(pds: &PDS, gds: &GDS, data: &[Option<f32>], bits_per_value: u8) -> Result<Vec<u8>, Grib1Error> {
+    let data: Vec<f32> = data.iter().map(|v| v.unwrap_or(f32::NAN)).collect();
+    encode(pds, gds, &data, bits_per_value)
+}
+
+pub(crate) fn encode_pds(pds: &PDS, has_gds: bool, has_bitmap: bool) -> Vec<u8> {
+    let mut section = vec![];
+    section.extend(write_u24_be((28 + pds.local_extension.len()) as u32));
+    section.extend([pds.parameter_table_version_number, pds.identification_of_center, pds.generating_process_id_number, pds.grid_identification]);
+    // Flags for the presence of the GDS and the bit-map
+    section.push(if has_gds { 128 } else { 0 } | if has_bitmap { 64 } else { 0 });
+    section.extend([pds.indicator_of_parameter_and_units, pds.indicator_of_type_of_level_or_layer]);
+    section.extend(pds.level_or_layer_value.to_be_bytes());
+    section.extend([pds.year, pds.month, pds.day, pds.hour, pds.minute]);
+    section.extend([pds.forecast_time_unit, pds.p1_period_of_time, pds.p2_period_of_time, pds.time_range_indicator]);
+    section.extend(pds.number_included_in_average.to_be_bytes());
+    section.extend([pds.number_missing_from_averages_or_accumulations, pds.century_of_initial_reference_time, pds.identification_of_sub_center]);
+    section.extend(write_i16_be(pds.decimal_scale_factor));
+    section.extend(&pds.local_extension);
+
+    section
+}
+
+pub(crate) fn encode_gds(gds: &GDS) -> Result<Vec<u8>, Grib1Error> {
+    // The regular grid shares the layout of the first 32 octets with the rotated one
+    let (grid, length, representation_type) = match gds.data {
+        DataRepresentation::RegularLatLon(grid) => (grid.into(), 32, 0),
+        DataRepresentation::RotatedLatLon(grid) => (grid, 42, 10),
+        _ => return Err(Grib1Error::UnsupportedGrid(gds.data_representation_type)),
+    };
+
+    let millidegrees = |value: f32| write_i24_be((value as f64 * 1000.0).round() as i32);
+    // The vertical coordinate parameters follow the grid
+    let count = gds.vertical_coordinates.len();
+    if count > 255 {
+        return Err(Grib1Error::InvalidGrid(format!("There are {} vertical coordinate parameters, at most 255 can be given", count)));
+    }
+    let location = if count > 0 { length as u8 + 1 } else { 255 };
+
+    let mut section: Vec<u8> = vec![];
+    section.extend(write_u24_be((length + count * 4) as u32));
+    section.extend([count as u8, location, representation_type]);
+    section.extend(grid.number_of_lat_values.to_be_bytes());
+    section.extend(grid.number_of_lon_values.to_be_bytes());
+    section.extend(millidegrees(grid.latitude_of_first_grid_point));
+    section.extend(millidegrees(grid.longitude_of_first_grid_point));
+    section.push(grid.resolution_and_component_flags);
+    section.extend(millidegrees(grid.latitude_of_last_grid_point));
+    section.extend(millidegrees(grid.longitude_of_last_grid_point));
+    section.extend(grid.i_direction_increment.to_be_bytes());
+    section.extend(grid.j_direction_increment.to_be_bytes());
+    section.push(grid.scanning_mode);
+    section.extend([0; 4]);
+    section.extend(millidegrees(grid.latitude_of_southern_pole));
+    section.extend(millidegrees(grid.longitude_of_southern_pole));
+    section.extend(ibm::to_ibm(grid.angle_of_rotation));
+    section.truncate(length);
+    section.extend(gds.vertical_coordinates.iter().flat_map(|value| ibm::to_ibm(*value)));
+
+    Ok(section)
+}
+
+// One bit per grid point, set if the value is present
+fn encode_bms(data: &[f32]) -> Vec<u8> {
+    let mut bits = vec![0u8; data.len().div_ceil(8)];
+    for (i, value) in data.iter().enumerate() {
+        if !value.is_nan() {
+            bits[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+
+    // Sections have an even number of octets
+    if (6 + bits.len()) % 2 == 1 {
+        bits.push(0);
+    }
+    let unused_bits = (bits.len() * 8 - data.len()) as u8;
+
+    let mut section = vec![];
+    section.extend(write_u24_be(6 + bits.len() as u32));
+    section.push(unused_bits);
+    // The bit-map follows rather than referring to a predefined one
+    section.extend([0, 0]);
+    section.extend(bits);
+
+    section
+}
+
+fn encode_bds(data: &[f32], decimal_scale_factor: i16, bits_per_value: u8) -> Result<Vec<u8>, Grib1Error> {
+    let (reference_bytes, binary_scale, integers) = pack_integers(data, decimal_scale_factor, bits_per_value)?;
+
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+    for value in integers {
+        writer.write(bits_per_value as u32, value)?;
+    }
+    writer.byte_align()?;
+    let mut packed = writer.into_writer();
+
+    // Sections have an even number of octets
+    let mut length = 11 + packed.len();
+    if length % 2 == 1 {
+        packed.push(0);
+        length += 1;
+    }
+    let unused_bits = (packed.len() * 8 - data.len() * bits_per_value as usize) as u8;
+
+    let mut section = vec![];
+    section.extend(write_u24_be(length as u32));
+    // Grid point data, simple packing, floating point values
+    section.push(unused_bits);
+    section.extend(write_i16_be(binary_scale as i16));
+    section.extend(reference_bytes);
+    section.push(bits_per_value);
+    section.extend(packed);
+
+    Ok(section)
+}
+
+// Second-order packing with a group per row: the smallest packed integer of each row is stored as a first-order value using `bits_per_value` bits,
+// followed by the differences to it using the width of that row. Returns None if the layout doesn't fit the 16 bit octet offsets of the header.
+fn encode_second_order_bds(data: &[f32], row_length: usize, decimal_scale_factor: i16, bits_per_value: u8) -> Result<Option<Vec<u8>>, Grib1Error> {
+    let (reference_bytes, binary_scale, integers) = pack_integers(data, decimal_scale_factor, bits_per_value)?;
+    let rows: Vec<&[u32]> = integers.chunks(row_length).collect();
+
+    let first_order: Vec<u32> = rows.iter().map(|row| row.iter().copied().min().unwrap_or(0)).collect();
+    let widths: Vec<u8> = rows.iter().zip(&first_order).map(|(row, min)| (32 - (row.iter().copied().max().unwrap_or(0) - min).leading_zeros()) as u8).collect();
+
+    // Octet numbers within the section of the first-order and second-order values
+    let n1 = 22 + rows.len();
+    let n2 = n1 + (rows.len() * bits_per_value as usize).div_ceil(8);
+    if rows.len() > u16::MAX as usize || n2 > u16::MAX as usize {
+        return Ok(None);
+    }
+
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+    for value in &first_order {
+        writer.write(bits_per_value as u32, *value)?;
+    }
+    writer.byte_align()?;
+    let first_order_bytes = writer.into_writer();
+
+    let mut writer = BitWriter::endian(Vec::new(), BigEndian);
+    let mut second_order_bits = 0;
+    for ((row, min), width) in rows.iter().zip(&first_order).zip(&widths) {
+        if *width == 0 {
+            continue;
+        }
+        for value in row.iter() {
+            writer.write(*width as u32, value - min)?;
+        }
+        second_order_bits += row.len() * *width as usize;
+    }
+    writer.byte_align()?;
+    let mut second_order_bytes = writer.into_writer();
+
+    // Sections have an even number of octets
+    let mut length = n2 - 1 + second_order_bytes.len();
+    if length % 2 == 1 {
+        second_order_bytes.push(0);
+        length += 1;
+    }
+    let unused_bits = (second_order_bytes.len() * 8 - second_order_bits) as u8;
+
+    let mut section = vec![];
+    section.extend(write_u24_be(length as u32));
+    // Grid point data, complex packing, floating point values, additional flags in octet 14
+    section.push(0x40 | 0x10 | unused_bits);
+    section.extend(write_i16_be(binary_scale as i16));
+    section.extend(reference_bytes);
+    section.push(bits_per_value);
+    section.extend((n1 as u16).to_be_bytes());
+    // Single datum at each point, no secondary bit-maps, second-order values of different widths
+    section.push(0x10);
+    section.extend((n2 as u16).to_be_bytes());
+    section.extend((rows.len() as u16).to_be_bytes());
+    // The number of second-order values only fits for small grids, decoders count the points of the grid instead
+    section.extend((data.len().min(u16::MAX as usize) as u16).to_be_bytes());
+    section.push(0);
+    section.extend(widths);
+    section.extend(first_order_bytes);
+    section.extend(second_order_bytes);
+
+    Ok(Some(section))
+}
+
+// Scale the values and turn them into the unsigned integers stored by simple packing, returning the IBM reference value and the binary scale used
+fn pack_integers(data: &[f32], decimal_scale_factor: i16, bits_per_value: u8) -> Result<([u8; 4], i32, Vec<u32>), Grib1Error> {
+    if bits_per_value == 0 || bits_per_value > 32 {
+        return Err(Grib1Error::UnsupportedBitsPerValue(bits_per_value));
+    }
+
+    let decimal_factor = 10f64.powi(decimal_scale_factor as i32);
+    let scaled: Vec<f64> = data.iter().map(|v| *v as f64 * decimal_factor).collect();
+    let (reference_bytes, reference, binary_scale) = packing_parameters(&scaled, bits_per_value);
+    let max_packed = ((1u64 << bits_per_value) - 1) as f64;
+    let factor = 2f64.powi(-binary_scale);
+
+    let integers = scaled.iter().map(|value| ((value - reference) * factor).round().clamp(0.0, max_packed) as u32).collect();
+    Ok((reference_bytes, binary_scale, integers))
+}
+
+// Find the reference value and the smallest binary scale that makes the range of the (decimally scaled) values fit in the available bits
+fn packing_parameters(scaled: &[f64], bits_per_value: u8) -> ([u8; 4], f64, i32) {
+    let min = scaled.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = scaled.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    if scaled.is_empty() {
+        packing_parameters_for_range(0.0, 0.0, bits_per_value)
+    } else {
+        packing_parameters_for_range(min, max, bits_per_value)
+    }
+}
+
+// The same for values known to be within the range from `min` to `max`, both scaled by the decimal factor
+pub(crate) fn packing_parameters_for_range(min: f64, max: f64, bits_per_value: u8) -> ([u8; 4], f64, i32) {
+    // The reference value is stored as an IBM float, so use the value that will actually be read back. It's rounded down so no value falls below it.
+    let reference_bytes = ibm::to_ibm_rounded(min as f32, Rounding::Down);
+    let reference = crate::codec::read_f32_ibm(&reference_bytes) as f64;
+
+    let max_packed = ((1u64 << bits_per_value) - 1) as f64;
+    let range = max - reference;
+    let mut binary_scale = 0;
+    if range > 0.0 {
+        binary_scale = (range / max_packed).log2().ceil() as i32;
+        while range / 2f64.powi(binary_scale) > max_packed {
+            binary_scale += 1;
+        }
+    }
+
+    (reference_bytes, reference, binary_scale)
+}
+
+/// Use a raw message as a template for a derived field: the PDS, GDS and BMS are copied verbatim and only the BDS is packed again from the new values,
+/// using the decimal scale factor of the PDS and the original number of bits per value. There must be a value for every point of the grid;
+/// the values at points missing according to the bit-map are ignored.
+pub fn with_values(template: &[u8], data: &[f32]) -> Result<Vec<u8>, Grib1Error> {
+    if template.len() < 8 || &template[0..4] != b"GRIB" {
+        return Err(Grib1Error::WrongHeader);
+    }
+    if template[7] != 1 {
+        return Err(Grib1Error::WrongVersion(template[7]));
+    }
+
+    let mut offset = 8;
+    let pds = next_section(template, &mut offset)?;
+    if pds.len() < 28 {
+        return Err(Grib1Error::MalformedMessage("The PDS is shorter than 28 octets".to_string()));
+    }
+    let gds = if pds[7] & 128 > 0 { Some(next_section(template, &mut offset)?) } else { None };
+    let bms = if pds[7] & 64 > 0 { Some(next_section(template, &mut offset)?) } else { None };
+    let bds = next_section(template, &mut offset)?;
+
+    let gds_bytes = gds.ok_or(Grib1Error::MissingSection("GDS"))?;
+    if gds_bytes.len() < if gds_bytes.get(5) == Some(&10) { 42 } else { 32 } || bds.len() < 11 {
+        return Err(Grib1Error::MalformedMessage("The GDS or BDS is too short".to_string()));
+    }
+    let expected = parse_gds(gds_bytes)?.number_of_points();
+    if data.len() != expected {
+        return Err(Grib1Error::ValueCountMismatch { expected, actual: data.len() });
+    }
+
+    let present = |i: usize| bms.map(|bms| bms.get(6 + i / 8).map(|b| b & (0x80 >> (i % 8)) > 0).unwrap_or(false)).unwrap_or(true);
+    let mut values = Vec::with_capacity(data.len());
+    for (i, value) in data.iter().enumerate() {
+        if present(i) {
+            if !value.is_finite() {
+                return Err(Grib1Error::NonFiniteValue(i));
+            }
+            values.push(*value);
+        }
+    }
+
+    // A constant field has no width to reuse
+    let bits_per_value = if bds[10] == 0 { 16 } else { bds[10] };
+    let bds = encode_bds(&values, read_i16_be(&pds[26..]), bits_per_value)?;
+
+    finalize(pds, gds, bms, &bds)
+}
+
+#[cfg(feature = "async-tokio")]
+impl Grib1Reader {
+    /// Use a message previously returned by the reader as a template for a derived field. See `writer::with_values`.
+    pub async fn with_values(&mut self, grib: &Grib, data: &[f32]) -> Result<Vec<u8>, Grib1Error> {
+        let template = self.read_raw(grib).await?;
+        with_values(&template, data)
+    }
+}
+
+/// The largest error packing introduces when the values are packed using `bits_per_value` bits, i.e. half a packing step
+pub fn quantisation_error(data: &[f32], decimal_scale_factor: i16, bits_per_value: u8) -> Result<f32, Grib1Error> {
+    if bits_per_value == 0 || bits_per_value > 32 {
+        return Err(Grib1Error::UnsupportedBitsPerValue(bits_per_value));
+    }
+
+    let decimal_factor = 10f64.powi(decimal_scale_factor as i32);
+    let scaled: Vec<f64> = data.iter().map(|v| *v as f64 * decimal_factor).collect();
+    let (_, _, binary_scale) = packing_parameters(&scaled, bits_per_value);
+
+    Ok((2f64.powi(binary_scale) / 2.0 / decimal_factor) as f32)
+}
+
+/// Re-encode a decoded message using a different number of bits per value, e.g. to reduce the size of an archive.
+/// The reference value and binary scale are recomputed; use `quantisation_error` to find the resulting precision.
+pub fn repack(grib: &Grib, bits_per_value: u8) -> Result<Vec<u8>, Grib1Error> {
+    let gds = grib.gds.as_ref().ok_or(Grib1Error::MissingSection("GDS"))?;
+
+    encode(&grib.pds, gds, &physical_values(grib)?, bits_per_value)
+}
+
+// The values with missing points as NaN, scaled as the PDS says they are. The encoders scale by the decimal scale factor of the PDS,
+// so values decoded without decimal scaling would otherwise be scaled twice.
+pub(crate) fn physical_values(grib: &Grib) -> Result<Cow<'_, [f32]>, Grib1Error> {
+    let bds = grib.bds.as_ref().ok_or(Grib1Error::MissingSection("BDS"))?;
+    let values = bds.nan_values();
+    let unscaled = grib.pds.decimal_scale_factor - bds.decimal_scale_factor;
+    if unscaled == 0 {
+        return Ok(values);
+    }
+    let factor = 10f64.powi(-(unscaled as i32));
+    Ok(Cow::Owned(values.iter().map(|value| (*value as f64 * factor) as f32).collect()))
+}
+
+impl Grib {
+    /// Re-encode the message using a different number of bits per value. See `writer::repack`.
+    pub fn repack(&self, bits_per_value: u8) -> Result<Vec<u8>, Grib1Error> {
+        repack(self, bits_per_value)
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod tests {
+    use super::*;
+    use crate::compare::assert_fields_close;
+    use crate::synthetic::MessageBuilder;
+    use crate::{Grib1Reader, SearchParams};
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn round_trip_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let source = &result[0];
+        let original = &source.bds.as_ref().unwrap().data;
+
+        let mut writer = Grib1Writer::new(Vec::new());
+        let length = writer.write(&source.pds, source.gds.as_ref().unwrap(), original, 12).await?;
+        let bytes = writer.into_inner().await?;
+        assert_eq!(length, bytes.len());
+
+        let decoded = Grib::from_bytes(&bytes)?;
+        assert_eq!(decoded.length as usize, bytes.len());
+        assert_eq!(decoded.pds.indicator_of_parameter_and_units, 33);
+        assert_eq!(decoded.gds.as_ref().unwrap().number_of_points(), original.len());
+
+        assert_eq!(decoded.bds.as_ref().unwrap().bits_per_value, 12);
+        assert_fields_close(&decoded, source, 1e-4, 0.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn repack_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 34, level: 700 }]).await?;
+        let original = &result[0].bds.as_ref().unwrap().data;
+
+        let bytes = result[0].repack(8)?;
+        assert!(bytes.len() < result[0].length as usize / 2 + 1024);
+
+        let repacked = Grib::from_bytes(&bytes)?.bds.unwrap();
+        let error = quantisation_error(original, 0, 8)? + 1e-4;
+        assert_eq!(repacked.bits_per_value, 8);
+        assert!(repacked.data.iter().zip(original).all(|(a, b)| (a - b).abs() <= error));
+
+        // The vertical coordinates of the GDS and the local extension of the PDS are kept
+        let coordinates = &result[0].gds.as_ref().unwrap().vertical_coordinates;
+        assert_eq!(coordinates.len(), 132);
+        assert_eq!(&crate::interpolate::vertical_coordinates(&bytes)?, coordinates);
+        let member = MessageBuilder::new(2, 1).pds(|pds| pds.identification_of_center = 7).local_extension(vec![1, 3, 4, 1, 255]).grib()?;
+        assert_eq!(member.pds.local_extension.len(), 17);
+        let repacked = member.repack(8)?;
+        assert_eq!(crate::ensemble::ensemble_member(&repacked)?.map(|member| member.number), Some(4));
+        assert_eq!(Grib::from_bytes(&repacked)?.pds.local_extension, member.pds.local_extension);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unscaled_repack_test() -> Result<(), Grib1Error> {
+        let bytes = MessageBuilder::new(2, 2).decimal_scale_factor(2).values(vec![1.25, 2.5, -3.75, 10.0]).build()?;
+        let path = std::env::temp_dir().join("grib1_reader_unscaled_repack_test.grb");
+        tokio::fs::write(&path, &bytes).await?;
+        let grib = Grib1Reader::open(&path).await?.decimal_scaling(false).read_where(|_| true).await?.remove(0);
+        tokio::fs::remove_file(&path).await?;
+        assert_eq!(grib.bds.as_ref().unwrap().data[0], 125.0);
+
+        // The values are written as the PDS says they are rather than scaled a second time
+        assert_eq!(Grib::from_bytes(&grib.repack(16)?)?.bds.unwrap().data, [1.25, 2.5, -3.75, 10.0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bitmap_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let source = &result[0];
+
+        let gds = crate::grid::RegularLatLonBuilder::new(5, 2).increments(1.0, 1.0).build()?;
+        let data = [Some(1.0), None, Some(2.5), Some(4.0), None, None, None, None, None, Some(-3.0)];
+        let bytes = encode_optional(&source.pds, &gds, &data, 8)?;
+
+        // PDS flag, then the bit-map section with 10 bits used out of two octets
+        assert_eq!(bytes[8 + 7], 128 | 64);
+        let bms = &bytes[8 + 28 + 32..];
+        assert_eq!(&bms[..8], &[0, 0, 8, 6, 0, 0, 0b10110000, 0b01000000]);
+
+        let decoded = Grib::from_bytes(&bytes)?;
+        let bitmap = decoded.bitmap.as_ref().unwrap();
+        assert_eq!((bitmap.number_of_bits, bitmap.count_present()), (10, 4));
+        assert!((0..12).all(|i| bitmap.is_present(i) == data.get(i).is_some_and(|v| v.is_some())));
+        let values = decoded.bds.unwrap().data;
+        assert_eq!(values.len(), data.len());
+        for (value, expected) in values.iter().zip(data) {
+            match expected {
+                Some(expected) => assert!((value - expected).abs() < 0.02),
+                None => assert!(value.is_nan()),
+            }
+        }
+
+        let nan = [f32::NAN; 10];
+        assert!(Grib::from_bytes(&encode(&source.pds, &gds, &nan, 8)?)?.bds.unwrap().data.iter().all(|v| v.is_nan()));
+        assert!(encode(&source.pds, &gds, &[f32::INFINITY; 10], 8).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn second_order_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let source = &result[0];
+        let gds = source.gds.as_ref().unwrap();
+        let original = &source.bds.as_ref().unwrap().data;
+
+        let simple = encode(&source.pds, gds, original, 16)?;
+        let second_order = encode_packed(&source.pds, gds, original, 16, Packing::SecondOrderRowByRow { fallback: false })?;
+        assert!(second_order.len() < simple.len());
+
+        // The same packed integers are stored, so the decoded values are identical
+        let decoded = Grib::from_bytes(&second_order)?.bds.unwrap();
+        assert_eq!(decoded.data_flag & 0xf0, 0x50);
+        assert_eq!(decoded.data, Grib::from_bytes(&simple)?.bds.unwrap().data);
+
+        // Noise doesn't benefit from second-order packing, so simple packing is used
+        let gds = crate::grid::RegularLatLonBuilder::new(4, 2).increments(1.0, 1.0).build()?;
+        let noise = [0.0, 255.0, 0.0, 255.0, 255.0, 0.0, 255.0, 0.0];
+        let bytes = encode_packed(&source.pds, &gds, &noise, 8, Packing::SecondOrderRowByRow { fallback: true })?;
+        assert_eq!(bytes, encode(&source.pds, &gds, &noise, 8)?);
+        let bytes = encode_packed(&source.pds, &gds, &noise, 8, Packing::SecondOrderRowByRow { fallback: false })?;
+        assert_eq!(Grib::from_bytes(&bytes)?.bds.unwrap().data, noise);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn append_test() -> Result<(), Grib1Error> {
+        let path = std::env::temp_dir().join("grib1_reader_append_test.grb");
+        let mut reader = Grib1Reader::new(BufReader::new(File::open("data/sample.grib").await?));
+        let v_wind = reader.read_binary(vec![SearchParams { param: 34, level: 700 }]).await?;
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        tokio::fs::write(&path, &v_wind).await?;
+
+        // Add a freshly encoded message and a copied one to the end of the existing file
+        let mut writer = Grib1Writer::append_to_file(&path).await?;
+        writer.write(&result[0].pds, result[0].gds.as_ref().unwrap(), &result[0].bds.as_ref().unwrap().data, 8).await?;
+        assert!(writer.append(&v_wind[..100]).await.is_err());
+        assert_eq!(writer.append(&v_wind).await?, v_wind.len());
+        writer.into_inner().await?;
+
+        let mut appended = Grib1Reader::new(BufReader::new(File::open(&path).await?));
+        let params: Vec<u8> = appended.inventory().await?.iter().map(|grib| grib.pds.indicator_of_parameter_and_units).collect();
+        assert_eq!(params, vec![34, 33, 34]);
+
+        // A file that was cut short is refused
+        tokio::fs::write(&path, &v_wind[..100]).await?;
+        assert!(Grib1Writer::append_to_file(&path).await.is_err());
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn finalize_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let pds = encode_pds(&result[0].pds, true, false);
+        let gds = encode_gds(result[0].gds.as_ref().unwrap())?;
+
+        let bytes = finalize(&pds, Some(&gds), None, &[0; 12])?;
+        assert_eq!(crate::codec::read_u24_be(&bytes[4..]) as usize, bytes.len());
+        assert_eq!(&bytes[bytes.len() - 4..], b"7777");
+        assert!(finalize(&pds, None, None, &[0; 12]).is_err());
+        assert!(finalize(&pds[..20], Some(&gds), None, &[0; 12]).is_err());
+        assert!(finalize(&pds, Some(&gds[..40]), None, &[0; 12]).is_err());
+
+        // Large messages give the length in units of 120 octets, corrected by the BDS length field
+        for size in [0x80_0000, 0x80_0000 + 1, 0x80_0000 + 100, 0x80_0000 + 32] {
+            let bytes = finalize(&pds, Some(&gds), None, &vec![0; size])?;
+            let units = crate::codec::read_u24_be(&bytes[4..]);
+            let bds_length = crate::codec::read_u24_be(&bytes[8 + 28 + gds.len()..]);
+            assert!(units & 0x80_0000 > 0 && bds_length < 120);
+            assert_eq!((units & 0x7f_ffff) as usize * 120 - bds_length as usize + 4, bytes.len());
+            assert_eq!(&bytes[bytes.len() - 4..], b"7777");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn with_values_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+        let template = reader.read_raw(&result[0]).await?;
+        let original = &result[0].bds.as_ref().unwrap().data;
+
+        let doubled: Vec<f32> = original.iter().map(|v| v * 2.0).collect();
+        let bytes = reader.with_values(&result[0], &doubled).await?;
+
+        // The PDS and the GDS including its vertical coordinates are copied verbatim
+        let headers = 8 + 28 + 570;
+        assert_eq!(bytes[8..headers], template[8..headers]);
+
+        let decoded = Grib::from_bytes(&bytes)?.bds.unwrap();
+        let tolerance = 2f32.powi(decoded.binary_scale_factor as i32) / 2.0 + 1e-4;
+        assert_eq!(decoded.bits_per_value, 16);
+        assert!(decoded.data.iter().zip(&doubled).all(|(a, b)| (a - b).abs() <= tolerance));
+
+        assert!(with_values(&template, &doubled[1..]).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn predefined_grid_test() -> Result<(), Grib1Error> {
+        let f = File::open("data/sample.grib").await?;
+        let mut reader = Grib1Reader::new(BufReader::new(f));
+        let result = reader.read(vec![SearchParams { param: 33, level: 700 }]).await?;
+
+        let mut pds = result[0].pds.clone();
+        pds.grid_identification = 2;
+        let mut data: Vec<f32> = (0..144 * 73).map(|i| (i % 100) as f32).collect();
+        let bytes = encode_predefined(&pds, &data, 8)?;
+        assert_eq!(bytes[8 + 7], 0);
+
+        let decoded = Grib::from_bytes(&bytes)?;
+        assert!(decoded.gds.is_none());
+        assert_eq!(decoded.pds.grid_identification, 2);
+        assert_eq!(decoded.bds.unwrap().data, data);
+
+        // With missing values the bit-map gives the number of points
+        data[5] = f32::NAN;
+        let decoded = Grib::from_bytes(&encode_predefined(&pds, &data, 8)?)?.bds.unwrap().data;
+        assert_eq!(decoded.len(), data.len());
+        assert!(decoded[5].is_nan());
+
+        assert!(encode_predefined(&pds, &data[1..], 8).is_err());
+        pds.grid_identification = 255;
+        assert!(encode_predefined(&pds, &data, 8).is_err());
+
+        Ok(())
+    }
+}